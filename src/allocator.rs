@@ -0,0 +1,423 @@
+use std::collections::HashMap;
+use std::os::raw::{c_int, c_void};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr::{self, NonNull};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, TryLockError};
+
+use libde265_sys::*;
+
+use crate::decoder::lock;
+use crate::user_data::Payloads;
+use crate::{Channel, ChromaFormat, Decoder, DecoderInput};
+
+pub(crate) const CHANNELS: [Channel; 3] = [Channel::Y, Channel::Cb, Channel::Cr];
+const PLANE_PADDING: usize = 16;
+
+/// Specification of the memory layout of an image
+/// requested by the decoder from an [`ImageAllocator`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ImageSpec {
+    pub chroma_format: ChromaFormat,
+    /// Width of the luma plane including the area outside
+    /// the conformance window.
+    pub width: u32,
+    /// Height of the luma plane including the area outside
+    /// the conformance window.
+    pub height: u32,
+    /// Required alignment of plane data and strides in bytes.
+    pub alignment: u32,
+    pub crop_left: u32,
+    pub crop_right: u32,
+    pub crop_top: u32,
+    pub crop_bottom: u32,
+    /// Bit depth of the luma plane.
+    pub bits_per_pixel_luma: u32,
+    /// Bit depth of the chroma planes.
+    pub bits_per_pixel_chroma: u32,
+}
+
+impl ImageSpec {
+    fn from_raw(spec: &de265_image_spec, img: *const de265_image) -> Self {
+        let chroma_format = ChromaFormat::from_raw(unsafe { de265_get_chroma_format(img) });
+        let bits_per_pixel = |channel: Channel| {
+            let value = unsafe { de265_get_bits_per_pixel(img, channel.index()) };
+            value.max(0) as u32
+        };
+        Self {
+            chroma_format,
+            width: spec.width.max(0) as _,
+            height: spec.height.max(0) as _,
+            alignment: spec.alignment.max(1) as _,
+            crop_left: spec.crop_left.max(0) as _,
+            crop_right: spec.crop_right.max(0) as _,
+            crop_top: spec.crop_top.max(0) as _,
+            crop_bottom: spec.crop_bottom.max(0) as _,
+            bits_per_pixel_luma: bits_per_pixel(Channel::Y),
+            bits_per_pixel_chroma: bits_per_pixel(Channel::Cb),
+        }
+    }
+
+    /// Returns `true` if the image has a plane for the given channel.
+    pub fn has_plane(&self, channel: Channel) -> bool {
+        channel == Channel::Y || self.chroma_format.has_chroma()
+    }
+
+    /// Width of the plane in pixels.
+    pub fn plane_width(&self, channel: Channel) -> u32 {
+        self.plane_size(channel).0
+    }
+
+    /// Height of the plane in pixels.
+    pub fn plane_height(&self, channel: Channel) -> u32 {
+        self.plane_size(channel).1
+    }
+
+    fn plane_size(&self, channel: Channel) -> (u32, u32) {
+        match channel {
+            Channel::Y => (self.width, self.height),
+            _ => self
+                .chroma_format
+                .chroma_dimensions(self.width, self.height)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Number of bytes used to store one pixel of the plane.
+    pub fn bytes_per_pixel(&self, channel: Channel) -> usize {
+        let bits_per_pixel = match channel {
+            Channel::Y => self.bits_per_pixel_luma,
+            Channel::Cb | Channel::Cr => self.bits_per_pixel_chroma,
+        };
+        bits_per_pixel.div_ceil(8) as usize
+    }
+
+    /// The minimal allowed stride (bytes per line) of the plane.
+    pub fn min_stride(&self, channel: Channel) -> usize {
+        let width = self.plane_width(channel) as usize;
+        width.next_multiple_of(self.alignment as usize) * self.bytes_per_pixel(channel)
+    }
+
+    /// The minimal allowed size of the plane buffer in bytes.
+    ///
+    /// It includes padding that is required by SIMD optimizations of the decoder.
+    pub fn min_plane_size(&self, channel: Channel) -> usize {
+        self.min_stride(channel) * self.plane_height(channel) as usize + PLANE_PADDING
+    }
+
+    fn is_valid_buffers(&self, buffers: &PlaneBuffers) -> bool {
+        CHANNELS.iter().all(
+            |&channel| match (self.has_plane(channel), buffers.get(channel)) {
+                (true, Some(buffer)) => self.is_valid_plane(channel, buffer),
+                (true, None) => false,
+                (false, _) => true,
+            },
+        )
+    }
+
+    fn is_valid_plane(&self, channel: Channel, buffer: &PlaneBuffer) -> bool {
+        let alignment = self.alignment as usize;
+        buffer.data.as_ptr() as usize % alignment == 0
+            && buffer.stride % alignment == 0
+            && buffer.stride % self.bytes_per_pixel(channel) == 0
+            && buffer.stride >= self.min_stride(channel)
+            && buffer.stride <= c_int::MAX as usize
+    }
+}
+
+/// Memory of one image plane provided by an [`ImageAllocator`].
+#[derive(Debug)]
+pub struct PlaneBuffer {
+    /// Pointer to the first byte of the plane.
+    /// It must be aligned to [`ImageSpec::alignment`].
+    pub data: NonNull<u8>,
+    /// Bytes per line. It must be a multiple of [`ImageSpec::alignment`]
+    /// and not less than [`ImageSpec::min_stride()`].
+    /// The buffer must contain at least `stride * plane_height + 16` bytes
+    /// (see [`ImageSpec::min_plane_size()`]).
+    pub stride: usize,
+    /// Arbitrary value that is returned by
+    /// [`Image::plane_user_data()`](crate::Image::plane_user_data).
+    pub user_data: *mut c_void,
+}
+
+// SAFETY: the buffer only describes memory provided by an allocator,
+// the memory is accessed by the decoder under the lock of its context.
+unsafe impl Send for PlaneBuffer {}
+
+/// Identifier of a plane buffer assigned by an [`ImageAllocator`],
+/// e.g. an index of a staging buffer in a pool of GPU memory.
+///
+/// It is returned by [`Image::plane_token()`](crate::Image::plane_token)
+/// for planes decoded into the buffer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PlaneToken(pub u64);
+
+/// Set of plane buffers of one image.
+#[derive(Debug, Default)]
+pub struct PlaneBuffers {
+    planes: [Option<PlaneBuffer>; 3],
+    tokens: [Option<PlaneToken>; 3],
+}
+
+impl PlaneBuffers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&mut self, channel: Channel, buffer: PlaneBuffer) {
+        self.planes[channel.index() as usize] = Some(buffer);
+        self.tokens[channel.index() as usize] = None;
+    }
+
+    /// Set the buffer of the plane together with its token
+    /// that is returned by [`Image::plane_token()`](crate::Image::plane_token).
+    pub fn set_with_token(&mut self, channel: Channel, buffer: PlaneBuffer, token: PlaneToken) {
+        self.planes[channel.index() as usize] = Some(buffer);
+        self.tokens[channel.index() as usize] = Some(token);
+    }
+
+    /// Returns the token of the plane set by [`PlaneBuffers::set_with_token()`].
+    ///
+    /// Tokens are kept in buffers passed into [`ImageAllocator::release()`],
+    /// so the allocator may retire them.
+    pub fn token(&self, channel: Channel) -> Option<PlaneToken> {
+        self.tokens[channel.index() as usize]
+    }
+
+    pub fn get(&self, channel: Channel) -> Option<&PlaneBuffer> {
+        self.planes[channel.index() as usize].as_ref()
+    }
+
+    pub fn take(&mut self, channel: Channel) -> Option<PlaneBuffer> {
+        self.planes[channel.index() as usize].take()
+    }
+}
+
+/// Allocator of memory for planes of decoded images.
+///
+/// It allows decoding images directly into memory owned by the application.
+///
+/// Note that `libde265` uses the custom allocator only for images that
+/// may be output. If the stream uses the SAO filter and worker threads are
+/// not started, the filter is applied in-place into images allocated by
+/// the default allocator.
+///
+/// # Safety
+///
+/// The decoder writes into buffers returned by [`ImageAllocator::allocate()`]
+/// and images borrow them, but only their alignment and strides
+/// are checked. Implementations must guarantee that memory of every
+/// [`PlaneBuffer`] is valid for reads and writes of `stride * plane_height + 16`
+/// bytes (see [`ImageSpec::min_plane_size()`]) and isn't accessed by anything
+/// else until the buffers are passed into [`ImageAllocator::release()`].
+pub unsafe trait ImageAllocator: Send {
+    /// Allocate planes of an image with the given specification.
+    ///
+    /// Buffers must be provided for all planes of the image
+    /// (see [`ImageSpec::has_plane()`]). Returning `None` or invalid buffers
+    /// causes the decoding error [`DeError::ErrorOutOfMemory`](crate::DeError::ErrorOutOfMemory).
+    fn allocate(&mut self, spec: &ImageSpec) -> Option<PlaneBuffers>;
+
+    /// Release buffers previously returned by [`ImageAllocator::allocate()`].
+    fn release(&mut self, buffers: PlaneBuffers);
+}
+
+type SharedAllocator = Arc<Mutex<Box<dyn ImageAllocator>>>;
+
+/// Data shared with image allocation functions of the decoder.
+pub(crate) struct Allocations {
+    payloads: Arc<Payloads>,
+    allocator: Mutex<Option<SharedAllocator>>,
+    last_token: AtomicUsize,
+    /// Buffers allocated by custom allocators.
+    ///
+    /// Buffers are identified by a token that is passed into the decoder
+    /// as user data of image planes, because the decoder may exchange
+    /// pixel data between images.
+    images: Mutex<HashMap<usize, AllocatedImage>>,
+}
+
+struct AllocatedImage {
+    allocator: SharedAllocator,
+    buffers: PlaneBuffers,
+    /// Total size of planes in bytes.
+    size: usize,
+}
+
+impl Allocations {
+    pub(crate) fn new(payloads: Arc<Payloads>) -> Self {
+        Self {
+            payloads,
+            allocator: Mutex::new(None),
+            last_token: AtomicUsize::new(0),
+            images: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the number of images allocated by custom allocators
+    /// and the total size of their planes in bytes.
+    pub(crate) fn picture_buffers(&self) -> (usize, usize) {
+        let images = lock(&self.images);
+        (images.len(), images.values().map(|image| image.size).sum())
+    }
+
+    fn insert(&self, image: AllocatedImage) -> usize {
+        let mut token = 0;
+        while token == 0 {
+            token = self
+                .last_token
+                .fetch_add(1, Ordering::Relaxed)
+                .wrapping_add(1);
+        }
+        lock(&self.images).insert(token, image);
+        token
+    }
+
+    /// Returns user data of the plane provided by a custom allocator.
+    pub(crate) fn plane_user_data(&self, token: usize, channel: Channel) -> Option<*mut c_void> {
+        let images = lock(&self.images);
+        let image = images.get(&token)?;
+        Some(
+            image
+                .buffers
+                .get(channel)
+                .map_or(ptr::null_mut(), |b| b.user_data),
+        )
+    }
+
+    /// Returns the token of the plane provided by a custom allocator.
+    pub(crate) fn plane_token(&self, token: usize, channel: Channel) -> Option<PlaneToken> {
+        lock(&self.images).get(&token)?.buffers.token(channel)
+    }
+}
+
+/// Image allocation functions that delegate to the custom allocator
+/// or to the default ones and drop payloads of released images.
+pub(crate) static IMAGE_ALLOCATION: de265_image_allocation = de265_image_allocation {
+    get_buffer: Some(get_buffer),
+    release_buffer: Some(release_buffer),
+};
+
+unsafe extern "C" fn get_buffer(
+    ctx: *mut de265_decoder_context,
+    spec: *mut de265_image_spec,
+    img: *mut de265_image,
+    userdata: *mut c_void,
+) -> c_int {
+    let allocations = unsafe { (userdata as *const Allocations).as_ref() };
+    let allocator = allocations.and_then(|a| lock(&a.allocator).clone());
+    let (Some(allocations), Some(allocator)) = (allocations, allocator) else {
+        let default = unsafe { &*de265_get_default_image_allocation_functions() };
+        return match default.get_buffer {
+            Some(get_buffer) => unsafe { get_buffer(ctx, spec, img, ptr::null_mut()) },
+            None => 0,
+        };
+    };
+
+    let spec = ImageSpec::from_raw(unsafe { &*spec }, img);
+    // Panics must not unwind into C code.
+    let buffers = panic::catch_unwind(AssertUnwindSafe(|| try_lock(&allocator)?.allocate(&spec)));
+    let Ok(Some(buffers)) = buffers else {
+        return 0;
+    };
+
+    if !spec.is_valid_buffers(&buffers) {
+        release_to(&allocator, buffers);
+        return 0;
+    }
+
+    let size = CHANNELS
+        .iter()
+        .filter_map(|&channel| {
+            let buffer = buffers.get(channel).filter(|_| spec.has_plane(channel))?;
+            Some(buffer.stride * spec.plane_height(channel) as usize)
+        })
+        .sum();
+    let token = allocations.insert(AllocatedImage {
+        allocator,
+        buffers,
+        size,
+    });
+    let images = lock(&allocations.images);
+    let buffers = &images[&token].buffers;
+    for channel in CHANNELS {
+        if let Some(buffer) = buffers.get(channel).filter(|_| spec.has_plane(channel)) {
+            let stride = buffer.stride / spec.bytes_per_pixel(channel);
+            unsafe {
+                de265_set_image_plane(
+                    img,
+                    channel.index(),
+                    buffer.data.as_ptr() as _,
+                    stride as _,
+                    token as _,
+                )
+            };
+        }
+    }
+    1
+}
+
+unsafe extern "C" fn release_buffer(
+    ctx: *mut de265_decoder_context,
+    img: *mut de265_image,
+    userdata: *mut c_void,
+) {
+    let mut allocated = None;
+    if let Some(allocations) = unsafe { (userdata as *const Allocations).as_ref() } {
+        // Payload of the image that was never output is not required anymore.
+        let token = unsafe { de265_get_image_user_data(img) } as usize;
+        allocations.payloads.remove(token);
+        let token = unsafe { de265_get_image_plane_user_data(img, 0) } as usize;
+        allocated = lock(&allocations.images).remove(&token);
+    }
+    match allocated {
+        Some(image) => release_to(&image.allocator, image.buffers),
+        None => {
+            let default = unsafe { &*de265_get_default_image_allocation_functions() };
+            if let Some(release_buffer) = default.release_buffer {
+                unsafe { release_buffer(ctx, img, ptr::null_mut()) };
+            }
+        }
+    }
+}
+
+fn release_to(allocator: &SharedAllocator, buffers: PlaneBuffers) {
+    // Panics must not unwind into C code. Buffers are leaked in this case.
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+        if let Some(mut allocator) = try_lock(allocator) {
+            allocator.release(buffers);
+        }
+    }));
+}
+
+/// Lock the allocator if it isn't already locked (e.g. by a reentrant call).
+///
+/// A lock poisoned by panic of the allocator is ignored.
+fn try_lock(allocator: &SharedAllocator) -> Option<MutexGuard<'_, Box<dyn ImageAllocator>>> {
+    match allocator.try_lock() {
+        Ok(guard) => Some(guard),
+        Err(TryLockError::Poisoned(err)) => Some(err.into_inner()),
+        Err(TryLockError::WouldBlock) => None,
+    }
+}
+
+impl DecoderInput {
+    /// Set an allocator of memory for planes of images decoded after this call.
+    ///
+    /// Images that were allocated before this call are released by
+    /// the allocator that allocated them. The allocator is kept alive
+    /// while the decoder is alive or there are images allocated by it.
+    pub fn set_image_allocator(&mut self, allocator: Box<dyn ImageAllocator>) {
+        let allocator = Arc::new(Mutex::new(allocator));
+        *lock(&self.context.allocations.allocator) = Some(allocator);
+    }
+}
+
+impl Decoder {
+    /// See [`DecoderInput::set_image_allocator()`].
+    pub fn set_image_allocator(&mut self, allocator: Box<dyn ImageAllocator>) {
+        self.input_mut().set_image_allocator(allocator);
+    }
+}
@@ -0,0 +1,54 @@
+use ndarray::{Array3, ArrayView2};
+
+use crate::{Channel, Image, OwnedImage, PlaneViewError, RgbImageBuffer};
+
+impl Image<'_> {
+    /// Returns a two-dimensional array (rows x columns) of samples
+    /// of the plane with 8 or less bits per pixel without copying.
+    ///
+    /// See [`PlaneView::as_array()`](crate::PlaneView::as_array).
+    pub fn plane_array(&self, channel: Channel) -> Result<ArrayView2<'_, u8>, PlaneViewError> {
+        self.plane_view(channel).as_array()
+    }
+
+    /// Returns a two-dimensional array (rows x columns) of samples
+    /// of the plane with more than 8 bits per pixel without copying.
+    ///
+    /// Returns [`PlaneViewError::Misaligned`] if the plane data
+    /// is not aligned to 2 bytes (e.g. if it is provided by a custom
+    /// [`ImageAllocator`](crate::ImageAllocator)).
+    pub fn plane_array_u16(&self, channel: Channel) -> Result<ArrayView2<'_, u16>, PlaneViewError> {
+        self.plane_view(channel).as_array_u16()
+    }
+
+    /// Convert the picture into an array with the shape
+    /// `(height, width, 3)` of R, G and B components.
+    ///
+    /// See [`Image::to_rgb8()`] for details of the conversion.
+    pub fn to_array3(&self) -> Array3<u8> {
+        rgb_to_array3(self.to_rgb8())
+    }
+}
+
+impl OwnedImage {
+    /// See [`Image::plane_array()`].
+    pub fn plane_array(&self, channel: Channel) -> Result<ArrayView2<'_, u8>, PlaneViewError> {
+        self.plane_view(channel).as_array()
+    }
+
+    /// See [`Image::plane_array_u16()`].
+    pub fn plane_array_u16(&self, channel: Channel) -> Result<ArrayView2<'_, u16>, PlaneViewError> {
+        self.plane_view(channel).as_array_u16()
+    }
+
+    /// See [`Image::to_array3()`].
+    pub fn to_array3(&self) -> Array3<u8> {
+        rgb_to_array3(self.to_rgb8())
+    }
+}
+
+fn rgb_to_array3(rgb: RgbImageBuffer) -> Array3<u8> {
+    let shape = (rgb.height as usize, rgb.width as usize, 3);
+    Array3::from_shape_vec(shape, rgb.data)
+        .expect("size of RGB data always matches dimensions of the picture")
+}
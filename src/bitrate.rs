@@ -0,0 +1,76 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::TimeBase;
+
+/// Default duration of the window of [`BitrateMeter`].
+pub(crate) const DEFAULT_BITRATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Estimator of bitrate of pushed data over a rolling window of PTS values.
+///
+/// PTS values may come in decoding order (not monotonic), so bytes
+/// are grouped by PTS, and the window ends at the greatest PTS.
+pub(crate) struct BitrateMeter {
+    window: Duration,
+    bytes_per_pts: BTreeMap<i64, u64>,
+}
+
+impl BitrateMeter {
+    pub fn new() -> Self {
+        Self {
+            window: DEFAULT_BITRATE_WINDOW,
+            bytes_per_pts: BTreeMap::new(),
+        }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    pub fn set_window(&mut self, window: Duration, timebase: TimeBase) {
+        self.window = window;
+        self.evict(timebase);
+    }
+
+    pub fn push(&mut self, pts: i64, bytes: u64, timebase: TimeBase) {
+        *self.bytes_per_pts.entry(pts).or_default() += bytes;
+        self.evict(timebase);
+    }
+
+    pub fn clear(&mut self) {
+        self.bytes_per_pts.clear();
+    }
+
+    /// Returns estimated bitrate in bits per second or `None` if
+    /// the window contains less than two different PTS values.
+    pub fn bitrate(&self, timebase: TimeBase) -> Option<u64> {
+        let count = self.bytes_per_pts.len() as u128;
+        let (&first, _) = self.bytes_per_pts.first_key_value()?;
+        let (&last, _) = self.bytes_per_pts.last_key_value()?;
+        let span = timebase.to_duration(last.checked_sub(first)?)?.as_nanos();
+        if count < 2 || span == 0 {
+            return None;
+        }
+        // `count` pictures cover `count - 1` intervals between
+        // the first and last PTS, plus the duration of the last picture.
+        let duration = span * count / (count - 1);
+        let bits = self.bytes_per_pts.values().sum::<u64>() as u128 * 8;
+        u64::try_from(bits * 1_000_000_000 / duration).ok()
+    }
+
+    /// Remove PTS values that are out of the window.
+    fn evict(&mut self, timebase: TimeBase) {
+        let Some((&last, _)) = self.bytes_per_pts.last_key_value() else {
+            return;
+        };
+        while let Some((&first, _)) = self.bytes_per_pts.first_key_value() {
+            let age = last
+                .checked_sub(first)
+                .and_then(|span| timebase.to_duration(span));
+            if age.is_some_and(|age| age <= self.window) {
+                break;
+            }
+            self.bytes_per_pts.pop_first();
+        }
+    }
+}
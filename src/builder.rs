@@ -0,0 +1,117 @@
+use crate::{
+    Acceleration, DeError, DecodePreset, Decoder, DecoderInput, DecoderOutput, ParamBool, ParamI32,
+    Result, new_decoder,
+};
+
+/// Builder to configure a decoder before its first use.
+///
+/// ```
+/// use libde265_rs::{Acceleration, DecoderBuilder};
+///
+/// let (input, output) = DecoderBuilder::new()
+///     .worker_threads(2)
+///     .acceleration(Acceleration::Auto)
+///     .suppress_faulty_pictures(true)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DecoderBuilder {
+    worker_threads: Option<u32>,
+    acceleration: Option<Acceleration>,
+    params_bool: Vec<(ParamBool, bool)>,
+    params_i32: Vec<(ParamI32, i32)>,
+    preset: Option<DecodePreset>,
+}
+
+impl DecoderBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the number of background decoding threads.
+    ///
+    /// By default, all decoding is done in the main thread (no multi-threading).
+    pub fn worker_threads(mut self, num_threads: u32) -> Self {
+        self.worker_threads = Some(num_threads);
+        self
+    }
+
+    /// Set acceleration method, default: [`Acceleration::Auto`]
+    pub fn acceleration(mut self, val: Acceleration) -> Self {
+        self.acceleration = Some(val);
+        self
+    }
+
+    /// Set a bool decoding parameter.
+    pub fn parameter_bool(mut self, param: ParamBool, val: bool) -> Self {
+        self.params_bool.push((param, val));
+        self
+    }
+
+    /// Set an integer decoding parameter.
+    pub fn parameter_i32(mut self, param: ParamI32, val: i32) -> Self {
+        self.params_i32.push((param, val));
+        self
+    }
+
+    /// Perform SEI hash check on decoded pictures.
+    pub fn sei_check_hash(self, val: bool) -> Self {
+        self.parameter_bool(ParamBool::SeiCheckHash, val)
+    }
+
+    /// Do not output frames with decoding errors, default: `false` (output all images)
+    pub fn suppress_faulty_pictures(self, val: bool) -> Self {
+        self.parameter_bool(ParamBool::SuppressFaultyPictures, val)
+    }
+
+    /// Disable deblocking
+    pub fn disable_deblocking(self, val: bool) -> Self {
+        self.parameter_bool(ParamBool::DisableDeblocking, val)
+    }
+
+    /// Disable SAO filter
+    pub fn disable_sao(self, val: bool) -> Self {
+        self.parameter_bool(ParamBool::DisableSAO, val)
+    }
+
+    /// Set parameters of the given preset,
+    /// see [`DecoderInput::apply_preset()`].
+    pub fn preset(mut self, preset: DecodePreset) -> Self {
+        self.preset = Some(preset);
+        self
+    }
+
+    /// Create a new decoder with the specified configuration
+    /// and return its input and output halves.
+    pub fn build(&self) -> Result<(DecoderInput, DecoderOutput)> {
+        if self.worker_threads == Some(0) {
+            return Err(DeError::ErrorInvalidNumberOfWorkerThreads);
+        }
+
+        let (mut input, output) = new_decoder()?;
+        // Individual parameters take precedence over the preset.
+        if let Some(preset) = self.preset {
+            input.apply_preset(preset);
+        }
+        for &(param, val) in &self.params_bool {
+            input.set_parameter_bool(param, val);
+        }
+        for &(param, val) in &self.params_i32 {
+            input.set_parameter_i32(param, val);
+        }
+        if let Some(acceleration) = self.acceleration {
+            input.set_acceleration(acceleration);
+        }
+        if let Some(num_threads) = self.worker_threads {
+            input.start_worker_threads(num_threads)?;
+        }
+        Ok((input, output))
+    }
+
+    /// Create a new combined decoder with the specified configuration.
+    pub fn build_decoder(&self) -> Result<Decoder> {
+        let (input, output) = self.build()?;
+        Ok(Decoder::from_parts(input, output))
+    }
+}
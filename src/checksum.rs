@@ -0,0 +1,112 @@
+use crate::picture_hash::Md5;
+use crate::{Channel, Image, OwnedImage, PlaneView, Planes};
+
+/// Table of the CRC-32 (IEEE 802.3) algorithm for reflected polynomial.
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb88320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    data.iter().fold(crc, |crc, &byte| {
+        CRC32_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Pass bytes of samples of the plane row by row into the given function.
+/// Padding at the end of rows is skipped and 16-bit samples are passed
+/// in little-endian byte order.
+fn for_each_le_row(view: PlaneView<'_>, mut f: impl FnMut(&[u8])) {
+    if view.bytes_per_sample() == 1 || cfg!(target_endian = "little") {
+        view.rows().for_each(f);
+        return;
+    }
+    let mut buffer = Vec::with_capacity(view.width() as usize * 2);
+    for row in view.rows() {
+        buffer.clear();
+        buffer.extend(
+            row.chunks_exact(2)
+                .flat_map(|bytes| u16::from_ne_bytes([bytes[0], bytes[1]]).to_le_bytes()),
+        );
+        f(&buffer);
+    }
+}
+
+fn plane_crc32(planes: Planes<'_>, channel: Channel) -> Option<u32> {
+    let mut crc = 0xffffffff;
+    for_each_le_row(planes.get(channel)?, |row| crc = crc32_update(crc, row));
+    Some(!crc)
+}
+
+fn plane_md5(planes: Planes<'_>, channel: Channel) -> Option<[u8; 16]> {
+    let mut md5 = Md5::new();
+    for_each_le_row(planes.get(channel)?, |row| md5.update(row));
+    Some(md5.finish())
+}
+
+fn frame_md5(planes: Planes<'_>) -> [u8; 16] {
+    let mut md5 = Md5::new();
+    for view in [Some(planes.y), planes.cb, planes.cr].into_iter().flatten() {
+        for_each_le_row(view, |row| md5.update(row));
+    }
+    md5.finish()
+}
+
+impl Image<'_> {
+    /// Compute CRC-32 (IEEE 802.3) of samples of the plane.
+    ///
+    /// Only `width × height` samples of the plane are hashed, without
+    /// padding at the end of rows. Samples with more than 8 bits per pixel
+    /// are hashed as 16-bit words in little-endian byte order.
+    ///
+    /// Returns `None` if the image has no such plane.
+    pub fn plane_crc32(&self, channel: Channel) -> Option<u32> {
+        plane_crc32(self.planes(), channel)
+    }
+
+    /// Compute MD5 of samples of the plane like [`Image::plane_crc32()`].
+    ///
+    /// Unlike [`Image::compute_plane_md5()`], only samples inside
+    /// the conformance window are hashed.
+    pub fn plane_md5(&self, channel: Channel) -> Option<[u8; 16]> {
+        plane_md5(self.planes(), channel)
+    }
+
+    /// Compute MD5 of samples of all planes of the picture:
+    /// Y, then Cb and Cr (if any), see [`Image::plane_md5()`].
+    pub fn frame_md5(&self) -> [u8; 16] {
+        frame_md5(self.planes())
+    }
+}
+
+impl OwnedImage {
+    /// See [`Image::plane_crc32()`].
+    pub fn plane_crc32(&self, channel: Channel) -> Option<u32> {
+        plane_crc32(self.planes(), channel)
+    }
+
+    /// See [`Image::plane_md5()`].
+    pub fn plane_md5(&self, channel: Channel) -> Option<[u8; 16]> {
+        plane_md5(self.planes(), channel)
+    }
+
+    /// See [`Image::frame_md5()`].
+    pub fn frame_md5(&self) -> [u8; 16] {
+        frame_md5(self.planes())
+    }
+}
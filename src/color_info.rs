@@ -0,0 +1,73 @@
+use crate::{
+    Channel, ChromaFormat, ColorPrimaries, Image, MatrixCoefficients, OwnedImage,
+    TransferCharacteristics,
+};
+
+/// Colour metadata of a picture required to describe its colour space.
+///
+/// Values of consecutive pictures may be compared to detect
+/// changes of the colour space of a stream.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct ColorInfo {
+    /// Samples use the full range instead of the limited one.
+    pub full_range: bool,
+    pub primaries: ColorPrimaries,
+    /// Transfer characteristics signalled in VUI.
+    pub transfer: TransferCharacteristics,
+    /// Transfer characteristics from the alternative transfer
+    /// characteristics SEI message
+    /// (see [`Image::alternative_transfer_characteristics()`]).
+    pub alternative_transfer: Option<TransferCharacteristics>,
+    pub matrix: MatrixCoefficients,
+    pub chroma_format: ChromaFormat,
+    pub bit_depth_luma: u32,
+    pub bit_depth_chroma: u32,
+}
+
+impl ColorInfo {
+    /// Returns the transfer characteristics that should be used to
+    /// display the picture: the alternative one if it is present,
+    /// otherwise the one signalled in VUI.
+    pub fn preferred_transfer(&self) -> TransferCharacteristics {
+        self.alternative_transfer.unwrap_or(self.transfer)
+    }
+
+    /// Returns `true` if the preferred transfer function is a function of
+    /// high dynamic range video (see [`TransferCharacteristics::is_hdr()`]).
+    pub fn is_hdr(&self) -> bool {
+        self.preferred_transfer().is_hdr()
+    }
+}
+
+impl Image<'_> {
+    /// Returns colour metadata of the picture.
+    pub fn color_info(&self) -> ColorInfo {
+        ColorInfo {
+            full_range: self.full_range(),
+            primaries: self.color_primaries_enum(),
+            transfer: self.transfer_characteristics_enum(),
+            alternative_transfer: self.alternative_transfer_characteristics(),
+            matrix: self.matrix_coefficients_enum(),
+            chroma_format: self.chroma_format(),
+            bit_depth_luma: self.bits_per_pixel(Channel::Y),
+            bit_depth_chroma: self.bits_per_pixel(Channel::Cb),
+        }
+    }
+}
+
+impl OwnedImage {
+    /// See [`Image::color_info()`].
+    pub fn color_info(&self) -> ColorInfo {
+        ColorInfo {
+            full_range: self.full_range(),
+            primaries: self.color_primaries_enum(),
+            transfer: self.transfer_characteristics_enum(),
+            alternative_transfer: self.alternative_transfer_characteristics(),
+            matrix: self.matrix_coefficients_enum(),
+            chroma_format: self.chroma_format(),
+            bit_depth_luma: self.bits_per_pixel(Channel::Y),
+            bit_depth_chroma: self.bits_per_pixel(Channel::Cb),
+        }
+    }
+}
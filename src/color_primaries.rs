@@ -0,0 +1,123 @@
+use std::fmt;
+
+use crate::{Image, OwnedImage};
+
+/// Colour primaries of a picture (`colour_primaries` of VUI),
+/// table 2 of ITU-T H.273.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ColorPrimaries {
+    /// Rec. ITU-R BT.709 (code 1), also sRGB.
+    Bt709,
+    /// Rec. ITU-R BT.470 System M (code 4).
+    Bt470M,
+    /// Rec. ITU-R BT.470 System B, G (code 5), also BT.601 625 lines.
+    Bt470Bg,
+    /// SMPTE 170M (code 6), also BT.601 525 lines.
+    Smpte170M,
+    /// SMPTE 240M (code 7).
+    Smpte240M,
+    /// Generic film with colour filters using Illuminant C (code 8).
+    Film,
+    /// Rec. ITU-R BT.2020 and BT.2100 (code 9).
+    Bt2020,
+    /// SMPTE ST 428-1, CIE 1931 XYZ (code 10).
+    SmpteSt428,
+    /// SMPTE RP 431-2, DCI-P3 (code 11).
+    SmpteRp431,
+    /// SMPTE EG 432-1, Display P3 (code 12).
+    SmpteEg432,
+    /// EBU Tech. 3213-E (code 22).
+    Ebu3213,
+    /// Unspecified (code 2), the primaries are determined by the application.
+    Unspecified,
+    /// Code reserved for future use by H.273.
+    Reserved(u8),
+}
+
+impl ColorPrimaries {
+    /// Returns the primaries by their H.273 code.
+    pub const fn from_code(code: u8) -> Self {
+        match code {
+            1 => Self::Bt709,
+            2 => Self::Unspecified,
+            4 => Self::Bt470M,
+            5 => Self::Bt470Bg,
+            6 => Self::Smpte170M,
+            7 => Self::Smpte240M,
+            8 => Self::Film,
+            9 => Self::Bt2020,
+            10 => Self::SmpteSt428,
+            11 => Self::SmpteRp431,
+            12 => Self::SmpteEg432,
+            22 => Self::Ebu3213,
+            _ => Self::Reserved(code),
+        }
+    }
+
+    /// Returns the H.273 code of the primaries.
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::Bt709 => 1,
+            Self::Unspecified => 2,
+            Self::Bt470M => 4,
+            Self::Bt470Bg => 5,
+            Self::Smpte170M => 6,
+            Self::Smpte240M => 7,
+            Self::Film => 8,
+            Self::Bt2020 => 9,
+            Self::SmpteSt428 => 10,
+            Self::SmpteRp431 => 11,
+            Self::SmpteEg432 => 12,
+            Self::Ebu3213 => 22,
+            Self::Reserved(code) => code,
+        }
+    }
+}
+
+impl From<u8> for ColorPrimaries {
+    fn from(code: u8) -> Self {
+        Self::from_code(code)
+    }
+}
+
+impl From<ColorPrimaries> for u8 {
+    fn from(value: ColorPrimaries) -> Self {
+        value.code()
+    }
+}
+
+impl fmt::Display for ColorPrimaries {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Bt709 => "BT.709",
+            Self::Bt470M => "BT.470 M",
+            Self::Bt470Bg => "BT.470 BG",
+            Self::Smpte170M => "SMPTE 170M",
+            Self::Smpte240M => "SMPTE 240M",
+            Self::Film => "Film",
+            Self::Bt2020 => "BT.2020",
+            Self::SmpteSt428 => "SMPTE ST 428-1",
+            Self::SmpteRp431 => "SMPTE RP 431-2",
+            Self::SmpteEg432 => "SMPTE EG 432-1",
+            Self::Ebu3213 => "EBU Tech. 3213-E",
+            Self::Unspecified => "Unspecified",
+            Self::Reserved(code) => return write!(f, "Reserved ({code})"),
+        };
+        f.write_str(name)
+    }
+}
+
+impl Image<'_> {
+    /// Returns the colour primaries of the picture,
+    /// see [`Image::colour_primaries()`] for the raw code.
+    pub fn color_primaries_enum(&self) -> ColorPrimaries {
+        ColorPrimaries::from_code(self.colour_primaries())
+    }
+}
+
+impl OwnedImage {
+    /// See [`Image::color_primaries_enum()`].
+    pub fn color_primaries_enum(&self) -> ColorPrimaries {
+        ColorPrimaries::from_code(self.colour_primaries())
+    }
+}
@@ -0,0 +1,141 @@
+//! Comparison of samples of decoded pictures.
+use crate::{Channel, ChromaFormat, CompareError, Image, OwnedImage, PlaneView};
+
+/// Decoded picture that may be compared,
+/// it is implemented for [`Image`] and [`OwnedImage`].
+pub trait Frame {
+    fn chroma_format(&self) -> ChromaFormat;
+    fn bits_per_pixel(&self, channel: Channel) -> u32;
+    fn plane_view(&self, channel: Channel) -> PlaneView<'_>;
+}
+
+impl Frame for Image<'_> {
+    fn chroma_format(&self) -> ChromaFormat {
+        Image::chroma_format(self)
+    }
+
+    fn bits_per_pixel(&self, channel: Channel) -> u32 {
+        Image::bits_per_pixel(self, channel)
+    }
+
+    fn plane_view(&self, channel: Channel) -> PlaneView<'_> {
+        Image::plane_view(self, channel)
+    }
+}
+
+impl Frame for OwnedImage {
+    fn chroma_format(&self) -> ChromaFormat {
+        OwnedImage::chroma_format(self)
+    }
+
+    fn bits_per_pixel(&self, channel: Channel) -> u32 {
+        OwnedImage::bits_per_pixel(self, channel)
+    }
+
+    fn plane_view(&self, channel: Channel) -> PlaneView<'_> {
+        OwnedImage::plane_view(self, channel)
+    }
+}
+
+/// Peak signal-to-noise ratio of planes in decibels.
+///
+/// It is `f64::INFINITY` for identical planes
+/// and for missing chroma planes of monochrome pictures.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PlanePsnr {
+    pub y: f64,
+    pub cb: f64,
+    pub cr: f64,
+}
+
+/// Returns PSNR of planes of the second picture relative to the first one.
+///
+/// Returns an error if pictures have different chroma formats,
+/// sizes or bit depths of planes.
+pub fn psnr(a: &impl Frame, b: &impl Frame) -> Result<PlanePsnr, CompareError> {
+    let mut values = [f64::INFINITY; 3];
+    for (channel, value) in planes(a, b)? {
+        let (sum, count) = fold_diffs(value, (0u64, 0u64), |(sum, count), diff| {
+            let diff = diff as u64;
+            (sum + diff * diff, count + 1)
+        });
+        if sum > 0 {
+            let peak = ((1u64 << a.bits_per_pixel(channel)) - 1) as f64;
+            let mse = sum as f64 / count as f64;
+            values[channel.index() as usize] = 10. * (peak * peak / mse).log10();
+        }
+    }
+    let [y, cb, cr] = values;
+    Ok(PlanePsnr { y, cb, cr })
+}
+
+/// Returns the maximum absolute difference between samples
+/// of all planes of pictures.
+///
+/// Returns an error if pictures have different chroma formats,
+/// sizes or bit depths of planes.
+pub fn max_abs_diff(a: &impl Frame, b: &impl Frame) -> Result<u16, CompareError> {
+    let max = planes(a, b)?
+        .into_iter()
+        .map(|(_, views)| fold_diffs(views, 0, u16::max))
+        .max();
+    Ok(max.unwrap_or_default())
+}
+
+/// Returns pairs of views of planes of pictures after checking
+/// that pictures have the same layout.
+fn planes<'a>(
+    a: &'a impl Frame,
+    b: &'a impl Frame,
+) -> Result<Vec<(Channel, [PlaneView<'a>; 2])>, CompareError> {
+    let chroma_format = a.chroma_format();
+    if chroma_format != b.chroma_format() {
+        return Err(CompareError::ChromaFormatMismatch(
+            chroma_format,
+            b.chroma_format(),
+        ));
+    }
+    let mut planes = Vec::with_capacity(chroma_format.plane_count());
+    for &channel in chroma_format.channels() {
+        let bits = (a.bits_per_pixel(channel), b.bits_per_pixel(channel));
+        if bits.0 != bits.1 {
+            return Err(CompareError::BitDepthMismatch {
+                channel,
+                first: bits.0,
+                second: bits.1,
+            });
+        }
+        let views = [a.plane_view(channel), b.plane_view(channel)];
+        let [first, second] = views.map(|view| (view.width(), view.height()));
+        if first != second {
+            return Err(CompareError::SizeMismatch {
+                channel,
+                first,
+                second,
+            });
+        }
+        planes.push((channel, views));
+    }
+    Ok(planes)
+}
+
+/// Folds absolute differences between samples of two planes.
+fn fold_diffs<T>(views: [PlaneView<'_>; 2], init: T, mut f: impl FnMut(T, u16) -> T) -> T {
+    let [a, b] = views;
+    let bytes_per_sample = a.bytes_per_sample();
+    let mut acc = init;
+    for (row_a, row_b) in a.rows().zip(b.rows()) {
+        if bytes_per_sample == 1 {
+            for (&x, &y) in row_a.iter().zip(row_b) {
+                acc = f(acc, x.abs_diff(y) as u16);
+            }
+        } else {
+            for (x, y) in row_a.chunks_exact(2).zip(row_b.chunks_exact(2)) {
+                let x = u16::from_ne_bytes([x[0], x[1]]);
+                let y = u16::from_ne_bytes([y[0], y[1]]);
+                acc = f(acc, x.abs_diff(y));
+            }
+        }
+    }
+    acc
+}
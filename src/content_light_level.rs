@@ -0,0 +1,84 @@
+use crate::{Image, OwnedImage};
+
+/// Content light level of the video (CTA-861.3), it is carried
+/// by the content light level information SEI message of HDR10 streams.
+///
+/// Zero values of the message mean that the level is unknown,
+/// they are represented by `None`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ContentLightLevel {
+    /// Maximum content light level (MaxCLL) in cd/m².
+    pub max_cll: Option<u16>,
+    /// Maximum frame-average light level (MaxFALL) in cd/m².
+    pub max_fall: Option<u16>,
+}
+
+impl ContentLightLevel {
+    /// Parse the payload of the content light level information SEI message.
+    pub(crate) fn parse(payload: &[u8]) -> Option<Self> {
+        let [cll_hi, cll_lo, fall_hi, fall_lo, ..] = *payload else {
+            return None;
+        };
+        let level = |bytes| Some(u16::from_be_bytes(bytes)).filter(|&v| v != 0);
+        Some(Self {
+            max_cll: level([cll_hi, cll_lo]),
+            max_fall: level([fall_hi, fall_lo]),
+        })
+    }
+}
+
+impl Image<'_> {
+    /// Returns the content light level of the picture.
+    ///
+    /// It is parsed from prefix SEI NAL units pushed by
+    /// [`DecoderInput::push_nal()`](crate::DecoderInput::push_nal) like
+    /// [`Image::mastering_display()`] and follows the same rules: the value
+    /// applies to the following pictures until it is replaced or a new coded
+    /// video sequence starts without it.
+    pub fn content_light_level(&self) -> Option<ContentLightLevel> {
+        self.sei.content_light_level
+    }
+}
+
+impl OwnedImage {
+    /// See [`Image::content_light_level()`].
+    pub fn content_light_level(&self) -> Option<ContentLightLevel> {
+        self.sei.content_light_level
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nal::rbsp_data;
+    use crate::sei::sei_messages;
+
+    use super::*;
+
+    #[test]
+    fn parse_sei_payload() {
+        // SEI RBSP with content light level information message:
+        // MaxCLL is unknown, MaxFALL is 400 (0x0190). Zero bytes of MaxCLL
+        // followed by 0x01 require the emulation prevention byte.
+        let nal = [0x4e, 0x01, 144, 4, 0, 0, 3, 1, 0x90, 0x80];
+        let rbsp = rbsp_data(&nal[2..]);
+        assert_eq!(rbsp, [144, 4, 0, 0, 1, 0x90, 0x80]);
+        let (payload_type, payload) = sei_messages(&rbsp).next().unwrap();
+        assert_eq!(payload_type, 144);
+        assert_eq!(
+            ContentLightLevel::parse(payload),
+            Some(ContentLightLevel {
+                max_cll: None,
+                max_fall: Some(400),
+            })
+        );
+
+        assert_eq!(
+            ContentLightLevel::parse(&[0x03, 0xe8, 0x01, 0x90]),
+            Some(ContentLightLevel {
+                max_cll: Some(1000),
+                max_fall: Some(400),
+            })
+        );
+        assert_eq!(ContentLightLevel::parse(&[0x03, 0xe8, 0x01]), None);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::decoder::lock;
+use crate::stream_info::CodedGeometry;
+use crate::{Channel, ChromaFormat, Image, OwnedImage, PlaneView};
+
+/// Conformance window of a picture: numbers of luma samples of the coded
+/// picture outside the displayed area on every side.
+///
+/// HEVC codes pictures with dimensions that are multiples of the minimal
+/// coding block size, the conformance window crops them to the size
+/// of the displayed picture.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct CropWindow {
+    pub left: u32,
+    pub right: u32,
+    pub top: u32,
+    pub bottom: u32,
+}
+
+impl CropWindow {
+    /// Returns the window in samples of the given plane, offsets for chroma
+    /// planes are divided by the subsampling factors of the chroma format.
+    pub fn for_plane(&self, chroma_format: ChromaFormat, channel: Channel) -> Self {
+        let (sub_width, sub_height) = match channel {
+            Channel::Y => (1, 1),
+            _ if !chroma_format.has_chroma() => (1, 1),
+            _ => chroma_format.subsampling(),
+        };
+        let (sub_width, sub_height) = (sub_width as u32, sub_height as u32);
+        Self {
+            left: self.left / sub_width,
+            right: self.right / sub_width,
+            top: self.top / sub_height,
+            bottom: self.bottom / sub_height,
+        }
+    }
+}
+
+impl Image<'_> {
+    /// Returns the conformance window of the picture.
+    ///
+    /// The window is taken from SPS pushed into the decoder.
+    /// Returns `None` if there is no SPS with the size of the image.
+    pub fn crop_window(&self) -> Option<CropWindow> {
+        let (width, height) = self.display_size();
+        let geometry = self.coded_geometry()?;
+        Some(CropWindow {
+            left: geometry.crop_left,
+            right: geometry.width - width - geometry.crop_left,
+            top: geometry.crop_top,
+            bottom: geometry.height - height - geometry.crop_top,
+        })
+    }
+
+    /// Size of the luma plane inside the conformance window.
+    ///
+    /// The decoder already crops decoded pictures, so it is equal
+    /// to the width and height of the luma plane.
+    pub fn display_size(&self) -> (u32, u32) {
+        (self.width(Channel::Y), self.height(Channel::Y))
+    }
+
+    /// Returns the sample aspect ratio (the ratio of the width
+    /// to the height of one sample) as `(width, height)`.
+    ///
+    /// The ratio is taken from VUI of SPS pushed into the decoder,
+    /// both predefined values of `aspect_ratio_idc` and explicitly
+    /// coded ratios are supported. Returns `None` if the ratio
+    /// is unspecified or there is no SPS with the size of the image.
+    pub fn sample_aspect_ratio(&self) -> Option<(u32, u32)> {
+        self.coded_geometry()?.sample_aspect_ratio
+    }
+
+    /// Returns the aspect ratio of the displayed picture as `(width, height)`
+    /// reduced to lowest terms. It is computed from
+    /// [`Image::display_size()`] and [`Image::sample_aspect_ratio()`].
+    ///
+    /// Returns `None` if the sample aspect ratio is unknown.
+    pub fn display_aspect_ratio(&self) -> Option<(u32, u32)> {
+        display_aspect_ratio(self.display_size(), self.sample_aspect_ratio()?)
+    }
+
+    /// Returns a view of the plane inside the conformance window.
+    ///
+    /// Planes returned by the decoder start at the conformance window
+    /// and have the display size, so it is the same view as
+    /// [`Image::plane_view()`]. Bytes between the end of the window
+    /// and the next row may contain samples outside the window.
+    pub fn cropped_plane(&self, channel: Channel) -> PlaneView<'_> {
+        self.plane_view(channel)
+    }
+
+    pub(crate) fn coded_geometry(&self) -> Option<CodedGeometry> {
+        let (width, height) = self.display_size();
+        let size = (width, height, self.chroma_format());
+        lock(&self.decoder.picture_hashes).geometry(size)
+    }
+}
+
+impl OwnedImage {
+    /// See [`Image::crop_window()`].
+    pub fn crop_window(&self) -> Option<CropWindow> {
+        self.crop_window
+    }
+
+    /// See [`Image::display_size()`].
+    pub fn display_size(&self) -> (u32, u32) {
+        (self.width(Channel::Y), self.height(Channel::Y))
+    }
+
+    /// See [`Image::sample_aspect_ratio()`].
+    pub fn sample_aspect_ratio(&self) -> Option<(u32, u32)> {
+        self.sample_aspect_ratio
+    }
+
+    /// See [`Image::display_aspect_ratio()`].
+    pub fn display_aspect_ratio(&self) -> Option<(u32, u32)> {
+        display_aspect_ratio(self.display_size(), self.sample_aspect_ratio?)
+    }
+
+    /// See [`Image::cropped_plane()`].
+    pub fn cropped_plane(&self, channel: Channel) -> PlaneView<'_> {
+        self.plane_view(channel)
+    }
+}
+
+fn display_aspect_ratio(
+    (width, height): (u32, u32),
+    (sar_width, sar_height): (u32, u32),
+) -> Option<(u32, u32)> {
+    let width = width as u64 * sar_width as u64;
+    let height = height as u64 * sar_height as u64;
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let divisor = gcd(width, height);
+    Some((
+        u32::try_from(width / divisor).ok()?,
+        u32::try_from(height / divisor).ok()?,
+    ))
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aspect_ratio_of_display() {
+        assert_eq!(display_aspect_ratio((316, 240), (1, 1)), Some((79, 60)));
+        assert_eq!(display_aspect_ratio((720, 576), (64, 45)), Some((16, 9)));
+        assert_eq!(display_aspect_ratio((720, 480), (10, 11)), Some((15, 11)));
+        assert_eq!(display_aspect_ratio((0, 480), (1, 1)), None);
+        assert_eq!(display_aspect_ratio((u32::MAX, 1), (u32::MAX, 1)), None);
+    }
+}
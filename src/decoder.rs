@@ -1,9 +1,38 @@
-use std::ptr::NonNull;
-use std::rc::Rc;
+use std::any::Any;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::io::{self, Read};
+use std::ops::ControlFlow;
+use std::os::raw::c_int;
+use std::ptr::{self, NonNull};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
 
 use libde265_sys::*;
 
-use crate::{DeError, Image, Result};
+use crate::allocator::{Allocations, IMAGE_ALLOCATION};
+use crate::bitrate::BitrateMeter;
+use crate::input_format::InputFormatChecker;
+use crate::latency::LatencyWindow;
+use crate::nal::{nal_temporal_id, nal_unit_type};
+use crate::picture_hash::PictureHashes;
+use crate::poc::PocTracker;
+use crate::realtime::{DEFAULT_REALTIME_FLOOR, RealtimeController};
+use crate::sei::SeiTracker;
+use crate::stream_info::{CodedGeometry, SpsScanner};
+use crate::thread_budget::ThreadGrant;
+use crate::user_data::{Payloads, is_first_slice_segment};
+use crate::{
+    Channel, ChromaFormat, DeError, Image, LatencyPercentiles, MemoryUsage, NalSource, NalUnitType,
+    ParseAccelerationError, PushNalsError, Result, StreamError, StreamInfo, TimeBase,
+};
+
+pub(crate) const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Maximum number of background decoding threads supported by `libde265`.
+pub const MAX_WORKER_THREADS: u32 = 32;
 
 /// Create a new decoder.
 pub fn new_decoder() -> Result<(DecoderInput, DecoderOutput)> {
@@ -11,14 +40,62 @@ pub fn new_decoder() -> Result<(DecoderInput, DecoderOutput)> {
     if decoder_context_ptr.is_null() {
         return Err(DeError::ErrorLibraryInitializationFailed);
     }
-    let context = Rc::new(DecoderContext {
+    let payloads = Arc::new(Payloads::default());
+    let context = Arc::new(DecoderContext {
         inner: decoder_context_ptr,
+        lock: Mutex::new(()),
+        checked_out: Mutex::new(CheckedOut::default()),
+        payloads: payloads.clone(),
+        allocations: Arc::new(Allocations::new(payloads)),
+        worker_threads: OnceLock::new(),
+        picture_ready: Condvar::new(),
+        end_of_stream: AtomicBool::new(false),
+        timebase: AtomicU64::new(TimeBase::MICROSECONDS.to_bits()),
+        picture_hashes: Mutex::new(PictureHashes::default()),
+        issues: Mutex::new(Vec::new()),
+        latencies: Mutex::new(LatencyWindow::default()),
+        output_order: Mutex::new(OutputOrder::default()),
     });
+    unsafe {
+        de265_set_image_allocation_functions(
+            decoder_context_ptr,
+            &IMAGE_ALLOCATION as *const _ as *mut _,
+            Arc::as_ptr(&context.allocations) as *mut _,
+        )
+    };
     Ok((
         DecoderInput {
             context: context.clone(),
+            params_i32: [-1; 4],
+            acceleration: Acceleration::Auto,
+            num_worker_threads: 0,
+            skip_until_keyframe: false,
+            max_pending_bytes: usize::MAX,
+            flushed: false,
+            decoded_to_end: false,
+            limit_tid: DEFAULT_LIMIT_TID,
+            framerate_ratio: 100,
+            stats: DecoderStats::default(),
+            cancelled: false,
+            stream_info: None,
+            sps_scanner: SpsScanner::default(),
+            poc_tracker: PocTracker::default(),
+            sei_tracker: SeiTracker::default(),
+            bytes_per_tid: BTreeMap::new(),
+            bitrate: BitrateMeter::new(),
+            suppressed_warnings: HashSet::new(),
+            pending_warnings: VecDeque::new(),
+            input_format_checker: None,
+            low_delay: false,
+            picture_pending: false,
+            low_delay_end_of_frame: false,
+        },
+        DecoderOutput {
+            context,
+            layers: LayerFilter::default(),
+            events: Mutex::default(),
+            pictures_output: AtomicU64::new(0),
         },
-        DecoderOutput { context },
     ))
 }
 
@@ -26,6 +103,9 @@ pub fn new_decoder() -> Result<(DecoderInput, DecoderOutput)> {
 #[non_exhaustive]
 pub enum ParamI32 {
     /// Dump SPS headers to a specified file-descriptor.
+    ///
+    /// Only `1` (stdout) and `2` (stderr) are supported by `libde265`,
+    /// see [`DecoderInput::dump_headers()`].
     DumpSpsHeaders = de265_param::DE265_DECODER_PARAM_DUMP_SPS_HEADERS as _,
     /// Dump VPS headers to a specified file-descriptor.
     DumpVpsHeaders = de265_param::DE265_DECODER_PARAM_DUMP_VPS_HEADERS as _,
@@ -35,6 +115,58 @@ pub enum ParamI32 {
     DumpSliceHeaders = de265_param::DE265_DECODER_PARAM_DUMP_SLICE_HEADERS as _,
 }
 
+impl ParamI32 {
+    fn index(&self) -> usize {
+        match self {
+            Self::DumpSpsHeaders => 0,
+            Self::DumpVpsHeaders => 1,
+            Self::DumpPpsHeaders => 2,
+            Self::DumpSliceHeaders => 3,
+        }
+    }
+}
+
+/// Kind of headers that may be dumped by the decoder.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum HeaderKind {
+    Vps,
+    Sps,
+    Pps,
+    Slice,
+}
+
+impl HeaderKind {
+    fn param(&self) -> ParamI32 {
+        match self {
+            Self::Vps => ParamI32::DumpVpsHeaders,
+            Self::Sps => ParamI32::DumpSpsHeaders,
+            Self::Pps => ParamI32::DumpPpsHeaders,
+            Self::Slice => ParamI32::DumpSliceHeaders,
+        }
+    }
+}
+
+/// Destination of dumped headers.
+///
+/// `libde265` can dump headers only into standard output streams.
+/// Other file descriptors (e.g. the write end of a pipe) are silently
+/// ignored by it, so dumped text can't be captured into memory
+/// without redirecting the standard output of the whole process.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DumpTarget {
+    Stdout,
+    Stderr,
+}
+
+impl DumpTarget {
+    fn fd(&self) -> i32 {
+        match self {
+            Self::Stdout => 1,
+            Self::Stderr => 2,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 #[non_exhaustive]
 pub enum ParamBool {
@@ -48,8 +180,26 @@ pub enum ParamBool {
     DisableSAO = de265_param::DE265_DECODER_PARAM_DISABLE_SAO as _,
 }
 
+/// Combination of decoding parameters that trades quality for speed.
+///
+/// See [`DecoderInput::apply_preset()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DecodePreset {
+    /// All filters are enabled, SEI hash check is enabled,
+    /// acceleration is [`Acceleration::Auto`].
+    Quality,
+    /// All filters are enabled, SEI hash check is disabled,
+    /// acceleration is [`Acceleration::Auto`] (defaults of `libde265`).
+    Balanced,
+    /// Deblocking and SAO filters are disabled, SEI hash check is disabled,
+    /// acceleration is [`Acceleration::Auto`].
+    /// Decoded pictures have visible artifacts, so it is intended for previews.
+    FastPreview,
+}
+
 /// Sorted such that a large ID includes all optimizations from lower IDs
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[non_exhaustive]
 pub enum Acceleration {
     /// only fallback implementation
@@ -67,46 +217,486 @@ pub enum Acceleration {
     Auto = de265_acceleration::de265_acceleration_AUTO as _,
 }
 
+impl Acceleration {
+    const ALL: [Self; 10] = [
+        Self::Scalar,
+        Self::MMS,
+        Self::SSE,
+        Self::SSE2,
+        Self::SSE4,
+        Self::AVX,
+        Self::AVX2,
+        Self::ARM,
+        Self::NEON,
+        Self::Auto,
+    ];
+
+    /// Returns the best acceleration method that is implemented
+    /// by `libde265` and supported by the current CPU.
+    pub fn detect() -> Self {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if std::arch::is_x86_feature_detected!("sse4.1") {
+                return Self::SSE4;
+            }
+            if std::arch::is_x86_feature_detected!("sse2") {
+                return Self::SSE2;
+            }
+            if std::arch::is_x86_feature_detected!("sse") {
+                return Self::SSE;
+            }
+        }
+        #[cfg(target_arch = "aarch64")]
+        {
+            if std::arch::is_aarch64_feature_detected!("neon") {
+                return Self::NEON;
+            }
+        }
+        Self::Scalar
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Scalar => "scalar",
+            Self::MMS => "mmx",
+            Self::SSE => "sse",
+            Self::SSE2 => "sse2",
+            Self::SSE4 => "sse4",
+            Self::AVX => "avx",
+            Self::AVX2 => "avx2",
+            Self::ARM => "arm",
+            Self::NEON => "neon",
+            Self::Auto => "auto",
+        }
+    }
+}
+
+impl fmt::Display for Acceleration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Acceleration {
+    type Err = ParseAccelerationError;
+
+    /// Parse the acceleration method from its case-insensitive name.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|a| a.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| ParseAccelerationError(s.to_string()))
+    }
+}
+
 pub(crate) struct DecoderContext {
-    pub(crate) inner: *mut de265_decoder_context,
+    inner: *mut de265_decoder_context,
+    /// Serializes calls of `libde265` functions, because the decoder
+    /// context (e.g. its output queue) isn't protected by internal locks.
+    lock: Mutex<()>,
+    /// Pictures referenced by alive images.
+    /// It is changed under the lock of the context.
+    pub(crate) checked_out: Mutex<CheckedOut>,
+    /// Typed payloads attached to pictures.
+    /// It is shared with image allocation functions of the decoder.
+    pub(crate) payloads: Arc<Payloads>,
+    /// Custom image allocators.
+    /// It is shared with image allocation functions of the decoder.
+    pub(crate) allocations: Arc<Allocations>,
+    /// Background decoding threads taken from the global budget.
+    /// They are returned into the budget after freeing of the decoder.
+    worker_threads: OnceLock<ThreadGrant>,
+    /// It is notified after decoding steps and changes of `end_of_stream`.
+    /// It is used with the lock of the context.
+    picture_ready: Condvar,
+    /// It is `true` if no more pictures will be output: the stream
+    /// was decoded to the end, or the decoding was cancelled or
+    /// the input half was dropped. It is changed under the lock of the context.
+    end_of_stream: AtomicBool,
+    /// Time base of PTS of pushed data and output pictures.
+    timebase: AtomicU64,
+    /// Hashes from decoded picture hash SEI messages of pushed NAL units.
+    pub(crate) picture_hashes: Mutex<PictureHashes>,
+    /// Decoding errors and warnings since the last output picture.
+    pub(crate) issues: Mutex<Vec<DeError>>,
+    /// Latencies of decoding of the last output pictures.
+    pub(crate) latencies: Mutex<LatencyWindow>,
+    /// Order of PTS of output pictures.
+    pub(crate) output_order: Mutex<OutputOrder>,
+}
+
+// SAFETY: The decoder context isn't bound to the thread that created it,
+// and all calls of `libde265` functions that use the context are serialized
+// with help of `DecoderContext::lock()`. Data shared with image allocation
+// functions is protected by its own locks.
+unsafe impl Send for DecoderContext {}
+unsafe impl Sync for DecoderContext {}
+
+impl DecoderContext {
+    /// Lock the decoder context to call `libde265` functions.
+    pub(crate) fn lock(&self) -> ContextGuard<'_> {
+        ContextGuard {
+            ptr: self.inner,
+            guard: lock(&self.lock),
+        }
+    }
+}
+
+/// Tracker of monotonicity of PTS of output pictures.
+#[derive(Default)]
+pub(crate) struct OutputOrder {
+    last_pts: Option<i64>,
+    reordered: bool,
+}
+
+impl OutputOrder {
+    pub(crate) fn observe(&mut self, pts: i64) {
+        if self.last_pts.is_some_and(|last_pts| pts < last_pts) {
+            self.reordered = true;
+        }
+        self.last_pts = Some(pts);
+    }
+}
+
+/// Pictures of the output queue referenced by alive [`Image`]s.
+///
+/// `libde265` gives access only to the picture at the head of the output
+/// queue. A picture released from the queue stays valid until the decoder
+/// reuses its memory for a new picture. So, the head picture is released
+/// when the next picture is checked out, and decoding is paused while
+/// images of released pictures are alive.
+#[derive(Debug, Default)]
+pub(crate) struct CheckedOut {
+    /// The picture at the head of the output queue that is released
+    /// from the queue when its image is dropped.
+    pub(crate) head: Option<usize>,
+    /// Number of alive images of pictures released from the output queue.
+    pub(crate) released: usize,
+    /// Number of alive images of pictures that stay in the output queue
+    /// after dropping of the images.
+    pub(crate) peeked: usize,
+}
+
+impl CheckedOut {
+    /// Release the checked out head picture from the output queue,
+    /// its image stays alive.
+    fn release_head(&mut self, context: &ContextGuard) {
+        if self.head.take().is_some() {
+            unsafe { de265_release_next_picture(context.ptr()) };
+            self.released += 1;
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.head.is_none() && self.released == 0 && self.peeked == 0
+    }
+}
+
+/// Guard that gives access to the locked decoder context.
+pub(crate) struct ContextGuard<'a> {
+    ptr: *mut de265_decoder_context,
+    guard: MutexGuard<'a, ()>,
+}
+
+impl ContextGuard<'_> {
+    #[inline(always)]
+    pub(crate) fn ptr(&self) -> *mut de265_decoder_context {
+        self.ptr
+    }
+
+    /// Release the lock and block the current thread until the condition
+    /// variable is notified or the timeout elapses, then lock the context again.
+    fn wait(self, condvar: &Condvar, timeout: Option<Duration>) -> Self {
+        let Self { ptr, guard } = self;
+        let guard = match timeout {
+            Some(timeout) => {
+                condvar
+                    .wait_timeout(guard, timeout)
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .0
+            }
+            None => condvar.wait(guard).unwrap_or_else(PoisonError::into_inner),
+        };
+        Self { ptr, guard }
+    }
+}
+
+/// Lock the mutex ignoring poisoning.
+///
+/// Data protected by mutexes of the crate stays consistent
+/// even if a thread panics while holding the lock.
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl DecoderContext {
+    pub(crate) fn timebase(&self) -> TimeBase {
+        TimeBase::from_bits(self.timebase.load(Ordering::Relaxed))
+    }
+
+    /// Attribute a decoding error or warning to the next output picture.
+    fn add_issue(&self, issue: DeError) {
+        let mut issues = lock(&self.issues);
+        if issues.len() < MAX_PICTURE_ISSUES {
+            issues.push(issue);
+        }
+    }
+
+    /// Mark that no more pictures will be output and wake up
+    /// threads waiting for pictures.
+    fn finish_output(&self) {
+        let guard = self.lock();
+        self.end_of_stream.store(true, Ordering::Release);
+        drop(guard);
+        self.picture_ready.notify_all();
+    }
+
+    /// Free the decoder.
+    ///
+    /// It doesn't wait for decoding of the input queue: worker threads
+    /// stop picking tasks once their pool is stopped by `libde265`,
+    /// so only tasks that are already running are finished.
+    fn free(&mut self) -> Result<()> {
+        if self.inner.is_null() {
+            return Ok(());
+        }
+        let inner = std::mem::replace(&mut self.inner, ptr::null_mut());
+        let result = DeError::from_raw(unsafe { de265_free_decoder(inner) });
+        // Worker threads are joined by freeing of the decoder.
+        #[cfg(feature = "tracing")]
+        if let Some(grant) = self.worker_threads.get() {
+            tracing::debug!(num_threads = grant.count(), "worker threads stopped");
+        }
+        result
+    }
 }
 
 impl Drop for DecoderContext {
     fn drop(&mut self) {
-        if !self.inner.is_null() {
-            unsafe { de265_free_decoder(self.inner) };
-        }
+        let _ = self.free();
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum DecodeResult {
     /// The decoding process was finished.
     Done,
     /// The decoding process isn't yet finished,
     /// and the [`DecoderInput::decode()`] method must be called again.
     CallAgain,
+    /// The decoder is waiting for more input data.
+    /// Push more data (or flush it at the end of a stream)
+    /// before calling the [`DecoderInput::decode()`] method again.
+    NeedsMoreInput,
+    /// The decoded picture buffer is full.
+    /// Extract some images before calling the [`DecoderInput::decode()`]
+    /// method again.
+    OutputQueueFull,
+}
+
+/// Result of [`DecoderInput::decode_some()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DecodeSomeResult {
+    /// Result of the last decoding step.
+    pub result: DecodeResult,
+    /// Number of performed decoding steps.
+    pub steps: u32,
+    /// `true` if there are decoded pictures waiting in the output queue.
+    pub has_pending_pictures: bool,
+    /// Wall-clock duration of the call.
+    pub elapsed: Duration,
+}
+
+/// Result of [`DecoderInput::try_push_data()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PushResult {
+    /// The data was pushed into the decoder.
+    Pushed,
+    /// The data wasn't pushed, because the limit of pending bytes
+    /// would be exceeded. Decode some of pending data before pushing it again.
+    WouldBlock,
 }
 
 /// Instance of this type is used to push input data for the decoder.
 pub struct DecoderInput {
-    context: Rc<DecoderContext>,
+    pub(crate) context: Arc<DecoderContext>,
+    /// Values of integer parameters, because libde265
+    /// doesn't provide a getter for them.
+    params_i32: [i32; 4],
+    acceleration: Acceleration,
+    num_worker_threads: u32,
+    skip_until_keyframe: bool,
+    max_pending_bytes: usize,
+    /// `flush_data()` was called after creating or resetting of the decoder.
+    flushed: bool,
+    /// `decode()` returned `DecodeResult::Done` after flushing.
+    decoded_to_end: bool,
+    /// Values of temporal layer settings, because libde265
+    /// doesn't provide getters for them.
+    limit_tid: u32,
+    framerate_ratio: u32,
+    stats: DecoderStats,
+    cancelled: bool,
+    /// Info from the last SPS pushed into the decoder.
+    stream_info: Option<StreamInfo>,
+    sps_scanner: SpsScanner,
+    poc_tracker: PocTracker,
+    sei_tracker: SeiTracker,
+    /// Bytes of NAL units pushed by `push_nal()` by temporal ID.
+    bytes_per_tid: BTreeMap<u8, u64>,
+    bitrate: BitrateMeter,
+    suppressed_warnings: HashSet<DeError>,
+    /// Warnings taken from the decoder by `decode()`,
+    /// which are not yet returned by `get_warning()`.
+    pending_warnings: VecDeque<DeError>,
+    /// It is `Some` if the strict input mode is enabled.
+    input_format_checker: Option<InputFormatChecker>,
+    low_delay: bool,
+    /// A VCL NAL unit was pushed by `push_nal()` after the last end of frame.
+    picture_pending: bool,
+    /// The end of frame was indicated by the low-delay mode,
+    /// and no data was pushed after that.
+    low_delay_end_of_frame: bool,
+}
+
+/// Counters of work done by a decoder.
+///
+/// The counters are maintained by the wrapper, see [`Decoder::stats()`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DecoderStats {
+    /// Number of bytes pushed into the decoder.
+    pub bytes_pushed: u64,
+    /// Number of NAL units pushed by [`DecoderInput::push_nal()`]
+    /// and methods based on it.
+    pub nals_pushed: u64,
+    /// Number of calls of [`DecoderInput::decode()`].
+    pub decode_calls: u64,
+    /// Number of errors returned by [`DecoderInput::decode()`].
+    pub errors: u64,
+    /// Number of pictures returned from the output queue.
+    pub pictures_output: u64,
+    /// Number of pictures dropped by the layer filter
+    /// (see [`DecoderOutput::set_layer_filter()`]).
+    ///
+    /// Faulty pictures suppressed by `libde265` itself aren't counted,
+    /// because it doesn't report them.
+    pub pictures_dropped: u64,
+    /// Number of warnings drained from the decoder by kind.
+    pub warnings: HashMap<DeError, u64>,
+    /// Number of drained warnings suppressed by
+    /// [`DecoderInput::suppress_warnings()`] by kind.
+    /// They are not counted in [`DecoderStats::warnings`].
+    pub suppressed_warnings: HashMap<DeError, u64>,
+    /// Number of resets of the decoder.
+    pub resets: u64,
+    /// Framerate ratio in percent currently set in the real-time mode,
+    /// or `None` if the mode is disabled (see [`Decoder::set_realtime_target()`]).
+    pub realtime_framerate_ratio: Option<u8>,
+    /// Number of changes of the framerate ratio in the real-time mode.
+    pub realtime_adjustments: u64,
+    /// Percentiles of latency of decoding of the last output pictures
+    /// (see [`Image::decode_latency()`]), or `None` if it wasn't measured.
+    pub decode_latency: Option<LatencyPercentiles>,
+}
+
+/// Maximum temporal layer decoded by `libde265` by default.
+const DEFAULT_LIMIT_TID: u32 = 6;
+
+/// Maximum number of warnings kept until they are drained
+/// (the same as the size of the warnings queue of `libde265`).
+const MAX_PENDING_WARNINGS: usize = 20;
+
+/// Maximum number of issues attributed to a single picture.
+const MAX_PICTURE_ISSUES: usize = 32;
+
+/// Direction of changing of the framerate by
+/// [`DecoderInput::change_framerate2()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FramerateChange {
+    /// Decode one more temporal layer.
+    More,
+    /// Decode one less temporal layer.
+    Less,
+    /// Don't change the framerate, only return the current ratio.
+    Query,
+}
+
+/// Framerate ratio: percent of pictures of the stream that are decoded.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FramerateRatio(u8);
+
+impl FramerateRatio {
+    /// Returns the ratio in percent (0-100).
+    pub fn as_percent(&self) -> u8 {
+        self.0
+    }
+
+    /// Returns `true` if all pictures are decoded.
+    pub fn is_full(&self) -> bool {
+        self.0 == 100
+    }
+}
+
+/// Snapshot of temporal layer settings and state of a decoder.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TemporalLayerInfo {
+    /// The maximum temporal layer ID in the stream.
+    pub highest_tid: u32,
+    /// ID of the currently decoded temporal substream.
+    pub current_tid: u32,
+    /// The maximum temporal layer ID to decode.
+    pub limit_tid: u32,
+    /// The framerate ratio in percent.
+    pub framerate_ratio_percent: u32,
 }
 
 impl DecoderInput {
     #[inline(always)]
-    fn inner(&self) -> *mut de265_decoder_context {
-        self.context.inner
+    fn lock(&self) -> ContextGuard<'_> {
+        self.context.lock()
     }
 
     /// Initialize background decoding threads.
     ///
     /// If this function is not called, all decoding is done in
     /// the main thread (no multi-threading).
+    ///
+    /// Worker threads may be started only once, because `libde265` doesn't provide
+    /// a way to stop or resize its thread pool. The threads are stopped when
+    /// the decoder is dropped. The number of threads is limited to
+    /// [`MAX_WORKER_THREADS`] and by the budget set with
+    /// [`set_global_thread_budget()`](crate::set_global_thread_budget).
+    /// If the budget is exhausted, no threads are started and decoding
+    /// is done in the calling thread; see [`DecoderInput::num_worker_threads()`].
     pub fn start_worker_threads(&mut self, num_threads: u32) -> Result<()> {
-        let result = unsafe {
-            de265_start_worker_threads(self.inner(), num_threads.min(i32::MAX as _) as _)
-        };
-        DeError::from_raw(result)
+        if num_threads == 0 {
+            return Ok(());
+        }
+        if self.num_worker_threads > 0 {
+            return Err(DeError::ErrorWorkerThreadsAlreadyStarted);
+        }
+        let grant = ThreadGrant::acquire(num_threads.min(MAX_WORKER_THREADS));
+        let num_threads = grant.count();
+        if num_threads == 0 {
+            return Ok(());
+        }
+        let result = unsafe { de265_start_worker_threads(self.lock().ptr(), num_threads as _) };
+        DeError::from_raw(result)?;
+        if self.context.worker_threads.set(grant).is_err() {
+            unreachable!("worker threads are started only once");
+        }
+        self.num_worker_threads = num_threads;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(num_threads, "worker threads started");
+        Ok(())
+    }
+
+    /// Returns the number of running background decoding threads.
+    pub fn num_worker_threads(&self) -> u32 {
+        self.num_worker_threads
     }
 
     /// Push more data into the decoder.
@@ -117,22 +707,76 @@ impl DecoderInput {
     /// The bytestream must contain all stuffing-bytes.
     /// This function only pushes data into the decoder, nothing will be decoded.
     pub fn push_data(&mut self, data: &[u8], pts: i64, user_data: usize) -> Result<()> {
+        self.check_not_cancelled()?;
+        if let Some(checker) = self.input_format_checker.as_mut() {
+            if let Some(kind) = checker.push(data) {
+                return Err(DeError::ErrorInvalidInputFormat(kind));
+            }
+        }
         let result = unsafe {
             de265_push_data(
-                self.inner(),
+                self.lock().ptr(),
                 data.as_ptr() as _,
                 data.len() as _,
                 pts,
                 user_data as _,
             )
         };
-        DeError::from_raw(result)
+        DeError::from_raw(result)?;
+        self.low_delay_end_of_frame = false;
+        self.stats.bytes_pushed += data.len() as u64;
+        if let Some(sps) = self.sps_scanner.push(data) {
+            self.set_sps(sps);
+        }
+        Ok(())
+    }
+
+    /// Push more data into the decoder if the limit of pending bytes
+    /// set by [`DecoderInput::set_max_pending_bytes()`] allows it.
+    ///
+    /// Returns [`PushResult::WouldBlock`] without pushing the data if
+    /// the number of pending bytes would exceed the limit. The data is
+    /// always pushed if there are no pending bytes, so a chunk bigger
+    /// than the limit doesn't block the input forever.
+    pub fn try_push_data(&mut self, data: &[u8], pts: i64, user_data: usize) -> Result<PushResult> {
+        let pending = self.number_of_input_bytes_pending();
+        if pending > 0 && data.len() > self.remaining_input_capacity() {
+            return Ok(PushResult::WouldBlock);
+        }
+        self.push_data(data, pts, user_data)?;
+        Ok(PushResult::Pushed)
+    }
+
+    /// Set the limit of bytes pending at the decoder input
+    /// that is used by [`DecoderInput::try_push_data()`].
+    ///
+    /// `usize::MAX` (default) means no limit.
+    pub fn set_max_pending_bytes(&mut self, limit: usize) {
+        self.max_pending_bytes = limit;
+    }
+
+    /// Returns the limit of pending bytes set by
+    /// [`DecoderInput::set_max_pending_bytes()`].
+    pub fn max_pending_bytes(&self) -> usize {
+        self.max_pending_bytes
+    }
+
+    /// Returns the number of bytes that may be pushed by
+    /// [`DecoderInput::try_push_data()`] without exceeding the limit
+    /// of pending bytes. It is `usize::MAX` if there is no limit.
+    pub fn remaining_input_capacity(&self) -> usize {
+        if self.max_pending_bytes == usize::MAX {
+            return usize::MAX;
+        }
+        self.max_pending_bytes
+            .saturating_sub(self.number_of_input_bytes_pending())
     }
 
     /// Indicate that the `push_data` method has just received data until the end of a NAL.
     /// The remaining pending input data is put into a NAL package and forwarded to the decoder.
     pub fn push_end_of_nal(&mut self) {
-        unsafe { de265_push_end_of_NAL(self.inner()) };
+        unsafe { de265_push_end_of_NAL(self.lock().ptr()) };
+        self.finish_sps_scan();
     }
 
     /// Indicate that the `push_data` method has just received data until the end of a frame.
@@ -140,24 +784,177 @@ impl DecoderInput {
     /// All data pending at the decoder input will be pushed into the decoder,
     /// and the decoded picture is pushed to the output queue.
     pub fn push_end_of_frame(&mut self) {
-        unsafe { de265_push_end_of_frame(self.inner()) };
+        unsafe { de265_push_end_of_frame(self.lock().ptr()) };
+        self.picture_pending = false;
+        self.finish_sps_scan();
+    }
+
+    fn finish_sps_scan(&mut self) {
+        if let Some(sps) = self.sps_scanner.finish() {
+            self.set_sps(sps);
+        }
+    }
+
+    /// Remember info and geometry from SPS found in the bytestream.
+    fn set_sps(&mut self, (info, geometry): (StreamInfo, CodedGeometry)) {
+        self.stream_info = Some(info);
+        lock(&self.context.picture_hashes).push_geometry(info, geometry);
     }
 
     /// Push a complete NAL unit without startcode into the decoder.
     ///
     /// The data must still contain all stuffing-bytes.
     /// This function only pushes data into the decoder, nothing will be decoded.
+    ///
+    /// The time of pushing of the first slice segment of a picture is remembered
+    /// to measure the latency of decoding (see [`Image::decode_latency()`]).
+    /// For this, the picture is identified by an internal token passed into
+    /// `libde265` instead of `user_data`, and [`Image::user_data()`] translates
+    /// it back. Tokens are told apart from user data of pictures pushed
+    /// by [`DecoderInput::push_data()`], so both methods may be used
+    /// for the same stream.
     pub fn push_nal(&mut self, data: &[u8], pts: i64, user_data: usize) -> Result<()> {
+        self.push_nal_inner(data, pts, user_data, None)
+    }
+
+    pub(crate) fn push_nal_inner(
+        &mut self,
+        data: &[u8],
+        pts: i64,
+        user_data: usize,
+        payload: Option<Box<dyn Any + Send>>,
+    ) -> Result<()> {
+        self.check_not_cancelled()?;
+        if self.skip_until_keyframe {
+            if self.skips_nal(data) {
+                return Ok(());
+            }
+            if nal_unit_type(data).is_some_and(NalUnitType::is_irap) {
+                self.skip_until_keyframe = false;
+            }
+        }
+        let slice_header = self.poc_tracker.push_nal(data);
+        let sei = self.sei_tracker.push_nal(data);
+        // The payload is dropped if the NAL unit doesn't start a picture.
+        let token = is_first_slice_segment(data).then(|| {
+            self.context
+                .payloads
+                .insert(user_data, payload, slice_header, sei)
+        });
         let result = unsafe {
             de265_push_NAL(
-                self.inner(),
+                self.lock().ptr(),
                 data.as_ptr() as _,
                 data.len() as _,
                 pts,
-                user_data as _,
+                token.unwrap_or(user_data) as _,
             )
         };
-        DeError::from_raw(result)
+        if let Err(err) = DeError::from_raw(result) {
+            if let Some(token) = token {
+                self.context.payloads.discard(token);
+            }
+            return Err(err);
+        }
+        self.low_delay_end_of_frame = false;
+        if nal_unit_type(data).is_some_and(NalUnitType::is_vcl) {
+            self.picture_pending = true;
+        }
+        self.stats.bytes_pushed += data.len() as u64;
+        self.stats.nals_pushed += 1;
+        if let Some(tid) = nal_temporal_id(data) {
+            *self.bytes_per_tid.entry(tid).or_default() += data.len() as u64;
+        }
+        self.bitrate
+            .push(pts, data.len() as u64, self.context.timebase());
+        if nal_unit_type(data) == Some(NalUnitType::SpsNut) {
+            if let Some(info) = StreamInfo::from_sps(data) {
+                self.stream_info = Some(info);
+            }
+        }
+        lock(&self.context.picture_hashes).push_nal(data, pts);
+        Ok(())
+    }
+
+    /// Returns numbers of bytes of NAL units pushed by [`DecoderInput::push_nal()`]
+    /// (and methods based on it) by temporal ID, sorted by temporal ID.
+    pub fn bytes_per_tid(&self) -> Vec<(u8, u64)> {
+        self.bytes_per_tid
+            .iter()
+            .map(|(&tid, &bytes)| (tid, bytes))
+            .collect()
+    }
+
+    /// Set the duration of the window used to estimate bitrate
+    /// by [`DecoderInput::bitrate()`] (1 second by default).
+    pub fn set_bitrate_window(&mut self, window: Duration) {
+        self.bitrate.set_window(window, self.context.timebase());
+    }
+
+    /// Returns the duration set by [`DecoderInput::set_bitrate_window()`].
+    pub fn bitrate_window(&self) -> Duration {
+        self.bitrate.window()
+    }
+
+    /// Returns bitrate (bits per second) of NAL units pushed by
+    /// [`DecoderInput::push_nal()`] (and methods based on it) estimated
+    /// over the window of their PTS values that ends at the greatest PTS.
+    ///
+    /// PTS values are converted into time with the decoder time base
+    /// (see [`DecoderInput::set_timebase()`]). Returns `None` if the window
+    /// contains less than two different PTS values.
+    pub fn bitrate(&self) -> Option<u64> {
+        self.bitrate.bitrate(self.context.timebase())
+    }
+
+    /// Push a batch of complete NAL units without startcodes (e.g. all NAL units
+    /// of a demuxed sample) with the same PTS and user data, and indicate
+    /// the end of frame after that.
+    ///
+    /// Returns the number of pushed NAL units. If pushing of a NAL unit fails,
+    /// the error contains its index; NAL units before it stay
+    /// in the decoder, and the end of frame isn't indicated.
+    pub fn push_nals<'a>(
+        &mut self,
+        nals: impl IntoIterator<Item = &'a [u8]>,
+        pts: i64,
+        user_data: usize,
+    ) -> std::result::Result<usize, PushNalsError> {
+        let mut count = 0;
+        for (index, nal) in nals.into_iter().enumerate() {
+            self.push_nal(nal, pts, user_data)
+                .map_err(|source| PushNalsError { index, source })?;
+            count += 1;
+        }
+        self.push_end_of_frame();
+        Ok(count)
+    }
+
+    /// Push all NAL units without startcodes of one access unit (all NAL units
+    /// of a picture) and indicate the end of frame.
+    ///
+    /// It is the low-latency path for data demuxed from a container:
+    /// the decoder knows that the picture is complete, so it may be decoded
+    /// and output without waiting for NAL units of the next access unit.
+    pub fn push_access_unit(&mut self, nals: &[&[u8]], pts: i64, user_data: usize) -> Result<()> {
+        self.push_nals(nals.iter().copied(), pts, user_data)
+            .map_err(|err| err.source)?;
+        Ok(())
+    }
+
+    /// Push a bytestream with startcodes that contains exactly one
+    /// access unit and indicate the end of frame.
+    ///
+    /// See [`DecoderInput::push_access_unit()`].
+    pub fn push_access_unit_annexb(
+        &mut self,
+        data: &[u8],
+        pts: i64,
+        user_data: usize,
+    ) -> Result<()> {
+        self.push_data(data, pts, user_data)?;
+        self.push_end_of_frame();
+        Ok(())
     }
 
     /// Indicate the end-of-stream.
@@ -165,15 +962,39 @@ impl DecoderInput {
     /// All data pending at the decoder input will be pushed into the decoder,
     /// and the decoded picture queue will be completely emptied.
     pub fn flush_data(&mut self) -> Result<()> {
-        let result = unsafe { de265_flush_data(self.inner()) };
-        DeError::from_raw(result)
+        // `de265_flush_data()` marks the end of stream that isn't cleared
+        // even by resetting, so the decoder couldn't decode another stream.
+        // The end of frame is handled the same way at the end of input data,
+        // but it is cleared by pushing of more data.
+        unsafe { de265_push_end_of_frame(self.lock().ptr()) };
+        self.picture_pending = false;
+        self.finish_sps_scan();
+        self.flushed = true;
+        Ok(())
+    }
+
+    /// Returns `true` if the end of the stream was signaled by
+    /// [`DecoderInput::flush_data()`] and the decoder wasn't reset after that.
+    pub fn is_flushed(&self) -> bool {
+        self.flushed
+    }
+
+    /// Returns parameters of the stream (dimensions, chroma format, bit depths,
+    /// profile, tier and level) from the last SPS pushed into the decoder.
+    ///
+    /// It is available as soon as the SPS is pushed, before any picture
+    /// is decoded, so it may be used to configure a renderer in advance.
+    /// SPS pushed by [`DecoderInput::push_data()`] is parsed when the start
+    /// code of the next NAL unit is pushed or the end of NAL is indicated.
+    pub fn stream_info(&self) -> Option<StreamInfo> {
+        self.stream_info
     }
 
     /// Return the number of bytes pending at the decoder input.
     ///
     /// Can be used to avoid overflowing the decoder with too much data.
     pub fn number_of_input_bytes_pending(&self) -> usize {
-        let value = unsafe { de265_get_number_of_input_bytes_pending(self.inner()) };
+        let value = unsafe { de265_get_number_of_input_bytes_pending(self.lock().ptr()) };
         value.max(0) as _
     }
 
@@ -181,7 +1002,7 @@ impl DecoderInput {
     ///
     /// Can be used to avoid overflowing the decoder with too much data.
     pub fn number_of_nal_units_pending(&self) -> usize {
-        let value = unsafe { de265_get_number_of_NAL_units_pending(self.inner()) };
+        let value = unsafe { de265_get_number_of_NAL_units_pending(self.lock().ptr()) };
         value.max(0) as _
     }
 
@@ -192,23 +1013,100 @@ impl DecoderInput {
     /// The result can be one of the following values:
     /// - [`DecodeResult::Done`] - decoding was finished;
     /// - [`DecodeResult::CallAgain`] - the decoding process isn't yet finished,
-    ///   and the [`DecoderInput::decode()`] method must be called again.
-    ///
-    /// There are a few errors that indicate that this method should be called again
-    /// (possibly after resolving the indicated problem).
-    /// - [`DeError::ErrorImageBufferFull`] - the decoded picture buffer is full,
-    ///   extract some images before continuing;
-    /// - [`DeError::ErrorWaitingForInputData`] - insert more data
-    ///   before continuing.
+    ///   and the [`DecoderInput::decode()`] method must be called again;
+    /// - [`DecodeResult::NeedsMoreInput`] - insert more data before continuing;
+    /// - [`DecodeResult::OutputQueueFull`] - the decoded picture buffer is full,
+    ///   extract some images before continuing.
+    ///
+    /// [`DecodeResult::OutputQueueFull`] is also returned without decoding
+    /// while images of pictures released from the output queue are alive
+    /// (see [`DecoderOutput::next_picture()`]), because the decoder reuses
+    /// memory of released pictures. Pictures that are held too long without
+    /// extracting the next ones fill the decoded picture buffer,
+    /// so this result is returned in this case too.
+    ///
+    /// Errors are returned only for conditions that can't be resolved
+    /// by pushing more data or extracting images.
     pub fn decode(&mut self) -> Result<DecodeResult> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("decode").entered();
+        let mut result = self.decode_step()?;
+        if self.low_delay {
+            if result == DecodeResult::NeedsMoreInput && self.picture_pending {
+                // All pushed NAL units are decoded, so the picture
+                // is finished without waiting for the next access unit.
+                self.push_end_of_frame();
+                self.low_delay_end_of_frame = true;
+                result = self.decode_step()?;
+            }
+            if result == DecodeResult::Done && self.low_delay_end_of_frame && !self.flushed {
+                result = DecodeResult::NeedsMoreInput;
+            }
+        }
+        Ok(result)
+    }
+
+    fn decode_step(&mut self) -> Result<DecodeResult> {
         let mut more = 0;
-        let result = unsafe { de265_decode(self.inner(), &mut more) };
-        DeError::from_raw(result).map(|_| {
-            if more > 0 {
-                DecodeResult::CallAgain
-            } else {
-                DecodeResult::Done
+        // The context is cloned to take warnings under its lock.
+        let context = self.context.clone();
+        let guard = context.lock();
+        if lock(&context.checked_out).released > 0 {
+            // The decoder may reuse memory of released pictures.
+            return Ok(DecodeResult::OutputQueueFull);
+        }
+        let result = unsafe { de265_decode(guard.ptr(), &mut more) };
+        self.stats.decode_calls += 1;
+        self.take_warnings(&guard);
+        let result = match DeError::from_raw(result) {
+            Ok(()) if more > 0 => Ok(DecodeResult::CallAgain),
+            Ok(()) => {
+                self.decoded_to_end = self.flushed;
+                if self.decoded_to_end {
+                    self.context.end_of_stream.store(true, Ordering::Release);
+                }
+                Ok(DecodeResult::Done)
+            }
+            Err(DeError::ErrorWaitingForInputData) => Ok(DecodeResult::NeedsMoreInput),
+            Err(DeError::ErrorImageBufferFull) => Ok(DecodeResult::OutputQueueFull),
+            Err(err) => {
+                #[cfg(feature = "tracing")]
+                tracing::error!(error = %err, "decoding error");
+                self.stats.errors += 1;
+                self.context.add_issue(err);
+                Err(err)
+            }
+        };
+        drop(guard);
+        self.context.picture_ready.notify_all();
+        result
+    }
+
+    /// Do decoding steps like [`DecoderInput::decode()`] does,
+    /// but not more than `max_steps` times (at least once).
+    ///
+    /// Decoding stops early if a step returns something other than
+    /// [`DecodeResult::CallAgain`] or an error. It bounds the time spent
+    /// in a single call, which is useful for UI threads and game loops.
+    ///
+    /// `libde265` doesn't report the number of pictures in the output queue,
+    /// so the result only tells if there are any.
+    pub fn decode_some(&mut self, max_steps: u32) -> Result<DecodeSomeResult> {
+        let start = Instant::now();
+        let mut steps = 0;
+        let result = loop {
+            let result = self.decode()?;
+            steps += 1;
+            if result != DecodeResult::CallAgain || steps >= max_steps {
+                break result;
             }
+        };
+        let has_pending_pictures = !unsafe { de265_peek_next_picture(self.lock().ptr()) }.is_null();
+        Ok(DecodeSomeResult {
+            result,
+            steps,
+            has_pending_pictures,
+            elapsed: start.elapsed(),
         })
     }
 
@@ -224,36 +1122,261 @@ impl DecoderInput {
     /// the `data` argument.
     #[deprecated(note = "you should use `push_data` or `push_nal` and `decode` methods instead.")]
     pub fn decode_data(&mut self, data: &[u8]) -> Result<()> {
+        self.check_not_cancelled()?;
         let result =
-            unsafe { de265_decode_data(self.inner(), data.as_ptr() as _, data.len() as _) };
+            unsafe { de265_decode_data(self.lock().ptr(), data.as_ptr() as _, data.len() as _) };
         DeError::from_raw(result)
     }
 
     /// Clear decoder state. Call this when skipping in the stream.
-    pub fn reset(&mut self) {
-        unsafe { de265_reset(self.inner()) };
+    ///
+    /// All payloads attached by [`DecoderInput::push_nal_with()`]
+    /// and not taken yet are dropped.
+    ///
+    /// Resetting frees all decoded pictures, so it returns
+    /// [`DeError::ErrorPictureIsBorrowed`] while an [`Image`] returned
+    /// by the [`DecoderOutput`] is alive.
+    pub fn reset(&mut self) -> Result<()> {
+        {
+            let context = self.lock();
+            if !lock(&self.context.checked_out).is_empty() {
+                return Err(DeError::ErrorPictureIsBorrowed);
+            }
+            // Pushing of data clears the end-of-frame mark that isn't cleared
+            // by resetting, otherwise the decoder would report the end
+            // of a new stream before pushing of its data. Pending data is
+            // removed by resetting.
+            unsafe { de265_push_data(context.ptr(), ptr::null(), 0, 0, ptr::null_mut()) };
+            unsafe { de265_reset(context.ptr()) };
+            self.context.end_of_stream.store(false, Ordering::Release);
+        }
+        self.flushed = false;
+        self.decoded_to_end = false;
+        self.picture_pending = false;
+        self.low_delay_end_of_frame = false;
+        self.sps_scanner = SpsScanner::default();
+        self.poc_tracker = PocTracker::default();
+        self.sei_tracker = SeiTracker::default();
+        if let Some(checker) = self.input_format_checker.as_mut() {
+            *checker = InputFormatChecker::default();
+        }
+        self.bitrate.clear();
+        lock(&self.context.picture_hashes).clear();
+        lock(&self.context.issues).clear();
+        *lock(&self.context.output_order) = OutputOrder::default();
+        self.stats.resets += 1;
+        self.context.payloads.clear();
+        #[cfg(feature = "tracing")]
+        tracing::debug!("decoder reset");
+        Ok(())
     }
 
-    pub fn get_warning(&self) -> Result<()> {
-        let result = unsafe { de265_get_warning(self.inner()) };
-        DeError::from_raw(result)
+    /// Cancel decoding: reset the decoder to abandon all pending work
+    /// and stop accepting input data.
+    ///
+    /// After cancelling, pushing data returns [`DeError::ErrorDecoderCancelled`].
+    /// Cancelling can't be undone, even by [`DecoderInput::reset()`].
+    /// Like [`DecoderInput::reset()`], it returns
+    /// [`DeError::ErrorPictureIsBorrowed`] while an [`Image`] is alive.
+    pub fn cancel(&mut self) -> Result<()> {
+        self.reset()?;
+        self.cancelled = true;
+        self.context.finish_output();
+        Ok(())
+    }
+
+    /// Returns `true` if decoding was cancelled by [`DecoderInput::cancel()`].
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn check_not_cancelled(&self) -> Result<()> {
+        if self.cancelled {
+            return Err(DeError::ErrorDecoderCancelled);
+        }
+        Ok(())
+    }
+
+    /// Set the time base of PTS values passed into the decoder
+    /// (microseconds by default).
+    ///
+    /// `libde265` doesn't interpret PTS values, they are passed to output
+    /// pictures as is. The time base is used by [`Image::pts_duration()`]
+    /// and [`Image::pts_in()`] to convert them.
+    pub fn set_timebase(&mut self, timebase: TimeBase) {
+        self.context
+            .timebase
+            .store(timebase.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Returns the time base set by [`DecoderInput::set_timebase()`].
+    pub fn timebase(&self) -> TimeBase {
+        self.context.timebase()
+    }
+
+    /// Enable or disable skipping of pictures until the next keyframe.
+    ///
+    /// While skipping is enabled, VCL NAL units pushed by [`DecoderInput::push_nal()`]
+    /// and methods based on it are dropped until a NAL unit of IRAP picture
+    /// (IDR, CRA or BLA) arrives. Then skipping is disabled automatically.
+    /// Non-VCL NAL units (parameter sets, SEI, etc.) are pushed as usual.
+    ///
+    /// It is useful for seeking: call [`DecoderInput::reset()`] and enable
+    /// skipping before pushing data from a new position in the stream.
+    ///
+    /// Data pushed by [`DecoderInput::push_data()`] is not inspected.
+    pub fn set_skip_until_keyframe(&mut self, enable: bool) {
+        self.skip_until_keyframe = enable;
+    }
+
+    /// Returns `true` if pictures are skipped until the next keyframe.
+    pub fn is_skipping_until_keyframe(&self) -> bool {
+        self.skip_until_keyframe
+    }
+
+    /// Enable or disable the strict input mode.
+    ///
+    /// In this mode the beginning of data pushed by [`DecoderInput::push_data()`]
+    /// is inspected, and pushing returns [`DeError::ErrorInvalidInputFormat`]
+    /// without pushing the data if it is recognized as an MP4 file or a raw H.264
+    /// stream. Only high-confidence signatures are recognized, so some wrong
+    /// data may still be pushed. The error is returned for all following data
+    /// until the decoder is reset.
+    ///
+    /// Enabling the mode restarts inspection from the next pushed data,
+    /// so it should be enabled before pushing the beginning of a stream.
+    pub fn set_strict_input(&mut self, enable: bool) {
+        self.input_format_checker = enable.then(InputFormatChecker::default);
+    }
+
+    /// Returns `true` if the strict input mode is enabled.
+    pub fn is_strict_input(&self) -> bool {
+        self.input_format_checker.is_some()
+    }
+
+    /// Enable or disable the low-delay mode.
+    ///
+    /// By default, the decoder finishes a picture only when NAL units
+    /// of the next access unit are pushed, or the end of frame is indicated.
+    /// In the low-delay mode, [`DecoderInput::decode()`] indicates the end
+    /// of frame (see [`DecoderInput::push_end_of_frame()`]) when all NAL units
+    /// pushed by [`DecoderInput::push_nal()`] are decoded, so the picture
+    /// is finished and all decoded pictures are output without waiting
+    /// for the next access unit or for filling of the reorder buffer.
+    /// `decode()` returns [`DecodeResult::NeedsMoreInput`] instead
+    /// of [`DecodeResult::Done`] after that.
+    ///
+    /// All NAL units of an access unit must be pushed before calling
+    /// `decode()`, otherwise a partially pushed picture is finished.
+    /// Data pushed by [`DecoderInput::push_data()`] isn't affected,
+    /// use [`DecoderInput::push_access_unit_annexb()`] instead.
+    ///
+    /// It only helps for streams whose output order equals decoding order
+    /// (e.g. streams without B-frames). Pictures of reordered streams may be
+    /// returned out of presentation order, which may be detected
+    /// by [`DecoderOutput::appears_reordered()`].
+    pub fn set_low_delay(&mut self, enable: bool) {
+        self.low_delay = enable;
+    }
+
+    /// Returns `true` if the low-delay mode is enabled.
+    pub fn is_low_delay(&self) -> bool {
+        self.low_delay
+    }
+
+    /// Returns `true` if the NAL unit must be dropped
+    /// because pictures are skipped until the next keyframe.
+    pub(crate) fn skips_nal(&self, data: &[u8]) -> bool {
+        self.skip_until_keyframe && nal_unit_type(data).is_some_and(|t| t.is_vcl() && !t.is_irap())
+    }
+
+    /// Drain the next pending decoder warning and return it as an error.
+    ///
+    /// Warnings suppressed by [`DecoderInput::suppress_warnings()`]
+    /// are skipped.
+    pub fn get_warning(&mut self) -> Result<()> {
+        loop {
+            let warning = match self.pending_warnings.pop_front() {
+                Some(warning) => warning,
+                None => {
+                    let result = unsafe { de265_get_warning(self.lock().ptr()) };
+                    let Err(warning) = DeError::from_raw(result) else {
+                        return Ok(());
+                    };
+                    warning
+                }
+            };
+            if self.suppressed_warnings.contains(&warning) {
+                *self.stats.suppressed_warnings.entry(warning).or_default() += 1;
+                #[cfg(feature = "tracing")]
+                tracing::trace!(%warning, "decoder warning suppressed");
+                continue;
+            }
+            *self.stats.warnings.entry(warning).or_default() += 1;
+            #[cfg(feature = "tracing")]
+            tracing::warn!(%warning, "decoder warning");
+            return Err(warning);
+        }
+    }
+
+    /// Move warnings of the decoder into the queue of pending warnings
+    /// and attribute not suppressed ones to the next output picture.
+    fn take_warnings(&mut self, context: &ContextGuard<'_>) {
+        loop {
+            let result = unsafe { de265_get_warning(context.ptr()) };
+            let Err(warning) = DeError::from_raw(result) else {
+                break;
+            };
+            if self.pending_warnings.len() < MAX_PENDING_WARNINGS {
+                self.pending_warnings.push_back(warning);
+            } else if let Some(last) = self.pending_warnings.back_mut() {
+                // Like the decoder does when its own queue is full.
+                *last = DeError::WarningWarningBufferFull;
+            }
+            if !self.suppressed_warnings.contains(&warning) {
+                self.context.add_issue(warning);
+            }
+        }
+    }
+
+    /// Set the list of warnings that are not returned by
+    /// [`DecoderInput::get_warning()`] and [`DecoderInput::warnings()`],
+    /// replacing the previous list.
+    ///
+    /// It is useful to ignore known-benign warnings of imperfect streams.
+    /// Suppressed warnings are still counted in
+    /// [`DecoderStats::suppressed_warnings`].
+    pub fn suppress_warnings(&mut self, warnings: &[DeError]) {
+        self.suppressed_warnings = warnings.iter().copied().collect();
+    }
+
+    /// Returns the list of warnings set by [`DecoderInput::suppress_warnings()`].
+    pub fn suppressed_warnings(&self) -> Vec<DeError> {
+        self.suppressed_warnings.iter().copied().collect()
+    }
+
+    /// Returns an iterator that drains all pending decoder warnings.
+    pub fn warnings(&mut self) -> Warnings<'_> {
+        Warnings { input: self }
     }
 
     /// Returns the maximum layer ID in the stream.
     ///
     /// Note that the maximum layer ID can change throughout the stream.
     pub fn highest_tid(&self) -> u32 {
-        unsafe { de265_get_highest_TID(self.inner()).max(0) as _ }
+        unsafe { de265_get_highest_TID(self.lock().ptr()).max(0) as _ }
     }
 
     /// Returns an ID of the currently decoded temporal substream.
     pub fn current_tid(&self) -> u32 {
-        unsafe { de265_get_current_TID(self.inner()).max(0) as _ }
+        unsafe { de265_get_current_TID(self.lock().ptr()).max(0) as _ }
     }
 
     /// Limits decoding to a maximum temporal layer (TID).
     pub fn set_limit_tid(&mut self, max_tid: u32) {
-        unsafe { de265_set_limit_TID(self.inner(), max_tid.min(i32::MAX as _) as _) };
+        let max_tid = max_tid.min(i32::MAX as _);
+        unsafe { de265_set_limit_TID(self.lock().ptr(), max_tid as _) };
+        self.limit_tid = max_tid;
     }
 
     /// It is used for a fine-grained selection of the frame-rate.
@@ -264,72 +1387,1107 @@ impl DecoderInput {
     ///
     /// The TID limit has a higher precedence than the framerate ratio. Hence, setting a higher
     /// framerate ratio will decode at TID limit without dropping.
+    ///
+    /// Values greater than 100 are clamped to 100
+    /// (it is a debug assertion failure).
     pub fn set_framerate_ratio(&mut self, percent: u8) {
-        unsafe { de265_set_framerate_ratio(self.inner(), percent as _) };
+        debug_assert!(percent <= 100, "framerate ratio must not exceed 100%");
+        let percent = percent.min(100);
+        unsafe { de265_set_framerate_ratio(self.lock().ptr(), percent as _) };
+        self.framerate_ratio = percent as u32;
     }
 
-    /// Increase or decrease the output frame-rate to some
+    /// Returns the framerate ratio in percent.
+    ///
+    /// It is the value last set by [`DecoderInput::set_framerate_ratio()`]
+    /// or returned by [`DecoderInput::change_framerate2()`] (100 by default).
+    /// The value is cached by the wrapper, so reading it doesn't change
+    /// the state of the decoder.
+    pub fn framerate_ratio(&self) -> u8 {
+        self.framerate_ratio as u8
+    }
+
+    /// Increase or decrease the output frame-rate to some
     /// discrete preferable value. Currently, these are non-dropped decoding at various
     /// TID layers.
     ///
     /// The `more_vs_less` argument can be one of [-1, 0, 1].
     ///
     /// Returns the corresponding framerate ratio.
+    #[deprecated(note = "use `change_framerate2()` instead.")]
     pub fn change_framerate(&mut self, more_vs_less: i8) -> u32 {
-        unsafe {
-            de265_change_framerate(self.inner(), more_vs_less.clamp(-1, 1) as i32).max(0) as _
+        let change = match more_vs_less {
+            1.. => FramerateChange::More,
+            0 => FramerateChange::Query,
+            ..0 => FramerateChange::Less,
+        };
+        self.change_framerate2(change).as_percent() as u32
+    }
+
+    /// Increase or decrease the output frame-rate to the next
+    /// discrete preferable value. Currently, these are non-dropped
+    /// decoding at various TID layers.
+    ///
+    /// Returns the corresponding framerate ratio. If the stream
+    /// has no active SPS yet, the ratio isn't changed.
+    pub fn change_framerate2(&mut self, change: FramerateChange) -> FramerateRatio {
+        let more = match change {
+            FramerateChange::More => 1,
+            FramerateChange::Less => -1,
+            FramerateChange::Query => 0,
+        };
+        let ratio: u32 = unsafe { de265_change_framerate(self.lock().ptr(), more).max(0) as _ };
+        let ratio = ratio.min(100);
+        self.framerate_ratio = ratio;
+        FramerateRatio(ratio as u8)
+    }
+
+    /// Returns a snapshot of temporal layer settings and state of the decoder.
+    pub fn temporal_layers(&self) -> TemporalLayerInfo {
+        TemporalLayerInfo {
+            highest_tid: self.highest_tid(),
+            current_tid: self.current_tid(),
+            limit_tid: self.limit_tid,
+            framerate_ratio_percent: self.framerate_ratio,
         }
     }
 
     /// Set an integer decoding parameter.
     pub fn set_parameter_i32(&mut self, param: ParamI32, val: i32) {
         unsafe {
-            de265_set_parameter_int(self.inner(), param as de265_param::Type, val);
+            de265_set_parameter_int(self.lock().ptr(), param as de265_param::Type, val);
         }
+        self.params_i32[param.index()] = val;
+    }
+
+    /// Enable dumping of headers of the given kind into the target
+    /// or disable it if the target is `None`.
+    ///
+    /// It is a typed alternative to setting dump parameters
+    /// with [`DecoderInput::set_parameter_i32()`].
+    /// See [`DumpTarget`] about why headers can't be dumped
+    /// into an in-memory buffer.
+    pub fn dump_headers(&mut self, kind: HeaderKind, target: Option<DumpTarget>) {
+        let fd = target.map_or(-1, |t| t.fd());
+        self.set_parameter_i32(kind.param(), fd);
+    }
+
+    /// Get an integer decoding parameter.
+    ///
+    /// The default value of all dump parameters is `-1` (dumping is disabled).
+    pub fn get_parameter_i32(&self, param: ParamI32) -> i32 {
+        self.params_i32[param.index()]
     }
 
     /// Set a bool decoding parameter.
     pub fn set_parameter_bool(&mut self, param: ParamBool, val: bool) {
         unsafe {
             de265_set_parameter_bool(
-                self.inner(),
+                self.lock().ptr(),
                 param as de265_param::Type,
                 if val { 1 } else { 0 },
             );
         }
     }
 
+    /// Set parameters of the given preset:
+    ///
+    /// | Preset                        | `SeiCheckHash` | `DisableDeblocking` | `DisableSAO` | Acceleration |
+    /// |-------------------------------|----------------|---------------------|--------------|--------------|
+    /// | [`DecodePreset::Quality`]     | `true`         | `false`             | `false`      | `Auto`       |
+    /// | [`DecodePreset::Balanced`]    | `false`        | `false`             | `false`      | `Auto`       |
+    /// | [`DecodePreset::FastPreview`] | `false`        | `true`              | `true`       | `Auto`       |
+    ///
+    /// Other parameters are not changed. Parameters may be changed
+    /// individually after applying a preset.
+    pub fn apply_preset(&mut self, preset: DecodePreset) {
+        let (sei_check_hash, disable_filters) = match preset {
+            DecodePreset::Quality => (true, false),
+            DecodePreset::Balanced => (false, false),
+            DecodePreset::FastPreview => (false, true),
+        };
+        self.set_parameter_bool(ParamBool::SeiCheckHash, sei_check_hash);
+        self.set_parameter_bool(ParamBool::DisableDeblocking, disable_filters);
+        self.set_parameter_bool(ParamBool::DisableSAO, disable_filters);
+        self.set_acceleration(Acceleration::Auto);
+    }
+
     /// Set acceleration method, default: [`Acceleration::Auto`]
     pub fn set_acceleration(&mut self, val: Acceleration) {
         unsafe {
             de265_set_parameter_int(
-                self.inner(),
+                self.lock().ptr(),
                 de265_param::DE265_DECODER_PARAM_ACCELERATION_CODE,
                 val as i32,
             );
         }
+        self.acceleration = val;
+    }
+
+    /// Get acceleration method, default: [`Acceleration::Auto`]
+    pub fn get_acceleration(&self) -> Acceleration {
+        self.acceleration
     }
 
     /// Get a bool decoding parameter.
     pub fn get_parameter_bool(&self, param: ParamBool) -> bool {
-        unsafe { de265_get_parameter_bool(self.inner(), param as de265_param::Type) != 0 }
+        unsafe { de265_get_parameter_bool(self.lock().ptr(), param as de265_param::Type) != 0 }
+    }
+}
+
+/// Iterator over pending decoder warnings.
+///
+/// It is created by the [`DecoderInput::warnings()`] method.
+pub struct Warnings<'a> {
+    input: &'a mut DecoderInput,
+}
+
+impl Iterator for Warnings<'_> {
+    type Item = DeError;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.input.get_warning().err()
+    }
+}
+
+impl Drop for DecoderInput {
+    fn drop(&mut self) {
+        // Nothing can be decoded without the input half.
+        self.context.finish_output();
     }
 }
 
 /// Instance of this type is used to receive decoded pictures.
 pub struct DecoderOutput {
-    context: Rc<DecoderContext>,
+    context: Arc<DecoderContext>,
+    layers: LayerFilter,
+    events: Mutex<StreamEvents>,
+    pictures_output: AtomicU64,
 }
 
-impl DecoderOutput {
-    #[inline(always)]
-    pub(crate) fn inner(&self) -> *mut de265_decoder_context {
-        self.context.inner
+/// Selection of output pictures by layer ID.
+#[derive(Default)]
+struct LayerFilter {
+    layer_id: Option<u8>,
+    state: Mutex<LayerState>,
+}
+
+#[derive(Default)]
+struct LayerState {
+    /// Layer IDs of all pictures observed in the output queue.
+    seen: BTreeSet<u8>,
+    /// Number of pictures of other layers released from the output queue.
+    dropped: u64,
+}
+
+/// Change of stream parameters detected between successive output pictures.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StreamEvent {
+    /// Size (width, height) of the luma channel has changed.
+    ResolutionChanged { from: (u32, u32), to: (u32, u32) },
+    /// Bit depth of the luma channel has changed.
+    BitDepthChanged { from: u32, to: u32 },
+    ChromaFormatChanged {
+        from: ChromaFormat,
+        to: ChromaFormat,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct PictureFormat {
+    size: (u32, u32),
+    bit_depth: u32,
+    chroma_format: ChromaFormat,
+}
+
+impl PictureFormat {
+    fn of_image(image_ptr: NonNull<de265_image>) -> Self {
+        let image = image_ptr.as_ptr() as *const de265_image;
+        let luma = Channel::Y.index();
+        unsafe {
+            Self {
+                size: (
+                    de265_get_image_width(image, luma).max(0) as u32,
+                    de265_get_image_height(image, luma).max(0) as u32,
+                ),
+                bit_depth: de265_get_bits_per_pixel(image, luma).max(0) as u32,
+                chroma_format: ChromaFormat::from_raw(de265_get_chroma_format(image)),
+            }
+        }
     }
+}
 
+#[derive(Default)]
+struct StreamEvents {
+    /// Format of the last picture observed at the head of the output queue.
+    last_format: Option<PictureFormat>,
+    queue: Vec<StreamEvent>,
+}
+
+impl StreamEvents {
+    /// Compare the format of the picture with the format of the previous one.
+    ///
+    /// The same picture may be observed several times (e.g. by peeking),
+    /// but it doesn't produce new events because its format is not changed.
+    fn observe(&mut self, image_ptr: NonNull<de265_image>) {
+        let format = PictureFormat::of_image(image_ptr);
+        let Some(last) = self.last_format.replace(format) else {
+            return;
+        };
+        if last.size != format.size {
+            self.queue.push(StreamEvent::ResolutionChanged {
+                from: last.size,
+                to: format.size,
+            });
+        }
+        if last.bit_depth != format.bit_depth {
+            self.queue.push(StreamEvent::BitDepthChanged {
+                from: last.bit_depth,
+                to: format.bit_depth,
+            });
+        }
+        if last.chroma_format != format.chroma_format {
+            self.queue.push(StreamEvent::ChromaFormatChanged {
+                from: last.chroma_format,
+                to: format.chroma_format,
+            });
+        }
+    }
+}
+
+/// Lock the decoder context and peek the next picture of the selected layer.
+///
+/// Pictures of other layers are released from the output queue
+/// together with their payloads. Changes of the format of selected
+/// pictures are recorded into `events`. If `check_out` is `true`,
+/// the head picture checked out by an alive image is released from
+/// the queue before peeking.
+fn peek_selected_picture<'a>(
+    context: &'a DecoderContext,
+    layers: &LayerFilter,
+    events: &Mutex<StreamEvents>,
+    check_out: bool,
+) -> (ContextGuard<'a>, Option<NonNull<de265_image>>) {
+    loop {
+        let guard = context.lock();
+        if check_out {
+            lock(&context.checked_out).release_head(&guard);
+        }
+        let image_ptr = unsafe { de265_peek_next_picture(guard.ptr()) };
+        let Some(image_ptr) = NonNull::new(image_ptr as *mut de265_image) else {
+            return (guard, None);
+        };
+        let layer_id = image_layer_id(image_ptr);
+        let mut state = lock(&layers.state);
+        state.seen.insert(layer_id);
+        if layers.layer_id.is_none_or(|id| id == layer_id) {
+            lock(events).observe(image_ptr);
+            return (guard, Some(image_ptr));
+        }
+        state.dropped += 1;
+        drop(state);
+        let user_data = unsafe { de265_get_image_user_data(image_ptr.as_ptr()) };
+        unsafe { de265_release_next_picture(guard.ptr()) };
+        drop(guard);
+        context.payloads.remove(user_data as usize);
+    }
+}
+
+fn image_layer_id(image_ptr: NonNull<de265_image>) -> u8 {
+    let mut layer_id: c_int = 0;
+    unsafe {
+        de265_get_image_NAL_header(
+            image_ptr.as_ptr(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            &mut layer_id,
+            ptr::null_mut(),
+        )
+    };
+    layer_id as u8
+}
+
+impl DecoderOutput {
     /// Return the next decoded picture if there is any.
-    pub fn next_picture(&mut self) -> Option<Image<'_>> {
-        let image_ptr = unsafe { de265_peek_next_picture(self.inner()) };
-        NonNull::new(image_ptr as _).map(|p| Image::new(self.context.as_ref(), p))
+    ///
+    /// The picture is removed from the output queue when the returned
+    /// image is dropped or when the next picture is requested,
+    /// whichever comes first.
+    ///
+    /// Several images may be alive at the same time (e.g. the previous
+    /// and the current pictures for temporal filters). But the decoder
+    /// reuses memory of pictures removed from the output queue, so
+    /// [`DecoderInput::decode()`] doesn't decode anything and returns
+    /// [`DecodeResult::OutputQueueFull`] until images of such pictures
+    /// are dropped.
+    ///
+    /// `libde265` doesn't tell if there is a picture after the head of
+    /// the output queue, so requesting the next picture removes the previous
+    /// one from the queue even if `None` is returned. Only the image of the last
+    /// requested picture may be kept alive while decoding, use
+    /// [`Image::to_owned()`] to keep other pictures across decoding steps.
+    pub fn next_picture(&self) -> Option<Image<'_>> {
+        // The image must be created under the lock to not race
+        // with resetting of the decoder.
+        let (_guard, image_ptr) =
+            peek_selected_picture(&self.context, &self.layers, &self.events, true);
+        let image_ptr = image_ptr?;
+        self.pictures_output.fetch_add(1, Ordering::Relaxed);
+        Some(Image::new(self.context.as_ref(), image_ptr, true))
+    }
+
+    /// Return the next decoded picture without removing it from the output queue.
+    ///
+    /// The picture stays in the queue after the returned image is dropped,
+    /// so the same picture is returned by the next call of this method
+    /// or [`DecoderOutput::next_picture()`]. Use [`DecoderOutput::pop_picture()`]
+    /// to remove it.
+    pub fn peek_picture(&mut self) -> Option<Image<'_>> {
+        let (_guard, image_ptr) =
+            peek_selected_picture(&self.context, &self.layers, &self.events, false);
+        image_ptr.map(|p| Image::new(self.context.as_ref(), p, false))
+    }
+
+    /// Block the current thread until the next decoded picture is available
+    /// and return it.
+    ///
+    /// Pictures are produced by [`DecoderInput::decode()`] called from another
+    /// thread (e.g. a thread that pushes data into the decoder), so this method
+    /// is useful when the input and output halves of the decoder are used
+    /// in different threads.
+    ///
+    /// Returns `None` at the end of the stream: when [`DecoderInput::decode()`]
+    /// has returned [`DecodeResult::Done`] after [`DecoderInput::flush_data()`],
+    /// when the decoding was cancelled or the input half was dropped.
+    /// Without flushing the end of the stream is never reached, so use a timeout
+    /// or flush the data at the end of the stream.
+    ///
+    /// Returns [`DeError::ErrorTimedOut`] if no picture is available
+    /// during the given `timeout`.
+    ///
+    /// Like with [`DecoderOutput::next_picture()`], images of the previous
+    /// pictures may be alive, but nothing is decoded until they are dropped.
+    pub fn recv_picture(&self, timeout: Option<Duration>) -> Result<Option<Image<'_>>> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            let (guard, image_ptr) =
+                peek_selected_picture(&self.context, &self.layers, &self.events, true);
+            if let Some(image_ptr) = image_ptr {
+                self.pictures_output.fetch_add(1, Ordering::Relaxed);
+                return Ok(Some(Image::new(self.context.as_ref(), image_ptr, true)));
+            }
+            if self.context.end_of_stream.load(Ordering::Acquire) {
+                return Ok(None);
+            }
+            let timeout = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(DeError::ErrorTimedOut);
+                    }
+                    Some(deadline - now)
+                }
+                None => None,
+            };
+            drop(guard.wait(&self.context.picture_ready, timeout));
+        }
+    }
+
+    /// Remove the next decoded picture from the output queue
+    /// (`de265_release_next_picture()` is called immediately).
+    ///
+    /// The payload attached to the picture by [`DecoderInput::push_nal_with()`]
+    /// is dropped. Returns `false` if the output queue is empty.
+    pub fn pop_picture(&mut self) -> bool {
+        let user_data = {
+            let (guard, image_ptr) =
+                peek_selected_picture(&self.context, &self.layers, &self.events, false);
+            let Some(image_ptr) = image_ptr else {
+                return false;
+            };
+            let user_data = unsafe { de265_get_image_user_data(image_ptr.as_ptr()) };
+            unsafe { de265_release_next_picture(guard.ptr()) };
+            user_data as usize
+        };
+        self.pictures_output.fetch_add(1, Ordering::Relaxed);
+        self.context.payloads.remove(user_data);
+        true
+    }
+
+    /// Returns `true` if there are decoded pictures waiting in the output queue.
+    ///
+    /// It may be used to decide whether to push more data into the decoder
+    /// or to extract decoded pictures first. The last picture returned by
+    /// [`DecoderOutput::next_picture()`] stays in the queue while
+    /// its image is alive, so it is taken into account too.
+    pub fn has_pending_pictures(&self) -> bool {
+        peek_selected_picture(&self.context, &self.layers, &self.events, false)
+            .1
+            .is_some()
+    }
+
+    /// Returns an iterator that yields decoded pictures until
+    /// the output queue is empty.
+    ///
+    /// Yielded images may be kept alive at the same time, see
+    /// [`DecoderOutput::next_picture()`] for details.
+    pub fn frames(&self) -> Frames<'_> {
+        Frames { output: self }
+    }
+
+    /// Output only pictures with the given layer ID (`nuh_layer_id`)
+    /// of a multi-layer stream, or all pictures if it is `None` (default).
+    ///
+    /// Pictures of other layers are released from the output queue
+    /// when the next picture is requested.
+    pub fn set_layer_filter(&mut self, layer_id: Option<u8>) {
+        self.layers.layer_id = layer_id;
+    }
+
+    /// Returns the layer ID set by [`DecoderOutput::set_layer_filter()`].
+    pub fn layer_filter(&self) -> Option<u8> {
+        self.layers.layer_id
+    }
+
+    /// Returns the set of layer IDs of all pictures observed
+    /// in the output queue so far, including filtered out ones.
+    pub fn layers_seen(&self) -> BTreeSet<u8> {
+        lock(&self.layers.state).seen.clone()
+    }
+
+    /// Take events about changes of stream parameters (resolution,
+    /// bit depth, chroma format) detected since the previous call.
+    ///
+    /// Events are detected by comparing the format of successive output
+    /// pictures when they reach the head of the output queue, so an event
+    /// is available before the first picture with the new format is returned.
+    /// Every change is reported exactly once.
+    pub fn take_events(&mut self) -> Vec<StreamEvent> {
+        std::mem::take(&mut lock(&self.events).queue)
+    }
+
+    /// Returns `true` if PTS of an output picture was less than PTS
+    /// of the previous one since creating or resetting of the decoder.
+    ///
+    /// It means that pictures of the current stream are output not in
+    /// presentation order (e.g. a stream with B-frames is decoded
+    /// in the low-delay mode, see [`DecoderInput::set_low_delay()`]),
+    /// or PTS values passed into the decoder are wrong.
+    pub fn appears_reordered(&self) -> bool {
+        lock(&self.context.output_order).reordered
+    }
+}
+
+/// Iterator over decoded pictures waiting in the output queue.
+///
+/// It is created by the [`DecoderOutput::frames()`] method.
+pub struct Frames<'a> {
+    output: &'a DecoderOutput,
+}
+
+impl<'a> Iterator for Frames<'a> {
+    type Item = Image<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.output.next_picture()
+    }
+}
+
+/// Decoder that owns both input and output halves.
+///
+/// It is convenient for the single-threaded case when you don't
+/// need to pass [`DecoderInput`] and [`DecoderOutput`] separately.
+/// Use [`Decoder::split()`] to get these halves if it is required.
+pub struct Decoder {
+    input: DecoderInput,
+    output: DecoderOutput,
+    realtime: Option<RealtimeController>,
+    realtime_floor: u8,
+}
+
+impl Decoder {
+    /// Create a new decoder.
+    pub fn new() -> Result<Self> {
+        let (input, output) = new_decoder()?;
+        Ok(Self::from_parts(input, output))
+    }
+
+    pub(crate) fn from_parts(input: DecoderInput, output: DecoderOutput) -> Self {
+        Self {
+            input,
+            output,
+            realtime: None,
+            realtime_floor: DEFAULT_REALTIME_FLOOR,
+        }
+    }
+
+    /// Split the decoder into input and output halves
+    /// that share the same decoder context.
+    pub fn split(self) -> (DecoderInput, DecoderOutput) {
+        (self.input, self.output)
+    }
+
+    /// Returns a reference to the input half of the decoder.
+    pub fn input(&self) -> &DecoderInput {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the input half of the decoder.
+    pub fn input_mut(&mut self) -> &mut DecoderInput {
+        &mut self.input
+    }
+
+    /// Returns a mutable reference to the output half of the decoder.
+    pub fn output_mut(&mut self) -> &mut DecoderOutput {
+        &mut self.output
+    }
+
+    /// Returns counters of work done by the decoder.
+    pub fn stats(&self) -> DecoderStats {
+        let mut stats = self.input.stats.clone();
+        stats.pictures_output = self.output.pictures_output.load(Ordering::Relaxed);
+        stats.pictures_dropped = lock(&self.output.layers.state).dropped;
+        if let Some(realtime) = &self.realtime {
+            stats.realtime_framerate_ratio = Some(realtime.ratio());
+            stats.realtime_adjustments = realtime.adjustments();
+        }
+        stats.decode_latency = lock(&self.output.context.latencies).percentiles();
+        stats
+    }
+
+    /// Returns an estimate of memory held by the decoder: pending input data
+    /// and picture buffers.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage::new(
+            self.input.number_of_input_bytes_pending(),
+            self.input.stream_info.as_ref(),
+            self.input.context.allocations.picture_buffers(),
+        )
+    }
+
+    /// Reset all counters returned by [`Decoder::stats()`].
+    pub fn reset_stats(&mut self) {
+        self.input.stats = DecoderStats::default();
+        self.output.pictures_output.store(0, Ordering::Relaxed);
+        lock(&self.output.layers.state).dropped = 0;
+        lock(&self.output.context.latencies).clear();
+        if let Some(realtime) = &mut self.realtime {
+            realtime.reset_adjustments();
+        }
+    }
+
+    /// See [`DecoderInput::start_worker_threads()`].
+    pub fn start_worker_threads(&mut self, num_threads: u32) -> Result<()> {
+        self.input.start_worker_threads(num_threads)
+    }
+
+    /// See [`DecoderInput::num_worker_threads()`].
+    pub fn num_worker_threads(&self) -> u32 {
+        self.input.num_worker_threads()
+    }
+
+    /// See [`DecoderInput::push_data()`].
+    pub fn push_data(&mut self, data: &[u8], pts: i64, user_data: usize) -> Result<()> {
+        self.input.push_data(data, pts, user_data)
+    }
+
+    /// See [`DecoderInput::push_end_of_nal()`].
+    pub fn push_end_of_nal(&mut self) {
+        self.input.push_end_of_nal();
+    }
+
+    /// See [`DecoderInput::push_end_of_frame()`].
+    pub fn push_end_of_frame(&mut self) {
+        self.input.push_end_of_frame();
+    }
+
+    /// See [`DecoderInput::push_nals()`].
+    pub fn push_nals<'a>(
+        &mut self,
+        nals: impl IntoIterator<Item = &'a [u8]>,
+        pts: i64,
+        user_data: usize,
+    ) -> std::result::Result<usize, PushNalsError> {
+        self.input.push_nals(nals, pts, user_data)
+    }
+
+    /// See [`DecoderInput::push_access_unit()`].
+    pub fn push_access_unit(&mut self, nals: &[&[u8]], pts: i64, user_data: usize) -> Result<()> {
+        self.input.push_access_unit(nals, pts, user_data)
+    }
+
+    /// See [`DecoderInput::push_access_unit_annexb()`].
+    pub fn push_access_unit_annexb(
+        &mut self,
+        data: &[u8],
+        pts: i64,
+        user_data: usize,
+    ) -> Result<()> {
+        self.input.push_access_unit_annexb(data, pts, user_data)
+    }
+
+    /// See [`DecoderInput::bytes_per_tid()`].
+    pub fn bytes_per_tid(&self) -> Vec<(u8, u64)> {
+        self.input.bytes_per_tid()
+    }
+
+    /// See [`DecoderInput::set_bitrate_window()`].
+    pub fn set_bitrate_window(&mut self, window: Duration) {
+        self.input.set_bitrate_window(window);
+    }
+
+    /// See [`DecoderInput::bitrate_window()`].
+    pub fn bitrate_window(&self) -> Duration {
+        self.input.bitrate_window()
+    }
+
+    /// See [`DecoderInput::bitrate()`].
+    pub fn bitrate(&self) -> Option<u64> {
+        self.input.bitrate()
+    }
+
+    /// See [`DecoderInput::push_nal()`].
+    pub fn push_nal(&mut self, data: &[u8], pts: i64, user_data: usize) -> Result<()> {
+        self.input.push_nal(data, pts, user_data)
+    }
+
+    /// See [`DecoderInput::flush_data()`].
+    pub fn flush_data(&mut self) -> Result<()> {
+        self.input.flush_data()
+    }
+
+    /// See [`DecoderInput::try_push_data()`].
+    pub fn try_push_data(&mut self, data: &[u8], pts: i64, user_data: usize) -> Result<PushResult> {
+        self.input.try_push_data(data, pts, user_data)
+    }
+
+    /// See [`DecoderInput::set_max_pending_bytes()`].
+    pub fn set_max_pending_bytes(&mut self, limit: usize) {
+        self.input.set_max_pending_bytes(limit);
+    }
+
+    /// See [`DecoderInput::max_pending_bytes()`].
+    pub fn max_pending_bytes(&self) -> usize {
+        self.input.max_pending_bytes()
+    }
+
+    /// See [`DecoderInput::remaining_input_capacity()`].
+    pub fn remaining_input_capacity(&self) -> usize {
+        self.input.remaining_input_capacity()
+    }
+
+    /// See [`DecoderInput::is_flushed()`].
+    pub fn is_flushed(&self) -> bool {
+        self.input.is_flushed()
+    }
+
+    /// Returns `true` if the stream was flushed and completely decoded,
+    /// and all decoded pictures were extracted from the output queue.
+    pub fn is_finished(&self) -> bool {
+        self.input.flushed
+            && self.input.decoded_to_end
+            && self.input.number_of_input_bytes_pending() == 0
+            && self.input.number_of_nal_units_pending() == 0
+            && !self.output.has_pending_pictures()
+    }
+
+    /// See [`DecoderInput::number_of_input_bytes_pending()`].
+    pub fn number_of_input_bytes_pending(&self) -> usize {
+        self.input.number_of_input_bytes_pending()
+    }
+
+    /// See [`DecoderInput::number_of_nal_units_pending()`].
+    pub fn number_of_nal_units_pending(&self) -> usize {
+        self.input.number_of_nal_units_pending()
+    }
+
+    /// See [`DecoderInput::decode()`].
+    ///
+    /// If the real-time target is set (see [`Decoder::set_realtime_target()`]),
+    /// the framerate ratio is adjusted before decoding.
+    pub fn decode(&mut self) -> Result<DecodeResult> {
+        self.update_realtime();
+        self.input.decode()
+    }
+
+    /// Set the target interval between output pictures for real-time decoding.
+    ///
+    /// When the target is set, decoding drivers ([`Decoder::decode()`] and methods
+    /// based on it) measure durations between output pictures, including the time
+    /// spent by the application to handle them. If pictures are output slower
+    /// than the target, the framerate ratio (see [`DecoderInput::set_framerate_ratio()`])
+    /// is lowered step by step, but not below the floor
+    /// (see [`Decoder::set_realtime_floor()`]). When the decoder catches up,
+    /// the ratio is raised back up to 100 percent.
+    ///
+    /// The current ratio is returned in [`DecoderStats::realtime_framerate_ratio`].
+    /// Note that `libde265` drops pictures only by temporal layers, so the ratio
+    /// has no effect on streams with a single temporal layer.
+    ///
+    /// `None` disables the real-time mode and restores the ratio to 100 percent.
+    pub fn set_realtime_target(&mut self, target: Option<Duration>) {
+        match target {
+            Some(target) => {
+                let ratio = self.input.framerate_ratio();
+                let mut realtime = RealtimeController::new(target, self.realtime_floor, ratio);
+                let pictures_output = self.output.pictures_output.load(Ordering::Relaxed);
+                realtime.on_pictures(pictures_output, Instant::now());
+                if realtime.ratio() != ratio {
+                    self.input.set_framerate_ratio(realtime.ratio());
+                }
+                self.realtime = Some(realtime);
+            }
+            None => {
+                if self.realtime.take().is_some() {
+                    self.input.set_framerate_ratio(100);
+                }
+            }
+        }
+    }
+
+    /// Returns the target interval between output pictures for real-time decoding.
+    pub fn realtime_target(&self) -> Option<Duration> {
+        self.realtime.as_ref().map(|realtime| realtime.target())
+    }
+
+    /// Set the minimal framerate ratio in percent set in the real-time mode
+    /// (see [`Decoder::set_realtime_target()`]). The default is 25 percent.
+    ///
+    /// Values greater than 100 are clamped to 100.
+    pub fn set_realtime_floor(&mut self, percent: u8) {
+        self.realtime_floor = percent.min(100);
+        let new_ratio = self
+            .realtime
+            .as_mut()
+            .and_then(|realtime| realtime.set_floor(self.realtime_floor));
+        if let Some(ratio) = new_ratio {
+            self.input.set_framerate_ratio(ratio);
+        }
+    }
+
+    /// Returns the minimal framerate ratio set in the real-time mode.
+    pub fn realtime_floor(&self) -> u8 {
+        self.realtime_floor
+    }
+
+    fn update_realtime(&mut self) {
+        let Some(realtime) = &mut self.realtime else {
+            return;
+        };
+        let pictures_output = self.output.pictures_output.load(Ordering::Relaxed);
+        if let Some(ratio) = realtime.on_pictures(pictures_output, Instant::now()) {
+            self.input.set_framerate_ratio(ratio);
+        }
+    }
+
+    /// See [`DecoderInput::decode_some()`].
+    pub fn decode_some(&mut self, max_steps: u32) -> Result<DecodeSomeResult> {
+        self.input.decode_some(max_steps)
+    }
+
+    /// See [`DecoderInput::reset()`].
+    ///
+    /// Images returned by the decoder borrow it, but an image
+    /// leaked by [`std::mem::forget()`] still holds its picture,
+    /// so resetting returns [`DeError::ErrorPictureIsBorrowed`].
+    pub fn reset(&mut self) -> Result<()> {
+        self.input.reset()?;
+        if let Some(realtime) = &mut self.realtime {
+            realtime.restart();
+        }
+        Ok(())
+    }
+
+    /// See [`DecoderInput::cancel()`].
+    pub fn cancel(&mut self) -> Result<()> {
+        self.input.cancel()
+    }
+
+    /// See [`DecoderInput::stream_info()`].
+    pub fn stream_info(&self) -> Option<StreamInfo> {
+        self.input.stream_info()
+    }
+
+    /// See [`DecoderInput::is_cancelled()`].
+    pub fn is_cancelled(&self) -> bool {
+        self.input.is_cancelled()
+    }
+
+    /// Prepare the decoder for decoding of another stream:
+    /// reset it and clear all counters, warnings and stream events.
+    ///
+    /// Returns `false` if the decoder can't be reused because it was cancelled
+    /// or a leaked image still holds its picture.
+    pub(crate) fn recycle(&mut self) -> bool {
+        if self.input.is_cancelled() || self.reset().is_err() {
+            return false;
+        }
+        self.input.warnings().for_each(drop);
+        self.reset_stats();
+        *lock(&self.output.events) = StreamEvents::default();
+        lock(&self.output.layers.state).seen.clear();
+        true
+    }
+
+    /// Free the decoder and return an error reported by `libde265`,
+    /// instead of ignoring it on drop.
+    ///
+    /// Pending work is abandoned without decoding.
+    pub fn close(self) -> Result<()> {
+        let Decoder { input, output, .. } = self;
+        let context = input.context.clone();
+        drop(input);
+        drop(output);
+        match Arc::try_unwrap(context) {
+            Ok(mut context) => context.free(),
+            // Unreachable, because images borrow the decoder.
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// See [`DecoderInput::set_timebase()`].
+    pub fn set_timebase(&mut self, timebase: TimeBase) {
+        self.input.set_timebase(timebase);
+    }
+
+    /// See [`DecoderInput::timebase()`].
+    pub fn timebase(&self) -> TimeBase {
+        self.input.timebase()
+    }
+
+    /// See [`DecoderInput::set_skip_until_keyframe()`].
+    pub fn set_skip_until_keyframe(&mut self, enable: bool) {
+        self.input.set_skip_until_keyframe(enable);
+    }
+
+    /// See [`DecoderInput::is_skipping_until_keyframe()`].
+    pub fn is_skipping_until_keyframe(&self) -> bool {
+        self.input.is_skipping_until_keyframe()
+    }
+
+    /// See [`DecoderInput::set_strict_input()`].
+    pub fn set_strict_input(&mut self, enable: bool) {
+        self.input.set_strict_input(enable);
+    }
+
+    /// See [`DecoderInput::is_strict_input()`].
+    pub fn is_strict_input(&self) -> bool {
+        self.input.is_strict_input()
+    }
+
+    /// See [`DecoderInput::set_low_delay()`].
+    pub fn set_low_delay(&mut self, enable: bool) {
+        self.input.set_low_delay(enable);
+    }
+
+    /// See [`DecoderInput::is_low_delay()`].
+    pub fn is_low_delay(&self) -> bool {
+        self.input.is_low_delay()
+    }
+
+    /// See [`DecoderOutput::appears_reordered()`].
+    pub fn appears_reordered(&self) -> bool {
+        self.output.appears_reordered()
+    }
+
+    /// See [`DecoderOutput::next_picture()`].
+    pub fn next_picture(&self) -> Option<Image<'_>> {
+        self.output.next_picture()
+    }
+
+    /// See [`DecoderOutput::peek_picture()`].
+    pub fn peek_picture(&mut self) -> Option<Image<'_>> {
+        self.output.peek_picture()
+    }
+
+    /// See [`DecoderOutput::recv_picture()`].
+    pub fn recv_picture(&self, timeout: Option<Duration>) -> Result<Option<Image<'_>>> {
+        self.output.recv_picture(timeout)
+    }
+
+    /// See [`DecoderOutput::pop_picture()`].
+    pub fn pop_picture(&mut self) -> bool {
+        self.output.pop_picture()
+    }
+
+    /// See [`DecoderOutput::set_layer_filter()`].
+    pub fn set_layer_filter(&mut self, layer_id: Option<u8>) {
+        self.output.set_layer_filter(layer_id);
+    }
+
+    /// See [`DecoderOutput::layer_filter()`].
+    pub fn layer_filter(&self) -> Option<u8> {
+        self.output.layer_filter()
+    }
+
+    /// See [`DecoderOutput::layers_seen()`].
+    pub fn layers_seen(&self) -> BTreeSet<u8> {
+        self.output.layers_seen()
+    }
+
+    /// See [`DecoderOutput::take_events()`].
+    pub fn take_events(&mut self) -> Vec<StreamEvent> {
+        self.output.take_events()
+    }
+
+    /// See [`DecoderOutput::has_pending_pictures()`].
+    pub fn has_pending_pictures(&self) -> bool {
+        self.output.has_pending_pictures()
+    }
+
+    /// See [`DecoderOutput::frames()`].
+    pub fn frames(&self) -> Frames<'_> {
+        self.output.frames()
+    }
+
+    /// Decode data until the next picture is available.
+    ///
+    /// The `feed` callback is invoked each time the decoder needs more input
+    /// data. It should push data into the given decoder input and return `true`,
+    /// or return `false` at the end of the stream, in which case the pending data
+    /// is flushed.
+    ///
+    /// Returns `None` when the end of the stream is reached
+    /// and all pictures have been output. Until the stream is flushed,
+    /// [`DecodeResult::Done`] returned by the decoder after the end
+    /// of a frame (e.g. pushed by [`DecoderInput::push_access_unit()`])
+    /// doesn't mean the end of the stream, so `feed` is invoked again.
+    pub fn decode_next_picture(
+        &mut self,
+        mut feed: impl FnMut(&mut DecoderInput) -> Result<bool>,
+    ) -> Result<Option<Image<'_>>> {
+        loop {
+            if self.output.has_pending_pictures() {
+                return Ok(self.output.next_picture());
+            }
+            match self.decode()? {
+                DecodeResult::Done if self.input.is_flushed() => {
+                    return Ok(self.output.next_picture());
+                }
+                DecodeResult::CallAgain | DecodeResult::OutputQueueFull => {}
+                DecodeResult::Done | DecodeResult::NeedsMoreInput => {
+                    if !feed(&mut self.input)? {
+                        self.input.flush_data()?;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Decode an h265 bytestream read from the given reader.
+    ///
+    /// The data is pushed into the decoder by chunks, and the end of stream
+    /// is flushed when the reader returns EOF. The `on_image` callback is invoked
+    /// for each decoded picture in output order. Returning [`ControlFlow::Break`]
+    /// from the callback stops decoding and resets the decoder.
+    pub fn decode_from_reader<R: Read>(
+        &mut self,
+        mut reader: R,
+        mut on_image: impl FnMut(Image<'_>) -> ControlFlow<()>,
+    ) -> std::result::Result<(), StreamError> {
+        let mut buf = vec![0; READ_BUFFER_SIZE];
+        loop {
+            let result = self.decode()?;
+            let flow = self.output.frames().try_for_each(&mut on_image);
+            if flow.is_break() {
+                self.reset()?;
+                return Ok(());
+            }
+            match result {
+                DecodeResult::Done => return Ok(()),
+                DecodeResult::CallAgain | DecodeResult::OutputQueueFull => {}
+                DecodeResult::NeedsMoreInput => match reader.read(&mut buf) {
+                    Ok(0) => self.flush_data()?, // EOF
+                    Ok(size) => self.push_data(&buf[0..size], 0, 0)?,
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                    Err(err) => return Err(err.into()),
+                },
+            }
+        }
+    }
+
+    /// Decode an h265 bytestream provided by the `input_source` callback
+    /// until the end of the stream.
+    ///
+    /// The `input_source` callback is invoked each time the decoder needs more
+    /// input data. It receives an empty buffer, should append the next chunk
+    /// of the stream to it, and return the number of appended bytes.
+    /// Zero means the end of the stream, in which case the pending data is
+    /// flushed ([`Decoder::flush_data()`] is called exactly once).
+    /// Errors of kind [`io::ErrorKind::Interrupted`] are ignored,
+    /// and the callback is invoked again.
+    ///
+    /// The `on_frame` callback is invoked for each decoded picture in output order.
+    ///
+    /// Unlike [`Decoder::decode_from_reader()`], the input doesn't have to
+    /// implement [`Read`], so data may be fed from sockets, ring buffers, etc.
+    pub fn run_until_eof(
+        &mut self,
+        mut input_source: impl FnMut(&mut Vec<u8>) -> io::Result<usize>,
+        mut on_frame: impl FnMut(Image<'_>),
+    ) -> std::result::Result<(), StreamError> {
+        let mut buf = Vec::with_capacity(READ_BUFFER_SIZE);
+        let mut flushed = false;
+        loop {
+            let result = self.decode()?;
+            self.output.frames().for_each(&mut on_frame);
+            match result {
+                DecodeResult::Done => return Ok(()),
+                DecodeResult::CallAgain | DecodeResult::OutputQueueFull => {}
+                // The stream has been flushed, there is nothing to wait for.
+                DecodeResult::NeedsMoreInput if flushed => return Ok(()),
+                DecodeResult::NeedsMoreInput => {
+                    buf.clear();
+                    match input_source(&mut buf) {
+                        Ok(0) => {
+                            self.flush_data()?;
+                            flushed = true;
+                        }
+                        Ok(size) => self.push_data(&buf[..size.min(buf.len())], 0, 0)?,
+                        Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                        Err(err) => return Err(err.into()),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Decode NAL units provided by the `source` until the end of the stream.
+    ///
+    /// NAL units are requested from the source each time the decoder needs
+    /// more input data. The end of frame is indicated after NAL units with
+    /// [`SourceNal::is_access_unit_end`](crate::SourceNal::is_access_unit_end) set. When the source returns `None`,
+    /// the pending data is flushed.
+    ///
+    /// The `on_frame` callback is invoked for each decoded picture in output order.
+    pub fn run_from_source(
+        &mut self,
+        source: &mut impl NalSource,
+        mut on_frame: impl FnMut(Image<'_>),
+    ) -> std::result::Result<(), StreamError> {
+        let mut flushed = false;
+        loop {
+            let result = self.decode()?;
+            self.output.frames().for_each(&mut on_frame);
+            match result {
+                DecodeResult::CallAgain | DecodeResult::OutputQueueFull => {}
+                DecodeResult::Done | DecodeResult::NeedsMoreInput if flushed => return Ok(()),
+                // The decoder reports `Done` after decoding of a frame
+                // whose end was indicated, but the stream may continue.
+                DecodeResult::Done | DecodeResult::NeedsMoreInput => match source.next_nal()? {
+                    Some(nal) => {
+                        self.push_nal(nal.data, nal.pts, 0)?;
+                        if nal.is_access_unit_end {
+                            self.push_end_of_frame();
+                        }
+                    }
+                    None => {
+                        self.flush_data()?;
+                        flushed = true;
+                    }
+                },
+            }
+        }
     }
 }
@@ -0,0 +1,55 @@
+use image::{DynamicImage, ImageBuffer};
+
+use crate::rgb::high_bit_depth;
+use crate::{Image, OwnedImage, Rgb16ImageBuffer, RgbImageBuffer};
+
+impl Image<'_> {
+    /// Convert the picture into [`DynamicImage`] of the `image` crate.
+    ///
+    /// Pictures with 8 bits per sample are converted into
+    /// [`DynamicImage::ImageRgb8`] by [`Image::to_rgb8()`], pictures
+    /// with high bit depth - into [`DynamicImage::ImageRgb16`]
+    /// by [`Image::to_rgb16()`].
+    pub fn to_dynamic_image(&self) -> DynamicImage {
+        if high_bit_depth(|channel| self.bits_per_pixel(channel)) {
+            rgb16_to_dynamic(self.to_rgb16())
+        } else {
+            rgb8_to_dynamic(self.to_rgb8())
+        }
+    }
+}
+
+impl OwnedImage {
+    /// See [`Image::to_dynamic_image()`].
+    pub fn to_dynamic_image(&self) -> DynamicImage {
+        if high_bit_depth(|channel| self.bits_per_pixel(channel)) {
+            rgb16_to_dynamic(self.to_rgb16())
+        } else {
+            rgb8_to_dynamic(self.to_rgb8())
+        }
+    }
+}
+
+impl From<&Image<'_>> for DynamicImage {
+    fn from(image: &Image<'_>) -> Self {
+        image.to_dynamic_image()
+    }
+}
+
+impl From<&OwnedImage> for DynamicImage {
+    fn from(image: &OwnedImage) -> Self {
+        image.to_dynamic_image()
+    }
+}
+
+fn rgb8_to_dynamic(buffer: RgbImageBuffer) -> DynamicImage {
+    let buffer = ImageBuffer::from_raw(buffer.width, buffer.height, buffer.data)
+        .expect("size of RGB data always matches dimensions of the picture");
+    DynamicImage::ImageRgb8(buffer)
+}
+
+fn rgb16_to_dynamic(buffer: Rgb16ImageBuffer) -> DynamicImage {
+    let buffer = ImageBuffer::from_raw(buffer.width, buffer.height, buffer.data)
+        .expect("size of RGB data always matches dimensions of the picture");
+    DynamicImage::ImageRgb16(buffer)
+}
@@ -1,9 +1,12 @@
 use std::borrow::Cow;
 use std::ffi::CStr;
+use std::io;
 
 use libde265_sys::de265_error as de;
 use thiserror::Error;
 
+use crate::{Channel, ChromaFormat, InputKind};
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Error)]
 #[non_exhaustive]
 pub enum DeError {
@@ -109,12 +112,154 @@ pub enum DeError {
     WarningReferenceImageChromaFormatDoesNotMatch,
     #[error("Warning: Invalid slice header index access")]
     WarningInvalidSliceHeaderIndexAccess,
+    #[error("Error: Number of worker threads must be greater than zero")]
+    ErrorInvalidNumberOfWorkerThreads,
+    #[error("Error: Worker threads are already started")]
+    ErrorWorkerThreadsAlreadyStarted,
+    #[error("Error: Decoded picture is still borrowed by an image")]
+    ErrorPictureIsBorrowed,
+    #[error("Error: Decoding was cancelled")]
+    ErrorDecoderCancelled,
+    #[error("Error: Timed out waiting for a decoded picture")]
+    ErrorTimedOut,
+    #[error("Error: Input data is not a raw h265 stream ({0:?})")]
+    ErrorInvalidInputFormat(InputKind),
+    #[error("Error: Destination buffer is too small ({0} bytes are required)")]
+    ErrorBufferTooSmall(usize),
+    #[error("Error: Stride of destination buffer is too small ({0} bytes are required)")]
+    ErrorStrideTooSmall(usize),
     #[error("{}", get_error_text(*.0))]
     Unknown(u32),
 }
 
 pub type Result<T> = std::result::Result<T, DeError>;
 
+/// Error of decoding a stream read from an external source.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum StreamError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Decoder(#[from] DeError),
+}
+
+/// Error of probing a stream by [`probe_stream()`](crate::probe_stream).
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ProbeError {
+    #[error("No valid SPS within the probed part of the stream")]
+    NotFound,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Error of parsing an acceleration method from a string.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Error)]
+#[error("Unknown acceleration method: {0}")]
+pub struct ParseAccelerationError(pub String);
+
+/// Error of parsing HEVC decoder configuration record.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Error)]
+#[non_exhaustive]
+pub enum HvccError {
+    #[error("Unexpected end of HEVC decoder configuration record")]
+    UnexpectedEnd,
+    #[error("Unsupported version of HEVC decoder configuration record: {0}")]
+    UnsupportedVersion(u8),
+    #[error("Invalid size of NAL unit length field: {0}")]
+    InvalidNalLengthSize(u8),
+    #[error("Zero-length NAL unit in HEVC decoder configuration record")]
+    ZeroLengthNalUnit,
+}
+
+/// Error of pushing a sample with length-prefixed NAL units.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Error)]
+#[non_exhaustive]
+pub enum SampleError {
+    #[error("Invalid size of NAL unit length field: {0}")]
+    InvalidNalLengthSize(u8),
+    #[error("Truncated NAL unit length field at offset {offset}")]
+    TruncatedNalLength { offset: usize },
+    #[error("NAL unit at offset {offset} with length {length} overruns the sample")]
+    NalLengthOverrun { offset: usize, length: usize },
+    #[error(transparent)]
+    Decoder(#[from] DeError),
+}
+
+/// Error of pushing a batch of NAL units by
+/// [`DecoderInput::push_nals()`](crate::DecoderInput::push_nals).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Error)]
+#[error("Failed to push NAL unit with index {index}: {source}")]
+pub struct PushNalsError {
+    /// Index of the NAL unit that wasn't pushed.
+    pub index: usize,
+    pub source: DeError,
+}
+
+/// Error of writing pictures by [`y4m::Writer`](crate::y4m::Writer).
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Y4mError {
+    #[error("YUV4MPEG2 output of pictures with {0} bits per pixel is not supported")]
+    UnsupportedBitDepth(u32),
+    #[error("Size or chroma format of the picture differs from the stream header")]
+    FormatChanged,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Error of comparison of pictures by functions of
+/// the [`compare`](crate::compare) module.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Error)]
+#[non_exhaustive]
+pub enum CompareError {
+    #[error("Chroma formats of pictures differ: {0:?} and {1:?}")]
+    ChromaFormatMismatch(ChromaFormat, ChromaFormat),
+    #[error("Sizes of {channel:?} planes differ: {first:?} and {second:?}")]
+    SizeMismatch {
+        channel: Channel,
+        first: (u32, u32),
+        second: (u32, u32),
+    },
+    #[error("Bit depths of {channel:?} planes differ: {first} and {second}")]
+    BitDepthMismatch {
+        channel: Channel,
+        first: u32,
+        second: u32,
+    },
+}
+
+/// Error of saving pictures into PNG files by
+/// [`Image::save_png()`](crate::Image::save_png) and
+/// [`Image::save_plane_png()`](crate::Image::save_plane_png).
+#[cfg(feature = "png")]
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum PngError {
+    #[error("The picture has no {0:?} plane")]
+    NoPlane(Channel),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Encoding(#[from] png::EncodingError),
+}
+
+/// Error of access to samples of a plane by [`PlaneView`](crate::PlaneView).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Error)]
+#[non_exhaustive]
+pub enum PlaneViewError {
+    #[error("Row {row} is out of bounds of the plane with height {height}")]
+    RowOutOfBounds { row: u32, height: u32 },
+    #[error("Size of samples of the plane is {bytes_per_sample} bytes, not {element_size}")]
+    SampleSizeMismatch {
+        bytes_per_sample: usize,
+        element_size: usize,
+    },
+    #[error("Rows of the plane are not aligned to {alignment} bytes")]
+    Misaligned { alignment: usize },
+}
+
 impl DeError {
     pub fn from_raw(raw: de::Type) -> Result<()> {
         let error = match raw {
@@ -0,0 +1,115 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::decoder::READ_BUFFER_SIZE;
+use crate::{DeError, DecodeResult, Decoder, OwnedImage, StreamError, StreamInfo};
+
+/// Open the h265 file and decode it.
+///
+/// The file is read and decoded by chunks while iterating over returned frames.
+/// The stream info is available as soon as the first frame is decoded.
+pub fn decode_file(path: impl AsRef<Path>) -> Result<DecodedFrames<File>, StreamError> {
+    let file = File::open(path)?;
+    DecodedFrames::new(file)
+}
+
+/// Iterator over owned frames decoded from a reader.
+///
+/// It is created by the [`decode_file()`] function.
+/// If decoding fails, the iterator yields all frames decoded so far
+/// followed by the error, and then it yields nothing.
+/// If the decoder reported warnings at the end of the stream (e.g. because
+/// the stream is truncated), the first of them is yielded as a trailing error,
+/// all of them are available by [`DecodedFrames::warnings()`].
+pub struct DecodedFrames<R> {
+    decoder: Decoder,
+    reader: R,
+    buf: Vec<u8>,
+    frames: VecDeque<OwnedImage>,
+    info: Option<StreamInfo>,
+    warnings: Vec<DeError>,
+    error: Option<StreamError>,
+    finished: bool,
+}
+
+impl<R: Read> DecodedFrames<R> {
+    /// Create an iterator over frames decoded from the given reader.
+    pub fn new(reader: R) -> Result<Self, StreamError> {
+        let mut decoded_frames = Self {
+            decoder: Decoder::new()?,
+            reader,
+            buf: vec![0; READ_BUFFER_SIZE],
+            frames: VecDeque::new(),
+            info: None,
+            warnings: Vec::new(),
+            error: None,
+            finished: false,
+        };
+        decoded_frames.fill_frames();
+        if !decoded_frames.frames.is_empty() {
+            decoded_frames.info = decoded_frames.decoder.stream_info();
+        }
+        Ok(decoded_frames)
+    }
+
+    /// Returns information about the stream
+    /// or `None` if the stream doesn't contain frames.
+    pub fn stream_info(&self) -> Option<StreamInfo> {
+        self.info
+    }
+
+    /// Returns warnings reported by the decoder at the end of the stream.
+    pub fn warnings(&self) -> &[DeError] {
+        &self.warnings
+    }
+
+    fn fill_frames(&mut self) {
+        if let Err(err) = self.decode_next_frames() {
+            self.error = Some(err);
+            self.finished = true;
+        }
+    }
+
+    /// Drive the decoder until some frames are available or the end of the stream.
+    fn decode_next_frames(&mut self) -> Result<(), StreamError> {
+        while self.frames.is_empty() && !self.finished {
+            let result = self.decoder.decode()?;
+            for image in self.decoder.frames() {
+                self.frames.push_back(image.to_owned());
+            }
+            match result {
+                DecodeResult::Done => {
+                    self.finished = true;
+                    self.warnings.extend(self.decoder.input_mut().warnings());
+                    if let Some(&warning) = self.warnings.first() {
+                        return Err(warning.into());
+                    }
+                }
+                DecodeResult::CallAgain | DecodeResult::OutputQueueFull => {}
+                DecodeResult::NeedsMoreInput => match self.reader.read(&mut self.buf) {
+                    Ok(0) => self.decoder.flush_data()?, // EOF
+                    Ok(size) => self.decoder.push_data(&self.buf[0..size], 0, 0)?,
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                    Err(err) => return Err(err.into()),
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for DecodedFrames<R> {
+    type Item = Result<OwnedImage, StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.frames.is_empty() {
+            self.fill_frames();
+        }
+        match self.frames.pop_front() {
+            Some(frame) => Some(Ok(frame)),
+            None => self.error.take().map(Err),
+        }
+    }
+}
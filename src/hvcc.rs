@@ -0,0 +1,204 @@
+use crate::{DecoderInput, HvccError, Result, SampleError};
+
+/// HEVC decoder configuration record (the payload of `hvcC` box)
+/// as defined in ISO/IEC 14496-15.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct HvccConfig {
+    pub configuration_version: u8,
+    pub general_profile_space: u8,
+    pub general_tier_flag: bool,
+    pub general_profile_idc: u8,
+    pub general_profile_compatibility_flags: u32,
+    /// 48-bit value.
+    pub general_constraint_indicator_flags: u64,
+    pub general_level_idc: u8,
+    pub min_spatial_segmentation_idc: u16,
+    pub parallelism_type: u8,
+    pub chroma_format_idc: u8,
+    pub bit_depth_luma: u8,
+    pub bit_depth_chroma: u8,
+    pub avg_frame_rate: u16,
+    pub constant_frame_rate: u8,
+    pub num_temporal_layers: u8,
+    pub temporal_id_nested: bool,
+    /// Size in bytes of the NAL unit length field in samples - 1, 2 or 4.
+    pub nal_length_size: u8,
+    pub arrays: Vec<HvccNalArray>,
+}
+
+/// Array of NAL units of the same type stored in [`HvccConfig`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HvccNalArray {
+    pub array_completeness: bool,
+    pub nal_unit_type: u8,
+    /// NAL units without start codes.
+    pub nal_units: Vec<Vec<u8>>,
+}
+
+impl HvccConfig {
+    /// Parse HEVC decoder configuration record.
+    pub fn parse(data: &[u8]) -> std::result::Result<Self, HvccError> {
+        let mut reader = ByteReader { data };
+        let configuration_version = reader.read_u8()?;
+        if configuration_version != 1 {
+            return Err(HvccError::UnsupportedVersion(configuration_version));
+        }
+        let byte = reader.read_u8()?;
+        let general_profile_space = byte >> 6;
+        let general_tier_flag = byte & 0x20 != 0;
+        let general_profile_idc = byte & 0x1f;
+        let general_profile_compatibility_flags = reader.read_uint(4)? as u32;
+        let general_constraint_indicator_flags = reader.read_uint(6)?;
+        let general_level_idc = reader.read_u8()?;
+        let min_spatial_segmentation_idc = reader.read_uint(2)? as u16 & 0x0fff;
+        let parallelism_type = reader.read_u8()? & 0x03;
+        let chroma_format_idc = reader.read_u8()? & 0x03;
+        let bit_depth_luma = (reader.read_u8()? & 0x07) + 8;
+        let bit_depth_chroma = (reader.read_u8()? & 0x07) + 8;
+        let avg_frame_rate = reader.read_uint(2)? as u16;
+        let byte = reader.read_u8()?;
+        let constant_frame_rate = byte >> 6;
+        let num_temporal_layers = (byte >> 3) & 0x07;
+        let temporal_id_nested = byte & 0x04 != 0;
+        let nal_length_size = (byte & 0x03) + 1;
+        if nal_length_size == 3 {
+            return Err(HvccError::InvalidNalLengthSize(nal_length_size));
+        }
+
+        let num_of_arrays = reader.read_u8()?;
+        let mut arrays = Vec::with_capacity(num_of_arrays as usize);
+        for _ in 0..num_of_arrays {
+            let byte = reader.read_u8()?;
+            let num_nalus = reader.read_uint(2)? as usize;
+            let mut nal_units = Vec::with_capacity(num_nalus.min(reader.data.len() / 2));
+            for _ in 0..num_nalus {
+                let nal_unit_length = reader.read_uint(2)? as usize;
+                if nal_unit_length == 0 {
+                    return Err(HvccError::ZeroLengthNalUnit);
+                }
+                nal_units.push(reader.read_bytes(nal_unit_length)?.to_vec());
+            }
+            arrays.push(HvccNalArray {
+                array_completeness: byte & 0x80 != 0,
+                nal_unit_type: byte & 0x3f,
+                nal_units,
+            });
+        }
+
+        Ok(Self {
+            configuration_version,
+            general_profile_space,
+            general_tier_flag,
+            general_profile_idc,
+            general_profile_compatibility_flags,
+            general_constraint_indicator_flags,
+            general_level_idc,
+            min_spatial_segmentation_idc,
+            parallelism_type,
+            chroma_format_idc,
+            bit_depth_luma,
+            bit_depth_chroma,
+            avg_frame_rate,
+            constant_frame_rate,
+            num_temporal_layers,
+            temporal_id_nested,
+            nal_length_size,
+            arrays,
+        })
+    }
+
+    /// Returns an iterator over all NAL units (parameter sets and SEI)
+    /// stored in the configuration record.
+    pub fn nal_units(&self) -> impl Iterator<Item = &[u8]> {
+        self.arrays
+            .iter()
+            .flat_map(|array| array.nal_units.iter().map(|nal| nal.as_slice()))
+    }
+}
+
+impl DecoderInput {
+    /// Push all NAL units stored in HEVC decoder configuration record
+    /// (VPS, SPS, PPS, etc.) into the decoder.
+    ///
+    /// This method should be called before pushing samples
+    /// of the corresponding track.
+    pub fn push_hvcc_config(&mut self, config: &HvccConfig) -> Result<()> {
+        for nal in config.nal_units() {
+            self.push_nal(nal, 0, 0)?;
+        }
+        Ok(())
+    }
+
+    /// Push a sample (access unit) that contains NAL units prefixed
+    /// with their big-endian length, as they are stored in MP4 files.
+    ///
+    /// The `nal_length_size` is a size in bytes of the length field - 1, 2 or 4
+    /// (see [`HvccConfig::nal_length_size`]).
+    /// The whole sample is validated before pushing any NAL unit into the decoder.
+    /// After pushing all NAL units, the end of frame is indicated.
+    pub fn push_sample(
+        &mut self,
+        data: &[u8],
+        nal_length_size: u8,
+        pts: i64,
+        user_data: usize,
+    ) -> std::result::Result<(), SampleError> {
+        if !matches!(nal_length_size, 1 | 2 | 4) {
+            return Err(SampleError::InvalidNalLengthSize(nal_length_size));
+        }
+        let length_size = nal_length_size as usize;
+        let mut nal_units = Vec::new();
+        let mut offset = 0;
+        while offset < data.len() {
+            let Some(length_bytes) = data.get(offset..offset + length_size) else {
+                return Err(SampleError::TruncatedNalLength { offset });
+            };
+            let length = length_bytes
+                .iter()
+                .fold(0, |value, &b| (value << 8) | b as usize);
+            let start = offset + length_size;
+            let nal = start
+                .checked_add(length)
+                .and_then(|end| data.get(start..end));
+            let Some(nal) = nal else {
+                return Err(SampleError::NalLengthOverrun { offset, length });
+            };
+            if !nal.is_empty() {
+                nal_units.push(nal);
+            }
+            offset = start + nal.len();
+        }
+
+        for nal in nal_units {
+            self.push_nal(nal, pts, user_data)?;
+        }
+        self.push_end_of_frame();
+        Ok(())
+    }
+}
+
+struct ByteReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    fn read_bytes(&mut self, size: usize) -> std::result::Result<&'a [u8], HvccError> {
+        if self.data.len() < size {
+            return Err(HvccError::UnexpectedEnd);
+        }
+        let (bytes, rest) = self.data.split_at(size);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> std::result::Result<u8, HvccError> {
+        self.read_bytes(1).map(|bytes| bytes[0])
+    }
+
+    /// Read big-endian unsigned integer with given size in bytes.
+    fn read_uint(&mut self, size: usize) -> std::result::Result<u64, HvccError> {
+        let bytes = self.read_bytes(size)?;
+        Ok(bytes.iter().fold(0, |value, &b| (value << 8) | b as u64))
+    }
+}
@@ -0,0 +1,88 @@
+use crate::nv12::{write_chroma_420, write_luma};
+use crate::{Channel, ChromaFormat, DeError, Image, OwnedImage, PlaneView, Result};
+
+impl Image<'_> {
+    /// Convert the picture into I420 format - 8-bit luma plane followed
+    /// by Cb and Cr planes with half width and height, all planes
+    /// without padding at the end of rows.
+    ///
+    /// Samples of pictures with high bit depth are reduced to 8 bits.
+    /// Chroma of 4:2:2 and 4:4:4 pictures is downsampled like in
+    /// [`Image::to_nv12()`], chroma of monochrome pictures is filled with 128.
+    pub fn to_i420(&self) -> Vec<u8> {
+        let (planes, chroma_format) = self.yuv_source();
+        to_i420(planes, chroma_format)
+    }
+
+    /// Size of the picture in I420 format in bytes. Sizes of chroma planes
+    /// are rounded up for odd width and height, so the size is equal to
+    /// `width * height + 2 * ((width + 1) / 2) * ((height + 1) / 2)`.
+    pub fn i420_size(&self) -> usize {
+        i420_size(self.width(Channel::Y), self.height(Channel::Y))
+    }
+
+    /// Same as [`Image::to_i420()`], but writes samples into `dst`.
+    ///
+    /// Returns the number of written bytes (see [`Image::i420_size()`])
+    /// or [`DeError::ErrorBufferTooSmall`] if `dst` is smaller.
+    pub fn write_i420_into(&self, dst: &mut [u8]) -> Result<usize> {
+        let (planes, chroma_format) = self.yuv_source();
+        write_i420(planes, chroma_format, dst)
+    }
+}
+
+impl OwnedImage {
+    /// See [`Image::to_i420()`].
+    pub fn to_i420(&self) -> Vec<u8> {
+        let (planes, chroma_format) = self.yuv_source();
+        to_i420(planes, chroma_format)
+    }
+
+    /// See [`Image::i420_size()`].
+    pub fn i420_size(&self) -> usize {
+        i420_size(self.width(Channel::Y), self.height(Channel::Y))
+    }
+
+    /// See [`Image::write_i420_into()`].
+    pub fn write_i420_into(&self, dst: &mut [u8]) -> Result<usize> {
+        let (planes, chroma_format) = self.yuv_source();
+        write_i420(planes, chroma_format, dst)
+    }
+}
+
+fn i420_size(width: u32, height: u32) -> usize {
+    let (width, height) = (width as usize, height as usize);
+    width * height + 2 * width.div_ceil(2) * height.div_ceil(2)
+}
+
+fn to_i420(planes: [(PlaneView<'_>, u32); 3], chroma_format: ChromaFormat) -> Vec<u8> {
+    let (luma, _) = planes[0];
+    let mut data = vec![0; i420_size(luma.width(), luma.height())];
+    // The buffer has the required size, so writing can't fail.
+    let _ = write_i420(planes, chroma_format, &mut data);
+    data
+}
+
+fn write_i420(
+    planes: [(PlaneView<'_>, u32); 3],
+    chroma_format: ChromaFormat,
+    dst: &mut [u8],
+) -> Result<usize> {
+    let [luma, cb, cr] = planes;
+    let width = luma.0.width() as usize;
+    let height = luma.0.height() as usize;
+    let size = i420_size(luma.0.width(), luma.0.height());
+    if dst.len() < size {
+        return Err(DeError::ErrorBufferTooSmall(size));
+    }
+    if size == 0 {
+        return Ok(0);
+    }
+    let chroma_size = (width.div_ceil(2), height.div_ceil(2));
+    let (y, chroma) = dst[..size].split_at_mut(width * height);
+    let (u, v) = chroma.split_at_mut(chroma.len() / 2);
+    write_luma(luma, y, width);
+    write_chroma_420(cb, chroma_format, chroma_size, u, chroma_size.0, 1);
+    write_chroma_420(cr, chroma_format, chroma_size, v, chroma_size.0, 1);
+    Ok(size)
+}
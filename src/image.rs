@@ -1,11 +1,20 @@
-use std::ffi::CStr;
+use std::any::Any;
+use std::borrow::Cow;
+use std::cell::OnceCell;
+use std::fmt;
 use std::os::raw::{c_char, c_int, c_void};
 use std::ptr;
 use std::ptr::NonNull;
+use std::time::Duration;
 
 use libde265_sys::*;
 
-use crate::DecoderContext;
+use crate::decoder::lock;
+use crate::sei::SeiMetadata;
+use crate::{
+    DeError, DecoderContext, NalUnitType, OwnedImage, PlaneToken, PlaneView, PlaneViewError,
+    Result, TimeBase,
+};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Channel {
@@ -32,41 +41,189 @@ pub enum ChromaFormat {
     C444,
 }
 
-#[derive(Debug, Copy, Clone)]
+impl ChromaFormat {
+    pub(crate) fn from_raw(raw: de265_chroma::Type) -> Self {
+        match raw {
+            de265_chroma::de265_chroma_mono => Self::Mono,
+            de265_chroma::de265_chroma_420 => Self::C420,
+            de265_chroma::de265_chroma_422 => Self::C422,
+            de265_chroma::de265_chroma_444 => Self::C444,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns horizontal and vertical divisors of sizes of luma planes
+    /// that give sizes of chroma planes, e.g. `(2, 2)` for 4:2:0.
+    ///
+    /// Returns `(0, 0)` for monochrome pictures that have no chroma planes.
+    pub const fn subsampling(self) -> (u8, u8) {
+        match self {
+            Self::Mono => (0, 0),
+            Self::C420 => (2, 2),
+            Self::C422 => (2, 1),
+            Self::C444 => (1, 1),
+        }
+    }
+
+    /// Returns `true` if pictures have chroma planes.
+    pub const fn has_chroma(self) -> bool {
+        !matches!(self, Self::Mono)
+    }
+
+    /// Returns the number of planes of pictures (1 or 3).
+    pub const fn plane_count(self) -> usize {
+        if self.has_chroma() { 3 } else { 1 }
+    }
+
+    /// Returns sizes of chroma planes of a picture with the given
+    /// sizes of the luma plane, or `None` for monochrome pictures.
+    ///
+    /// Odd sizes are rounded up like in the decoder, e.g. chroma planes
+    /// of a 4:2:0 picture with 5x3 luma plane have 3x2 samples.
+    pub const fn chroma_dimensions(self, luma_width: u32, luma_height: u32) -> Option<(u32, u32)> {
+        let (sub_width, sub_height) = self.subsampling();
+        if !self.has_chroma() {
+            return None;
+        }
+        Some((
+            luma_width.div_ceil(sub_width as u32),
+            luma_height.div_ceil(sub_height as u32),
+        ))
+    }
+
+    /// Returns channels of planes of pictures.
+    pub(crate) fn channels(self) -> &'static [Channel] {
+        &[Channel::Y, Channel::Cb, Channel::Cr][..self.plane_count()]
+    }
+}
+
+#[derive(Copy, Clone)]
 pub struct NalHeader {
-    pub unit_type: u8,
-    pub unit_name: &'static CStr,
+    pub unit_type: NalUnitType,
     pub layer_id: u8,
     pub temporal_id: u8,
 }
 
+impl NalHeader {
+    /// Returns the mnemonic of the NAL unit type, e.g. `IDR_W_RADL`
+    /// (see [`NalUnitType::name()`]).
+    pub fn unit_name(&self) -> &'static str {
+        self.unit_type.name()
+    }
+}
+
+impl fmt::Debug for NalHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NalHeader")
+            .field(
+                "unit_type",
+                &format_args!("{} ({})", self.unit_type, self.unit_type.to_u8()),
+            )
+            .field("layer_id", &self.layer_id)
+            .field("temporal_id", &self.temporal_id)
+            .finish()
+    }
+}
+
 pub struct Image<'a> {
     inner: *const de265_image,
-    decoder: &'a DecoderContext,
+    pub(crate) decoder: &'a DecoderContext,
+    /// The picture is checked out by [`DecoderOutput::next_picture()`](crate::DecoderOutput::next_picture)
+    /// and must be released from the output queue, otherwise it is peeked.
+    release: bool,
+    /// Decoding errors and warnings attributed to the picture.
+    issues: Vec<DeError>,
+    /// Time from pushing of the picture to its output.
+    latency: Option<Duration>,
+    /// Metadata from SEI messages pushed before the picture.
+    pub(crate) sei: SeiMetadata,
+    /// Payload moved from the decoder by [`Image::user_data_ref()`].
+    pub(crate) payload: OnceCell<Option<Box<dyn Any + Send>>>,
 }
 
 impl<'a> Drop for Image<'a> {
     fn drop(&mut self) {
-        unsafe { de265_release_next_picture(self.decoder.inner) };
+        let token = self.token();
+        {
+            let context = self.decoder.lock();
+            let mut checked_out = lock(&self.decoder.checked_out);
+            if !self.release {
+                checked_out.peeked -= 1;
+            } else if checked_out.head == Some(self.inner as usize) {
+                unsafe { de265_release_next_picture(context.ptr()) };
+                checked_out.head = None;
+            } else {
+                // The picture was released from the output queue
+                // when the next one was checked out.
+                checked_out.released -= 1;
+            }
+        }
+        if self.release {
+            self.decoder.payloads.remove(token);
+        } else if let Some(Some(payload)) = self.payload.take() {
+            // The payload is kept for the output of the peeked picture.
+            self.decoder.payloads.restore(token, payload);
+        }
     }
 }
 
 impl<'a> Image<'a> {
-    pub(crate) fn new(decoder: &'a DecoderContext, ptr: NonNull<de265_image>) -> Self {
-        Self {
+    pub(crate) fn new(
+        decoder: &'a DecoderContext,
+        ptr: NonNull<de265_image>,
+        release: bool,
+    ) -> Self {
+        {
+            let mut checked_out = lock(&decoder.checked_out);
+            if release {
+                checked_out.head = Some(ptr.as_ptr() as usize);
+            } else {
+                checked_out.peeked += 1;
+            }
+        }
+        // Issues are attributed to the picture only when it is taken
+        // from the output queue, a peeked picture just shows them.
+        let issues = if release {
+            std::mem::take(&mut *lock(&decoder.issues))
+        } else {
+            lock(&decoder.issues).clone()
+        };
+        let token = unsafe { de265_get_image_user_data(ptr.as_ptr()) } as usize;
+        let latency = decoder
+            .payloads
+            .pushed_at(token)
+            .map(|pushed_at| pushed_at.elapsed());
+        let sei = decoder.payloads.sei(token);
+        if release {
+            if let Some(latency) = latency {
+                lock(&decoder.latencies).push(latency);
+            }
+            let pts = unsafe { de265_get_image_PTS(ptr.as_ptr()) };
+            lock(&decoder.output_order).observe(pts as i64);
+        }
+        let image = Self {
             inner: ptr.as_ptr(),
             decoder,
+            release,
+            issues,
+            latency,
+            sei,
+            payload: OnceCell::new(),
+        };
+        #[cfg(feature = "tracing")]
+        if release {
+            tracing::debug!(
+                pts = image.pts(),
+                width = image.width(Channel::Y),
+                height = image.height(Channel::Y),
+                "picture output"
+            );
         }
+        image
     }
 
     pub fn chroma_format(&self) -> ChromaFormat {
-        match unsafe { de265_get_chroma_format(self.inner) } {
-            de265_chroma::de265_chroma_mono => ChromaFormat::Mono,
-            de265_chroma::de265_chroma_420 => ChromaFormat::C420,
-            de265_chroma::de265_chroma_422 => ChromaFormat::C422,
-            de265_chroma::de265_chroma_444 => ChromaFormat::C444,
-            _ => unreachable!(),
-        }
+        ChromaFormat::from_raw(unsafe { de265_get_chroma_format(self.inner) })
     }
 
     pub fn width(&self, channel: Channel) -> u32 {
@@ -85,6 +242,10 @@ impl<'a> Image<'a> {
     }
 
     /// Returns the plane data and bytes per line (stride).
+    ///
+    /// Samples of planes with more than 8 bits per pixel are stored
+    /// as 16-bit words in native byte order, so the data contains raw bytes
+    /// of these words. Use [`Image::plane_u16()`] to get such samples.
     pub fn plane(&self, channel: Channel) -> (&[u8], usize) {
         let mut stride: c_int = 0;
         let buf = unsafe { de265_get_image_plane(self.inner, channel.index(), &mut stride) };
@@ -96,21 +257,190 @@ impl<'a> Image<'a> {
         (unsafe { std::slice::from_raw_parts(buf, size) }, stride)
     }
 
+    /// Returns samples of a plane with more than 8 bits per pixel
+    /// and samples per line (stride).
+    ///
+    /// Returns `None` if the plane has 8 or less bits per pixel.
+    /// The samples are borrowed from the image, unless the plane data
+    /// is not aligned to 2 bytes (e.g. if it is provided by a custom
+    /// [`ImageAllocator`](crate::ImageAllocator)), in which case they are copied.
+    pub fn plane_u16(&self, channel: Channel) -> Option<(Cow<'_, [u16]>, usize)> {
+        if self.bits_per_pixel(channel) <= 8 {
+            return None;
+        }
+        let (buf, stride) = self.plane(channel);
+        Some((samples_u16(buf), stride / 2))
+    }
+
+    /// Returns a view of the plane memory that allows to interpret
+    /// samples as `u8` or `u16` without copying.
+    pub fn plane_view(&self, channel: Channel) -> PlaneView<'_> {
+        let (data, stride) = self.plane(channel);
+        let bytes_per_sample = self.bits_per_pixel(channel).div_ceil(8) as usize;
+        PlaneView::new(
+            data,
+            stride,
+            self.width(channel),
+            self.height(channel),
+            bytes_per_sample,
+        )
+    }
+
+    /// Returns the value of the sample of the plane at the given position
+    /// widened to `u16` regardless of the bit depth.
+    ///
+    /// Returns `None` if the position is outside of the plane or
+    /// the picture doesn't have the plane (e.g. chroma planes of
+    /// monochrome pictures), see [`PlaneView::sample()`].
+    pub fn sample(&self, channel: Channel, x: u32, y: u32) -> Option<u16> {
+        self.plane_view(channel).sample(x, y)
+    }
+
+    /// Returns an iterator over bytes of samples of rows of the plane
+    /// without padding at the end of rows.
+    ///
+    /// Each row contains `width * bytes_per_sample` bytes, see
+    /// [`PlaneView::rows()`].
+    pub fn rows(
+        &self,
+        channel: Channel,
+    ) -> impl ExactSizeIterator<Item = &[u8]> + DoubleEndedIterator {
+        self.plane_view(channel).rows()
+    }
+
+    /// Returns an iterator over samples of rows of a plane with more
+    /// than 8 bits per pixel, see [`PlaneView::rows_u16()`].
+    pub fn rows_u16(
+        &self,
+        channel: Channel,
+    ) -> std::result::Result<
+        impl ExactSizeIterator<Item = &[u16]> + DoubleEndedIterator,
+        PlaneViewError,
+    > {
+        self.plane_view(channel).rows_u16()
+    }
+
+    /// Copy samples of the plane into `dst` without padding at the end of rows.
+    ///
+    /// Samples of planes with more than 8 bits per pixel are copied
+    /// as 16-bit words in native byte order. Returns the number of written
+    /// bytes (`width * height * bytes_per_sample`) or
+    /// [`DeError::ErrorBufferTooSmall`] if `dst` is smaller.
+    pub fn copy_plane_into(&self, channel: Channel, dst: &mut [u8]) -> Result<usize> {
+        let view = self.plane_view(channel);
+        let row_size = view.width() as usize * view.bytes_per_sample();
+        let size = row_size * view.height() as usize;
+        if dst.len() < size {
+            return Err(DeError::ErrorBufferTooSmall(size));
+        }
+        if size > 0 {
+            for (src_row, dst_row) in view.as_u8_rows().zip(dst.chunks_exact_mut(row_size)) {
+                dst_row.copy_from_slice(src_row);
+            }
+        }
+        Ok(size)
+    }
+
+    /// Returns samples of the plane without padding at the end of rows,
+    /// see [`Image::copy_plane_into()`].
+    pub fn plane_to_vec(&self, channel: Channel) -> Vec<u8> {
+        let bytes_per_sample = self.bits_per_pixel(channel).div_ceil(8) as usize;
+        let size = self.width(channel) as usize * self.height(channel) as usize * bytes_per_sample;
+        let mut data = vec![0; size];
+        let written = self.copy_plane_into(channel, &mut data).unwrap_or(0);
+        data.truncate(written);
+        data
+    }
+
+    /// Returns user data of the plane provided by [`ImageAllocator`](crate::ImageAllocator).
+    ///
+    /// It is null for planes allocated by the default allocator.
     pub fn plane_user_data(&self, channel: Channel) -> *mut c_void {
-        unsafe { de265_get_image_plane_user_data(self.inner, channel.index()) }
+        let raw = unsafe { de265_get_image_plane_user_data(self.inner, channel.index()) };
+        self.decoder
+            .allocations
+            .plane_user_data(raw as usize, channel)
+            .unwrap_or(raw)
     }
 
+    /// Returns the token of the plane assigned by [`ImageAllocator`](crate::ImageAllocator)
+    /// with [`PlaneBuffers::set_with_token()`](crate::PlaneBuffers::set_with_token).
+    ///
+    /// It allows finding the buffer the plane was decoded into.
+    /// Returns `None` for planes allocated by the default allocator
+    /// or without a token.
+    pub fn plane_token(&self, channel: Channel) -> Option<PlaneToken> {
+        let raw = unsafe { de265_get_image_plane_user_data(self.inner, channel.index()) };
+        self.decoder.allocations.plane_token(raw as usize, channel)
+    }
+
+    /// Returns user data passed into the decoder with the data of the picture.
     pub fn user_data(&self) -> usize {
+        self.decoder.payloads.user_data(self.token())
+    }
+
+    /// Returns user data passed into the decoder with the data
+    /// of the picture as a pointer, or `None` if it is zero.
+    pub fn user_data_ptr(&self) -> Option<NonNull<c_void>> {
+        NonNull::new(self.user_data() as *mut c_void)
+    }
+
+    /// Returns the raw user data of the picture, that is a token
+    /// for pictures pushed by [`DecoderInput::push_nal()`](crate::DecoderInput::push_nal).
+    pub(crate) fn token(&self) -> usize {
         let ptr = unsafe { de265_get_image_user_data(self.inner) };
         ptr as usize
     }
 
-    /// The presentation time stamp in microseconds.
+    /// Returns the time from pushing of the first slice segment of the picture
+    /// by [`DecoderInput::push_nal()`](crate::DecoderInput::push_nal)
+    /// (or methods based on it) to returning of the picture from the output queue.
+    ///
+    /// Returns `None` for pictures whose data was pushed
+    /// by [`DecoderInput::push_data()`](crate::DecoderInput::push_data).
+    pub fn decode_latency(&self) -> Option<Duration> {
+        self.latency
+    }
+
+    /// The presentation time stamp in units of the decoder time base
+    /// (microseconds by default, see [`DecoderInput::set_timebase()`](crate::DecoderInput::set_timebase)).
     pub fn pts(&self) -> i64 {
         let value = unsafe { de265_get_image_PTS(self.inner) };
         value as i64
     }
 
+    /// The presentation time stamp as a duration.
+    ///
+    /// Returns `None` if the PTS is negative.
+    pub fn pts_duration(&self) -> Option<Duration> {
+        self.decoder.timebase().to_duration(self.pts())
+    }
+
+    /// The presentation time stamp converted into the given time base.
+    pub fn pts_in(&self, timebase: TimeBase) -> i64 {
+        self.decoder.timebase().rescale(self.pts(), timebase)
+    }
+
+    /// Returns `true` if no decoding errors or warnings
+    /// were attributed to the picture (see [`Image::decode_issues()`]).
+    pub fn decode_was_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Returns decoding errors and warnings attributed to the picture.
+    ///
+    /// The attribution is approximate: these are errors returned by
+    /// [`DecoderInput::decode()`](crate::DecoderInput::decode) and warnings
+    /// issued by the decoder after the previous picture was taken
+    /// from the output queue. Because of reordering of pictures,
+    /// issues of a damaged picture may be attributed to a picture
+    /// that is output before it. Warnings suppressed by
+    /// [`DecoderInput::suppress_warnings()`](crate::DecoderInput::suppress_warnings)
+    /// are not attributed.
+    pub fn decode_issues(&self) -> &[DeError] {
+        &self.issues
+    }
+
     /// Get NAL-header information of this frame.
     pub fn nal_header(&self) -> NalHeader {
         let mut unit_type: c_int = 0;
@@ -128,15 +458,8 @@ impl<'a> Image<'a> {
             )
         }
 
-        let unit_name = if unit_name.is_null() {
-            c""
-        } else {
-            unsafe { CStr::from_ptr(unit_name) }
-        };
-
         NalHeader {
-            unit_type: c_int_to_u8(unit_type),
-            unit_name,
+            unit_type: NalUnitType::from_u8(c_int_to_u8(unit_type)),
             layer_id: c_int_to_u8(layer_id),
             temporal_id: c_int_to_u8(temporal_id),
         }
@@ -147,20 +470,90 @@ impl<'a> Image<'a> {
         value != 0
     }
 
+    /// Returns the raw `colour_primaries` code from VUI,
+    /// see [`Image::color_primaries_enum()`] for the typed value.
     pub fn colour_primaries(&self) -> u8 {
         let value = unsafe { de265_get_image_colour_primaries(self.inner) };
         c_int_to_u8(value)
     }
 
+    /// Returns the raw `transfer_characteristics` code from VUI, see
+    /// [`Image::transfer_characteristics_enum()`] for the typed value.
     pub fn transfer_characteristics(&self) -> u8 {
         let value = unsafe { de265_get_image_transfer_characteristics(self.inner) };
         c_int_to_u8(value)
     }
 
+    /// Returns the raw `matrix_coeffs` code from VUI,
+    /// see [`Image::matrix_coefficients_enum()`] for the typed value.
     pub fn matrix_coefficients(&self) -> u8 {
         let value = unsafe { de265_get_image_matrix_coefficients(self.inner) };
         c_int_to_u8(value)
     }
+
+    /// Copy the picture into an [`OwnedImage`] that doesn't borrow the decoder.
+    ///
+    /// Planes are copied with the same stride as in the decoder.
+    /// A payload attached by [`DecoderInput::push_nal_with()`](crate::DecoderInput::push_nal_with)
+    /// is not copied.
+    pub fn to_owned(&self) -> OwnedImage {
+        OwnedImage::from_image(self, false)
+    }
+
+    /// Copy the picture into an [`OwnedImage`] without padding
+    /// at the end of rows of planes to save memory.
+    pub fn to_owned_trimmed(&self) -> OwnedImage {
+        OwnedImage::from_image(self, true)
+    }
+}
+
+/// Prints metadata of the picture without its samples.
+impl fmt::Debug for Image<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let chroma_format = self.chroma_format();
+        let mut debug = f.debug_struct("Image");
+        debug.field("chroma_format", &chroma_format);
+        let names = ["y", "cb", "cr"];
+        for (&channel, name) in chroma_format.channels().iter().zip(names) {
+            debug.field(
+                name,
+                &format_args!(
+                    "{}x{} {}-bit",
+                    self.width(channel),
+                    self.height(channel),
+                    self.bits_per_pixel(channel)
+                ),
+            );
+        }
+        debug
+            .field("pts", &self.pts())
+            .field(
+                "nal_unit_type",
+                &format_args!("{}", self.nal_header().unit_type),
+            )
+            .field("full_range", &self.full_range())
+            .field("colour_primaries", &self.colour_primaries())
+            .field("transfer_characteristics", &self.transfer_characteristics())
+            .field("matrix_coefficients", &self.matrix_coefficients())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Interprets plane data as 16-bit samples, copies them
+/// if the data is not aligned.
+pub(crate) fn samples_u16(buf: &[u8]) -> Cow<'_, [u16]> {
+    let ptr = buf.as_ptr().cast::<u16>();
+    if ptr.is_aligned() {
+        // SAFETY: the pointer is aligned and the buffer contains
+        // `buf.len() / 2` initialized words.
+        Cow::Borrowed(unsafe { std::slice::from_raw_parts(ptr, buf.len() / 2) })
+    } else {
+        Cow::Owned(
+            buf.chunks_exact(2)
+                .map(|bytes| u16::from_ne_bytes([bytes[0], bytes[1]]))
+                .collect(),
+        )
+    }
 }
 
 #[inline(always)]
@@ -0,0 +1,92 @@
+use crate::{NalUnitType, split_annex_b};
+
+/// Maximal number of bytes at the beginning of a stream
+/// inspected to recognize a wrong input format.
+const MAX_CHECKED_BYTES: usize = 4096;
+
+/// Kind of input data that is not a raw h265 stream.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum InputKind {
+    /// MP4 (ISO BMFF) file.
+    Mp4,
+    /// Raw H.264 (AVC) stream.
+    H264,
+}
+
+/// Checker of the beginning of data pushed by
+/// [`DecoderInput::push_data()`](crate::DecoderInput::push_data).
+#[derive(Default)]
+pub(crate) struct InputFormatChecker {
+    prefix: Vec<u8>,
+    /// The format is recognized or the limit of inspected bytes is reached.
+    finished: bool,
+    wrong_kind: Option<InputKind>,
+}
+
+impl InputFormatChecker {
+    /// Inspect the next chunk of data and return the kind of input
+    /// if it is recognized as something other than h265 stream.
+    pub fn push(&mut self, data: &[u8]) -> Option<InputKind> {
+        if !self.finished {
+            let size = (MAX_CHECKED_BYTES - self.prefix.len()).min(data.len());
+            self.prefix.extend_from_slice(&data[..size]);
+            match check_prefix(&self.prefix) {
+                Some(Format::Hevc) => self.finished = true,
+                Some(Format::Wrong(kind)) => {
+                    self.finished = true;
+                    self.wrong_kind = Some(kind);
+                }
+                None => self.finished = self.prefix.len() == MAX_CHECKED_BYTES,
+            }
+            if self.finished {
+                self.prefix = Vec::new();
+            }
+        }
+        self.wrong_kind
+    }
+}
+
+enum Format {
+    Hevc,
+    Wrong(InputKind),
+}
+
+/// Recognize the format by high-confidence signatures only,
+/// so a valid h265 stream is never recognized as a wrong one.
+fn check_prefix(data: &[u8]) -> Option<Format> {
+    // MP4 file starts with a box: 32-bit size and 4-character type.
+    if let Some(header) = data.get(..8) {
+        let size = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+        let is_start_code = header[..3] == [0, 0, 1];
+        if size >= 8 && !is_start_code && matches!(&header[4..], b"ftyp" | b"moov") {
+            return Some(Format::Wrong(InputKind::Mp4));
+        }
+    }
+
+    let (mut h264_sps, mut h264_pps, mut h264_idr) = (false, false, false);
+    for nal in split_annex_b(data) {
+        let [first, second, ..] = *nal.data else {
+            continue;
+        };
+        // VPS, SPS or PPS of the base layer with zero temporal ID.
+        let nal_unit_type = NalUnitType::from_u8((first >> 1) & 0x3f);
+        if first & 0x81 == 0 && nal_unit_type.is_parameter_set() && second == 1 {
+            return Some(Format::Hevc);
+        }
+        // H.264 NAL header: forbidden_zero_bit, nal_ref_idc and nal_unit_type.
+        // Parameter sets and IDR slices always have non-zero nal_ref_idc.
+        if first & 0x80 == 0 && first & 0x60 != 0 {
+            match first & 0x1f {
+                5 => h264_idr = true,
+                7 => h264_sps = true,
+                8 => h264_pps = true,
+                _ => {}
+            }
+        }
+        if h264_sps && h264_pps && h264_idr {
+            return Some(Format::Wrong(InputKind::H264));
+        }
+    }
+    None
+}
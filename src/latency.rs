@@ -0,0 +1,57 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Number of the last output pictures whose latencies are used
+/// to compute [`LatencyPercentiles`].
+const LATENCY_WINDOW: usize = 1024;
+
+/// Percentiles of latency of decoding (see [`Image::decode_latency()`](crate::Image::decode_latency))
+/// of the last 1024 output pictures.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct LatencyPercentiles {
+    /// Number of pictures the percentiles are computed for.
+    pub samples: usize,
+    /// Median latency.
+    pub p50: Duration,
+    /// Latency not exceeded by 90% of pictures.
+    pub p90: Duration,
+    /// Latency not exceeded by 99% of pictures.
+    pub p99: Duration,
+    /// Maximal latency.
+    pub max: Duration,
+}
+
+/// Latencies of decoding of the last output pictures.
+#[derive(Default)]
+pub(crate) struct LatencyWindow {
+    latencies: VecDeque<Duration>,
+}
+
+impl LatencyWindow {
+    pub fn push(&mut self, latency: Duration) {
+        if self.latencies.len() == LATENCY_WINDOW {
+            self.latencies.pop_front();
+        }
+        self.latencies.push_back(latency);
+    }
+
+    pub fn clear(&mut self) {
+        self.latencies.clear();
+    }
+
+    pub fn percentiles(&self) -> Option<LatencyPercentiles> {
+        let mut sorted: Vec<Duration> = self.latencies.iter().copied().collect();
+        sorted.sort_unstable();
+        let max = *sorted.last()?;
+        // Nearest-rank method.
+        let percentile = |p: usize| sorted[(sorted.len() * p).div_ceil(100).max(1) - 1];
+        Some(LatencyPercentiles {
+            samples: sorted.len(),
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max,
+        })
+    }
+}
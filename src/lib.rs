@@ -3,13 +3,96 @@
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 #![doc = include_str!("../README.md")]
 
+mod allocator;
+#[cfg(feature = "ndarray")]
+mod array;
+mod bitrate;
+mod builder;
+mod checksum;
+mod color_info;
+mod color_primaries;
+mod content_light_level;
+mod crop_window;
 mod decoder;
+#[cfg(feature = "image")]
+mod dynamic_image;
 mod errors;
+mod file;
+mod hvcc;
+mod i420;
 mod image;
+mod input_format;
+mod latency;
+mod logging;
+mod luma;
+mod mastering_display;
+mod matrix_coefficients;
+mod memory;
+mod nal;
+mod nv12;
+mod owned_image;
+mod picture_hash;
+mod picture_type;
+mod plane_view;
+mod planes;
+#[cfg(feature = "png")]
+mod png_file;
+mod pnm;
+mod poc;
+mod pool;
+mod probe;
+mod raw_plane;
+mod realtime;
+mod rgb;
+mod sei;
+mod source;
+mod stream_info;
+mod thread_budget;
+mod timebase;
+mod transfer_characteristics;
+mod user_data;
+mod widen;
+mod yuv444;
 
+pub mod compare;
+pub mod y4m;
+
+pub use allocator::*;
+pub use builder::*;
+pub use color_info::*;
+pub use color_primaries::*;
+pub use content_light_level::*;
+pub use crop_window::*;
 pub use decoder::*;
 pub use errors::*;
+pub use file::*;
+pub use hvcc::*;
 pub use image::*;
+pub use input_format::*;
+pub use latency::*;
+pub use logging::*;
+pub use luma::*;
+pub use mastering_display::*;
+pub use matrix_coefficients::*;
+pub use memory::*;
+pub use nal::*;
+pub use nv12::*;
+pub use owned_image::*;
+pub use picture_hash::*;
+pub use picture_type::*;
+pub use plane_view::*;
+pub use planes::*;
+pub use pool::*;
+pub use probe::*;
+pub use raw_plane::*;
+pub use rgb::*;
+pub use source::*;
+pub use stream_info::*;
+pub use thread_budget::*;
+pub use timebase::*;
+pub use transfer_characteristics::*;
+pub use widen::*;
+pub use yuv444::*;
 
 /// Returns a version of a `libde265` library as an array of version parts -
 /// [major, minor, maintenance].
@@ -20,14 +103,6 @@ pub fn version() -> [u8; 3] {
     [major, minor, maintenance]
 }
 
-pub fn disable_logging() {
-    unsafe { libde265_sys::de265_disable_logging() };
-}
-
-pub fn set_verbosity(level: u8) {
-    unsafe { libde265_sys::de265_set_verbosity(level as _) };
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,94 @@
+use std::sync::Mutex;
+
+use crate::decoder::lock;
+
+/// Verbosity level of messages printed by `libde265`.
+///
+/// The level is global for all decoders, because `libde265`
+/// doesn't support per-decoder logging settings. Errors can't be
+/// suppressed by a level, use [`disable_logging()`] to suppress them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum VerbosityLevel {
+    /// Print errors only (default).
+    Errors,
+    /// Print errors and informational messages.
+    Info,
+    /// Print errors, informational and debug messages.
+    Debug,
+    /// Print all messages including tracing of decoding process.
+    Trace,
+}
+
+impl VerbosityLevel {
+    fn from_raw(level: u8) -> Self {
+        match level {
+            0 => Self::Errors,
+            1 => Self::Info,
+            2 => Self::Debug,
+            _ => Self::Trace,
+        }
+    }
+
+    fn as_raw(&self) -> u8 {
+        match self {
+            Self::Errors => 0,
+            Self::Info => 1,
+            Self::Debug => 2,
+            Self::Trace => 3,
+        }
+    }
+}
+
+/// The current verbosity level, because `libde265`
+/// doesn't provide a getter for it.
+static VERBOSITY: Mutex<VerbosityLevel> = Mutex::new(VerbosityLevel::Errors);
+
+pub fn disable_logging() {
+    unsafe { libde265_sys::de265_disable_logging() };
+}
+
+/// Set the global verbosity level of `libde265` from its raw value.
+///
+/// See [`set_verbosity_level()`].
+pub fn set_verbosity(level: u8) {
+    set_verbosity_level(VerbosityLevel::from_raw(level));
+}
+
+/// Set the global verbosity level of `libde265`.
+pub fn set_verbosity_level(level: VerbosityLevel) {
+    let mut current = lock(&VERBOSITY);
+    unsafe { libde265_sys::de265_set_verbosity(level.as_raw() as _) };
+    *current = level;
+}
+
+/// Returns the global verbosity level of `libde265` set
+/// by [`set_verbosity_level()`] or [`LoggingGuard`].
+pub fn verbosity_level() -> VerbosityLevel {
+    *lock(&VERBOSITY)
+}
+
+/// Guard that sets the global verbosity level of `libde265`
+/// and restores the previous level on drop.
+///
+/// Guards should be dropped in the reverse order of their creation,
+/// otherwise the restored level is the level of a dropped guard.
+#[must_use = "the previous verbosity level is restored when the guard is dropped"]
+pub struct LoggingGuard {
+    previous: VerbosityLevel,
+}
+
+impl LoggingGuard {
+    pub fn new(level: VerbosityLevel) -> Self {
+        let mut current = lock(&VERBOSITY);
+        let previous = *current;
+        unsafe { libde265_sys::de265_set_verbosity(level.as_raw() as _) };
+        *current = level;
+        Self { previous }
+    }
+}
+
+impl Drop for LoggingGuard {
+    fn drop(&mut self) {
+        set_verbosity_level(self.previous);
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{Channel, Image};
+
+/// Luma plane of a picture as 8-bit grayscale image,
+/// see [`Image::to_luma8()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LumaBuffer {
+    pub width: u32,
+    pub height: u32,
+    /// Samples row by row without padding.
+    pub data: Vec<u8>,
+}
+
+impl LumaBuffer {
+    pub fn pixel(&self, x: u32, y: u32) -> Option<u8> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.data
+            .get(y as usize * self.width as usize + x as usize)
+            .copied()
+    }
+}
+
+impl Image<'_> {
+    /// Returns the luma plane as 8-bit grayscale image without
+    /// padding at the end of rows.
+    ///
+    /// Samples with high bit depth are shifted to 8 bits with rounding.
+    /// If `full_range` is `true` and the picture has limited range
+    /// (see [`Image::full_range()`]), samples are expanded from
+    /// the range `16..=235` to `0..=255`.
+    pub fn to_luma8(&self, full_range: bool) -> LumaBuffer {
+        let bits = self.bits_per_pixel(Channel::Y);
+        let expand = full_range && !self.full_range();
+        let mut data = self.plane_to_vec(Channel::Y);
+        if bits > 8 {
+            data = data
+                .chunks_exact(2)
+                .map(|v| to_luma8(u16::from_ne_bytes([v[0], v[1]]) as u32, bits, expand))
+                .collect();
+        } else if expand {
+            data.iter_mut()
+                .for_each(|v| *v = to_luma8(*v as u32, bits, expand));
+        }
+        LumaBuffer {
+            width: self.width(Channel::Y),
+            height: self.height(Channel::Y),
+            data,
+        }
+    }
+}
+
+/// Converts a luma sample into 8 bits with rounding, `expand`
+/// maps limited range of samples into full one.
+fn to_luma8(value: u32, bits: u32, expand: bool) -> u8 {
+    let shift = bits.saturating_sub(8);
+    let value = if expand {
+        let black = 16 << shift;
+        let range = 219 << shift;
+        (value.saturating_sub(black) * 255 + range / 2) / range
+    } else {
+        (value + (1 << shift >> 1)) >> shift
+    };
+    value.min(u8::MAX as u32) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_expansion() {
+        for (value, expected) in [(0, 0), (16, 0), (17, 1), (126, 128), (235, 255), (255, 255)] {
+            assert_eq!(to_luma8(value, 8, true), expected, "{value}");
+        }
+        for (value, expected) in [(0, 0), (64, 0), (504, 128), (940, 255), (1023, 255)] {
+            assert_eq!(to_luma8(value, 10, true), expected, "{value}");
+        }
+    }
+
+    #[test]
+    fn shift_with_rounding() {
+        for (value, expected) in [
+            (0, 0),
+            (1, 0),
+            (2, 1),
+            (5, 1),
+            (6, 2),
+            (1020, 255),
+            (1023, 255),
+        ] {
+            assert_eq!(to_luma8(value, 10, false), expected, "{value}");
+        }
+        assert_eq!(to_luma8(200, 8, false), 200);
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{Image, OwnedImage};
+
+/// Colour volume of the display used to master the video
+/// (SMPTE ST 2086), it is carried by the mastering display
+/// colour volume SEI message of HDR10 streams.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct MasteringDisplay {
+    /// Chromaticity coordinates `(x, y)` of primaries in increments
+    /// of 0.00002, in the order of the SEI message
+    /// (usually green, blue and red).
+    pub primaries: [(u16, u16); 3],
+    /// Chromaticity coordinates `(x, y)` of the white point
+    /// in increments of 0.00002.
+    pub white_point: (u16, u16),
+    /// Maximal luminance of the display in units of 0.0001 cd/m².
+    pub max_luminance: u32,
+    /// Minimal luminance of the display in units of 0.0001 cd/m².
+    pub min_luminance: u32,
+}
+
+impl MasteringDisplay {
+    /// Parse the payload of the mastering display colour volume SEI message.
+    pub(crate) fn parse(payload: &[u8]) -> Option<Self> {
+        let payload: &[u8; 24] = payload.get(..24)?.try_into().ok()?;
+        let u16_at = |pos: usize| u16::from_be_bytes([payload[pos], payload[pos + 1]]);
+        let u32_at = |pos: usize| {
+            u32::from_be_bytes([
+                payload[pos],
+                payload[pos + 1],
+                payload[pos + 2],
+                payload[pos + 3],
+            ])
+        };
+        Some(Self {
+            primaries: [0, 4, 8].map(|pos| (u16_at(pos), u16_at(pos + 2))),
+            white_point: (u16_at(12), u16_at(14)),
+            max_luminance: u32_at(16),
+            min_luminance: u32_at(20),
+        })
+    }
+}
+
+impl Image<'_> {
+    /// Returns the mastering display colour volume (SMPTE ST 2086)
+    /// of the picture.
+    ///
+    /// `libde265` doesn't provide it, so it is parsed from prefix SEI
+    /// NAL units pushed by [`DecoderInput::push_nal()`](crate::DecoderInput::push_nal)
+    /// (and methods based on it). Returns `None` for pictures
+    /// pushed by [`DecoderInput::push_data()`](crate::DecoderInput::push_data)
+    /// and for pictures of coded video sequences without this SEI message.
+    pub fn mastering_display(&self) -> Option<&MasteringDisplay> {
+        self.sei.mastering_display.as_ref()
+    }
+}
+
+impl OwnedImage {
+    /// See [`Image::mastering_display()`].
+    pub fn mastering_display(&self) -> Option<&MasteringDisplay> {
+        self.sei.mastering_display.as_ref()
+    }
+}
@@ -0,0 +1,217 @@
+use std::fmt;
+
+use crate::{Image, OwnedImage};
+
+/// Matrix coefficients used to derive luma and chroma signals from RGB
+/// (`matrix_coeffs` of VUI), table 4 of ITU-T H.273.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MatrixCoefficients {
+    /// Identity matrix (code 0), planes contain G, B and R components.
+    Identity,
+    /// Rec. ITU-R BT.709 (code 1).
+    Bt709,
+    /// Unspecified (code 2), the matrix is determined by the application.
+    Unspecified,
+    /// United States Federal Communications Commission (code 4).
+    Fcc,
+    /// Rec. ITU-R BT.470 System B, G (code 5), also BT.601 625 lines.
+    Bt470Bg,
+    /// SMPTE 170M (code 6), also BT.601 525 lines.
+    Smpte170M,
+    /// SMPTE 240M (code 7).
+    Smpte240M,
+    /// YCgCo (code 8).
+    YCgCo,
+    /// Rec. ITU-R BT.2020 non-constant luminance system (code 9).
+    Bt2020Ncl,
+    /// Rec. ITU-R BT.2020 constant luminance system (code 10).
+    Bt2020Cl,
+    /// SMPTE ST 2085, Y'D'zD'x (code 11).
+    SmpteSt2085,
+    /// Chromaticity-derived non-constant luminance system (code 12).
+    ChromaDerivedNcl,
+    /// Chromaticity-derived constant luminance system (code 13).
+    ChromaDerivedCl,
+    /// ICtCp of Rec. ITU-R BT.2100 (code 14).
+    ICtCp,
+    /// Unknown code, it is reserved for future use by H.273
+    /// or isn't supported by this crate.
+    Reserved(u8),
+}
+
+/// Matrix used if the matrix of a picture is unknown
+/// or isn't defined by `Kr` and `Kb` constants.
+///
+/// BT.601 is used for pictures of standard definition
+/// (up to 576 rows and narrower than 1280 columns),
+/// BT.709 is used for other pictures.
+fn default_for_size(width: usize, height: usize) -> MatrixCoefficients {
+    if width >= 1280 || height > 576 {
+        MatrixCoefficients::Bt709
+    } else {
+        MatrixCoefficients::Smpte170M
+    }
+}
+
+impl MatrixCoefficients {
+    /// Returns the matrix by its H.273 code.
+    pub const fn from_code(code: u8) -> Self {
+        match code {
+            0 => Self::Identity,
+            1 => Self::Bt709,
+            2 => Self::Unspecified,
+            4 => Self::Fcc,
+            5 => Self::Bt470Bg,
+            6 => Self::Smpte170M,
+            7 => Self::Smpte240M,
+            8 => Self::YCgCo,
+            9 => Self::Bt2020Ncl,
+            10 => Self::Bt2020Cl,
+            11 => Self::SmpteSt2085,
+            12 => Self::ChromaDerivedNcl,
+            13 => Self::ChromaDerivedCl,
+            14 => Self::ICtCp,
+            _ => Self::Reserved(code),
+        }
+    }
+
+    /// Returns the H.273 code of the matrix.
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::Identity => 0,
+            Self::Bt709 => 1,
+            Self::Unspecified => 2,
+            Self::Fcc => 4,
+            Self::Bt470Bg => 5,
+            Self::Smpte170M => 6,
+            Self::Smpte240M => 7,
+            Self::YCgCo => 8,
+            Self::Bt2020Ncl => 9,
+            Self::Bt2020Cl => 10,
+            Self::SmpteSt2085 => 11,
+            Self::ChromaDerivedNcl => 12,
+            Self::ChromaDerivedCl => 13,
+            Self::ICtCp => 14,
+            Self::Reserved(code) => code,
+        }
+    }
+
+    /// Returns `Kr` and `Kb` constants of matrices that are defined by them.
+    fn kr_kb(self) -> Option<(f32, f32)> {
+        match self {
+            Self::Bt709 => Some((0.2126, 0.0722)),
+            Self::Fcc => Some((0.30, 0.11)),
+            Self::Bt470Bg | Self::Smpte170M => Some((0.299, 0.114)),
+            Self::Smpte240M => Some((0.212, 0.087)),
+            Self::Bt2020Ncl => Some((0.2627, 0.0593)),
+            _ => None,
+        }
+    }
+
+    /// Returns coefficients to convert normalized `Y'`, `Cb` and `Cr` values
+    /// into `R'G'B'` as `[cr_r, cb_g, cr_g, cb_b]`, so that:
+    ///
+    /// ```text
+    /// R = Y + cr_r * Cr
+    /// G = Y + cb_g * Cb + cr_g * Cr
+    /// B = Y + cb_b * Cb
+    /// ```
+    ///
+    /// Returns `None` for matrices that aren't defined by `Kr` and `Kb`
+    /// constants (e.g. [`MatrixCoefficients::Identity`],
+    /// [`MatrixCoefficients::YCgCo`] and constant luminance systems),
+    /// as well as for unspecified and reserved ones.
+    pub fn yuv_to_rgb_coefficients(self) -> Option<[f32; 4]> {
+        let (kr, kb) = self.kr_kb()?;
+        let kg = 1. - kr - kb;
+        Some([
+            2. * (1. - kr),
+            -2. * kb * (1. - kb) / kg,
+            -2. * kr * (1. - kr) / kg,
+            2. * (1. - kb),
+        ])
+    }
+
+    /// Returns coefficients used to convert pictures with the given size
+    /// into RGB. The default matrix chosen by the size of the picture
+    /// is used if the matrix doesn't have
+    /// [coefficients](MatrixCoefficients::yuv_to_rgb_coefficients).
+    pub(crate) fn conversion_coefficients(self, width: usize, height: usize) -> [f32; 4] {
+        self.yuv_to_rgb_coefficients()
+            .or_else(|| default_for_size(width, height).yuv_to_rgb_coefficients())
+            .unwrap_or_default()
+    }
+}
+
+impl From<u8> for MatrixCoefficients {
+    fn from(code: u8) -> Self {
+        Self::from_code(code)
+    }
+}
+
+impl From<MatrixCoefficients> for u8 {
+    fn from(value: MatrixCoefficients) -> Self {
+        value.code()
+    }
+}
+
+impl fmt::Display for MatrixCoefficients {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Identity => "Identity",
+            Self::Bt709 => "BT.709",
+            Self::Unspecified => "Unspecified",
+            Self::Fcc => "FCC",
+            Self::Bt470Bg => "BT.470 BG",
+            Self::Smpte170M => "SMPTE 170M",
+            Self::Smpte240M => "SMPTE 240M",
+            Self::YCgCo => "YCgCo",
+            Self::Bt2020Ncl => "BT.2020 NCL",
+            Self::Bt2020Cl => "BT.2020 CL",
+            Self::SmpteSt2085 => "SMPTE ST 2085",
+            Self::ChromaDerivedNcl => "Chroma-derived NCL",
+            Self::ChromaDerivedCl => "Chroma-derived CL",
+            Self::ICtCp => "ICtCp",
+            Self::Reserved(code) => return write!(f, "Reserved ({code})"),
+        };
+        f.write_str(name)
+    }
+}
+
+impl Image<'_> {
+    /// Returns the matrix coefficients of the picture,
+    /// see [`Image::matrix_coefficients()`] for the raw code.
+    pub fn matrix_coefficients_enum(&self) -> MatrixCoefficients {
+        MatrixCoefficients::from_code(self.matrix_coefficients())
+    }
+}
+
+impl OwnedImage {
+    /// See [`Image::matrix_coefficients_enum()`].
+    pub fn matrix_coefficients_enum(&self) -> MatrixCoefficients {
+        MatrixCoefficients::from_code(self.matrix_coefficients())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_matrix_by_size() {
+        let bt601 = MatrixCoefficients::Smpte170M.yuv_to_rgb_coefficients();
+        let bt709 = MatrixCoefficients::Bt709.yuv_to_rgb_coefficients();
+        let unspecified = MatrixCoefficients::Unspecified;
+        assert_eq!(Some(unspecified.conversion_coefficients(720, 576)), bt601);
+        assert_eq!(Some(unspecified.conversion_coefficients(1280, 720)), bt709);
+        assert_eq!(Some(unspecified.conversion_coefficients(960, 720)), bt709);
+        let identity = MatrixCoefficients::Identity;
+        assert_eq!(Some(identity.conversion_coefficients(1920, 1080)), bt709);
+        // Known matrices don't depend on the size.
+        let bt2020 = MatrixCoefficients::Bt2020Ncl;
+        assert_eq!(
+            Some(bt2020.conversion_coefficients(720, 576)),
+            bt2020.yuv_to_rgb_coefficients()
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use crate::StreamInfo;
+
+/// Estimate of memory held by a decoder, see [`Decoder::memory_usage()`](crate::Decoder::memory_usage).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MemoryUsage {
+    /// Number of bytes pushed into the decoder but not yet decoded.
+    pub input_bytes: usize,
+    /// Number of picture buffers held by the decoder, including
+    /// reference pictures and pictures waiting in the output queue.
+    ///
+    /// It is estimated from the size of the decoded picture buffer
+    /// signalled in the last SPS pushed into the decoder.
+    pub output_pictures: usize,
+    /// Size of planes of one picture in bytes.
+    ///
+    /// It is estimated from the dimensions and bit depth of pictures
+    /// in the last SPS. If a custom [`ImageAllocator`](crate::ImageAllocator)
+    /// is installed, it is the exact average size of buffers
+    /// provided by the allocator.
+    pub estimated_picture_bytes: usize,
+    /// Total number of bytes of pending input and picture buffers.
+    pub total_estimate: usize,
+}
+
+impl MemoryUsage {
+    /// `allocated` is the number of images allocated by a custom allocator
+    /// and the total size of their planes.
+    pub(crate) fn new(
+        input_bytes: usize,
+        stream_info: Option<&StreamInfo>,
+        (allocated_pictures, allocated_bytes): (usize, usize),
+    ) -> Self {
+        // Not all pictures are allocated by a custom allocator
+        // (e.g. pictures before applying of the SAO filter).
+        let output_pictures = stream_info
+            .map_or(0, |info| info.max_dec_pic_buffering as usize)
+            .max(allocated_pictures);
+        let estimated_picture_bytes = allocated_bytes
+            .checked_div(allocated_pictures)
+            .unwrap_or_else(|| stream_info.map_or(0, picture_size));
+        Self {
+            input_bytes,
+            output_pictures,
+            estimated_picture_bytes,
+            total_estimate: input_bytes + output_pictures * estimated_picture_bytes,
+        }
+    }
+}
+
+/// Size of planes of a picture with the given stream parameters.
+fn picture_size(info: &StreamInfo) -> usize {
+    let luma_size = (info.width * info.height) as usize;
+    let chroma_size = info
+        .chroma_format
+        .chroma_dimensions(info.width, info.height)
+        .map_or(0, |(width, height)| 2 * (width * height) as usize);
+    luma_size * info.bits_per_pixel.div_ceil(8) as usize
+        + chroma_size * info.chroma_bits_per_pixel.div_ceil(8) as usize
+}
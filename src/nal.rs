@@ -0,0 +1,528 @@
+use std::fmt;
+
+use crate::{Image, OwnedImage};
+
+/// NAL unit extracted from an Annex B byte stream.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NalUnit<'a> {
+    /// Offset of the first byte of the NAL unit (after the start code)
+    /// from the beginning of the stream.
+    pub offset: u64,
+    /// NAL unit data without the start code and trailing zero bytes.
+    pub data: &'a [u8],
+}
+
+/// Split an Annex B byte stream into NAL units.
+///
+/// Both 3-byte (`0x000001`) and 4-byte (`0x00000001`) start codes are supported.
+/// The data before the first start code is ignored.
+pub fn split_annex_b(data: &[u8]) -> AnnexBNalUnits<'_> {
+    let pos = find_start_code(data).map_or(data.len(), |pos| pos + 3);
+    AnnexBNalUnits { data, pos }
+}
+
+/// Iterator over NAL units of an Annex B byte stream.
+///
+/// It is created by the [`split_annex_b()`] function.
+#[derive(Debug, Clone)]
+pub struct AnnexBNalUnits<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Iterator for AnnexBNalUnits<'a> {
+    type Item = NalUnit<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pos < self.data.len() {
+            let start = self.pos;
+            let rest = &self.data[start..];
+            let end = match find_start_code(rest) {
+                Some(pos) => {
+                    self.pos = start + pos + 3;
+                    start + pos
+                }
+                None => {
+                    self.pos = self.data.len();
+                    self.data.len()
+                }
+            };
+            let nal_data = trim_trailing_zeros(&self.data[start..end]);
+            if !nal_data.is_empty() {
+                return Some(NalUnit {
+                    offset: start as u64,
+                    data: nal_data,
+                });
+            }
+        }
+        None
+    }
+}
+
+/// Incremental splitter of an Annex B byte stream into NAL units.
+///
+/// The stream may be pushed by chunks of arbitrary size,
+/// NAL units straddling chunk boundaries are handled correctly.
+///
+/// ```
+/// use libde265_rs::AnnexBSplitter;
+///
+/// let mut splitter = AnnexBSplitter::new();
+/// splitter.push(&[0, 0, 0, 1, 0x40, 0x01, 0x0c, 0, 0]);
+/// assert!(splitter.next_nal().is_none());
+/// splitter.push(&[1, 0x42, 0x01]);
+/// assert_eq!(splitter.next_nal().unwrap().data, &[0x40, 0x01, 0x0c]);
+/// assert!(splitter.next_nal().is_none());
+/// let last = splitter.finish().unwrap();
+/// assert_eq!(last.data, &[0x42, 0x01]);
+/// assert_eq!(last.offset, 10);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AnnexBSplitter {
+    buffer: Vec<u8>,
+    /// Offset of the first byte of the buffer from the beginning of the stream.
+    buffer_offset: u64,
+    /// Number of bytes at the beginning of the buffer that
+    /// were already returned and may be removed.
+    consumed: usize,
+    /// Position in the buffer of the first byte of the current NAL unit,
+    /// if a start code has been found.
+    nal_start: Option<usize>,
+    /// Position in the buffer from which searching a start code is resumed.
+    scan_pos: usize,
+}
+
+impl AnnexBSplitter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Push the next chunk of the stream.
+    pub fn push(&mut self, data: &[u8]) {
+        self.compact();
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Returns the next complete NAL unit if there is any.
+    ///
+    /// A NAL unit is complete when the start code of the following
+    /// NAL unit has been pushed.
+    pub fn next_nal(&mut self) -> Option<NalUnit<'_>> {
+        self.compact();
+        loop {
+            let rest = &self.buffer[self.scan_pos..];
+            let Some(pos) = find_start_code(rest) else {
+                // The last two bytes can be the beginning of a start code.
+                self.scan_pos = self.buffer.len().saturating_sub(2).max(self.scan_pos);
+                if self.nal_start.is_none() {
+                    // Skip data before the first start code.
+                    self.consumed = self.scan_pos;
+                }
+                return None;
+            };
+            let code_pos = self.scan_pos + pos;
+            self.scan_pos = code_pos + 3;
+            let Some(start) = self.nal_start.replace(code_pos + 3) else {
+                // Skip data before the first start code.
+                self.consumed = code_pos + 3;
+                continue;
+            };
+            self.consumed = code_pos + 3;
+            let nal_len = trim_trailing_zeros(&self.buffer[start..code_pos]).len();
+            if nal_len > 0 {
+                return Some(NalUnit {
+                    offset: self.buffer_offset + start as u64,
+                    data: &self.buffer[start..start + nal_len],
+                });
+            }
+        }
+    }
+
+    /// Indicate the end of the stream and return the last NAL unit if there is any.
+    ///
+    /// All pushed data is consumed, so the splitter may be used
+    /// to split the following stream.
+    pub fn finish(&mut self) -> Option<NalUnit<'_>> {
+        self.compact();
+        let start = self.nal_start.take();
+        self.consumed = self.buffer.len();
+        self.scan_pos = self.buffer.len();
+        let start = start?;
+        let nal_data = trim_trailing_zeros(&self.buffer[start..]);
+        if nal_data.is_empty() {
+            return None;
+        }
+        Some(NalUnit {
+            offset: self.buffer_offset + start as u64,
+            data: nal_data,
+        })
+    }
+
+    /// Remove already consumed bytes from the buffer.
+    fn compact(&mut self) {
+        if self.consumed == 0 {
+            return;
+        }
+        self.buffer.drain(..self.consumed);
+        self.buffer_offset += self.consumed as u64;
+        self.scan_pos -= self.consumed;
+        if let Some(start) = self.nal_start.as_mut() {
+            *start -= self.consumed;
+        }
+        self.consumed = 0;
+    }
+}
+
+/// Type of HEVC NAL unit (table 7-1 of H.265).
+///
+/// Variants are named after the mnemonics of the specification,
+/// reserved and unspecified types are represented by [`NalUnitType::Unknown`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum NalUnitType {
+    /// Coded slice segment of a non-TSA, non-STSA trailing picture
+    /// (sub-layer non-reference).
+    TrailN,
+    /// Coded slice segment of a non-TSA, non-STSA trailing picture.
+    TrailR,
+    /// Coded slice segment of a temporal sub-layer access (TSA) picture
+    /// (sub-layer non-reference).
+    TsaN,
+    /// Coded slice segment of a temporal sub-layer access (TSA) picture.
+    TsaR,
+    /// Coded slice segment of a step-wise temporal sub-layer access (STSA)
+    /// picture (sub-layer non-reference).
+    StsaN,
+    /// Coded slice segment of a step-wise temporal sub-layer access (STSA)
+    /// picture.
+    StsaR,
+    /// Coded slice segment of a random access decodable leading (RADL)
+    /// picture (sub-layer non-reference).
+    RadlN,
+    /// Coded slice segment of a random access decodable leading (RADL)
+    /// picture.
+    RadlR,
+    /// Coded slice segment of a random access skipped leading (RASL)
+    /// picture (sub-layer non-reference).
+    RaslN,
+    /// Coded slice segment of a random access skipped leading (RASL)
+    /// picture.
+    RaslR,
+    /// Coded slice segment of a broken link access (BLA) picture
+    /// that may have RASL pictures.
+    BlaWLp,
+    /// Coded slice segment of a broken link access (BLA) picture
+    /// that may have RADL pictures.
+    BlaWRadl,
+    /// Coded slice segment of a broken link access (BLA) picture
+    /// without leading pictures.
+    BlaNLp,
+    /// Coded slice segment of an instantaneous decoding refresh (IDR)
+    /// picture that may have RADL pictures.
+    IdrWRadl,
+    /// Coded slice segment of an instantaneous decoding refresh (IDR)
+    /// picture without leading pictures.
+    IdrNLp,
+    /// Coded slice segment of a clean random access (CRA) picture.
+    CraNut,
+    /// Video parameter set.
+    VpsNut,
+    /// Sequence parameter set.
+    SpsNut,
+    /// Picture parameter set.
+    PpsNut,
+    /// Access unit delimiter.
+    AudNut,
+    /// End of sequence.
+    EosNut,
+    /// End of bitstream.
+    EobNut,
+    /// Filler data.
+    FdNut,
+    /// Prefix supplemental enhancement information (SEI).
+    PrefixSeiNut,
+    /// Suffix supplemental enhancement information (SEI).
+    SuffixSeiNut,
+    /// Reserved (10-15, 22-31 and 41-47) or unspecified (48-63) type.
+    Unknown(u8),
+}
+
+/// Mnemonics of NAL unit types from table 7-1 of H.265.
+const NAL_UNIT_TYPE_NAMES: [&str; 64] = [
+    "TRAIL_N",
+    "TRAIL_R",
+    "TSA_N",
+    "TSA_R",
+    "STSA_N",
+    "STSA_R",
+    "RADL_N",
+    "RADL_R",
+    "RASL_N",
+    "RASL_R",
+    "RSV_VCL_N10",
+    "RSV_VCL_R11",
+    "RSV_VCL_N12",
+    "RSV_VCL_R13",
+    "RSV_VCL_N14",
+    "RSV_VCL_R15",
+    "BLA_W_LP",
+    "BLA_W_RADL",
+    "BLA_N_LP",
+    "IDR_W_RADL",
+    "IDR_N_LP",
+    "CRA_NUT",
+    "RSV_IRAP_VCL22",
+    "RSV_IRAP_VCL23",
+    "RSV_VCL24",
+    "RSV_VCL25",
+    "RSV_VCL26",
+    "RSV_VCL27",
+    "RSV_VCL28",
+    "RSV_VCL29",
+    "RSV_VCL30",
+    "RSV_VCL31",
+    "VPS_NUT",
+    "SPS_NUT",
+    "PPS_NUT",
+    "AUD_NUT",
+    "EOS_NUT",
+    "EOB_NUT",
+    "FD_NUT",
+    "PREFIX_SEI_NUT",
+    "SUFFIX_SEI_NUT",
+    "RSV_NVCL41",
+    "RSV_NVCL42",
+    "RSV_NVCL43",
+    "RSV_NVCL44",
+    "RSV_NVCL45",
+    "RSV_NVCL46",
+    "RSV_NVCL47",
+    "UNSPEC48",
+    "UNSPEC49",
+    "UNSPEC50",
+    "UNSPEC51",
+    "UNSPEC52",
+    "UNSPEC53",
+    "UNSPEC54",
+    "UNSPEC55",
+    "UNSPEC56",
+    "UNSPEC57",
+    "UNSPEC58",
+    "UNSPEC59",
+    "UNSPEC60",
+    "UNSPEC61",
+    "UNSPEC62",
+    "UNSPEC63",
+];
+
+impl NalUnitType {
+    /// Returns the type by the value of `nal_unit_type` field of NAL header.
+    pub const fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::TrailN,
+            1 => Self::TrailR,
+            2 => Self::TsaN,
+            3 => Self::TsaR,
+            4 => Self::StsaN,
+            5 => Self::StsaR,
+            6 => Self::RadlN,
+            7 => Self::RadlR,
+            8 => Self::RaslN,
+            9 => Self::RaslR,
+            16 => Self::BlaWLp,
+            17 => Self::BlaWRadl,
+            18 => Self::BlaNLp,
+            19 => Self::IdrWRadl,
+            20 => Self::IdrNLp,
+            21 => Self::CraNut,
+            32 => Self::VpsNut,
+            33 => Self::SpsNut,
+            34 => Self::PpsNut,
+            35 => Self::AudNut,
+            36 => Self::EosNut,
+            37 => Self::EobNut,
+            38 => Self::FdNut,
+            39 => Self::PrefixSeiNut,
+            40 => Self::SuffixSeiNut,
+            _ => Self::Unknown(value),
+        }
+    }
+
+    /// Returns the raw value of the type.
+    pub const fn to_u8(self) -> u8 {
+        match self {
+            Self::TrailN => 0,
+            Self::TrailR => 1,
+            Self::TsaN => 2,
+            Self::TsaR => 3,
+            Self::StsaN => 4,
+            Self::StsaR => 5,
+            Self::RadlN => 6,
+            Self::RadlR => 7,
+            Self::RaslN => 8,
+            Self::RaslR => 9,
+            Self::BlaWLp => 16,
+            Self::BlaWRadl => 17,
+            Self::BlaNLp => 18,
+            Self::IdrWRadl => 19,
+            Self::IdrNLp => 20,
+            Self::CraNut => 21,
+            Self::VpsNut => 32,
+            Self::SpsNut => 33,
+            Self::PpsNut => 34,
+            Self::AudNut => 35,
+            Self::EosNut => 36,
+            Self::EobNut => 37,
+            Self::FdNut => 38,
+            Self::PrefixSeiNut => 39,
+            Self::SuffixSeiNut => 40,
+            Self::Unknown(value) => value,
+        }
+    }
+
+    /// Returns the mnemonic of the type from the specification,
+    /// e.g. `IDR_W_RADL` or `RSV_VCL24`.
+    pub fn name(self) -> &'static str {
+        NAL_UNIT_TYPE_NAMES
+            .get(self.to_u8() as usize)
+            .copied()
+            .unwrap_or("INVALID")
+    }
+
+    /// Returns `true` for NAL units with coded slice segments (types 0-31).
+    pub fn is_vcl(self) -> bool {
+        self.to_u8() < 32
+    }
+
+    /// Returns `true` for slice segments of intra random access point
+    /// (IRAP) pictures: BLA, IDR, CRA and reserved IRAP types (16-23).
+    pub fn is_irap(self) -> bool {
+        (16..=23).contains(&self.to_u8())
+    }
+
+    /// Returns `true` for slice segments of IDR pictures (types 19 and 20).
+    pub fn is_idr(self) -> bool {
+        matches!(self, Self::IdrWRadl | Self::IdrNLp)
+    }
+
+    /// Returns `true` for slice segments of BLA pictures (types 16-18).
+    pub fn is_bla(self) -> bool {
+        matches!(self, Self::BlaWLp | Self::BlaWRadl | Self::BlaNLp)
+    }
+
+    /// Returns `true` for slice segments of RADL and RASL pictures (types 6-9).
+    pub fn is_leading(self) -> bool {
+        (6..=9).contains(&self.to_u8())
+    }
+
+    /// Returns `true` for slice segments of pictures that may be used for
+    /// reference by pictures of the same temporal sub-layer.
+    ///
+    /// Sub-layer non-reference pictures have even types from 0 to 14
+    /// (`TRAIL_N`, `TSA_N`, `STSA_N`, `RADL_N`, `RASL_N` and reserved ones),
+    /// all other VCL NAL unit types are reference ones.
+    pub fn is_reference(self) -> bool {
+        let value = self.to_u8();
+        value < 32 && (value > 14 || value % 2 == 1)
+    }
+
+    /// Returns `true` for video, sequence and picture parameter sets.
+    pub fn is_parameter_set(self) -> bool {
+        matches!(self, Self::VpsNut | Self::SpsNut | Self::PpsNut)
+    }
+
+    /// Returns `true` for prefix and suffix SEI NAL units.
+    pub fn is_sei(self) -> bool {
+        matches!(self, Self::PrefixSeiNut | Self::SuffixSeiNut)
+    }
+}
+
+impl From<u8> for NalUnitType {
+    fn from(value: u8) -> Self {
+        Self::from_u8(value)
+    }
+}
+
+impl From<NalUnitType> for u8 {
+    fn from(value: NalUnitType) -> Self {
+        value.to_u8()
+    }
+}
+
+impl fmt::Display for NalUnitType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Image<'_> {
+    /// Returns `true` if the picture is an intra random access point
+    /// (IRAP) picture, i.e. IDR, CRA or BLA picture.
+    /// Decoding may be started from such pictures.
+    pub fn is_irap(&self) -> bool {
+        self.nal_header().unit_type.is_irap()
+    }
+
+    /// Returns `true` if the picture is an IDR picture.
+    pub fn is_idr(&self) -> bool {
+        self.nal_header().unit_type.is_idr()
+    }
+
+    /// Returns `true` if the picture may be used for reference by pictures
+    /// of the same temporal sub-layer (see [`NalUnitType::is_reference()`]).
+    pub fn is_reference(&self) -> bool {
+        self.nal_header().unit_type.is_reference()
+    }
+}
+
+impl OwnedImage {
+    /// See [`Image::is_irap()`].
+    pub fn is_irap(&self) -> bool {
+        self.nal_header().unit_type.is_irap()
+    }
+
+    /// See [`Image::is_idr()`].
+    pub fn is_idr(&self) -> bool {
+        self.nal_header().unit_type.is_idr()
+    }
+
+    /// See [`Image::is_reference()`].
+    pub fn is_reference(&self) -> bool {
+        self.nal_header().unit_type.is_reference()
+    }
+}
+
+/// Returns the type of the NAL unit without start code.
+pub(crate) fn nal_unit_type(data: &[u8]) -> Option<NalUnitType> {
+    data.first().map(|b| NalUnitType::from_u8((b >> 1) & 0x3f))
+}
+
+/// Returns the temporal ID of the NAL unit without start code.
+pub(crate) fn nal_temporal_id(data: &[u8]) -> Option<u8> {
+    let tid_plus1 = data.get(1)? & 0x7;
+    tid_plus1.checked_sub(1)
+}
+
+/// Returns RBSP data with removed emulation prevention bytes.
+pub(crate) fn rbsp_data(data: &[u8]) -> Vec<u8> {
+    let mut rbsp = Vec::with_capacity(data.len());
+    let mut zeros = 0;
+    for &byte in data {
+        if zeros >= 2 && byte == 3 {
+            zeros = 0;
+            continue;
+        }
+        zeros = if byte == 0 { zeros + 1 } else { 0 };
+        rbsp.push(byte);
+    }
+    rbsp
+}
+
+/// Returns position of the first 3-byte start code (`0x000001`).
+fn find_start_code(data: &[u8]) -> Option<usize> {
+    data.windows(3).position(|w| w == [0, 0, 1])
+}
+
+pub(crate) fn trim_trailing_zeros(data: &[u8]) -> &[u8] {
+    let len = data.iter().rposition(|&b| b != 0).map_or(0, |pos| pos + 1);
+    &data[..len]
+}
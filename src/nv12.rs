@@ -0,0 +1,229 @@
+use crate::allocator::CHANNELS;
+use crate::{ChromaFormat, DeError, Image, OwnedImage, PlaneView, Result};
+
+/// Picture converted into NV12 format (8-bit luma plane followed by
+/// a plane with interleaved Cb and Cr samples), see [`Image::to_nv12()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Nv12Buffer {
+    /// Luma samples, `y_stride` bytes per row.
+    pub y: Vec<u8>,
+    /// Interleaved Cb and Cr samples, `uv_stride` bytes per row.
+    pub uv: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    pub y_stride: usize,
+    pub uv_stride: usize,
+}
+
+impl Image<'_> {
+    /// Convert the picture into NV12 format.
+    ///
+    /// Samples of pictures with high bit depth are reduced to 8 bits.
+    /// Chroma of 4:2:2 and 4:4:4 pictures is downsampled to 4:2:0
+    /// by averaging of two vertically adjacent samples (4:2:2) or
+    /// 2x2 blocks of samples (4:4:4). Chroma of monochrome pictures
+    /// is filled with 128.
+    pub fn to_nv12(&self) -> Nv12Buffer {
+        let (planes, chroma_format) = self.yuv_source();
+        to_nv12(planes, chroma_format)
+    }
+
+    /// Same as [`Image::to_nv12()`], but writes samples into the given
+    /// buffers with the given strides (`(y_stride, uv_stride)`) in bytes.
+    ///
+    /// Returns [`DeError::ErrorStrideTooSmall`] if a stride is less than
+    /// the size of a row, or [`DeError::ErrorBufferTooSmall`] if a buffer
+    /// is less than `stride * rows`. Padding at the end of rows
+    /// isn't changed.
+    pub fn write_nv12_into(
+        &self,
+        y: &mut [u8],
+        uv: &mut [u8],
+        strides: (usize, usize),
+    ) -> Result<()> {
+        let (planes, chroma_format) = self.yuv_source();
+        write_nv12(planes, chroma_format, y, uv, strides)
+    }
+
+    pub(crate) fn yuv_source(&self) -> ([(PlaneView<'_>, u32); 3], ChromaFormat) {
+        let planes =
+            CHANNELS.map(|channel| (self.plane_view(channel), self.bits_per_pixel(channel)));
+        (planes, self.chroma_format())
+    }
+}
+
+impl OwnedImage {
+    /// See [`Image::to_nv12()`].
+    pub fn to_nv12(&self) -> Nv12Buffer {
+        let (planes, chroma_format) = self.yuv_source();
+        to_nv12(planes, chroma_format)
+    }
+
+    /// See [`Image::write_nv12_into()`].
+    pub fn write_nv12_into(
+        &self,
+        y: &mut [u8],
+        uv: &mut [u8],
+        strides: (usize, usize),
+    ) -> Result<()> {
+        let (planes, chroma_format) = self.yuv_source();
+        write_nv12(planes, chroma_format, y, uv, strides)
+    }
+
+    pub(crate) fn yuv_source(&self) -> ([(PlaneView<'_>, u32); 3], ChromaFormat) {
+        let planes =
+            CHANNELS.map(|channel| (self.plane_view(channel), self.bits_per_pixel(channel)));
+        (planes, self.chroma_format())
+    }
+}
+
+fn to_nv12(planes: [(PlaneView<'_>, u32); 3], chroma_format: ChromaFormat) -> Nv12Buffer {
+    let (luma, _) = planes[0];
+    let width = luma.width();
+    let height = luma.height();
+    let y_stride = width as usize;
+    let uv_stride = width.div_ceil(2) as usize * 2;
+    let mut y = vec![0; y_stride * height as usize];
+    let mut uv = vec![0; uv_stride * height.div_ceil(2) as usize];
+    // Buffers have the required size, so writing can't fail.
+    let _ = write_nv12(
+        planes,
+        chroma_format,
+        &mut y,
+        &mut uv,
+        (y_stride, uv_stride),
+    );
+    Nv12Buffer {
+        y,
+        uv,
+        width,
+        height,
+        y_stride,
+        uv_stride,
+    }
+}
+
+fn write_nv12(
+    planes: [(PlaneView<'_>, u32); 3],
+    chroma_format: ChromaFormat,
+    y: &mut [u8],
+    uv: &mut [u8],
+    (y_stride, uv_stride): (usize, usize),
+) -> Result<()> {
+    let [luma, cb, cr] = planes;
+    let width = luma.0.width() as usize;
+    let height = luma.0.height() as usize;
+    let uv_width = width.div_ceil(2);
+    let uv_height = height.div_ceil(2);
+    if y_stride < width {
+        return Err(DeError::ErrorStrideTooSmall(width));
+    }
+    if uv_stride < uv_width * 2 {
+        return Err(DeError::ErrorStrideTooSmall(uv_width * 2));
+    }
+    if y.len() < y_stride * height {
+        return Err(DeError::ErrorBufferTooSmall(y_stride * height));
+    }
+    if uv.len() < uv_stride * uv_height {
+        return Err(DeError::ErrorBufferTooSmall(uv_stride * uv_height));
+    }
+    if width == 0 || height == 0 {
+        return Ok(());
+    }
+    write_luma(luma, y, y_stride);
+    write_chroma_420(cb, chroma_format, (uv_width, uv_height), uv, uv_stride, 2);
+    write_chroma_420(
+        cr,
+        chroma_format,
+        (uv_width, uv_height),
+        &mut uv[1..],
+        uv_stride,
+        2,
+    );
+    Ok(())
+}
+
+/// Writes 8-bit luma samples into `dst`, padding at the end of rows
+/// isn't changed.
+pub(crate) fn write_luma((luma, bits): (PlaneView<'_>, u32), dst: &mut [u8], stride: usize) {
+    let width = luma.width() as usize;
+    for (src_row, dst_row) in luma.as_u8_rows().zip(dst.chunks_mut(stride)) {
+        let dst_row = &mut dst_row[..width];
+        if luma.bytes_per_sample() == 1 {
+            dst_row.copy_from_slice(src_row);
+        } else {
+            for (x, dst) in dst_row.iter_mut().enumerate() {
+                *dst = to_u8(sample(src_row, x, 2), bits);
+            }
+        }
+    }
+}
+
+/// Writes 8-bit chroma samples downsampled to 4:2:0 with the given size
+/// into `dst`. Samples of a row are written with `step` bytes between them.
+/// Chroma of monochrome pictures is filled with 128.
+pub(crate) fn write_chroma_420(
+    (plane, bits): (PlaneView<'_>, u32),
+    chroma_format: ChromaFormat,
+    (width, height): (usize, usize),
+    dst: &mut [u8],
+    stride: usize,
+    step: usize,
+) {
+    // Number of chroma samples averaged into one sample of 4:2:0 plane.
+    let (block_width, block_height) = match chroma_format.subsampling() {
+        _ if !chroma_format.has_chroma() => (0, 0),
+        (sub_width, sub_height) => (2 / sub_width as usize, 2 / sub_height as usize),
+    };
+    for (y, dst_row) in dst.chunks_mut(stride).take(height).enumerate() {
+        let rows = (0..block_height).map(|dy| (y * block_height + dy) as u32);
+        for x in 0..width {
+            dst_row[x * step] = if !chroma_format.has_chroma() {
+                128
+            } else {
+                let columns = x * block_width..(x + 1) * block_width;
+                to_u8(average(plane, rows.clone(), columns), bits)
+            };
+        }
+    }
+}
+
+/// Returns the rounded average of samples in the given rows and columns,
+/// indexes outside the plane are clamped to its edges.
+fn average(
+    plane: PlaneView<'_>,
+    rows: impl Iterator<Item = u32>,
+    columns: std::ops::Range<usize>,
+) -> u32 {
+    let last_row = plane.height().saturating_sub(1);
+    let last_column = (plane.width() as usize).saturating_sub(1);
+    let bytes_per_sample = plane.bytes_per_sample();
+    let mut sum = 0;
+    let mut count = 0;
+    for y in rows {
+        let row = plane.row(y.min(last_row)).unwrap_or_default();
+        for x in columns.clone() {
+            sum += sample(row, x.min(last_column), bytes_per_sample);
+            count += 1;
+        }
+    }
+    if count == 0 {
+        return 0;
+    }
+    (sum + count / 2) / count
+}
+
+fn sample(row: &[u8], x: usize, bytes_per_sample: usize) -> u32 {
+    let offset = x * bytes_per_sample;
+    match row.get(offset..offset + bytes_per_sample) {
+        Some(&[value]) => value as u32,
+        Some(&[b0, b1]) => u16::from_ne_bytes([b0, b1]) as u32,
+        _ => 0,
+    }
+}
+
+/// Keeps the most significant 8 bits of a sample.
+fn to_u8(value: u32, bits: u32) -> u8 {
+    (value >> bits.saturating_sub(8)).min(u8::MAX as u32) as u8
+}
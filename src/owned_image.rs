@@ -0,0 +1,253 @@
+use std::borrow::Cow;
+use std::ffi::c_void;
+use std::ptr::NonNull;
+use std::time::Duration;
+
+use crate::image::samples_u16;
+use crate::sei::SeiMetadata;
+use crate::{
+    Channel, ChromaFormat, CropWindow, DeError, FrameTiming, Image, NalHeader, PictureType,
+    PlaneView, PlaneViewError, TimeBase,
+};
+
+#[derive(Debug, Clone, Default)]
+struct OwnedPlane {
+    data: Vec<u8>,
+    stride: usize,
+    width: u32,
+    height: u32,
+    bits_per_pixel: u32,
+}
+
+/// Decoded picture that owns its data.
+///
+/// Unlike [`Image`], it doesn't borrow the decoder and may outlive it
+/// or be sent into another thread. It is created by [`Image::to_owned()`]
+/// or [`Image::to_owned_trimmed()`].
+#[derive(Debug, Clone)]
+pub struct OwnedImage {
+    planes: [OwnedPlane; 3],
+    chroma_format: ChromaFormat,
+    pts: i64,
+    timebase: TimeBase,
+    user_data: usize,
+    nal_header: NalHeader,
+    full_range: bool,
+    colour_primaries: u8,
+    transfer_characteristics: u8,
+    matrix_coefficients: u8,
+    issues: Vec<DeError>,
+    latency: Option<Duration>,
+    pub(crate) crop_window: Option<CropWindow>,
+    pub(crate) sample_aspect_ratio: Option<(u32, u32)>,
+    pub(crate) timing: Option<FrameTiming>,
+    pub(crate) poc: Option<i32>,
+    pub(crate) picture_type: PictureType,
+    pub(crate) sei: SeiMetadata,
+}
+
+impl OwnedImage {
+    /// Copy the picture, `trim_padding` removes bytes after the end
+    /// of each row of planes.
+    pub(crate) fn from_image(image: &Image, trim_padding: bool) -> Self {
+        let chroma_format = image.chroma_format();
+        let copy_plane = |channel: Channel| {
+            if !chroma_format.has_chroma() && channel != Channel::Y {
+                return OwnedPlane::default();
+            }
+            let width = image.width(channel);
+            let height = image.height(channel);
+            let bits_per_pixel = image.bits_per_pixel(channel);
+            let (data, stride) = if trim_padding {
+                let row_size = width as usize * bits_per_pixel.div_ceil(8) as usize;
+                (image.plane_to_vec(channel), row_size)
+            } else {
+                let (data, stride) = image.plane(channel);
+                (data.to_vec(), stride)
+            };
+            OwnedPlane {
+                data,
+                stride,
+                width,
+                height,
+                bits_per_pixel,
+            }
+        };
+        Self {
+            planes: [
+                copy_plane(Channel::Y),
+                copy_plane(Channel::Cb),
+                copy_plane(Channel::Cr),
+            ],
+            chroma_format,
+            pts: image.pts(),
+            timebase: image.decoder.timebase(),
+            user_data: image.user_data(),
+            nal_header: image.nal_header(),
+            full_range: image.full_range(),
+            colour_primaries: image.colour_primaries(),
+            transfer_characteristics: image.transfer_characteristics(),
+            matrix_coefficients: image.matrix_coefficients(),
+            issues: image.decode_issues().to_vec(),
+            latency: image.decode_latency(),
+            crop_window: image.crop_window(),
+            sample_aspect_ratio: image.sample_aspect_ratio(),
+            timing: image.timing(),
+            poc: image.picture_order_count(),
+            picture_type: image.picture_type(),
+            sei: image.sei,
+        }
+    }
+
+    /// Replace samples of existing planes with samples returned by
+    /// the given function together with their bit depth.
+    /// The returned samples must not have padding at the end of rows.
+    pub(crate) fn map_planes(mut self, f: impl Fn(PlaneView<'_>, u32) -> (Vec<u8>, u32)) -> Self {
+        for &channel in self.chroma_format.channels() {
+            let view = self.plane_view(channel);
+            let (data, bits_per_pixel) = f(view, self.bits_per_pixel(channel));
+            let plane = &mut self.planes[channel.index() as usize];
+            plane.stride = plane.width as usize * bits_per_pixel.div_ceil(8) as usize;
+            plane.data = data;
+            plane.bits_per_pixel = bits_per_pixel;
+        }
+        self
+    }
+
+    #[inline(always)]
+    fn owned_plane(&self, channel: Channel) -> &OwnedPlane {
+        &self.planes[channel.index() as usize]
+    }
+
+    pub fn chroma_format(&self) -> ChromaFormat {
+        self.chroma_format
+    }
+
+    pub fn width(&self, channel: Channel) -> u32 {
+        self.owned_plane(channel).width
+    }
+
+    pub fn height(&self, channel: Channel) -> u32 {
+        self.owned_plane(channel).height
+    }
+
+    pub fn bits_per_pixel(&self, channel: Channel) -> u32 {
+        self.owned_plane(channel).bits_per_pixel
+    }
+
+    /// Returns the plane data and bytes per line (stride).
+    ///
+    /// See [`Image::plane()`].
+    pub fn plane(&self, channel: Channel) -> (&[u8], usize) {
+        let plane = self.owned_plane(channel);
+        (&plane.data, plane.stride)
+    }
+
+    /// See [`Image::plane_u16()`].
+    pub fn plane_u16(&self, channel: Channel) -> Option<(Cow<'_, [u16]>, usize)> {
+        if self.bits_per_pixel(channel) <= 8 {
+            return None;
+        }
+        let plane = self.owned_plane(channel);
+        Some((samples_u16(&plane.data), plane.stride / 2))
+    }
+
+    /// See [`Image::plane_view()`].
+    pub fn plane_view(&self, channel: Channel) -> PlaneView<'_> {
+        let plane = self.owned_plane(channel);
+        PlaneView::new(
+            &plane.data,
+            plane.stride,
+            plane.width,
+            plane.height,
+            plane.bits_per_pixel.div_ceil(8) as usize,
+        )
+    }
+
+    /// See [`Image::sample()`].
+    pub fn sample(&self, channel: Channel, x: u32, y: u32) -> Option<u16> {
+        self.plane_view(channel).sample(x, y)
+    }
+
+    /// See [`Image::rows()`].
+    pub fn rows(
+        &self,
+        channel: Channel,
+    ) -> impl ExactSizeIterator<Item = &[u8]> + DoubleEndedIterator {
+        self.plane_view(channel).rows()
+    }
+
+    /// See [`Image::rows_u16()`].
+    pub fn rows_u16(
+        &self,
+        channel: Channel,
+    ) -> Result<impl ExactSizeIterator<Item = &[u16]> + DoubleEndedIterator, PlaneViewError> {
+        self.plane_view(channel).rows_u16()
+    }
+
+    pub fn user_data(&self) -> usize {
+        self.user_data
+    }
+
+    /// See [`Image::user_data_ptr()`].
+    pub fn user_data_ptr(&self) -> Option<NonNull<c_void>> {
+        NonNull::new(self.user_data as *mut c_void)
+    }
+
+    /// The presentation time stamp in units of the time base
+    /// of the decoder (see [`Image::pts()`]).
+    pub fn pts(&self) -> i64 {
+        self.pts
+    }
+
+    /// See [`Image::pts_duration()`].
+    pub fn pts_duration(&self) -> Option<Duration> {
+        self.timebase.to_duration(self.pts)
+    }
+
+    /// See [`Image::pts_in()`].
+    pub fn pts_in(&self, timebase: TimeBase) -> i64 {
+        self.timebase.rescale(self.pts, timebase)
+    }
+
+    /// Time base of the decoder that decoded the picture.
+    pub fn timebase(&self) -> TimeBase {
+        self.timebase
+    }
+
+    /// See [`Image::decode_was_clean()`].
+    pub fn decode_was_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// See [`Image::decode_issues()`].
+    pub fn decode_issues(&self) -> &[DeError] {
+        &self.issues
+    }
+
+    /// See [`Image::decode_latency()`].
+    pub fn decode_latency(&self) -> Option<Duration> {
+        self.latency
+    }
+
+    /// NAL-header information of this frame.
+    pub fn nal_header(&self) -> NalHeader {
+        self.nal_header
+    }
+
+    pub fn full_range(&self) -> bool {
+        self.full_range
+    }
+
+    pub fn colour_primaries(&self) -> u8 {
+        self.colour_primaries
+    }
+
+    pub fn transfer_characteristics(&self) -> u8 {
+        self.transfer_characteristics
+    }
+
+    pub fn matrix_coefficients(&self) -> u8 {
+        self.matrix_coefficients
+    }
+}
@@ -0,0 +1,415 @@
+use std::collections::VecDeque;
+
+use crate::allocator::CHANNELS;
+use crate::decoder::lock;
+use crate::nal::{nal_unit_type, rbsp_data};
+use crate::sei::sei_messages;
+use crate::stream_info::{CodedGeometry, parse_sps};
+use crate::{Channel, ChromaFormat, Image, NalUnitType, StreamInfo};
+
+/// `payloadType` of the decoded picture hash SEI message.
+const DECODED_PICTURE_HASH: u32 = 132;
+
+/// Maximal number of hashes from SEI messages waiting for their pictures.
+const MAX_PENDING_HASHES: usize = 64;
+
+/// Maximal number of remembered geometries of pictures from different SPS.
+const MAX_GEOMETRIES: usize = 8;
+
+/// Type of hash of decoded picture planes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PictureHashType {
+    Md5,
+    Crc,
+    Checksum,
+}
+
+/// Hash of a plane of a decoded picture.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PlaneHash {
+    Md5([u8; 16]),
+    Crc(u16),
+    Checksum(u32),
+}
+
+impl PlaneHash {
+    pub fn hash_type(&self) -> PictureHashType {
+        match self {
+            Self::Md5(_) => PictureHashType::Md5,
+            Self::Crc(_) => PictureHashType::Crc,
+            Self::Checksum(_) => PictureHashType::Checksum,
+        }
+    }
+}
+
+/// Result of verification of a plane hash.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PlaneHashCheck {
+    pub channel: Channel,
+    /// The hash from the decoded picture hash SEI message.
+    pub expected: PlaneHash,
+    /// The hash of the decoded plane.
+    pub computed: PlaneHash,
+}
+
+impl PlaneHashCheck {
+    pub fn is_ok(&self) -> bool {
+        self.expected == self.computed
+    }
+}
+
+/// Result of verification of a decoded picture
+/// by the decoded picture hash SEI message.
+///
+/// It is returned by [`Image::hash_check()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct HashCheck {
+    pub planes: Vec<PlaneHashCheck>,
+}
+
+impl HashCheck {
+    /// Returns `true` if hashes of all planes match.
+    pub fn is_ok(&self) -> bool {
+        self.planes.iter().all(|plane| plane.is_ok())
+    }
+
+    /// Returns channels whose hashes don't match.
+    pub fn failed_planes(&self) -> Vec<Channel> {
+        self.planes
+            .iter()
+            .filter(|plane| !plane.is_ok())
+            .map(|plane| plane.channel)
+            .collect()
+    }
+}
+
+/// Contents of the decoded picture hash SEI message of a picture.
+///
+/// It is returned by [`Image::picture_hash_sei()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PictureHashSei {
+    pub hash_type: PictureHashType,
+    /// Hashes of planes in the order Y, Cb, Cr
+    /// (only Y for monochrome pictures).
+    pub planes: Vec<PlaneHash>,
+}
+
+/// Hashes from decoded picture hash SEI messages pushed into a decoder
+/// and geometries of decoded pictures from pushed SPS.
+///
+/// Pictures are matched with hashes by PTS of NAL units and
+/// with geometries by the size of the conformance window.
+#[derive(Default)]
+pub(crate) struct PictureHashes {
+    hashes: VecDeque<(i64, Vec<PlaneHash>)>,
+    geometries: VecDeque<(PictureSize, CodedGeometry)>,
+}
+
+/// Size of the conformance window and chroma format of a picture.
+pub(crate) type PictureSize = (u32, u32, ChromaFormat);
+
+impl PictureHashes {
+    /// Remember hashes if the NAL unit is a suffix SEI with
+    /// decoded picture hash message or the geometry if it is a SPS.
+    pub fn push_nal(&mut self, data: &[u8], pts: i64) {
+        let Some(nal_unit_type) = nal_unit_type(data) else {
+            return;
+        };
+        if nal_unit_type == NalUnitType::SpsNut {
+            if let Some((info, geometry)) = parse_sps(data) {
+                self.push_geometry(info, geometry);
+            }
+            return;
+        }
+        if nal_unit_type != NalUnitType::SuffixSeiNut {
+            return;
+        }
+        let Some(hashes) = data
+            .get(2..)
+            .and_then(|payload| parse_decoded_picture_hash(&rbsp_data(payload)))
+        else {
+            return;
+        };
+        if self.hashes.len() == MAX_PENDING_HASHES {
+            self.hashes.pop_front();
+        }
+        self.hashes.push_back((pts, hashes));
+    }
+
+    /// Remember the geometry of pictures from SPS.
+    pub fn push_geometry(&mut self, info: StreamInfo, geometry: CodedGeometry) {
+        let size = (info.width, info.height, info.chroma_format);
+        self.geometries.retain(|&(s, _)| s != size);
+        if self.geometries.len() == MAX_GEOMETRIES {
+            self.geometries.pop_front();
+        }
+        self.geometries.push_back((size, geometry));
+    }
+
+    fn hashes(&self, pts: i64) -> Option<&[PlaneHash]> {
+        self.hashes
+            .iter()
+            .rev()
+            .find(|(hash_pts, _)| *hash_pts == pts)
+            .map(|(_, hashes)| hashes.as_slice())
+    }
+
+    pub fn geometry(&self, size: PictureSize) -> Option<CodedGeometry> {
+        self.geometries
+            .iter()
+            .find(|&&(s, _)| s == size)
+            .map(|&(_, geometry)| geometry)
+    }
+
+    pub fn clear(&mut self) {
+        self.hashes.clear();
+        self.geometries.clear();
+    }
+}
+
+/// Parse SEI RBSP and return hashes of planes from
+/// the decoded picture hash message if there is any.
+fn parse_decoded_picture_hash(rbsp: &[u8]) -> Option<Vec<PlaneHash>> {
+    let (_, payload) =
+        sei_messages(rbsp).find(|&(payload_type, _)| payload_type == DECODED_PICTURE_HASH)?;
+    let (&hash_type, hashes) = payload.split_first()?;
+    let hash_size = match hash_type {
+        0 => 16,
+        1 => 2,
+        2 => 4,
+        _ => return None,
+    };
+    let planes = hashes.len() / hash_size;
+    if !(1..=3).contains(&planes) {
+        return None;
+    }
+    let hashes = hashes
+        .chunks_exact(hash_size)
+        .take(planes)
+        .map(|hash| match hash_type {
+            0 => PlaneHash::Md5(hash.try_into().unwrap()),
+            1 => PlaneHash::Crc(u16::from_be_bytes(hash.try_into().unwrap())),
+            _ => PlaneHash::Checksum(u32::from_be_bytes(hash.try_into().unwrap())),
+        })
+        .collect();
+    Some(hashes)
+}
+
+impl Image<'_> {
+    /// Compute the hash of the plane like an encoder does for
+    /// the decoded picture hash SEI message: over the whole decoded plane,
+    /// including the area outside the conformance window.
+    ///
+    /// The size of the decoded plane is taken from SPS pushed
+    /// into the decoder.
+    ///
+    /// Returns `None` if the image has no such plane,
+    /// there is no SPS with the size of the image, or the decoded plane
+    /// has samples above, to the left or below the conformance window,
+    /// because only samples starting at the window are accessible.
+    pub fn plane_hash(&self, channel: Channel, hash_type: PictureHashType) -> Option<PlaneHash> {
+        let chroma_format = self.chroma_format();
+        let size = (
+            self.width(Channel::Y),
+            self.height(Channel::Y),
+            chroma_format,
+        );
+        let geometry = lock(&self.decoder.picture_hashes).geometry(size)?;
+        let (plane, stride) = self.plane(channel);
+        if plane.is_empty() {
+            return None;
+        }
+        let (sub_width, sub_height) = match channel {
+            Channel::Y => (1, 1),
+            _ if chroma_format.has_chroma() => chroma_format.subsampling(),
+            _ => return None,
+        };
+        let (sub_width, sub_height) = (sub_width as u32, sub_height as u32);
+        let bytes_per_pixel = self.bits_per_pixel(channel).div_ceil(8) as usize;
+        let width = (geometry.width / sub_width) as usize;
+        let height = (geometry.height / sub_height) as usize;
+        if width == 0 || height == 0 || stride < width * bytes_per_pixel {
+            return None;
+        }
+        // The plane returned by the decoder starts at the conformance window.
+        if geometry.crop_left / sub_width != 0 || geometry.crop_top / sub_height != 0 {
+            return None;
+        }
+        let size = stride * (height - 1) + width * bytes_per_pixel;
+        let data = plane.get(..size)?;
+        let rows = data
+            .chunks(stride)
+            .map(|row| &row[..width * bytes_per_pixel]);
+        let hash = match hash_type {
+            PictureHashType::Md5 => {
+                let mut md5 = Md5::new();
+                rows.for_each(|row| md5.update(row));
+                PlaneHash::Md5(md5.finish())
+            }
+            PictureHashType::Crc => {
+                let mut crc = 0xffff;
+                for byte in rows.flatten() {
+                    crc = crc_process_byte(crc, *byte);
+                }
+                crc = crc_process_byte(crc, 0);
+                PlaneHash::Crc(crc_process_byte(crc, 0))
+            }
+            PictureHashType::Checksum => {
+                let mut sum = 0u32;
+                for (y, row) in rows.enumerate() {
+                    for (i, &byte) in row.iter().enumerate() {
+                        let x = i / bytes_per_pixel;
+                        let xor_mask = (x & 0xff) ^ (y & 0xff) ^ (x >> 8) ^ (y >> 8);
+                        sum = sum.wrapping_add((byte as usize ^ xor_mask) as u32);
+                    }
+                }
+                PlaneHash::Checksum(sum)
+            }
+        };
+        Some(hash)
+    }
+
+    /// Compute MD5 of the plane like an encoder does for the decoded
+    /// picture hash SEI message, see [`Image::plane_hash()`].
+    pub fn compute_plane_md5(&self, channel: Channel) -> Option<[u8; 16]> {
+        match self.plane_hash(channel, PictureHashType::Md5)? {
+            PlaneHash::Md5(md5) => Some(md5),
+            _ => None,
+        }
+    }
+
+    /// Returns contents of the decoded picture hash SEI message of the picture.
+    ///
+    /// Hashes are taken from suffix SEI NAL units like
+    /// in [`Image::hash_check()`].
+    pub fn picture_hash_sei(&self) -> Option<PictureHashSei> {
+        let planes = lock(&self.decoder.picture_hashes)
+            .hashes(self.pts())?
+            .to_vec();
+        Some(PictureHashSei {
+            hash_type: planes.first()?.hash_type(),
+            planes,
+        })
+    }
+
+    /// Verify the image by the decoded picture hash SEI message.
+    ///
+    /// Hashes are taken from suffix SEI NAL units pushed by
+    /// [`DecoderInput::push_nal()`](crate::DecoderInput::push_nal)
+    /// (and methods based on it) with the same PTS as the picture,
+    /// so every access unit must have unique PTS.
+    ///
+    /// Returns `None` if there is no such SEI message for the picture.
+    pub fn hash_check(&self) -> Option<HashCheck> {
+        let expected = lock(&self.decoder.picture_hashes)
+            .hashes(self.pts())?
+            .to_vec();
+        let planes = CHANNELS
+            .into_iter()
+            .zip(expected)
+            .map(|(channel, expected)| {
+                let computed = self.plane_hash(channel, expected.hash_type())?;
+                Some(PlaneHashCheck {
+                    channel,
+                    expected,
+                    computed,
+                })
+            })
+            .collect::<Option<_>>()?;
+        Some(HashCheck { planes })
+    }
+}
+
+fn crc_process_byte(mut crc: u16, byte: u8) -> u16 {
+    for bit in 0..8 {
+        let bit_value = ((byte >> (7 - bit)) & 1) as u16;
+        let msb = crc >> 15;
+        crc = (crc << 1) | bit_value;
+        if msb == 1 {
+            crc ^= 0x1021;
+        }
+    }
+    crc
+}
+
+/// Implementation of MD5 message-digest algorithm (RFC 1321).
+pub(crate) struct Md5 {
+    state: [u32; 4],
+    buffer: [u8; 64],
+    buffer_len: usize,
+    total_len: u64,
+}
+
+const MD5_SHIFTS: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, //
+    5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, //
+    4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, //
+    6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+impl Md5 {
+    pub fn new() -> Self {
+        Self {
+            state: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476],
+            buffer: [0; 64],
+            buffer_len: 0,
+            total_len: 0,
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.total_len = self.total_len.wrapping_add(data.len() as u64);
+        while !data.is_empty() {
+            let size = (64 - self.buffer_len).min(data.len());
+            self.buffer[self.buffer_len..self.buffer_len + size].copy_from_slice(&data[..size]);
+            self.buffer_len += size;
+            data = &data[size..];
+            if self.buffer_len == 64 {
+                let block = self.buffer;
+                self.process_block(&block);
+                self.buffer_len = 0;
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> [u8; 16] {
+        let bits_len = self.total_len.wrapping_mul(8);
+        self.update(&[0x80]);
+        while self.buffer_len != 56 {
+            self.update(&[0]);
+        }
+        self.update(&bits_len.to_le_bytes());
+        let mut digest = [0; 16];
+        for (chunk, value) in digest.chunks_exact_mut(4).zip(self.state) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        digest
+    }
+
+    fn process_block(&mut self, block: &[u8; 64]) {
+        let words: [u32; 16] = std::array::from_fn(|i| {
+            u32::from_le_bytes(block[i * 4..i * 4 + 4].try_into().unwrap())
+        });
+        let [mut a, mut b, mut c, mut d] = self.state;
+        for (i, shift) in MD5_SHIFTS.into_iter().enumerate() {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let k = ((i as f64 + 1.0).sin().abs() * 4294967296.0) as u32;
+            let f = f.wrapping_add(a).wrapping_add(k).wrapping_add(words[g]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(f.rotate_left(shift));
+        }
+        for (state, value) in self.state.iter_mut().zip([a, b, c, d]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use crate::{Image, OwnedImage};
+
+/// Type of coded picture by types of its slices.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum PictureType {
+    /// Intra-coded picture, it is decoded without references
+    /// to other pictures.
+    I,
+    /// Predicted picture, its slices reference one list of pictures.
+    P,
+    /// Bi-predicted picture, its slices reference two lists of pictures.
+    B,
+    /// The type can't be determined.
+    #[default]
+    Unknown,
+}
+
+impl PictureType {
+    /// Returns the type by the value of `slice_type` from the slice header.
+    pub(crate) fn from_slice_type(slice_type: u32) -> Self {
+        match slice_type {
+            0 => Self::B,
+            1 => Self::P,
+            2 => Self::I,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl Image<'_> {
+    /// Returns the type of the picture.
+    ///
+    /// Pictures with a NAL unit type of IRAP (IDR, CRA or BLA) picture are
+    /// always [`PictureType::I`]. Types of other pictures are taken
+    /// from the header of their first slice segment, so they are known
+    /// only for pictures pushed by [`DecoderInput::push_nal()`](crate::DecoderInput::push_nal)
+    /// (and methods based on it) after their parameter sets.
+    /// Other slices of the picture may have "lower" types, e.g. a `B` picture
+    /// may contain `P` and `I` slices.
+    pub fn picture_type(&self) -> PictureType {
+        if self.is_irap() {
+            return PictureType::I;
+        }
+        self.decoder
+            .payloads
+            .picture_type(self.token())
+            .unwrap_or_default()
+    }
+}
+
+impl OwnedImage {
+    /// See [`Image::picture_type()`].
+    pub fn picture_type(&self) -> PictureType {
+        self.picture_type
+    }
+}
@@ -0,0 +1,219 @@
+use std::mem;
+
+use crate::PlaneViewError;
+
+/// View of the memory of an image plane, see [`Image::plane_view()`](crate::Image::plane_view).
+///
+/// Samples of planes with more than 8 bits per pixel are stored
+/// as 16-bit words in native byte order.
+#[derive(Debug, Copy, Clone)]
+pub struct PlaneView<'a> {
+    data: &'a [u8],
+    stride: usize,
+    width: u32,
+    height: u32,
+    bytes_per_sample: usize,
+}
+
+impl<'a> PlaneView<'a> {
+    pub(crate) fn new(
+        data: &'a [u8],
+        stride: usize,
+        width: u32,
+        height: u32,
+        bytes_per_sample: usize,
+    ) -> Self {
+        Self {
+            data,
+            stride,
+            width,
+            height,
+            bytes_per_sample,
+        }
+    }
+
+    /// Pointer to the first byte of the plane.
+    pub fn as_ptr(&self) -> *const u8 {
+        self.data.as_ptr()
+    }
+
+    /// Returns bytes of the plane including padding at the end of rows.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Bytes per line.
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Width of the plane in samples.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Number of bytes used to store one sample (1 or 2).
+    pub fn bytes_per_sample(&self) -> usize {
+        self.bytes_per_sample
+    }
+
+    /// Returns bytes of samples of the row with the given index
+    /// without padding at the end of the row.
+    pub fn row(&self, y: u32) -> Result<&'a [u8], PlaneViewError> {
+        let out_of_bounds = PlaneViewError::RowOutOfBounds {
+            row: y,
+            height: self.height,
+        };
+        if y >= self.height {
+            return Err(out_of_bounds);
+        }
+        let start = y as usize * self.stride;
+        let end = start + self.width as usize * self.bytes_per_sample;
+        self.data.get(start..end).ok_or(out_of_bounds)
+    }
+
+    /// Returns an iterator over bytes of samples of all rows.
+    pub fn as_u8_rows(&self) -> impl Iterator<Item = &'a [u8]> + use<'a> {
+        let view = *self;
+        (0..self.height).map_while(move |y| view.row(y).ok())
+    }
+
+    /// Returns the value of the sample at the given position widened
+    /// to `u16`, or `None` if the position is outside of the plane.
+    pub fn sample(&self, x: u32, y: u32) -> Option<u16> {
+        if x >= self.width {
+            return None;
+        }
+        let row = self.row(y).ok()?;
+        let start = x as usize * self.bytes_per_sample;
+        match self.bytes_per_sample {
+            1 => row.get(start).map(|&value| value as u16),
+            _ => row
+                .get(start..start + 2)
+                .map(|bytes| u16::from_ne_bytes([bytes[0], bytes[1]])),
+        }
+    }
+
+    /// Returns an iterator over bytes of samples of all rows that
+    /// may be iterated in both directions.
+    ///
+    /// Unlike [`PlaneView::as_u8_rows()`], it yields exactly
+    /// [`PlaneView::height()`] rows, or nothing if the data of the plane
+    /// doesn't contain all rows.
+    pub fn rows(&self) -> impl ExactSizeIterator<Item = &'a [u8]> + DoubleEndedIterator + use<'a> {
+        let view = *self;
+        (0..self.complete_rows()).map(move |y| view.row(y).unwrap_or_default())
+    }
+
+    /// Returns samples of the row with the given index if the plane
+    /// has 2 bytes per sample.
+    pub fn row_u16(&self, y: u32) -> Result<&'a [u16], PlaneViewError> {
+        self.check_layout::<u16>()?;
+        let row = self.row(y)?;
+        // SAFETY: the row is aligned to 2 bytes (checked by `check_layout()`),
+        // and any bit pattern is a valid `u16`.
+        Ok(unsafe { std::slice::from_raw_parts(row.as_ptr().cast(), row.len() / 2) })
+    }
+
+    /// Returns an iterator over samples of all rows if the plane
+    /// has 2 bytes per sample.
+    pub fn as_u16_rows(&self) -> Result<impl Iterator<Item = &'a [u16]> + use<'a>, PlaneViewError> {
+        self.check_layout::<u16>()?;
+        let view = *self;
+        Ok((0..self.height).map_while(move |y| view.row_u16(y).ok()))
+    }
+
+    /// Returns an iterator over samples of all rows if the plane
+    /// has 2 bytes per sample, see [`PlaneView::rows()`].
+    pub fn rows_u16(
+        &self,
+    ) -> Result<
+        impl ExactSizeIterator<Item = &'a [u16]> + DoubleEndedIterator + use<'a>,
+        PlaneViewError,
+    > {
+        self.check_layout::<u16>()?;
+        let view = *self;
+        Ok((0..self.complete_rows()).map(move |y| view.row_u16(y).unwrap_or_default()))
+    }
+
+    /// Returns samples of the row with the given index as a slice of `T`.
+    ///
+    /// The size of `T` must be equal to [`PlaneView::bytes_per_sample()`].
+    #[cfg(feature = "bytemuck")]
+    pub fn row_as<T: bytemuck::Pod>(&self, y: u32) -> Result<&'a [T], PlaneViewError> {
+        self.check_layout::<T>()?;
+        bytemuck::try_cast_slice(self.row(y)?).map_err(|_| PlaneViewError::Misaligned {
+            alignment: mem::align_of::<T>(),
+        })
+    }
+
+    /// Returns an iterator over samples of all rows as slices of `T`.
+    ///
+    /// The size of `T` must be equal to [`PlaneView::bytes_per_sample()`].
+    #[cfg(feature = "bytemuck")]
+    pub fn rows_as<T: bytemuck::Pod>(
+        &self,
+    ) -> Result<impl Iterator<Item = &'a [T]> + use<'a, T>, PlaneViewError> {
+        self.check_layout::<T>()?;
+        let view = *self;
+        Ok((0..self.height).map_while(move |y| view.row_as(y).ok()))
+    }
+
+    /// Returns a two-dimensional array (rows x columns) of samples
+    /// of the plane with 1 byte per sample without copying.
+    #[cfg(feature = "ndarray")]
+    pub fn as_array(&self) -> Result<ndarray::ArrayView2<'a, u8>, PlaneViewError> {
+        self.array_view()
+    }
+
+    /// Returns a two-dimensional array (rows x columns) of samples
+    /// of the plane with 2 bytes per sample without copying.
+    #[cfg(feature = "ndarray")]
+    pub fn as_array_u16(&self) -> Result<ndarray::ArrayView2<'a, u16>, PlaneViewError> {
+        self.array_view()
+    }
+
+    #[cfg(feature = "ndarray")]
+    fn array_view<T>(&self) -> Result<ndarray::ArrayView2<'a, T>, PlaneViewError> {
+        use ndarray::ShapeBuilder;
+
+        self.check_layout::<T>()?;
+        if self.height > 0 {
+            // Checks that the data contains the last row.
+            self.row(self.height - 1)?;
+        }
+        let shape = (self.height as usize, self.width as usize)
+            .strides((self.stride / mem::size_of::<T>(), 1));
+        // SAFETY: the data is aligned for `T` and contains all rows with
+        // the given stride (checked above), any bit pattern is a valid
+        // `u8` or `u16`, and the view borrows the data for `'a`.
+        Ok(unsafe { ndarray::ArrayView2::from_shape_ptr(shape, self.data.as_ptr().cast()) })
+    }
+
+    /// Returns the height of the plane if its data contains all rows, or 0.
+    fn complete_rows(&self) -> u32 {
+        if self.height > 0 && self.row(self.height - 1).is_err() {
+            return 0;
+        }
+        self.height
+    }
+
+    /// Checks that all rows may be interpreted as slices of `T`.
+    fn check_layout<T>(&self) -> Result<(), PlaneViewError> {
+        if mem::size_of::<T>() != self.bytes_per_sample {
+            return Err(PlaneViewError::SampleSizeMismatch {
+                bytes_per_sample: self.bytes_per_sample,
+                element_size: mem::size_of::<T>(),
+            });
+        }
+        let alignment = mem::align_of::<T>();
+        if self.data.as_ptr() as usize % alignment != 0 || self.stride % alignment != 0 {
+            return Err(PlaneViewError::Misaligned { alignment });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{Channel, ChromaFormat, Image, OwnedImage, PlaneView};
+
+/// Views of all planes of a picture, see [`Image::planes()`].
+#[derive(Debug, Copy, Clone)]
+pub struct Planes<'a> {
+    pub y: PlaneView<'a>,
+    /// Chroma planes are `None` for monochrome pictures.
+    pub cb: Option<PlaneView<'a>>,
+    pub cr: Option<PlaneView<'a>>,
+}
+
+impl<'a> Planes<'a> {
+    fn new(chroma_format: ChromaFormat, plane_view: impl Fn(Channel) -> PlaneView<'a>) -> Self {
+        let chroma_view = |channel| chroma_format.has_chroma().then(|| plane_view(channel));
+        Self {
+            y: plane_view(Channel::Y),
+            cb: chroma_view(Channel::Cb),
+            cr: chroma_view(Channel::Cr),
+        }
+    }
+
+    /// Returns the view of the plane of the given channel.
+    pub fn get(&self, channel: Channel) -> Option<PlaneView<'a>> {
+        match channel {
+            Channel::Y => Some(self.y),
+            Channel::Cb => self.cb,
+            Channel::Cr => self.cr,
+        }
+    }
+
+    /// Returns bytes of the plane of the given channel with padding
+    /// at the end of rows, see [`PlaneView::as_bytes()`].
+    pub fn data(&self, channel: Channel) -> Option<&'a [u8]> {
+        self.get(channel).map(|view| view.as_bytes())
+    }
+
+    /// Returns pointers to the first bytes of Y, Cb and Cr planes
+    /// and their strides (bytes per line) to pass them into foreign code.
+    ///
+    /// Pointers are `None` for missing or empty planes. The pointed memory
+    /// is valid while the image that the planes belong to is alive.
+    pub fn as_raw_parts(&self) -> [(Option<*const u8>, usize); 3] {
+        [Channel::Y, Channel::Cb, Channel::Cr].map(|channel| match self.get(channel) {
+            Some(view) if !view.as_bytes().is_empty() => (Some(view.as_ptr()), view.stride()),
+            _ => (None, 0),
+        })
+    }
+}
+
+impl Image<'_> {
+    /// Returns views of all planes of the picture.
+    pub fn planes(&self) -> Planes<'_> {
+        Planes::new(self.chroma_format(), |channel| self.plane_view(channel))
+    }
+}
+
+impl OwnedImage {
+    /// See [`Image::planes()`].
+    pub fn planes(&self) -> Planes<'_> {
+        Planes::new(self.chroma_format(), |channel| self.plane_view(channel))
+    }
+}
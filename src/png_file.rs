@@ -0,0 +1,102 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use png::{BitDepth, ColorType, Encoder};
+
+use crate::rgb::high_bit_depth;
+use crate::{BitExpansion, Channel, Image, OwnedImage, PlaneView, PngError};
+
+impl Image<'_> {
+    /// Save the picture into a PNG file.
+    ///
+    /// Pictures with 8 bits per sample are converted by
+    /// [`Image::to_rgb8()`] and saved as 8-bit RGB, pictures
+    /// with high bit depth - by [`Image::to_rgb16()`] and saved
+    /// as 16-bit RGB.
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), PngError> {
+        if high_bit_depth(|channel| self.bits_per_pixel(channel)) {
+            let rgb = self.to_rgb16();
+            save_rgb16(path.as_ref(), rgb.width, rgb.height, &rgb.data)
+        } else {
+            let rgb = self.to_rgb8();
+            save(path.as_ref(), (rgb.width, rgb.height), RGB8, &rgb.data)
+        }
+    }
+
+    /// Save samples of the plane into a grayscale PNG file.
+    ///
+    /// Planes with more than 8 bits per pixel are saved with 16 bits
+    /// per pixel, samples are widened with [`BitExpansion::Replicate`].
+    pub fn save_plane_png(&self, channel: Channel, path: impl AsRef<Path>) -> Result<(), PngError> {
+        let plane = self
+            .planes()
+            .get(channel)
+            .ok_or(PngError::NoPlane(channel))?;
+        save_plane(path.as_ref(), plane, self.bits_per_pixel(channel))
+    }
+}
+
+impl OwnedImage {
+    /// See [`Image::save_png()`].
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), PngError> {
+        if high_bit_depth(|channel| self.bits_per_pixel(channel)) {
+            let rgb = self.to_rgb16();
+            save_rgb16(path.as_ref(), rgb.width, rgb.height, &rgb.data)
+        } else {
+            let rgb = self.to_rgb8();
+            save(path.as_ref(), (rgb.width, rgb.height), RGB8, &rgb.data)
+        }
+    }
+
+    /// See [`Image::save_plane_png()`].
+    pub fn save_plane_png(&self, channel: Channel, path: impl AsRef<Path>) -> Result<(), PngError> {
+        let plane = self
+            .planes()
+            .get(channel)
+            .ok_or(PngError::NoPlane(channel))?;
+        save_plane(path.as_ref(), plane, self.bits_per_pixel(channel))
+    }
+}
+
+const RGB8: (ColorType, BitDepth) = (ColorType::Rgb, BitDepth::Eight);
+
+fn save_rgb16(path: &Path, width: u32, height: u32, data: &[u16]) -> Result<(), PngError> {
+    // Samples of PNG are stored in big-endian byte order.
+    let data: Vec<u8> = data.iter().flat_map(|value| value.to_be_bytes()).collect();
+    let format = (ColorType::Rgb, BitDepth::Sixteen);
+    save(path, (width, height), format, &data)
+}
+
+fn save_plane(path: &Path, plane: PlaneView<'_>, bits: u32) -> Result<(), PngError> {
+    let size = (plane.width(), plane.height());
+    if plane.bytes_per_sample() == 1 {
+        let data: Vec<u8> = plane.rows().flatten().copied().collect();
+        return save(path, size, (ColorType::Grayscale, BitDepth::Eight), &data);
+    }
+    let data: Vec<u8> = plane
+        .rows()
+        .flat_map(|row| row.chunks_exact(2))
+        .flat_map(|bytes| {
+            let value = u16::from_ne_bytes([bytes[0], bytes[1]]);
+            BitExpansion::Replicate.widen(value, bits, 16).to_be_bytes()
+        })
+        .collect();
+    save(path, size, (ColorType::Grayscale, BitDepth::Sixteen), &data)
+}
+
+fn save(
+    path: &Path,
+    (width, height): (u32, u32),
+    (color_type, bit_depth): (ColorType, BitDepth),
+    data: &[u8],
+) -> Result<(), PngError> {
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = Encoder::new(file, width, height);
+    encoder.set_color(color_type);
+    encoder.set_depth(bit_depth);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(data)?;
+    writer.finish()?;
+    Ok(())
+}
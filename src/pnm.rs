@@ -0,0 +1,107 @@
+use std::io::{self, Write};
+
+use crate::rgb::high_bit_depth;
+use crate::{Channel, Image, OwnedImage, PlaneView};
+
+impl Image<'_> {
+    /// Write the picture converted into RGB as a binary PPM (P6) image.
+    ///
+    /// Pictures with 8 bits per sample are converted by
+    /// [`Image::to_rgb8()`], pictures with high bit depth - by
+    /// [`Image::to_rgb16()`] and written with 16 bits per component
+    /// (maxval 65535).
+    pub fn write_ppm(&self, w: impl Write) -> io::Result<()> {
+        if high_bit_depth(|channel| self.bits_per_pixel(channel)) {
+            let rgb = self.to_rgb16();
+            write_ppm16(w, rgb.width, rgb.height, &rgb.data)
+        } else {
+            let rgb = self.to_rgb8();
+            write_ppm8(w, rgb.width, rgb.height, &rgb.data)
+        }
+    }
+
+    /// Write samples of the plane as a binary PGM (P5) image.
+    ///
+    /// Maxval of the image is the maximal value of the bit depth
+    /// of the plane. Samples of planes with more than 8 bits per pixel
+    /// are written as 16-bit words in big-endian byte order.
+    ///
+    /// Returns an error of [`io::ErrorKind::InvalidInput`] kind
+    /// if the image has no such plane.
+    pub fn write_pgm(&self, channel: Channel, w: impl Write) -> io::Result<()> {
+        write_pgm(w, self.planes().get(channel), self.bits_per_pixel(channel))
+    }
+}
+
+impl OwnedImage {
+    /// See [`Image::write_ppm()`].
+    pub fn write_ppm(&self, w: impl Write) -> io::Result<()> {
+        if high_bit_depth(|channel| self.bits_per_pixel(channel)) {
+            let rgb = self.to_rgb16();
+            write_ppm16(w, rgb.width, rgb.height, &rgb.data)
+        } else {
+            let rgb = self.to_rgb8();
+            write_ppm8(w, rgb.width, rgb.height, &rgb.data)
+        }
+    }
+
+    /// See [`Image::write_pgm()`].
+    pub fn write_pgm(&self, channel: Channel, w: impl Write) -> io::Result<()> {
+        write_pgm(w, self.planes().get(channel), self.bits_per_pixel(channel))
+    }
+}
+
+fn write_header(
+    w: &mut impl Write,
+    magic: &str,
+    (width, height): (u32, u32),
+    maxval: u32,
+) -> io::Result<()> {
+    write!(w, "{magic}\n{width} {height}\n{maxval}\n")
+}
+
+fn write_ppm8(mut w: impl Write, width: u32, height: u32, data: &[u8]) -> io::Result<()> {
+    write_header(&mut w, "P6", (width, height), u8::MAX as u32)?;
+    w.write_all(data)
+}
+
+fn write_ppm16(mut w: impl Write, width: u32, height: u32, data: &[u16]) -> io::Result<()> {
+    write_header(&mut w, "P6", (width, height), u16::MAX as u32)?;
+    let row_len = width as usize * 3;
+    let mut buffer = Vec::with_capacity(row_len * 2);
+    for row in data.chunks_exact(row_len.max(1)) {
+        write_words(&mut w, &mut buffer, row.iter().copied())?;
+    }
+    Ok(())
+}
+
+fn write_pgm(mut w: impl Write, plane: Option<PlaneView<'_>>, bits: u32) -> io::Result<()> {
+    let plane = plane.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "the picture has no such plane")
+    })?;
+    let maxval = (1u32 << bits.clamp(1, 16)) - 1;
+    write_header(&mut w, "P5", (plane.width(), plane.height()), maxval)?;
+    if plane.bytes_per_sample() == 1 {
+        return plane.rows().try_for_each(|row| w.write_all(row));
+    }
+    let mut buffer = Vec::with_capacity(plane.width() as usize * 2);
+    for row in plane.rows() {
+        let words = row
+            .chunks_exact(2)
+            .map(|bytes| u16::from_ne_bytes([bytes[0], bytes[1]]));
+        write_words(&mut w, &mut buffer, words)?;
+    }
+    Ok(())
+}
+
+/// Write 16-bit values in big-endian byte order required by PNM formats,
+/// `buffer` is used to collect bytes of values before writing.
+fn write_words(
+    w: &mut impl Write,
+    buffer: &mut Vec<u8>,
+    words: impl Iterator<Item = u16>,
+) -> io::Result<()> {
+    buffer.clear();
+    buffer.extend(words.flat_map(u16::to_be_bytes));
+    w.write_all(buffer)
+}
@@ -0,0 +1,225 @@
+use crate::nal::{nal_temporal_id, nal_unit_type};
+use crate::stream_info::{BitReader, parse_sps};
+use crate::user_data::is_first_slice_segment;
+use crate::{Image, NalUnitType, OwnedImage, PictureType};
+
+/// Parameters of SPS required to parse slice headers.
+#[derive(Debug, Copy, Clone)]
+struct SpsParams {
+    separate_colour_plane: bool,
+    log2_max_poc_lsb: u32,
+}
+
+/// Parameters of PPS required to parse slice headers.
+#[derive(Debug, Copy, Clone)]
+struct PpsParams {
+    sps_id: u32,
+    output_flag_present: bool,
+    num_extra_slice_header_bits: u32,
+}
+
+/// Values parsed from the header of the first slice segment of a picture.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct SliceHeader {
+    pub poc: Option<i32>,
+    pub picture_type: PictureType,
+}
+
+/// Tracker of picture order counts and types of pictures pushed by
+/// [`DecoderInput::push_nal()`](crate::DecoderInput::push_nal).
+///
+/// `libde265` doesn't provide POC of decoded pictures, so it is derived
+/// from slice headers like the decoder does (clause 8.3.1 of H.265).
+#[derive(Debug)]
+pub(crate) struct PocTracker {
+    sps: [Option<SpsParams>; 16],
+    pps: [Option<PpsParams>; 64],
+    /// POC of the previous picture with temporal ID 0
+    /// that is not a RASL, RADL or sub-layer non-reference picture.
+    prev_tid0_poc: i32,
+    /// A picture was pushed after the start of the stream
+    /// or the last end of sequence NAL unit.
+    in_sequence: bool,
+}
+
+impl Default for PocTracker {
+    fn default() -> Self {
+        Self {
+            sps: [None; 16],
+            pps: [None; 64],
+            prev_tid0_poc: 0,
+            in_sequence: false,
+        }
+    }
+}
+
+impl PocTracker {
+    /// Handle the pushed NAL unit and return values from the slice header
+    /// if the NAL unit contains the first slice segment of a picture.
+    pub fn push_nal(&mut self, data: &[u8]) -> Option<SliceHeader> {
+        let nal_unit_type = nal_unit_type(data)?;
+        if nal_unit_type == NalUnitType::SpsNut {
+            let (_, geometry) = parse_sps(data)?;
+            *self.sps.get_mut(geometry.sps_id as usize)? = Some(SpsParams {
+                separate_colour_plane: geometry.separate_colour_plane,
+                log2_max_poc_lsb: geometry.log2_max_poc_lsb,
+            });
+            return None;
+        }
+        if nal_unit_type == NalUnitType::PpsNut {
+            let (pps_id, params) = parse_pps(data)?;
+            *self.pps.get_mut(pps_id as usize)? = Some(params);
+            return None;
+        }
+        if nal_unit_type == NalUnitType::EosNut {
+            self.in_sequence = false;
+            return None;
+        }
+        if !is_first_slice_segment(data) {
+            return None;
+        }
+        let header = match self.parse_slice_header(data, nal_unit_type) {
+            Some((poc, picture_type)) => SliceHeader {
+                poc: Some(poc),
+                picture_type,
+            },
+            // Slices of IRAP pictures are always intra-coded.
+            None => SliceHeader {
+                poc: None,
+                picture_type: if nal_unit_type.is_irap() {
+                    PictureType::I
+                } else {
+                    PictureType::Unknown
+                },
+            },
+        };
+        Some(header)
+    }
+
+    fn parse_slice_header(
+        &mut self,
+        data: &[u8],
+        nal_unit_type: NalUnitType,
+    ) -> Option<(i32, PictureType)> {
+        let mut reader = BitReader::new(data.get(2..)?);
+        // first_slice_segment_in_pic_flag
+        reader.skip(1)?;
+        if nal_unit_type.is_irap() {
+            // no_output_of_prior_pics_flag
+            reader.skip(1)?;
+        }
+        let pps_id = reader.read_ue()?;
+        let pps = (*self.pps.get(pps_id as usize)?)?;
+        let sps = (*self.sps.get(pps.sps_id as usize)?)?;
+        // slice_reserved_flag
+        reader.skip(pps.num_extra_slice_header_bits as usize)?;
+        let picture_type = PictureType::from_slice_type(reader.read_ue()?);
+        if pps.output_flag_present {
+            // pic_output_flag
+            reader.skip(1)?;
+        }
+        if sps.separate_colour_plane {
+            // colour_plane_id
+            reader.skip(2)?;
+        }
+        let lsb = if nal_unit_type.is_idr() {
+            0
+        } else {
+            reader.read(sps.log2_max_poc_lsb)? as i32
+        };
+
+        // IDR and BLA pictures, and CRA pictures at the start
+        // of a coded video sequence reset the most significant bits.
+        let no_rasl_output = nal_unit_type.is_irap()
+            && (nal_unit_type.is_idr() || nal_unit_type.is_bla() || !self.in_sequence);
+        let msb = if no_rasl_output {
+            0
+        } else {
+            poc_msb(self.prev_tid0_poc, lsb, 1 << sps.log2_max_poc_lsb)
+        };
+        let poc = msb + lsb;
+
+        if nal_temporal_id(data)? == 0
+            && !nal_unit_type.is_leading()
+            && nal_unit_type.is_reference()
+        {
+            self.prev_tid0_poc = poc;
+        }
+        self.in_sequence = true;
+        Some((poc, picture_type))
+    }
+}
+
+/// Returns the most significant bits of POC of a picture with the given
+/// least significant bits, `prev_poc` is POC of the previous picture
+/// with temporal ID 0.
+fn poc_msb(prev_poc: i32, lsb: i32, max_lsb: i32) -> i32 {
+    let prev_lsb = prev_poc & (max_lsb - 1);
+    let prev_msb = prev_poc - prev_lsb;
+    if lsb < prev_lsb && prev_lsb - lsb >= max_lsb / 2 {
+        prev_msb + max_lsb
+    } else if lsb > prev_lsb && lsb - prev_lsb > max_lsb / 2 {
+        prev_msb - max_lsb
+    } else {
+        prev_msb
+    }
+}
+
+/// Parse the beginning of PPS NAL unit (with NAL header).
+fn parse_pps(data: &[u8]) -> Option<(u32, PpsParams)> {
+    let mut reader = BitReader::new(data.get(2..)?);
+    let pps_id = reader.read_ue()?;
+    let sps_id = reader.read_ue()?;
+    // dependent_slice_segments_enabled_flag
+    reader.skip(1)?;
+    let output_flag_present = reader.read(1)? == 1;
+    let num_extra_slice_header_bits = reader.read(3)?;
+    let params = PpsParams {
+        sps_id,
+        output_flag_present,
+        num_extra_slice_header_bits,
+    };
+    Some((pps_id, params))
+}
+
+impl Image<'_> {
+    /// Returns the picture order count (the full `PicOrderCntVal`,
+    /// not only its least significant bits) of the picture.
+    ///
+    /// `libde265` doesn't provide it, so POC is derived from slice headers
+    /// of NAL units pushed by [`DecoderInput::push_nal()`](crate::DecoderInput::push_nal)
+    /// (and methods based on it). Returns `None` for pictures
+    /// pushed by [`DecoderInput::push_data()`](crate::DecoderInput::push_data)
+    /// or if their parameter sets were not pushed.
+    pub fn picture_order_count(&self) -> Option<i32> {
+        self.decoder.payloads.poc(self.token())
+    }
+}
+
+impl OwnedImage {
+    /// See [`Image::picture_order_count()`].
+    pub fn picture_order_count(&self) -> Option<i32> {
+        self.poc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn msb_of_poc() {
+        // 4 bits of LSB
+        assert_eq!(poc_msb(0, 1, 16), 0);
+        assert_eq!(poc_msb(14, 15, 16), 0);
+        // LSB wraps around after the previous picture.
+        assert_eq!(poc_msb(15, 0, 16), 16);
+        assert_eq!(poc_msb(30, 2, 16), 32);
+        // A picture that precedes the previous one in output order.
+        assert_eq!(poc_msb(17, 14, 16), 0);
+        assert_eq!(poc_msb(16, 10, 16), 0);
+        assert_eq!(poc_msb(16, 7, 16), 16);
+        // Negative POC of leading pictures
+        assert_eq!(poc_msb(0, 14, 16), -16);
+    }
+}
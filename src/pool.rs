@@ -0,0 +1,121 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+
+use crate::decoder::lock;
+use crate::{Decoder, DecoderBuilder, Result};
+
+/// Pool of decoders that are reused to decode many short streams
+/// without creating and freeing a decoder context for each of them.
+///
+/// Decoders are handed out as [`PooledDecoder`] objects. A dropped
+/// pooled decoder is reset and returned into the pool, if the pool
+/// has less idle decoders than its capacity. Worker threads of the decoder
+/// stay started, so they aren't taken from the global budget again
+/// (see [`set_global_thread_budget()`](crate::set_global_thread_budget)).
+///
+/// Settings changed through a pooled decoder (parameters, framerate,
+/// layer filter, etc.) are kept by the decoder returned into the pool.
+///
+/// The pool may be shared between threads.
+#[derive(Clone)]
+pub struct DecoderPool {
+    inner: Arc<PoolInner>,
+}
+
+struct PoolInner {
+    capacity: usize,
+    builder: DecoderBuilder,
+    idle: Mutex<Vec<Decoder>>,
+}
+
+impl DecoderPool {
+    /// Create a pool that keeps up to `capacity` idle decoders
+    /// created with the default configuration.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_builder(capacity, DecoderBuilder::new())
+    }
+
+    /// Create a pool that keeps up to `capacity` idle decoders
+    /// created by the given builder.
+    pub fn with_builder(capacity: usize, builder: DecoderBuilder) -> Self {
+        Self {
+            inner: Arc::new(PoolInner {
+                capacity,
+                builder,
+                idle: Mutex::new(Vec::with_capacity(capacity)),
+            }),
+        }
+    }
+
+    /// Returns the maximal number of idle decoders kept by the pool.
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity
+    }
+
+    /// Returns the number of idle decoders in the pool.
+    pub fn idle_count(&self) -> usize {
+        lock(&self.inner.idle).len()
+    }
+
+    /// Take an idle decoder from the pool or create a new one
+    /// if the pool is empty.
+    pub fn get(&self) -> Result<PooledDecoder> {
+        let idle = lock(&self.inner.idle).pop();
+        let decoder = match idle {
+            Some(decoder) => decoder,
+            None => self.inner.builder.build_decoder()?,
+        };
+        Ok(PooledDecoder {
+            decoder: Some(decoder),
+            pool: self.inner.clone(),
+        })
+    }
+}
+
+/// Decoder taken from a [`DecoderPool`].
+///
+/// It dereferences to [`Decoder`] and is returned into the pool on drop.
+pub struct PooledDecoder {
+    decoder: Option<Decoder>,
+    pool: Arc<PoolInner>,
+}
+
+impl PooledDecoder {
+    /// Take the decoder out of the pool, so it isn't returned on drop.
+    pub fn detach(mut self) -> Decoder {
+        self.decoder.take().expect("the decoder is taken only once")
+    }
+}
+
+impl Deref for PooledDecoder {
+    type Target = Decoder;
+
+    fn deref(&self) -> &Decoder {
+        self.decoder
+            .as_ref()
+            .expect("the decoder is taken only on drop")
+    }
+}
+
+impl DerefMut for PooledDecoder {
+    fn deref_mut(&mut self) -> &mut Decoder {
+        self.decoder
+            .as_mut()
+            .expect("the decoder is taken only on drop")
+    }
+}
+
+impl Drop for PooledDecoder {
+    fn drop(&mut self) {
+        let Some(mut decoder) = self.decoder.take() else {
+            return;
+        };
+        if lock(&self.pool.idle).len() >= self.pool.capacity || !decoder.recycle() {
+            return;
+        }
+        let mut idle = lock(&self.pool.idle);
+        if idle.len() < self.pool.capacity {
+            idle.push(decoder);
+        }
+    }
+}
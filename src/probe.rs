@@ -0,0 +1,33 @@
+use std::io::{self, Read};
+
+use crate::decoder::READ_BUFFER_SIZE;
+use crate::stream_info::SpsScanner;
+use crate::{ProbeError, StreamInfo};
+
+/// Get information about a raw h265 stream (Annex B byte stream)
+/// from SPS at its beginning without decoding pictures.
+///
+/// Not more than `max_bytes` bytes are read from the reader.
+/// Returns [`ProbeError::NotFound`] if there is no valid SPS
+/// in these bytes.
+pub fn probe_stream(reader: impl Read, max_bytes: usize) -> Result<StreamInfo, ProbeError> {
+    let mut reader = reader.take(max_bytes as u64);
+    let mut buf = vec![0; READ_BUFFER_SIZE.min(max_bytes).max(1)];
+    let mut scanner = SpsScanner::default();
+    loop {
+        let size = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(size) => size,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err.into()),
+        };
+        if let Some((info, _)) = scanner.push(&buf[..size]) {
+            return Ok(info);
+        }
+    }
+    // The last SPS may be truncated, but the info is at its beginning.
+    scanner
+        .finish()
+        .map(|(info, _)| info)
+        .ok_or(ProbeError::NotFound)
+}
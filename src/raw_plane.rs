@@ -0,0 +1,85 @@
+use std::ptr::NonNull;
+
+use crate::{Channel, Image, OwnedImage, PlaneView};
+
+/// Pointer to the memory of an image plane with its layout,
+/// see [`Image::plane_raw()`].
+///
+/// It is intended for copying of planes by foreign code,
+/// e.g. uploading into GPU textures.
+///
+/// # Safety
+///
+/// The memory is valid for reads of `pitch * height` bytes while the image
+/// that the plane was taken from is alive, rows start at multiples of `pitch`
+/// from `ptr`. Only the first `width * bytes_per_sample` bytes of each row
+/// are samples, the rest of the row is padding. The memory must not be written.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RawPlane {
+    /// Pointer to the first byte of the plane.
+    pub ptr: NonNull<u8>,
+    /// Bytes per line.
+    pub pitch: usize,
+    /// Width of the plane in samples.
+    pub width: u32,
+    pub height: u32,
+    /// Number of bytes used to store one sample (1 or 2).
+    /// Samples with 2 bytes are stored in native byte order.
+    pub bytes_per_sample: u8,
+}
+
+impl RawPlane {
+    fn new(view: PlaneView<'_>) -> Option<Self> {
+        let data = view.as_bytes();
+        if data.is_empty() || data.len() < view.stride() * view.height() as usize {
+            return None;
+        }
+        Some(Self {
+            ptr: NonNull::from(data).cast(),
+            pitch: view.stride(),
+            width: view.width(),
+            height: view.height(),
+            bytes_per_sample: view.bytes_per_sample() as u8,
+        })
+    }
+
+    fn for_each_row_ptr(&self, mut f: impl FnMut(*const u8)) {
+        for y in 0..self.height as usize {
+            // SAFETY: all rows are inside the memory of the plane.
+            f(unsafe { self.ptr.as_ptr().add(y * self.pitch) });
+        }
+    }
+}
+
+impl Image<'_> {
+    /// Returns the pointer to the memory of the plane and its layout,
+    /// or `None` if the picture doesn't have the plane.
+    ///
+    /// The memory stays valid while the image is alive,
+    /// see [`RawPlane`] for details.
+    pub fn plane_raw(&self, channel: Channel) -> Option<RawPlane> {
+        RawPlane::new(self.plane_view(channel))
+    }
+
+    /// Calls `f` with pointers to the first bytes of all rows
+    /// of the plane from top to bottom, see [`Image::plane_raw()`].
+    pub fn for_each_row_ptr(&self, channel: Channel, f: impl FnMut(*const u8)) {
+        if let Some(raw) = self.plane_raw(channel) {
+            raw.for_each_row_ptr(f);
+        }
+    }
+}
+
+impl OwnedImage {
+    /// See [`Image::plane_raw()`].
+    pub fn plane_raw(&self, channel: Channel) -> Option<RawPlane> {
+        RawPlane::new(self.plane_view(channel))
+    }
+
+    /// See [`Image::for_each_row_ptr()`].
+    pub fn for_each_row_ptr(&self, channel: Channel, f: impl FnMut(*const u8)) {
+        if let Some(raw) = self.plane_raw(channel) {
+            raw.for_each_row_ptr(f);
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Default minimal framerate ratio set by [`RealtimeController`].
+pub(crate) const DEFAULT_REALTIME_FLOOR: u8 = 25;
+
+/// Number of output pictures measured before the ratio may be changed.
+const WINDOW: usize = 5;
+
+/// Step of changing of the framerate ratio in percent.
+const STEP: u8 = 10;
+
+/// Controller of the framerate ratio that keeps the output of pictures
+/// in pace with the target interval between pictures.
+///
+/// Durations are measured between successive output pictures, so they
+/// include the time spent by the application to handle a picture.
+/// When `ratio` percent of pictures are decoded, each output picture
+/// may take `target * 100 / ratio`.
+pub(crate) struct RealtimeController {
+    target: Duration,
+    floor: u8,
+    ratio: u8,
+    pictures_output: u64,
+    last_output: Option<Instant>,
+    durations: VecDeque<Duration>,
+    adjustments: u64,
+}
+
+impl RealtimeController {
+    pub fn new(target: Duration, floor: u8, ratio: u8) -> Self {
+        Self {
+            target,
+            floor,
+            ratio: ratio.max(floor),
+            pictures_output: 0,
+            last_output: None,
+            durations: VecDeque::with_capacity(WINDOW),
+            adjustments: 0,
+        }
+    }
+
+    pub fn target(&self) -> Duration {
+        self.target
+    }
+
+    pub fn ratio(&self) -> u8 {
+        self.ratio
+    }
+
+    pub fn adjustments(&self) -> u64 {
+        self.adjustments
+    }
+
+    /// Set a new floor, returns the new ratio if it has to be raised.
+    pub fn set_floor(&mut self, floor: u8) -> Option<u8> {
+        self.floor = floor;
+        if self.ratio < floor {
+            self.ratio = floor;
+            self.durations.clear();
+            return Some(floor);
+        }
+        None
+    }
+
+    pub fn reset_adjustments(&mut self) {
+        self.adjustments = 0;
+    }
+
+    /// Forget measured durations, e.g. after reset of the decoder.
+    pub fn restart(&mut self) {
+        self.last_output = None;
+        self.durations.clear();
+    }
+
+    /// Register the total number of pictures output by the moment `now`.
+    ///
+    /// Returns the new framerate ratio if it has to be changed.
+    pub fn on_pictures(&mut self, pictures_output: u64, now: Instant) -> Option<u8> {
+        // The counter may be reset by `Decoder::reset_stats()`.
+        let count = pictures_output.saturating_sub(self.pictures_output);
+        self.pictures_output = pictures_output;
+        if count == 0 {
+            return None;
+        }
+        let last_output = self.last_output.replace(now)?;
+        let duration = now.saturating_duration_since(last_output) / count as u32;
+        for _ in 0..count.min(WINDOW as u64) {
+            if self.durations.len() == WINDOW {
+                self.durations.pop_front();
+            }
+            self.durations.push_back(duration);
+        }
+        if self.durations.len() < WINDOW {
+            return None;
+        }
+
+        let average = self.durations.iter().sum::<Duration>() / WINDOW as u32;
+        let new_ratio = if average > self.allowed_duration(self.ratio) {
+            self.ratio.saturating_sub(STEP).max(self.floor)
+        } else if self.ratio < 100 {
+            // Raise the ratio only with a margin, so that
+            // it doesn't oscillate around the limit.
+            let higher = self.ratio.saturating_add(STEP).min(100);
+            if average * 4 < self.allowed_duration(higher) * 3 {
+                higher
+            } else {
+                self.ratio
+            }
+        } else {
+            self.ratio
+        };
+        if new_ratio == self.ratio {
+            return None;
+        }
+        self.ratio = new_ratio;
+        self.adjustments += 1;
+        self.durations.clear();
+        Some(new_ratio)
+    }
+
+    fn allowed_duration(&self, ratio: u8) -> Duration {
+        self.target * 100 / ratio.max(1) as u32
+    }
+}
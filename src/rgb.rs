@@ -0,0 +1,646 @@
+use crate::{
+    Channel, ChromaFormat, Image, MatrixCoefficients, OwnedImage, PlaneView, Yuv444Buffer,
+};
+
+/// Picture converted into RGB with 8 bits per component,
+/// see [`Image::to_rgb8()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RgbImageBuffer {
+    pub width: u32,
+    pub height: u32,
+    /// Interleaved R, G and B components of pixels, row by row
+    /// without padding.
+    pub data: Vec<u8>,
+}
+
+impl RgbImageBuffer {
+    /// Returns R, G and B components of the pixel.
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 3]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let offset = (y as usize * self.width as usize + x as usize) * 3;
+        let rgb = self.data.get(offset..offset + 3)?;
+        Some([rgb[0], rgb[1], rgb[2]])
+    }
+}
+
+/// Picture converted into RGBA with 8 bits per component,
+/// see [`Image::to_rgba8()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RgbaImageBuffer {
+    pub width: u32,
+    pub height: u32,
+    /// Interleaved R, G, B and A components of pixels, row by row
+    /// without padding.
+    pub data: Vec<u8>,
+}
+
+impl RgbaImageBuffer {
+    /// Returns R, G, B and A components of the pixel.
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let offset = (y as usize * self.width as usize + x as usize) * 4;
+        let rgba = self.data.get(offset..offset + 4)?;
+        Some([rgba[0], rgba[1], rgba[2], rgba[3]])
+    }
+}
+
+/// Picture converted into RGB with 16 bits per component,
+/// see [`Image::to_rgb16()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Rgb16ImageBuffer {
+    pub width: u32,
+    pub height: u32,
+    /// Interleaved R, G and B components of pixels, row by row
+    /// without padding.
+    pub data: Vec<u16>,
+}
+
+impl Rgb16ImageBuffer {
+    /// Returns R, G and B components of the pixel.
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u16; 3]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let offset = (y as usize * self.width as usize + x as usize) * 3;
+        let rgb = self.data.get(offset..offset + 3)?;
+        Some([rgb[0], rgb[1], rgb[2]])
+    }
+}
+
+/// Picture converted into BGRA with 8 bits per component,
+/// see [`Image::to_bgra8()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct BgraImageBuffer {
+    pub width: u32,
+    pub height: u32,
+    /// Bytes per row, including padding at the end of the row.
+    pub stride: usize,
+    /// Interleaved B, G, R and A components of pixels, row by row.
+    /// Bytes of padding at the end of rows are zeroes.
+    pub data: Vec<u8>,
+}
+
+impl BgraImageBuffer {
+    /// Returns B, G, R and A components of the pixel.
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let offset = y as usize * self.stride + x as usize * 4;
+        let bgra = self.data.get(offset..offset + 4)?;
+        Some([bgra[0], bgra[1], bgra[2], bgra[3]])
+    }
+}
+
+impl Image<'_> {
+    /// Convert the picture into RGB with 8 bits per component.
+    ///
+    /// Coefficients of [`Image::matrix_coefficients_enum()`] are used
+    /// (see [`MatrixCoefficients::yuv_to_rgb_coefficients()`]). If the matrix
+    /// is unspecified or isn't defined by `Kr` and `Kb` constants,
+    /// BT.601 coefficients are used for pictures of standard definition
+    /// (up to 576 rows and narrower than 1280 columns) and BT.709 ones
+    /// for other pictures. Samples are expected in the limited
+    /// range, unless [`Image::full_range()`] is `true`. Chroma planes
+    /// of 4:2:0 and 4:2:2 pictures are upsampled with bilinear filtering
+    /// (chroma samples are co-sited with even luma samples horizontally
+    /// and located between rows of luma samples vertically).
+    /// Pixels that have chroma samples only on one side (e.g. the first
+    /// row and the last column of pictures with even width) use
+    /// the nearest column or row of chroma samples.
+    /// Monochrome pictures are converted into gray.
+    ///
+    /// To upsample chroma with another filter, convert the picture
+    /// with [`Image::to_yuv444()`] and then into RGB
+    /// with [`Yuv444Buffer::to_rgb8()`].
+    pub fn to_rgb8(&self) -> RgbImageBuffer {
+        let mut data = Vec::new();
+        let (width, height, _) = self.converter().convert_into(PixelOrder::Rgb, 1, &mut data);
+        RgbImageBuffer {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// Convert the picture into RGBA with 8 bits per component
+    /// and the given value of alpha of all pixels.
+    ///
+    /// See [`Image::to_rgb8()`] for details of the conversion.
+    pub fn to_rgba8(&self, alpha: u8) -> RgbaImageBuffer {
+        let mut data = Vec::new();
+        let (width, height, _) =
+            self.converter()
+                .convert_into(PixelOrder::Rgba(alpha), 1, &mut data);
+        RgbaImageBuffer {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// Convert the picture into RGB with 16 bits per component.
+    ///
+    /// Unlike [`Image::to_rgb8()`], it keeps the precision of pictures
+    /// with high bit depth, components use the full range `0..=65535`.
+    /// Pictures with 8 bits per sample are converted too.
+    /// See [`Image::to_rgb8()`] for details of the conversion.
+    pub fn to_rgb16(&self) -> Rgb16ImageBuffer {
+        let mut data = Vec::new();
+        let (width, height, _) = self.converter().convert_into(PixelOrder::Rgb, 1, &mut data);
+        Rgb16ImageBuffer {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// Same as [`Image::to_rgba8()`], but writes components of pixels into
+    /// the given vector, replacing its content. The vector doesn't
+    /// reallocate if its capacity is enough for the picture, so it may be
+    /// reused to convert every frame of a stream.
+    ///
+    /// Returns the width and height of the converted picture.
+    pub fn into_rgba8(&self, alpha: u8, dst: &mut Vec<u8>) -> (u32, u32) {
+        let (width, height, _) = self
+            .converter()
+            .convert_into(PixelOrder::Rgba(alpha), 1, dst);
+        (width, height)
+    }
+
+    /// Convert the picture into BGRA with 8 bits per component
+    /// and the given value of alpha of all pixels.
+    ///
+    /// Rows of the result are padded with zeroes so that the number of bytes
+    /// per row (stride) is a multiple of `row_alignment`, e.g. 256 for
+    /// uploading into Direct3D textures. Values 0 and 1 mean rows
+    /// without padding.
+    ///
+    /// See [`Image::to_rgb8()`] for details of the conversion.
+    pub fn to_bgra8(&self, alpha: u8, row_alignment: usize) -> BgraImageBuffer {
+        let mut data = Vec::new();
+        let (width, height, stride) =
+            self.converter()
+                .convert_into(PixelOrder::Bgra(alpha), row_alignment, &mut data);
+        BgraImageBuffer {
+            width,
+            height,
+            stride,
+            data,
+        }
+    }
+
+    /// Same as [`Image::to_bgra8()`], but writes components of pixels into
+    /// the given vector, replacing its content. The vector doesn't
+    /// reallocate if its capacity is enough for the picture.
+    ///
+    /// Returns the width, height and stride of the converted picture.
+    pub fn into_bgra8(
+        &self,
+        alpha: u8,
+        row_alignment: usize,
+        dst: &mut Vec<u8>,
+    ) -> (u32, u32, usize) {
+        self.converter()
+            .convert_into(PixelOrder::Bgra(alpha), row_alignment, dst)
+    }
+
+    fn converter(&self) -> Converter<'_> {
+        Converter {
+            planes: CHANNELS.map(|channel| {
+                Samples::new(self.plane_view(channel), self.bits_per_pixel(channel))
+            }),
+            chroma_format: self.chroma_format(),
+            matrix_coefficients: self.matrix_coefficients(),
+            full_range: self.full_range(),
+        }
+    }
+}
+
+impl OwnedImage {
+    /// See [`Image::to_rgb8()`].
+    pub fn to_rgb8(&self) -> RgbImageBuffer {
+        let mut data = Vec::new();
+        let (width, height, _) = self.converter().convert_into(PixelOrder::Rgb, 1, &mut data);
+        RgbImageBuffer {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// See [`Image::to_rgba8()`].
+    pub fn to_rgba8(&self, alpha: u8) -> RgbaImageBuffer {
+        let mut data = Vec::new();
+        let (width, height, _) =
+            self.converter()
+                .convert_into(PixelOrder::Rgba(alpha), 1, &mut data);
+        RgbaImageBuffer {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// See [`Image::to_rgb16()`].
+    pub fn to_rgb16(&self) -> Rgb16ImageBuffer {
+        let mut data = Vec::new();
+        let (width, height, _) = self.converter().convert_into(PixelOrder::Rgb, 1, &mut data);
+        Rgb16ImageBuffer {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// See [`Image::to_bgra8()`].
+    pub fn to_bgra8(&self, alpha: u8, row_alignment: usize) -> BgraImageBuffer {
+        let mut data = Vec::new();
+        let (width, height, stride) =
+            self.converter()
+                .convert_into(PixelOrder::Bgra(alpha), row_alignment, &mut data);
+        BgraImageBuffer {
+            width,
+            height,
+            stride,
+            data,
+        }
+    }
+
+    /// See [`Image::into_bgra8()`].
+    pub fn into_bgra8(
+        &self,
+        alpha: u8,
+        row_alignment: usize,
+        dst: &mut Vec<u8>,
+    ) -> (u32, u32, usize) {
+        self.converter()
+            .convert_into(PixelOrder::Bgra(alpha), row_alignment, dst)
+    }
+
+    /// See [`Image::into_rgba8()`].
+    pub fn into_rgba8(&self, alpha: u8, dst: &mut Vec<u8>) -> (u32, u32) {
+        let (width, height, _) = self
+            .converter()
+            .convert_into(PixelOrder::Rgba(alpha), 1, dst);
+        (width, height)
+    }
+
+    fn converter(&self) -> Converter<'_> {
+        Converter {
+            planes: CHANNELS.map(|channel| {
+                Samples::new(self.plane_view(channel), self.bits_per_pixel(channel))
+            }),
+            chroma_format: self.chroma_format(),
+            matrix_coefficients: self.matrix_coefficients(),
+            full_range: self.full_range(),
+        }
+    }
+}
+
+impl Yuv444Buffer {
+    /// See [`Image::to_rgb8()`].
+    pub fn to_rgb8(&self) -> RgbImageBuffer {
+        let mut data = Vec::new();
+        let (width, height, _) = self.converter().convert_into(PixelOrder::Rgb, 1, &mut data);
+        RgbImageBuffer {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// See [`Image::to_rgba8()`].
+    pub fn to_rgba8(&self, alpha: u8) -> RgbaImageBuffer {
+        let mut data = Vec::new();
+        let (width, height, _) =
+            self.converter()
+                .convert_into(PixelOrder::Rgba(alpha), 1, &mut data);
+        RgbaImageBuffer {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// See [`Image::to_rgb16()`].
+    pub fn to_rgb16(&self) -> Rgb16ImageBuffer {
+        let mut data = Vec::new();
+        let (width, height, _) = self.converter().convert_into(PixelOrder::Rgb, 1, &mut data);
+        Rgb16ImageBuffer {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// See [`Image::to_bgra8()`].
+    pub fn to_bgra8(&self, alpha: u8, row_alignment: usize) -> BgraImageBuffer {
+        let mut data = Vec::new();
+        let (width, height, stride) =
+            self.converter()
+                .convert_into(PixelOrder::Bgra(alpha), row_alignment, &mut data);
+        BgraImageBuffer {
+            width,
+            height,
+            stride,
+            data,
+        }
+    }
+
+    fn converter(&self) -> Converter<'_> {
+        Converter {
+            planes: CHANNELS.map(|channel| {
+                Samples::new(self.plane_view(channel), self.bits_per_pixel(channel))
+            }),
+            chroma_format: ChromaFormat::C444,
+            matrix_coefficients: self.matrix_coefficients(),
+            full_range: self.full_range(),
+        }
+    }
+}
+
+const CHANNELS: [Channel; 3] = [Channel::Y, Channel::Cb, Channel::Cr];
+
+/// Order of components of output pixels.
+#[derive(Debug, Copy, Clone)]
+enum PixelOrder<T> {
+    Rgb,
+    Rgba(T),
+    Bgra(T),
+}
+
+impl<T> PixelOrder<T> {
+    fn pixel_size(&self) -> usize {
+        match self {
+            Self::Rgb => 3,
+            Self::Rgba(_) | Self::Bgra(_) => 4,
+        }
+    }
+}
+
+struct Converter<'a> {
+    planes: [Samples<'a>; 3],
+    chroma_format: ChromaFormat,
+    matrix_coefficients: u8,
+    full_range: bool,
+}
+
+impl Converter<'_> {
+    /// Replaces the content of `data` with pixels of the picture.
+    /// Rows are padded with default values of `T` up to a multiple
+    /// of `row_alignment` components.
+    /// Returns the width, height and stride (in components) of the picture.
+    fn convert_into<T: Component>(
+        &self,
+        order: PixelOrder<T>,
+        row_alignment: usize,
+        data: &mut Vec<T>,
+    ) -> (u32, u32, usize) {
+        let [luma, cb, cr] = &self.planes;
+        let width = luma.width();
+        let height = luma.height();
+        let row_size = width * order.pixel_size();
+        let stride = row_size.next_multiple_of(row_alignment.max(1));
+        data.clear();
+        data.reserve(stride * height);
+
+        let [cr_r, cb_g, cr_g, cb_b] = MatrixCoefficients::from_code(self.matrix_coefficients)
+            .conversion_coefficients(width, height);
+        let (sub_x, sub_y) = self.chroma_format.subsampling();
+        for y in 0..height {
+            let luma_row = luma.row(y);
+            let (y0, y1, fy) = taps(chroma_y(y, sub_y as usize), cb.height());
+            let cb_rows = (cb.row(y0), cb.row(y1));
+            let cr_rows = (cr.row(y0), cr.row(y1));
+            for x in 0..width {
+                let value = self.luma(luma.get(luma_row, x), luma.bits);
+                let rgb = if !self.chroma_format.has_chroma() {
+                    [T::from_normalized(value); 3]
+                } else {
+                    let x_taps = taps(chroma_x(x, sub_x as usize), cb.width());
+                    let pb = self.chroma(cb.bilinear(cb_rows, x_taps, fy), cb.bits);
+                    let pr = self.chroma(cr.bilinear(cr_rows, x_taps, fy), cr.bits);
+                    let r = value + cr_r * pr;
+                    let g = value + cb_g * pb + cr_g * pr;
+                    let b = value + cb_b * pb;
+                    [r, g, b].map(T::from_normalized)
+                };
+                let [r, g, b] = rgb;
+                match order {
+                    PixelOrder::Rgb => data.extend_from_slice(&rgb),
+                    PixelOrder::Rgba(alpha) => data.extend_from_slice(&[r, g, b, alpha]),
+                    PixelOrder::Bgra(alpha) => data.extend_from_slice(&[b, g, r, alpha]),
+                }
+            }
+            data.resize(data.len() + stride - row_size, T::default());
+        }
+        (width as u32, height as u32, stride)
+    }
+
+    /// Normalize a luma sample into the range `0..=1`.
+    fn luma(&self, value: f32, bits: u32) -> f32 {
+        if self.full_range {
+            value / max_value(bits)
+        } else {
+            (value / scale(bits) - 16.) / 219.
+        }
+    }
+
+    /// Normalize a chroma sample into the range `-0.5..=0.5`.
+    fn chroma(&self, value: f32, bits: u32) -> f32 {
+        if self.full_range {
+            (value - (1 << bits.saturating_sub(1)) as f32) / max_value(bits)
+        } else {
+            (value / scale(bits) - 128.) / 224.
+        }
+    }
+}
+
+fn max_value(bits: u32) -> f32 {
+    ((1u32 << bits.min(16)) - 1) as f32
+}
+
+/// Returns `true` if the picture with the given bit depths of planes
+/// is converted into RGB with 16 bits per component.
+pub(crate) fn high_bit_depth(bits_per_pixel: impl Fn(Channel) -> u32) -> bool {
+    bits_per_pixel(Channel::Y) > 8 || bits_per_pixel(Channel::Cb) > 8
+}
+
+/// Multiplier of limited range values for the bit depth.
+fn scale(bits: u32) -> f32 {
+    (1 << bits.saturating_sub(8)) as f32
+}
+
+/// Type of components of output pixels.
+trait Component: Copy + Default {
+    /// Converts a value in the range `0..=1` into the component,
+    /// values outside the range are clamped.
+    fn from_normalized(value: f32) -> Self;
+}
+
+impl Component for u8 {
+    fn from_normalized(value: f32) -> Self {
+        (value.clamp(0., 1.) * u8::MAX as f32).round() as u8
+    }
+}
+
+impl Component for u16 {
+    fn from_normalized(value: f32) -> Self {
+        (value.clamp(0., 1.) * u16::MAX as f32).round() as u16
+    }
+}
+
+/// Horizontal position of the luma sample in a chroma plane,
+/// chroma samples are co-sited with even luma samples.
+pub(crate) fn chroma_x(x: usize, subsampling: usize) -> f32 {
+    x as f32 / subsampling as f32
+}
+
+/// Vertical position of the luma sample in a chroma plane, subsampled
+/// chroma samples are located between rows of luma samples.
+pub(crate) fn chroma_y(y: usize, subsampling: usize) -> f32 {
+    if subsampling > 1 {
+        (y as f32 - 0.5) / subsampling as f32
+    } else {
+        y as f32
+    }
+}
+
+/// Returns indexes of two samples around the fractional position
+/// and the weight of the second one. Positions outside the range
+/// `0..len` are clamped to the nearest sample.
+pub(crate) fn taps(pos: f32, len: usize) -> (usize, usize, f32) {
+    let last = len.saturating_sub(1);
+    let pos = pos.clamp(0., last as f32);
+    let i0 = pos.floor() as usize;
+    (i0, (i0 + 1).min(last), pos - i0 as f32)
+}
+
+/// Access to samples of a plane.
+pub(crate) struct Samples<'a> {
+    view: PlaneView<'a>,
+    pub(crate) bits: u32,
+}
+
+impl<'a> Samples<'a> {
+    pub(crate) fn new(view: PlaneView<'a>, bits: u32) -> Self {
+        Self { view, bits }
+    }
+
+    pub(crate) fn width(&self) -> usize {
+        self.view.width() as usize
+    }
+
+    pub(crate) fn height(&self) -> usize {
+        self.view.height() as usize
+    }
+
+    pub(crate) fn row(&self, y: usize) -> &'a [u8] {
+        self.view.row(y as u32).unwrap_or_default()
+    }
+
+    pub(crate) fn get(&self, row: &[u8], x: usize) -> f32 {
+        let bytes_per_sample = self.view.bytes_per_sample();
+        let offset = x * bytes_per_sample;
+        match row.get(offset..offset + bytes_per_sample) {
+            Some(&[value]) => value as f32,
+            Some(&[b0, b1]) => u16::from_ne_bytes([b0, b1]) as f32,
+            _ => 0.,
+        }
+    }
+
+    /// Interpolates samples of the top and bottom rows.
+    pub(crate) fn bilinear(
+        &self,
+        rows: (&[u8], &[u8]),
+        x_taps: (usize, usize, f32),
+        fy: f32,
+    ) -> f32 {
+        let (top, bottom) = rows;
+        let (x0, x1, fx) = x_taps;
+        let top = self.get(top, x0) * (1. - fx) + self.get(top, x1) * fx;
+        let bottom = self.get(bottom, x0) * (1. - fx) + self.get(bottom, x1) * fx;
+        top * (1. - fy) + bottom * fy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns R components of pixels of the 8-bit full range picture
+    /// with constant luma and Cb equal to 128, so R = 128 + 1.402 * (Cr - 128).
+    fn red(
+        cr: &[u8],
+        (width, height): (u32, u32),
+        (chroma_width, chroma_height): (u32, u32),
+        chroma_format: ChromaFormat,
+    ) -> Vec<u8> {
+        let luma = vec![128; (width * height) as usize];
+        let cb = vec![128; cr.len()];
+        let plane = |data, width: u32, height| {
+            Samples::new(PlaneView::new(data, width as usize, width, height, 1), 8)
+        };
+        let converter = Converter {
+            planes: [
+                plane(&luma, width, height),
+                plane(&cb, chroma_width, chroma_height),
+                plane(cr, chroma_width, chroma_height),
+            ],
+            chroma_format,
+            matrix_coefficients: 2,
+            full_range: true,
+        };
+        let mut data: Vec<u8> = Vec::new();
+        assert_eq!(
+            converter.convert_into(PixelOrder::Rgb, 1, &mut data),
+            (width, height, width as usize * 3)
+        );
+        data.chunks(3).map(|p| p[0]).collect()
+    }
+
+    #[test]
+    fn odd_size_with_420_chroma() {
+        // Pixels with even x are co-sited with chroma columns,
+        // pixels with odd x are between them. The last column
+        // of the picture with odd width is co-sited with the last
+        // column of chroma.
+        let red_x = red(&[128, 138, 128, 138], (3, 3), (2, 2), ChromaFormat::C420);
+        assert_eq!(red_x, [128, 135, 142, 128, 135, 142, 128, 135, 142]);
+
+        // Rows of chroma are located between luma rows 0 and 1,
+        // and between rows 2 and 3. The first row of the picture
+        // is above the first row of chroma and replicates it,
+        // the last row of the picture with odd height is above
+        // the last row of chroma.
+        let red_y = red(&[128, 128, 148, 148], (3, 3), (2, 2), ChromaFormat::C420);
+        assert_eq!(red_y, [128, 128, 128, 135, 135, 135, 149, 149, 149]);
+    }
+
+    #[test]
+    fn even_size_with_420_chroma() {
+        // The last column and the last row of the picture are beyond
+        // the last column and row of chroma and replicate them.
+        let red_x = red(&[128, 138, 128, 138], (4, 4), (2, 2), ChromaFormat::C420);
+        assert_eq!(red_x, [128, 135, 142, 142].repeat(4));
+
+        let red_y = red(&[128, 128, 148, 148], (4, 4), (2, 2), ChromaFormat::C420);
+        let rows: Vec<u8> = [128, 135, 149, 156].iter().flat_map(|&r| [r; 4]).collect();
+        assert_eq!(red_y, rows);
+    }
+
+    #[test]
+    fn odd_width_with_422_chroma() {
+        let red_x = red(&[128, 148], (3, 1), (2, 1), ChromaFormat::C422);
+        assert_eq!(red_x, [128, 142, 156]);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::nal::{nal_unit_type, rbsp_data};
+use crate::user_data::is_first_slice_segment;
+use crate::{ContentLightLevel, MasteringDisplay, NalUnitType, TransferCharacteristics};
+
+/// `payloadType` of the mastering display colour volume SEI message.
+const MASTERING_DISPLAY_COLOUR_VOLUME: u32 = 137;
+
+/// `payloadType` of the content light level information SEI message.
+const CONTENT_LIGHT_LEVEL_INFO: u32 = 144;
+
+/// `payloadType` of the alternative transfer characteristics SEI message.
+const ALTERNATIVE_TRANSFER_CHARACTERISTICS: u32 = 147;
+
+/// Metadata of a picture from SEI messages.
+#[derive(Debug, Copy, Clone, Default)]
+pub(crate) struct SeiMetadata {
+    pub mastering_display: Option<MasteringDisplay>,
+    pub content_light_level: Option<ContentLightLevel>,
+    pub alternative_transfer: Option<TransferCharacteristics>,
+}
+
+/// Tracker of SEI messages with HDR metadata in NAL units
+/// pushed by [`DecoderInput::push_nal()`](crate::DecoderInput::push_nal).
+///
+/// A message applies to the picture that follows it and to the rest
+/// of the coded video sequence, so it is kept until it is replaced
+/// or until the next IDR or BLA picture without this message.
+#[derive(Debug, Default)]
+pub(crate) struct SeiTracker {
+    /// Messages pushed after the previous picture.
+    pending: SeiMetadata,
+    current: SeiMetadata,
+}
+
+impl SeiTracker {
+    /// Handle the pushed NAL unit and return metadata of the picture
+    /// if the NAL unit contains its first slice segment.
+    pub fn push_nal(&mut self, data: &[u8]) -> Option<SeiMetadata> {
+        let nal_unit_type = nal_unit_type(data)?;
+        if nal_unit_type == NalUnitType::PrefixSeiNut {
+            let rbsp = rbsp_data(data.get(2..)?);
+            for (payload_type, payload) in sei_messages(&rbsp) {
+                match payload_type {
+                    MASTERING_DISPLAY_COLOUR_VOLUME => {
+                        if let Some(display) = MasteringDisplay::parse(payload) {
+                            self.pending.mastering_display = Some(display);
+                        }
+                    }
+                    CONTENT_LIGHT_LEVEL_INFO => {
+                        if let Some(level) = ContentLightLevel::parse(payload) {
+                            self.pending.content_light_level = Some(level);
+                        }
+                    }
+                    ALTERNATIVE_TRANSFER_CHARACTERISTICS => {
+                        if let Some(&code) = payload.first() {
+                            self.pending.alternative_transfer =
+                                Some(TransferCharacteristics::from_code(code));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            return None;
+        }
+        if nal_unit_type == NalUnitType::EosNut {
+            self.current = SeiMetadata::default();
+            return None;
+        }
+        if !is_first_slice_segment(data) {
+            return None;
+        }
+        let new_sequence = nal_unit_type.is_idr() || nal_unit_type.is_bla();
+        let pending = std::mem::take(&mut self.pending);
+        update(
+            &mut self.current.mastering_display,
+            pending.mastering_display,
+            new_sequence,
+        );
+        update(
+            &mut self.current.content_light_level,
+            pending.content_light_level,
+            new_sequence,
+        );
+        update(
+            &mut self.current.alternative_transfer,
+            pending.alternative_transfer,
+            new_sequence,
+        );
+        Some(self.current)
+    }
+}
+
+/// Replace the current value with the pending one
+/// or clear it at the start of a new coded video sequence.
+fn update<T>(current: &mut Option<T>, pending: Option<T>, new_sequence: bool) {
+    if pending.is_some() || new_sequence {
+        *current = pending;
+    }
+}
+
+/// Iterator over `(payloadType, payload)` of SEI messages in SEI RBSP
+/// (with removed emulation prevention bytes).
+///
+/// The iteration stops at the RBSP trailing bits or at a malformed message.
+pub(crate) struct SeiMessages<'a> {
+    rbsp: &'a [u8],
+    pos: usize,
+}
+
+/// Returns SEI messages of SEI RBSP.
+pub(crate) fn sei_messages(rbsp: &[u8]) -> SeiMessages<'_> {
+    SeiMessages { rbsp, pos: 0 }
+}
+
+impl SeiMessages<'_> {
+    /// Read a value coded as a sequence of `0xff` bytes and the last byte.
+    fn read_value(&mut self) -> Option<u32> {
+        let mut value = 0u32;
+        loop {
+            let byte = *self.rbsp.get(self.pos)?;
+            self.pos += 1;
+            value = value.checked_add(byte as u32)?;
+            if byte != 0xff {
+                return Some(value);
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for SeiMessages<'a> {
+    type Item = (u32, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The last byte contains RBSP trailing bits.
+        if self.pos + 1 >= self.rbsp.len() {
+            return None;
+        }
+        let message = (|| {
+            let payload_type = self.read_value()?;
+            let payload_size = self.read_value()? as usize;
+            let payload = self
+                .rbsp
+                .get(self.pos..self.pos.checked_add(payload_size)?)?;
+            self.pos += payload_size;
+            Some((payload_type, payload))
+        })();
+        if message.is_none() {
+            self.pos = self.rbsp.len();
+        }
+        message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alternative_transfer_characteristics() {
+        let mut tracker = SeiTracker::default();
+        // Prefix SEI with an unknown message (type 200 with 3 bytes)
+        // followed by the alternative transfer characteristics message.
+        let sei = [0x4e, 0x01, 200, 3, 1, 2, 3, 147, 1, 18, 0x80];
+        assert!(tracker.push_nal(&sei).is_none());
+        // The first slice segment of IDR picture.
+        let idr = [0x26, 0x01, 0x80];
+        let metadata = tracker.push_nal(&idr).unwrap();
+        assert_eq!(
+            metadata.alternative_transfer,
+            Some(TransferCharacteristics::AribStdB67)
+        );
+        // The value applies to the following pictures of the sequence.
+        let trail = [0x02, 0x01, 0x80];
+        let metadata = tracker.push_nal(&trail).unwrap();
+        assert_eq!(
+            metadata.alternative_transfer,
+            Some(TransferCharacteristics::AribStdB67)
+        );
+        // A malformed message is ignored.
+        let sei = [0x4e, 0x01, 147, 5, 18, 0x80];
+        assert!(tracker.push_nal(&sei).is_none());
+        let metadata = tracker.push_nal(&idr).unwrap();
+        assert_eq!(metadata.alternative_transfer, None);
+    }
+
+    #[test]
+    fn messages_of_rbsp() {
+        // Payload type 300 is coded as 0xff followed by 45.
+        let rbsp = [5, 2, 1, 2, 0xff, 45, 1, 7, 0x80];
+        let messages: Vec<_> = sei_messages(&rbsp).collect();
+        assert_eq!(messages, [(5, &[1, 2][..]), (300, &[7][..])]);
+
+        // The size of the second message exceeds the RBSP.
+        let rbsp = [5, 1, 1, 137, 24, 0, 0x80];
+        let messages: Vec<_> = sei_messages(&rbsp).collect();
+        assert_eq!(messages, [(5, &[1][..])]);
+    }
+}
@@ -0,0 +1,89 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::decoder::READ_BUFFER_SIZE;
+use crate::{AnnexBSplitter, StreamError};
+
+/// NAL unit provided by a [`NalSource`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SourceNal<'a> {
+    /// NAL unit data without the start code.
+    pub data: &'a [u8],
+    /// The presentation time stamp in units of the decoder time base.
+    pub pts: i64,
+    /// `true` if it is the last NAL unit of an access unit, so the end
+    /// of frame is indicated right after pushing it into the decoder.
+    /// If a source doesn't know boundaries of access units, it should be `false`.
+    pub is_access_unit_end: bool,
+}
+
+/// Provider of NAL units (e.g. a demuxer of a container)
+/// that feeds [`Decoder::run_from_source()`](crate::Decoder::run_from_source).
+pub trait NalSource {
+    /// Returns the next NAL unit or `None` at the end of the stream.
+    fn next_nal(&mut self) -> Result<Option<SourceNal<'_>>, StreamError>;
+}
+
+/// Source of NAL units read from an Annex B byte stream (e.g. a raw h265 file).
+///
+/// The byte stream has no timing information and boundaries of access units,
+/// so all NAL units have zero PTS and `is_access_unit_end` is `false`.
+pub struct AnnexBFileSource<R = File> {
+    reader: R,
+    splitter: AnnexBSplitter,
+    buf: Vec<u8>,
+    /// Data of the last returned NAL unit.
+    nal: Vec<u8>,
+    eof: bool,
+}
+
+impl AnnexBFileSource<File> {
+    /// Open the h265 file.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> AnnexBFileSource<R> {
+    /// Create a source that reads the byte stream from the given reader.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            splitter: AnnexBSplitter::new(),
+            buf: vec![0; READ_BUFFER_SIZE],
+            nal: Vec::new(),
+            eof: false,
+        }
+    }
+}
+
+impl<R: Read> NalSource for AnnexBFileSource<R> {
+    fn next_nal(&mut self) -> Result<Option<SourceNal<'_>>, StreamError> {
+        loop {
+            let nal = if self.eof {
+                self.splitter.finish()
+            } else {
+                self.splitter.next_nal()
+            };
+            if let Some(nal) = nal {
+                self.nal.clear();
+                self.nal.extend_from_slice(nal.data);
+                return Ok(Some(SourceNal {
+                    data: &self.nal,
+                    pts: 0,
+                    is_access_unit_end: false,
+                }));
+            }
+            if self.eof {
+                return Ok(None);
+            }
+            match self.reader.read(&mut self.buf) {
+                Ok(0) => self.eof = true,
+                Ok(size) => self.splitter.push(&self.buf[..size]),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+}
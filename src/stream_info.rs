@@ -0,0 +1,557 @@
+use crate::nal::{nal_unit_type, trim_trailing_zeros};
+use crate::{ChromaFormat, Image, NalUnitType, OwnedImage};
+
+/// Basic information about a stream.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct StreamInfo {
+    /// Width of the luma plane.
+    pub width: u32,
+    /// Height of the luma plane.
+    pub height: u32,
+    pub chroma_format: ChromaFormat,
+    /// Bit depth of the luma plane.
+    pub bits_per_pixel: u32,
+    /// Bit depth of chroma planes.
+    pub chroma_bits_per_pixel: u32,
+    /// `general_profile_idc` (1 - Main, 2 - Main 10, 3 - Main Still Picture,
+    /// 4 - Format Range Extensions, etc.).
+    pub profile_idc: u8,
+    pub tier: Tier,
+    /// `general_level_idc` - the level number multiplied by 30
+    /// (e.g. 93 for level 3.1).
+    pub level_idc: u8,
+    /// Maximal number of pictures in the decoded picture buffer
+    /// (`sps_max_dec_pic_buffering_minus1 + 1` of the highest sub-layer).
+    pub max_dec_pic_buffering: u32,
+    /// Frame rate as `(numerator, denominator)` from VUI timing info
+    /// (`vui_time_scale / vui_num_units_in_tick`), if it is present.
+    pub frame_rate: Option<(u32, u32)>,
+    /// Sample aspect ratio as `(width, height)` from VUI,
+    /// `None` if it is unspecified.
+    pub sample_aspect_ratio: Option<(u32, u32)>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Tier {
+    Main,
+    High,
+}
+
+/// Timing info from VUI of SPS.
+///
+/// The frame rate is nominal: a tick of the clock is the duration
+/// of one frame only if the stream doesn't signal a fixed frame rate
+/// with other units (`elemental_duration_in_tc_minus1`) or fields
+/// instead of frames (`field_seq_flag`), and pictures may have
+/// other durations if the frame rate isn't fixed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct FrameTiming {
+    /// Number of time units in one tick of the clock.
+    pub num_units_in_tick: u32,
+    /// Number of time units in one second.
+    pub time_scale: u32,
+}
+
+impl FrameTiming {
+    /// Nominal number of frames per second.
+    pub fn frames_per_second(&self) -> f64 {
+        self.time_scale as f64 / self.num_units_in_tick as f64
+    }
+}
+
+impl StreamInfo {
+    /// Parse SPS NAL unit (with NAL header, without start code).
+    /// Dimensions are cropped by the conformance window like
+    /// dimensions of decoded pictures.
+    ///
+    /// Returns `None` if the NAL unit is not a valid SPS.
+    pub(crate) fn from_sps(nal: &[u8]) -> Option<Self> {
+        parse_sps(nal).map(|(info, _)| info)
+    }
+}
+
+/// Size of decoded pictures including the area outside
+/// the conformance window, offsets of the window in luma samples,
+/// the sample aspect ratio, timing info and parameters required
+/// to parse slice headers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct CodedGeometry {
+    pub sps_id: u32,
+    pub separate_colour_plane: bool,
+    pub log2_max_poc_lsb: u32,
+    pub width: u32,
+    pub height: u32,
+    pub crop_left: u32,
+    pub crop_top: u32,
+    pub sample_aspect_ratio: Option<(u32, u32)>,
+    pub timing: Option<FrameTiming>,
+}
+
+/// Parameters parsed from VUI.
+#[derive(Default)]
+struct Vui {
+    sample_aspect_ratio: Option<(u32, u32)>,
+    timing: Option<FrameTiming>,
+}
+
+/// Sample aspect ratios of `aspect_ratio_idc` values from 1 to 16.
+const SAMPLE_ASPECT_RATIOS: [(u32, u32); 16] = [
+    (1, 1),
+    (12, 11),
+    (10, 11),
+    (16, 11),
+    (40, 33),
+    (24, 11),
+    (20, 11),
+    (32, 11),
+    (80, 33),
+    (18, 11),
+    (15, 11),
+    (64, 33),
+    (160, 99),
+    (4, 3),
+    (3, 2),
+    (2, 1),
+];
+
+/// `aspect_ratio_idc` of sample aspect ratio coded explicitly.
+const EXTENDED_SAR: u32 = 255;
+
+impl Image<'_> {
+    /// Returns timing info from VUI of SPS pushed into the decoder.
+    ///
+    /// Returns `None` if SPS has no timing info or there is
+    /// no SPS with the size of the image.
+    pub fn timing(&self) -> Option<FrameTiming> {
+        self.coded_geometry()?.timing
+    }
+
+    /// Nominal number of frames per second of the stream,
+    /// see [`FrameTiming`].
+    pub fn frames_per_second(&self) -> Option<f64> {
+        self.timing().map(|timing| timing.frames_per_second())
+    }
+}
+
+impl OwnedImage {
+    /// See [`Image::timing()`].
+    pub fn timing(&self) -> Option<FrameTiming> {
+        self.timing
+    }
+
+    /// See [`Image::frames_per_second()`].
+    pub fn frames_per_second(&self) -> Option<f64> {
+        self.timing.map(|timing| timing.frames_per_second())
+    }
+}
+
+/// Parse SPS NAL unit (with NAL header, without start code).
+pub(crate) fn parse_sps(nal: &[u8]) -> Option<(StreamInfo, CodedGeometry)> {
+    if nal_unit_type(nal) != Some(NalUnitType::SpsNut) {
+        return None;
+    }
+    let mut reader = BitReader::new(nal.get(2..)?);
+    // sps_video_parameter_set_id
+    reader.skip(4)?;
+    let max_sub_layers_minus1 = reader.read(3)? as usize;
+    // sps_temporal_id_nesting_flag
+    reader.skip(1)?;
+
+    // profile_tier_level()
+    // general_profile_space
+    reader.skip(2)?;
+    let tier = if reader.read(1)? == 1 {
+        Tier::High
+    } else {
+        Tier::Main
+    };
+    let profile_idc = reader.read(5)? as u8;
+    // general_profile_compatibility_flag[32] and constraint flags
+    reader.skip(32 + 48)?;
+    let level_idc = reader.read(8)? as u8;
+    let mut sub_layers = [(false, false); 7];
+    for flags in sub_layers.iter_mut().take(max_sub_layers_minus1) {
+        *flags = (reader.read(1)? == 1, reader.read(1)? == 1);
+    }
+    if max_sub_layers_minus1 > 0 {
+        // reserved_zero_2bits
+        reader.skip(2 * (8 - max_sub_layers_minus1))?;
+    }
+    for &(profile_present, level_present) in sub_layers.iter().take(max_sub_layers_minus1) {
+        if profile_present {
+            reader.skip(88)?;
+        }
+        if level_present {
+            reader.skip(8)?;
+        }
+    }
+
+    let sps_id = reader.read_ue()?;
+    let chroma_format_idc = reader.read_ue()?;
+    let chroma_format = match chroma_format_idc {
+        0 => ChromaFormat::Mono,
+        1 => ChromaFormat::C420,
+        2 => ChromaFormat::C422,
+        3 => ChromaFormat::C444,
+        _ => return None,
+    };
+    let separate_colour_plane = chroma_format_idc == 3 && reader.read(1)? == 1;
+    let coded_width = reader.read_ue()?;
+    let coded_height = reader.read_ue()?;
+    let (mut width, mut height) = (coded_width, coded_height);
+    let (mut crop_left, mut crop_top) = (0, 0);
+    if reader.read(1)? == 1 {
+        // Units of the conformance window offsets.
+        let (sub_width, sub_height) = match chroma_format.subsampling() {
+            _ if separate_colour_plane || !chroma_format.has_chroma() => (1, 1),
+            (sub_width, sub_height) => (sub_width as u32, sub_height as u32),
+        };
+        let left = reader.read_ue()?;
+        let right = reader.read_ue()?;
+        let top = reader.read_ue()?;
+        let bottom = reader.read_ue()?;
+        width = width.checked_sub(left.checked_add(right)?.checked_mul(sub_width)?)?;
+        height = height.checked_sub(top.checked_add(bottom)?.checked_mul(sub_height)?)?;
+        crop_left = left * sub_width;
+        crop_top = top * sub_height;
+    }
+    let bits_per_pixel = reader.read_ue()?.checked_add(8)?;
+    let chroma_bits_per_pixel = reader.read_ue()?.checked_add(8)?;
+    let log2_max_poc_lsb = reader.read_ue()?.checked_add(4)?;
+    let sub_layer_ordering_info_present = reader.read(1)? == 1;
+    let first_sub_layer = if sub_layer_ordering_info_present {
+        0
+    } else {
+        max_sub_layers_minus1
+    };
+    let mut max_dec_pic_buffering = 0;
+    for _ in first_sub_layer..=max_sub_layers_minus1 {
+        max_dec_pic_buffering = reader.read_ue()?.checked_add(1)?;
+        // sps_max_num_reorder_pics and sps_max_latency_increase_plus1
+        reader.read_ue()?;
+        reader.read_ue()?;
+    }
+    // The rest of SPS is only needed for VUI, so a truncated
+    // or unsupported tail doesn't invalidate the basic info.
+    let mut vui = Vui::default();
+    let _ = parse_vui(&mut reader, log2_max_poc_lsb, &mut vui);
+    let info = StreamInfo {
+        width,
+        height,
+        chroma_format,
+        bits_per_pixel,
+        chroma_bits_per_pixel,
+        profile_idc,
+        tier,
+        level_idc,
+        max_dec_pic_buffering,
+        frame_rate: vui
+            .timing
+            .map(|timing| (timing.time_scale, timing.num_units_in_tick)),
+        sample_aspect_ratio: vui.sample_aspect_ratio,
+    };
+    let geometry = CodedGeometry {
+        sps_id,
+        separate_colour_plane,
+        log2_max_poc_lsb,
+        width: coded_width,
+        height: coded_height,
+        crop_left,
+        crop_top,
+        sample_aspect_ratio: vui.sample_aspect_ratio,
+        timing: vui.timing,
+    };
+    Some((info, geometry))
+}
+
+/// Parse the part of SPS after `sps_max_latency_increase_plus1`
+/// up to VUI timing info. Parameters are stored into `vui` as soon as
+/// they are parsed, so they are kept if the rest of SPS is truncated.
+fn parse_vui(reader: &mut BitReader, log2_max_poc_lsb: u32, vui: &mut Vui) -> Option<()> {
+    // log2_min_luma_coding_block_size_minus3, log2_diff_max_min_luma_coding_block_size,
+    // log2_min_luma_transform_block_size_minus2, log2_diff_max_min_luma_transform_block_size,
+    // max_transform_hierarchy_depth_inter and max_transform_hierarchy_depth_intra
+    for _ in 0..6 {
+        reader.read_ue()?;
+    }
+    // scaling_list_enabled_flag and sps_scaling_list_data_present_flag
+    if reader.read(1)? == 1 && reader.read(1)? == 1 {
+        skip_scaling_list_data(reader)?;
+    }
+    // amp_enabled_flag and sample_adaptive_offset_enabled_flag
+    reader.skip(2)?;
+    // pcm_enabled_flag
+    if reader.read(1)? == 1 {
+        // pcm_sample_bit_depth_luma_minus1 and pcm_sample_bit_depth_chroma_minus1
+        reader.skip(8)?;
+        // log2_min_pcm_luma_coding_block_size_minus3
+        // and log2_diff_max_min_pcm_luma_coding_block_size
+        reader.read_ue()?;
+        reader.read_ue()?;
+        // pcm_loop_filter_disabled_flag
+        reader.skip(1)?;
+    }
+    let num_short_term_ref_pic_sets = reader.read_ue()?;
+    if num_short_term_ref_pic_sets > 64 {
+        return None;
+    }
+    let mut num_delta_pocs = [0u32; 64];
+    for idx in 0..num_short_term_ref_pic_sets as usize {
+        num_delta_pocs[idx] = skip_st_ref_pic_set(reader, idx, &num_delta_pocs)?;
+    }
+    // long_term_ref_pics_present_flag
+    if reader.read(1)? == 1 {
+        let num_long_term_ref_pics = reader.read_ue()?;
+        for _ in 0..num_long_term_ref_pics {
+            // lt_ref_pic_poc_lsb_sps and used_by_curr_pic_lt_sps_flag
+            reader.skip(log2_max_poc_lsb as usize + 1)?;
+        }
+    }
+    // sps_temporal_mvp_enabled_flag and strong_intra_smoothing_enabled_flag
+    reader.skip(2)?;
+    // vui_parameters_present_flag
+    if reader.read(1)? == 0 {
+        return Some(());
+    }
+    // aspect_ratio_info_present_flag
+    if reader.read(1)? == 1 {
+        let aspect_ratio_idc = reader.read(8)?;
+        vui.sample_aspect_ratio = if aspect_ratio_idc == EXTENDED_SAR {
+            let sar_width = reader.read(16)?;
+            let sar_height = reader.read(16)?;
+            Some((sar_width, sar_height)).filter(|&(w, h)| w != 0 && h != 0)
+        } else {
+            // 0 is unspecified, values from 17 to 254 are reserved.
+            (aspect_ratio_idc as usize)
+                .checked_sub(1)
+                .and_then(|i| SAMPLE_ASPECT_RATIOS.get(i))
+                .copied()
+        };
+    }
+    // overscan_info_present_flag
+    if reader.read(1)? == 1 {
+        // overscan_appropriate_flag
+        reader.skip(1)?;
+    }
+    // video_signal_type_present_flag
+    if reader.read(1)? == 1 {
+        // video_format and video_full_range_flag
+        reader.skip(4)?;
+        // colour_description_present_flag
+        if reader.read(1)? == 1 {
+            // colour_primaries, transfer_characteristics and matrix_coeffs
+            reader.skip(24)?;
+        }
+    }
+    // chroma_loc_info_present_flag
+    if reader.read(1)? == 1 {
+        reader.read_ue()?;
+        reader.read_ue()?;
+    }
+    // neutral_chroma_indication_flag, field_seq_flag
+    // and frame_field_info_present_flag
+    reader.skip(3)?;
+    // default_display_window_flag
+    if reader.read(1)? == 1 {
+        for _ in 0..4 {
+            reader.read_ue()?;
+        }
+    }
+    // vui_timing_info_present_flag
+    if reader.read(1)? == 1 {
+        let num_units_in_tick = reader.read(32)?;
+        let time_scale = reader.read(32)?;
+        if num_units_in_tick != 0 && time_scale != 0 {
+            vui.timing = Some(FrameTiming {
+                num_units_in_tick,
+                time_scale,
+            });
+        }
+    }
+    Some(())
+}
+
+fn skip_scaling_list_data(reader: &mut BitReader) -> Option<()> {
+    for size_id in 0..4 {
+        let step = if size_id == 3 { 3 } else { 1 };
+        for _ in (0..6).step_by(step) {
+            // scaling_list_pred_mode_flag
+            if reader.read(1)? == 0 {
+                // scaling_list_pred_matrix_id_delta
+                reader.read_ue()?;
+                continue;
+            }
+            // Signed Exp-Golomb codes below have the same length as unsigned ones.
+            let coef_num = 64.min(1 << (4 + (size_id << 1)));
+            if size_id > 1 {
+                // scaling_list_dc_coef_minus8
+                reader.read_ue()?;
+            }
+            for _ in 0..coef_num {
+                // scaling_list_delta_coef
+                reader.read_ue()?;
+            }
+        }
+    }
+    Some(())
+}
+
+/// Skip `st_ref_pic_set(idx)` and return its number of delta POCs.
+fn skip_st_ref_pic_set(reader: &mut BitReader, idx: usize, num_delta_pocs: &[u32]) -> Option<u32> {
+    // inter_ref_pic_set_prediction_flag
+    if idx != 0 && reader.read(1)? == 1 {
+        // delta_rps_sign and abs_delta_rps_minus1
+        reader.skip(1)?;
+        reader.read_ue()?;
+        // The reference set is the previous one inside SPS.
+        let mut count = 0;
+        for _ in 0..=num_delta_pocs[idx - 1] {
+            // used_by_curr_pic_flag and use_delta_flag
+            if reader.read(1)? == 1 || reader.read(1)? == 1 {
+                count += 1;
+            }
+        }
+        return Some(count);
+    }
+    let num_negative_pics = reader.read_ue()?;
+    let num_positive_pics = reader.read_ue()?;
+    let count = num_negative_pics.checked_add(num_positive_pics)?;
+    if count > 32 {
+        return None;
+    }
+    for _ in 0..count {
+        // delta_poc_s0_minus1 / delta_poc_s1_minus1 and used_by_curr_pic_s0_flag
+        reader.read_ue()?;
+        reader.skip(1)?;
+    }
+    Some(count)
+}
+
+/// Reader of bits from RBSP that skips emulation prevention bytes.
+pub(crate) struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    zeros: usize,
+    current: u8,
+    bits_left: u8,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            zeros: 0,
+            current: 0,
+            bits_left: 0,
+        }
+    }
+
+    fn read_bit(&mut self) -> Option<u32> {
+        if self.bits_left == 0 {
+            let mut byte = *self.data.get(self.pos)?;
+            self.pos += 1;
+            if self.zeros >= 2 && byte == 3 {
+                byte = *self.data.get(self.pos)?;
+                self.pos += 1;
+                self.zeros = 0;
+            }
+            self.zeros = if byte == 0 { self.zeros + 1 } else { 0 };
+            self.current = byte;
+            self.bits_left = 8;
+        }
+        self.bits_left -= 1;
+        Some(((self.current >> self.bits_left) & 1) as u32)
+    }
+
+    /// Read up to 32 bits.
+    pub fn read(&mut self, bits: u32) -> Option<u32> {
+        let mut value = 0;
+        for _ in 0..bits {
+            value = (value << 1) | self.read_bit()?;
+        }
+        Some(value)
+    }
+
+    pub fn skip(&mut self, bits: usize) -> Option<()> {
+        for _ in 0..bits {
+            self.read_bit()?;
+        }
+        Some(())
+    }
+
+    /// Read Exp-Golomb-coded unsigned integer.
+    pub fn read_ue(&mut self) -> Option<u32> {
+        let mut leading_zeros = 0;
+        while self.read_bit()? == 0 {
+            leading_zeros += 1;
+            if leading_zeros > 31 {
+                return None;
+            }
+        }
+        let value = self.read(leading_zeros)?;
+        ((1u64 << leading_zeros) - 1 + value as u64).try_into().ok()
+    }
+}
+
+/// Maximal number of bytes of SPS collected by [`SpsScanner`].
+/// The most of stream info is at the beginning of SPS, the frame rate
+/// from VUI is ignored if it is beyond this limit.
+const MAX_SPS_PREFIX: usize = 1024;
+
+/// Scanner of raw h265 bytestream that finds SPS NAL units
+/// in data pushed by chunks of arbitrary size.
+#[derive(Default)]
+pub(crate) struct SpsScanner {
+    /// Number of consecutive zero bytes at the end of scanned data.
+    zeros: usize,
+    after_start_code: bool,
+    /// Beginning of the current SPS NAL unit.
+    sps: Option<Vec<u8>>,
+}
+
+impl SpsScanner {
+    /// Scan the next chunk of the bytestream and return info
+    /// from the last SPS that was ended in this chunk.
+    pub fn push(&mut self, data: &[u8]) -> Option<(StreamInfo, CodedGeometry)> {
+        let mut info = None;
+        for &byte in data {
+            if self.after_start_code {
+                self.after_start_code = false;
+                if NalUnitType::from((byte >> 1) & 0x3f) == NalUnitType::SpsNut {
+                    self.sps = Some(Vec::with_capacity(MAX_SPS_PREFIX));
+                }
+            }
+            if let Some(sps) = self.sps.as_mut() {
+                if sps.len() < MAX_SPS_PREFIX {
+                    sps.push(byte);
+                }
+            }
+            if byte == 0 {
+                self.zeros += 1;
+                continue;
+            }
+            if byte == 1 && self.zeros >= 2 {
+                self.after_start_code = true;
+                if let Some(mut sps) = self.sps.take() {
+                    if sps.len() < MAX_SPS_PREFIX {
+                        // Remove the start code of the next NAL unit.
+                        sps.pop();
+                    }
+                    info = parse_sps(trim_trailing_zeros(&sps)).or(info);
+                }
+            }
+            self.zeros = 0;
+        }
+        info
+    }
+
+    /// Indicate the end of the current NAL unit.
+    pub fn finish(&mut self) -> Option<(StreamInfo, CodedGeometry)> {
+        self.zeros = 0;
+        self.after_start_code = false;
+        let sps = self.sps.take()?;
+        parse_sps(trim_trailing_zeros(&sps))
+    }
+}
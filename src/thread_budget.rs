@@ -0,0 +1,67 @@
+use std::sync::Mutex;
+
+use crate::decoder::lock;
+
+/// Global limit of background decoding threads of all decoders.
+struct ThreadBudget {
+    limit: Option<u32>,
+    used: u32,
+}
+
+static BUDGET: Mutex<ThreadBudget> = Mutex::new(ThreadBudget {
+    limit: None,
+    used: 0,
+});
+
+/// Set the total number of background decoding threads that may be started
+/// by all decoders of the process, or remove the limit if it is `None` (default).
+///
+/// [`DecoderInput::start_worker_threads()`](crate::DecoderInput::start_worker_threads)
+/// starts only as many threads as remain in the budget. Threads return
+/// into the budget when their decoder is dropped. Lowering the budget
+/// doesn't stop already running threads.
+pub fn set_global_thread_budget(limit: Option<u32>) {
+    lock(&BUDGET).limit = limit;
+}
+
+/// Returns the limit set by [`set_global_thread_budget()`].
+pub fn global_thread_budget() -> Option<u32> {
+    lock(&BUDGET).limit
+}
+
+/// Returns the number of background decoding threads
+/// running in all decoders of the process.
+pub fn global_worker_threads() -> u32 {
+    lock(&BUDGET).used
+}
+
+/// Threads taken from the global budget.
+///
+/// They are returned into the budget on drop.
+pub(crate) struct ThreadGrant(u32);
+
+impl ThreadGrant {
+    /// Take up to `requested` threads from the global budget.
+    pub(crate) fn acquire(requested: u32) -> Self {
+        let mut budget = lock(&BUDGET);
+        let available = match budget.limit {
+            Some(limit) => limit.saturating_sub(budget.used),
+            None => u32::MAX,
+        };
+        let granted = requested.min(available);
+        budget.used += granted;
+        Self(granted)
+    }
+
+    #[inline(always)]
+    pub(crate) fn count(&self) -> u32 {
+        self.0
+    }
+}
+
+impl Drop for ThreadGrant {
+    fn drop(&mut self) {
+        let mut budget = lock(&BUDGET);
+        budget.used = budget.used.saturating_sub(self.0);
+    }
+}
@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+/// Time base of timestamps: duration of one tick is `num / den` seconds.
+///
+/// For example, MPEG-TS timestamps use 1/90000 time base,
+/// and MP4 tracks use 1/timescale.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TimeBase {
+    num: u32,
+    den: u32,
+}
+
+impl TimeBase {
+    /// Time base of microseconds (default for a decoder).
+    pub const MICROSECONDS: Self = Self {
+        num: 1,
+        den: 1_000_000,
+    };
+
+    /// Returns `None` if `num` or `den` is zero.
+    pub const fn new(num: u32, den: u32) -> Option<Self> {
+        if num == 0 || den == 0 {
+            return None;
+        }
+        Some(Self { num, den })
+    }
+
+    pub const fn num(&self) -> u32 {
+        self.num
+    }
+
+    pub const fn den(&self) -> u32 {
+        self.den
+    }
+
+    /// Convert a timestamp from this time base into the `target` one,
+    /// rounding to the nearest tick. The result is saturated to the range of `i64`.
+    pub fn rescale(&self, value: i64, target: TimeBase) -> i64 {
+        let numerator = value as i128 * self.num as i128 * target.den as i128;
+        let denominator = self.den as i128 * target.num as i128;
+        let result = div_round(numerator, denominator);
+        result.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+    }
+
+    /// Convert a timestamp from this time base into a duration.
+    ///
+    /// Returns `None` if the timestamp is negative or the duration overflows.
+    pub fn to_duration(&self, value: i64) -> Option<Duration> {
+        if value < 0 {
+            return None;
+        }
+        let nanos = div_round(
+            value as i128 * self.num as i128 * 1_000_000_000,
+            self.den as i128,
+        );
+        let secs = u64::try_from(nanos / 1_000_000_000).ok()?;
+        Some(Duration::new(secs, (nanos % 1_000_000_000) as u32))
+    }
+
+    pub(crate) fn to_bits(self) -> u64 {
+        (self.num as u64) << 32 | self.den as u64
+    }
+
+    pub(crate) fn from_bits(bits: u64) -> Self {
+        Self {
+            num: (bits >> 32) as u32,
+            den: bits as u32,
+        }
+    }
+}
+
+impl Default for TimeBase {
+    fn default() -> Self {
+        Self::MICROSECONDS
+    }
+}
+
+/// Divide with rounding to the nearest integer (halves away from zero).
+/// The `denominator` must be positive.
+fn div_round(numerator: i128, denominator: i128) -> i128 {
+    let half = denominator / 2;
+    if numerator >= 0 {
+        (numerator + half) / denominator
+    } else {
+        (numerator - half) / denominator
+    }
+}
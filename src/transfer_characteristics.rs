@@ -0,0 +1,174 @@
+use std::fmt;
+
+use crate::{Image, OwnedImage};
+
+/// Transfer characteristics of a picture (`transfer_characteristics`
+/// of VUI), table 3 of ITU-T H.273.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TransferCharacteristics {
+    /// Rec. ITU-R BT.709 (code 1).
+    Bt709,
+    /// Assumed display gamma 2.2, BT.470 System M (code 4).
+    Gamma22,
+    /// Assumed display gamma 2.8, BT.470 System B, G (code 5).
+    Gamma28,
+    /// SMPTE 170M (code 6), also BT.601.
+    Smpte170M,
+    /// SMPTE 240M (code 7).
+    Smpte240M,
+    /// Linear transfer characteristics (code 8).
+    Linear,
+    /// Logarithmic transfer characteristic with 100:1 range (code 9).
+    Log100,
+    /// Logarithmic transfer characteristic with 100 * sqrt(10):1 range (code 10).
+    Log100Sqrt10,
+    /// IEC 61966-2-4, xvYCC (code 11).
+    Iec61966,
+    /// Rec. ITU-R BT.1361 extended colour gamut system (code 12).
+    Bt1361,
+    /// IEC 61966-2-1, sRGB and sYCC (code 13).
+    Srgb,
+    /// Rec. ITU-R BT.2020 for 10-bit system (code 14).
+    Bt2020_10,
+    /// Rec. ITU-R BT.2020 for 12-bit system (code 15).
+    Bt2020_12,
+    /// SMPTE ST 2084, perceptual quantization (PQ) of HDR10 (code 16).
+    SmpteSt2084,
+    /// SMPTE ST 428-1 (code 17).
+    SmpteSt428,
+    /// ARIB STD-B67, hybrid log-gamma (HLG) of BT.2100 (code 18).
+    AribStdB67,
+    /// Unspecified (code 2), the characteristics are determined
+    /// by the application.
+    Unspecified,
+    /// Code reserved for future use by H.273.
+    Reserved(u8),
+}
+
+impl TransferCharacteristics {
+    /// Returns the characteristics by their H.273 code.
+    pub const fn from_code(code: u8) -> Self {
+        match code {
+            1 => Self::Bt709,
+            2 => Self::Unspecified,
+            4 => Self::Gamma22,
+            5 => Self::Gamma28,
+            6 => Self::Smpte170M,
+            7 => Self::Smpte240M,
+            8 => Self::Linear,
+            9 => Self::Log100,
+            10 => Self::Log100Sqrt10,
+            11 => Self::Iec61966,
+            12 => Self::Bt1361,
+            13 => Self::Srgb,
+            14 => Self::Bt2020_10,
+            15 => Self::Bt2020_12,
+            16 => Self::SmpteSt2084,
+            17 => Self::SmpteSt428,
+            18 => Self::AribStdB67,
+            _ => Self::Reserved(code),
+        }
+    }
+
+    /// Returns the H.273 code of the characteristics.
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::Bt709 => 1,
+            Self::Unspecified => 2,
+            Self::Gamma22 => 4,
+            Self::Gamma28 => 5,
+            Self::Smpte170M => 6,
+            Self::Smpte240M => 7,
+            Self::Linear => 8,
+            Self::Log100 => 9,
+            Self::Log100Sqrt10 => 10,
+            Self::Iec61966 => 11,
+            Self::Bt1361 => 12,
+            Self::Srgb => 13,
+            Self::Bt2020_10 => 14,
+            Self::Bt2020_12 => 15,
+            Self::SmpteSt2084 => 16,
+            Self::SmpteSt428 => 17,
+            Self::AribStdB67 => 18,
+            Self::Reserved(code) => code,
+        }
+    }
+
+    /// Returns `true` for transfer functions of high dynamic range video:
+    /// PQ ([`TransferCharacteristics::SmpteSt2084`]) and
+    /// HLG ([`TransferCharacteristics::AribStdB67`]).
+    pub const fn is_hdr(self) -> bool {
+        matches!(self, Self::SmpteSt2084 | Self::AribStdB67)
+    }
+}
+
+impl From<u8> for TransferCharacteristics {
+    fn from(code: u8) -> Self {
+        Self::from_code(code)
+    }
+}
+
+impl From<TransferCharacteristics> for u8 {
+    fn from(value: TransferCharacteristics) -> Self {
+        value.code()
+    }
+}
+
+impl fmt::Display for TransferCharacteristics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Bt709 => "BT.709",
+            Self::Gamma22 => "Gamma 2.2",
+            Self::Gamma28 => "Gamma 2.8",
+            Self::Smpte170M => "SMPTE 170M",
+            Self::Smpte240M => "SMPTE 240M",
+            Self::Linear => "Linear",
+            Self::Log100 => "Log 100:1",
+            Self::Log100Sqrt10 => "Log 316:1",
+            Self::Iec61966 => "IEC 61966-2-4",
+            Self::Bt1361 => "BT.1361",
+            Self::Srgb => "sRGB",
+            Self::Bt2020_10 => "BT.2020 10-bit",
+            Self::Bt2020_12 => "BT.2020 12-bit",
+            Self::SmpteSt2084 => "SMPTE ST 2084 (PQ)",
+            Self::SmpteSt428 => "SMPTE ST 428-1",
+            Self::AribStdB67 => "ARIB STD-B67 (HLG)",
+            Self::Unspecified => "Unspecified",
+            Self::Reserved(code) => return write!(f, "Reserved ({code})"),
+        };
+        f.write_str(name)
+    }
+}
+
+impl Image<'_> {
+    /// Returns the transfer characteristics of the picture,
+    /// see [`Image::transfer_characteristics()`] for the raw code.
+    pub fn transfer_characteristics_enum(&self) -> TransferCharacteristics {
+        TransferCharacteristics::from_code(self.transfer_characteristics())
+    }
+}
+
+impl Image<'_> {
+    /// Returns the preferred transfer characteristics from the alternative
+    /// transfer characteristics SEI message. E.g. HLG streams signal BT.709
+    /// in VUI for compatibility and ARIB STD-B67 in this message.
+    ///
+    /// The message is parsed from prefix SEI NAL units pushed by
+    /// [`DecoderInput::push_nal()`](crate::DecoderInput::push_nal) like
+    /// [`Image::mastering_display()`] and follows the same rules.
+    pub fn alternative_transfer_characteristics(&self) -> Option<TransferCharacteristics> {
+        self.sei.alternative_transfer
+    }
+}
+
+impl OwnedImage {
+    /// See [`Image::transfer_characteristics_enum()`].
+    pub fn transfer_characteristics_enum(&self) -> TransferCharacteristics {
+        TransferCharacteristics::from_code(self.transfer_characteristics())
+    }
+
+    /// See [`Image::alternative_transfer_characteristics()`].
+    pub fn alternative_transfer_characteristics(&self) -> Option<TransferCharacteristics> {
+        self.sei.alternative_transfer
+    }
+}
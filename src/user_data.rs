@@ -0,0 +1,260 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use crate::decoder::lock;
+use crate::nal::nal_unit_type;
+use crate::poc::SliceHeader;
+use crate::sei::SeiMetadata;
+use crate::{Decoder, DecoderInput, Image, NalUnitType, PictureType, Result};
+
+/// Number of entries inserted before the entry of an output picture,
+/// which may still belong to pictures waiting for output.
+///
+/// It is greater than the maximal number of reordered pictures,
+/// so older entries belong to pictures that were dropped by the decoder.
+const MAX_ENTRY_DISTANCE: usize = 32;
+
+/// Data attached to a picture that is identified by a token.
+struct Entry {
+    /// Sequence number of the entry.
+    seq: usize,
+    /// Raw user data passed with the first slice segment of the picture.
+    user_data: usize,
+    /// Time of pushing of the first slice segment of the picture.
+    pushed_at: Instant,
+    payload: Option<Box<dyn Any + Send>>,
+    /// Values parsed from the header of the first slice segment.
+    slice_header: Option<SliceHeader>,
+    /// Metadata from SEI messages pushed before the picture.
+    sei: Option<SeiMetadata>,
+}
+
+/// Storage of data attached to pictures pushed by [`DecoderInput::push_nal()`]
+/// and methods based on it: raw user data, time of pushing,
+/// typed payloads, picture order counts, types and SEI metadata.
+///
+/// An entry is identified by a token that is passed into the decoder
+/// as raw user data instead of the user data of the picture, so pictures
+/// with repeated PTS and user data are told apart. The token is the address
+/// of the entry allocated on the heap, so it isn't confused with numbers
+/// or pointers to application memory passed as user data of pictures
+/// pushed by [`DecoderInput::push_data()`].
+#[derive(Default)]
+pub(crate) struct Payloads {
+    last_seq: AtomicUsize,
+    items: Mutex<HashMap<usize, Box<Entry>>>,
+}
+
+impl Payloads {
+    pub(crate) fn insert(
+        &self,
+        user_data: usize,
+        payload: Option<Box<dyn Any + Send>>,
+        slice_header: Option<SliceHeader>,
+        sei: Option<SeiMetadata>,
+    ) -> usize {
+        let entry = Box::new(Entry {
+            seq: self.last_seq.fetch_add(1, Ordering::Relaxed),
+            user_data,
+            pushed_at: Instant::now(),
+            payload,
+            slice_header,
+            sei,
+        });
+        let token = &*entry as *const Entry as usize;
+        lock(&self.items).insert(token, entry);
+        token
+    }
+
+    /// Remove the entry of a released picture.
+    ///
+    /// Entries of pictures pushed long before it are removed too,
+    /// because these pictures were dropped by the decoder
+    /// and will never be output. Values that aren't tokens
+    /// of existing entries (e.g. user data passed into
+    /// [`DecoderInput::push_data()`]) are ignored.
+    pub(crate) fn remove(&self, token: usize) {
+        let removed = {
+            let mut items = lock(&self.items);
+            let Some(entry) = items.remove(&token) else {
+                return;
+            };
+            let oldest = entry.seq.saturating_sub(MAX_ENTRY_DISTANCE);
+            let stale: Vec<usize> = items
+                .iter()
+                .filter(|(_, item)| item.seq < oldest)
+                .map(|(&token, _)| token)
+                .collect();
+            let stale: Vec<Box<Entry>> = stale
+                .into_iter()
+                .filter_map(|token| items.remove(&token))
+                .collect();
+            (entry, stale)
+        };
+        // Payloads are dropped without the lock.
+        drop(removed);
+    }
+
+    /// Remove the entry of a picture whose data wasn't pushed.
+    pub(crate) fn discard(&self, token: usize) {
+        let entry = lock(&self.items).remove(&token);
+        drop(entry);
+    }
+
+    pub(crate) fn clear(&self) {
+        let items = std::mem::take(&mut *lock(&self.items));
+        drop(items);
+    }
+
+    /// Take the payload of the picture identified by the token.
+    pub(crate) fn take_payload(&self, token: usize) -> Option<Box<dyn Any + Send>> {
+        lock(&self.items).get_mut(&token)?.payload.take()
+    }
+
+    /// Return the payload taken by [`Payloads::take_payload()`]
+    /// if the entry of the picture still exists.
+    pub(crate) fn restore(&self, token: usize, payload: Box<dyn Any + Send>) {
+        let mut payload = Some(payload);
+        if let Some(entry) = lock(&self.items).get_mut(&token) {
+            entry.payload = payload.take();
+        }
+        // The payload of a removed entry is dropped without the lock.
+        drop(payload);
+    }
+
+    /// Returns raw user data of the picture passed into the decoder with the token.
+    pub(crate) fn user_data(&self, token: usize) -> usize {
+        lock(&self.items)
+            .get(&token)
+            .map_or(token, |entry| entry.user_data)
+    }
+
+    /// Returns the time of pushing of the picture identified by the token.
+    pub(crate) fn pushed_at(&self, token: usize) -> Option<Instant> {
+        lock(&self.items).get(&token).map(|entry| entry.pushed_at)
+    }
+
+    /// Returns the picture order count of the picture identified by the token.
+    pub(crate) fn poc(&self, token: usize) -> Option<i32> {
+        lock(&self.items).get(&token)?.slice_header?.poc
+    }
+
+    /// Returns metadata from SEI messages of the picture identified by the token.
+    pub(crate) fn sei(&self, token: usize) -> SeiMetadata {
+        lock(&self.items)
+            .get(&token)
+            .and_then(|entry| entry.sei)
+            .unwrap_or_default()
+    }
+
+    /// Returns the type of the picture identified by the token
+    /// parsed from its slice header.
+    pub(crate) fn picture_type(&self, token: usize) -> Option<PictureType> {
+        Some(lock(&self.items).get(&token)?.slice_header?.picture_type)
+    }
+}
+
+/// Returns `true` if the NAL unit contains the first slice segment of a picture.
+pub(crate) fn is_first_slice_segment(data: &[u8]) -> bool {
+    nal_unit_type(data).is_some_and(NalUnitType::is_vcl)
+        && data.get(2).is_some_and(|b| b & 0x80 != 0)
+}
+
+impl DecoderInput {
+    /// Push a complete NAL unit without startcode into the decoder
+    /// and attach a typed payload to the picture that starts with this NAL unit.
+    ///
+    /// The payload may be received with help of [`Image::take_user_data()`].
+    /// The payload is dropped immediately if the NAL unit doesn't contain
+    /// the first slice segment of a picture. Payloads of pictures that are
+    /// never output (e.g. suppressed faulty pictures) are dropped when the decoder
+    /// releases these pictures, outputs pictures pushed long after them,
+    /// is reset or is dropped.
+    pub fn push_nal_with<T: Send + 'static>(
+        &mut self,
+        data: &[u8],
+        pts: i64,
+        payload: T,
+    ) -> Result<()> {
+        self.push_nal_inner(data, pts, 0, Some(Box::new(payload)))
+    }
+
+    /// Same as [`DecoderInput::push_nal_with()`], but takes the payload
+    /// that is already boxed, e.g. to pass it through the decoder without
+    /// moving its value.
+    ///
+    /// The decoder owns the box until the payload is taken by
+    /// [`Image::take_user_data()`] or borrowed by [`Image::user_data_ref()`],
+    /// and drops it in the same cases as payloads of
+    /// [`DecoderInput::push_nal_with()`].
+    pub fn push_nal_boxed<T: Send + 'static>(
+        &mut self,
+        data: &[u8],
+        pts: i64,
+        payload: Box<T>,
+    ) -> Result<()> {
+        self.push_nal_inner(data, pts, 0, Some(payload))
+    }
+}
+
+impl Decoder {
+    /// See [`DecoderInput::push_nal_with()`].
+    pub fn push_nal_with<T: Send + 'static>(
+        &mut self,
+        data: &[u8],
+        pts: i64,
+        payload: T,
+    ) -> Result<()> {
+        self.input_mut().push_nal_with(data, pts, payload)
+    }
+
+    /// See [`DecoderInput::push_nal_boxed()`].
+    pub fn push_nal_boxed<T: Send + 'static>(
+        &mut self,
+        data: &[u8],
+        pts: i64,
+        payload: Box<T>,
+    ) -> Result<()> {
+        self.input_mut().push_nal_boxed(data, pts, payload)
+    }
+}
+
+impl Image<'_> {
+    /// Take the payload attached to the picture by
+    /// [`DecoderInput::push_nal_with()`] method.
+    ///
+    /// Returns `None` if there is no payload, its type is not `T`
+    /// or it was borrowed by [`Image::user_data_ref()`].
+    pub fn take_user_data<T: 'static>(&self) -> Option<T> {
+        if self.payload.get().is_some() {
+            return None;
+        }
+        let token = self.token();
+        let mut items = lock(&self.decoder.payloads.items);
+        let entry = items.get_mut(&token)?;
+        if !entry.payload.as_ref()?.is::<T>() {
+            return None;
+        }
+        let payload = entry.payload.take()?.downcast::<T>().ok()?;
+        Some(*payload)
+    }
+
+    /// Returns a reference to the payload attached to the picture by
+    /// [`DecoderInput::push_nal_with()`] or [`DecoderInput::push_nal_boxed()`].
+    ///
+    /// Returns `None` if there is no payload or its type is not `T`.
+    /// The payload is moved from the decoder into the image on the first
+    /// call and dropped together with the image, so it can't be taken
+    /// by [`Image::take_user_data()`] after that. The payload of a picture
+    /// returned by [`DecoderOutput::peek_picture()`](crate::DecoderOutput::peek_picture)
+    /// is given back to the decoder when the image is dropped.
+    pub fn user_data_ref<T: 'static>(&self) -> Option<&T> {
+        self.payload
+            .get_or_init(|| self.decoder.payloads.take_payload(self.token()))
+            .as_deref()?
+            .downcast_ref::<T>()
+    }
+}
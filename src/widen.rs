@@ -0,0 +1,79 @@
+use crate::{Image, OwnedImage, PlaneView};
+
+/// Method of filling low bits of samples widened to a higher bit depth,
+/// see [`Image::to_u16_planes()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum BitExpansion {
+    /// Fills low bits with zeros, e.g. 8-bit 255 becomes 16-bit 65280.
+    Shift,
+    /// Fills low bits by repeating high bits of the sample, so
+    /// the maximal value stays maximal, e.g. 8-bit 255 becomes 16-bit 65535.
+    #[default]
+    Replicate,
+}
+
+impl BitExpansion {
+    /// Widen the sample with `bits` bits to `target_bits` bits.
+    pub fn widen(self, value: u16, bits: u32, target_bits: u32) -> u16 {
+        if bits == 0 || target_bits <= bits {
+            return value;
+        }
+        let mut result = (value as u32) << (target_bits - bits);
+        if self == Self::Replicate {
+            let mut filled = bits;
+            while filled < target_bits {
+                result |= result >> filled;
+                filled *= 2;
+            }
+        }
+        result as u16
+    }
+}
+
+impl Image<'_> {
+    /// Copy the picture into an [`OwnedImage`] with samples of all planes
+    /// stored as 16-bit words in native byte order, so they may be
+    /// accessed by [`OwnedImage::plane_u16()`] regardless of
+    /// the bit depth of the stream.
+    ///
+    /// Samples are widened to `target_bits` bits (clamped to `9..=16`)
+    /// by the given method, and
+    /// [`OwnedImage::bits_per_pixel()`] returns the new bit depth.
+    /// Planes with a higher bit depth keep their samples.
+    /// Planes are copied without padding at the end of rows,
+    /// metadata of the picture is copied as is.
+    pub fn to_u16_planes(&self, target_bits: u8, expansion: BitExpansion) -> OwnedImage {
+        OwnedImage::from_image(self, true).map_planes(widen_plane(target_bits, expansion))
+    }
+}
+
+impl OwnedImage {
+    /// See [`Image::to_u16_planes()`].
+    pub fn to_u16_planes(&self, target_bits: u8, expansion: BitExpansion) -> OwnedImage {
+        self.clone().map_planes(widen_plane(target_bits, expansion))
+    }
+}
+
+fn widen_plane(
+    target_bits: u8,
+    expansion: BitExpansion,
+) -> impl Fn(PlaneView<'_>, u32) -> (Vec<u8>, u32) {
+    let target_bits = (target_bits as u32).clamp(9, 16);
+    move |view, bits| {
+        let target_bits = target_bits.max(bits);
+        let mut data = Vec::with_capacity(view.width() as usize * view.height() as usize * 2);
+        let mut push = |value: u16| {
+            let value = expansion.widen(value, bits, target_bits);
+            data.extend_from_slice(&value.to_ne_bytes());
+        };
+        for row in view.rows() {
+            if view.bytes_per_sample() == 1 {
+                row.iter().for_each(|&value| push(value as u16));
+            } else {
+                row.chunks_exact(2)
+                    .for_each(|bytes| push(u16::from_ne_bytes([bytes[0], bytes[1]])));
+            }
+        }
+        (data, target_bits)
+    }
+}
@@ -0,0 +1,137 @@
+//! Writer of decoded pictures into YUV4MPEG2 (`.y4m`) streams.
+use std::io::Write;
+
+use crate::{Channel, ChromaFormat, Image, StreamInfo, Y4mError};
+
+/// Frame rate used if it isn't known from parameters of [`Writer`]
+/// or timing info of pictures.
+const DEFAULT_FRAME_RATE: (u32, u32) = (25, 1);
+
+/// Parameters of a YUV4MPEG2 stream.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub struct Y4mParams {
+    /// Frame rate as `(numerator, denominator)`. If it is `None`, the frame
+    /// rate is taken from [`Image::timing()`] of the first picture
+    /// or 25 fps is used.
+    pub frame_rate: Option<(u32, u32)>,
+}
+
+impl Y4mParams {
+    /// Parameters with the frame rate from VUI timing info of the stream
+    /// (see [`StreamInfo::frame_rate`]).
+    pub fn from_stream_info(info: &StreamInfo) -> Self {
+        Self {
+            frame_rate: info.frame_rate,
+        }
+    }
+
+    /// Set the frame rate supplied by the caller. Zero values are ignored.
+    pub fn with_frame_rate(mut self, numerator: u32, denominator: u32) -> Self {
+        if numerator != 0 && denominator != 0 {
+            self.frame_rate = Some((numerator, denominator));
+        }
+        self
+    }
+}
+
+/// Writer of pictures into YUV4MPEG2 stream.
+///
+/// The stream header is written before the first picture, its size
+/// and chroma format are taken from this picture. Only pictures
+/// with 8 bits per pixel are supported now.
+pub struct Writer<W: Write> {
+    inner: W,
+    params: Y4mParams,
+    /// Size and chroma format of pictures written into the header.
+    format: Option<(u32, u32, ChromaFormat)>,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(inner: W, params: Y4mParams) -> Self {
+        Self {
+            inner,
+            params,
+            format: None,
+        }
+    }
+
+    /// Write the picture as a frame of the stream.
+    ///
+    /// Returns [`Y4mError::UnsupportedBitDepth`] for pictures with more than
+    /// 8 bits per pixel and [`Y4mError::FormatChanged`] if the size or chroma
+    /// format of the picture differs from the previous ones.
+    /// Nothing is written in these cases.
+    pub fn write_image(&mut self, image: &Image) -> Result<(), Y4mError> {
+        let chroma_format = image.chroma_format();
+        let channels = chroma_format.channels();
+        for &channel in channels {
+            let bits = image.bits_per_pixel(channel);
+            if bits > 8 {
+                return Err(Y4mError::UnsupportedBitDepth(bits));
+            }
+        }
+        let format = (
+            image.width(Channel::Y),
+            image.height(Channel::Y),
+            chroma_format,
+        );
+        match self.format {
+            Some(header_format) if header_format != format => {
+                return Err(Y4mError::FormatChanged);
+            }
+            Some(_) => {}
+            None => {
+                let frame_rate = self
+                    .params
+                    .frame_rate
+                    .or_else(|| {
+                        let timing = image.timing()?;
+                        Some((timing.time_scale, timing.num_units_in_tick))
+                    })
+                    .unwrap_or(DEFAULT_FRAME_RATE);
+                self.write_header(format, frame_rate)?;
+                self.format = Some(format);
+            }
+        }
+        self.inner.write_all(b"FRAME\n")?;
+        for &channel in channels {
+            for row in image.plane_view(channel).as_u8_rows() {
+                self.inner.write_all(row)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_header(
+        &mut self,
+        (width, height, chroma_format): (u32, u32, ChromaFormat),
+        (num, den): (u32, u32),
+    ) -> Result<(), Y4mError> {
+        let colour_space = match chroma_format {
+            ChromaFormat::Mono => "mono",
+            ChromaFormat::C420 => "420jpeg",
+            ChromaFormat::C422 => "422",
+            ChromaFormat::C444 => "444",
+        };
+        writeln!(
+            self.inner,
+            "YUV4MPEG2 W{width} H{height} F{num}:{den} Ip A0:0 C{colour_space}"
+        )?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), Y4mError> {
+        self.inner.flush()?;
+        Ok(())
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
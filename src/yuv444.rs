@@ -0,0 +1,289 @@
+use crate::allocator::CHANNELS;
+use crate::rgb::{Samples, chroma_x, chroma_y, taps};
+use crate::{Channel, ChromaFormat, Image, OwnedImage, PlaneView};
+
+/// Filter used to upsample chroma planes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum ChromaFilter {
+    /// Takes the chroma sample whose area contains the luma sample.
+    Nearest,
+    /// Interpolates chroma samples taking into account their location
+    /// (see [`Image::to_rgb8()`]).
+    #[default]
+    Bilinear,
+}
+
+/// Picture with chroma planes upsampled to the size of the luma plane,
+/// see [`Image::to_yuv444()`].
+///
+/// Samples of planes with more than 8 bits per pixel are stored
+/// as 16-bit words in native byte order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Yuv444Buffer {
+    planes: [Vec<u8>; 3],
+    bits_per_pixel: [u32; 3],
+    width: u32,
+    height: u32,
+    full_range: bool,
+    matrix_coefficients: u8,
+}
+
+impl Yuv444Buffer {
+    /// Width of all planes.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height of all planes.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn bits_per_pixel(&self, channel: Channel) -> u32 {
+        self.bits_per_pixel[channel.index() as usize]
+    }
+
+    /// Returns samples of the plane without padding at the end of rows.
+    pub fn plane(&self, channel: Channel) -> &[u8] {
+        &self.planes[channel.index() as usize]
+    }
+
+    /// See [`Image::plane_view()`].
+    pub fn plane_view(&self, channel: Channel) -> PlaneView<'_> {
+        let bytes_per_sample = self.bits_per_pixel(channel).div_ceil(8) as usize;
+        PlaneView::new(
+            self.plane(channel),
+            self.width as usize * bytes_per_sample,
+            self.width,
+            self.height,
+            bytes_per_sample,
+        )
+    }
+
+    /// See [`Image::full_range()`].
+    pub fn full_range(&self) -> bool {
+        self.full_range
+    }
+
+    /// See [`Image::matrix_coefficients()`].
+    pub fn matrix_coefficients(&self) -> u8 {
+        self.matrix_coefficients
+    }
+}
+
+impl Image<'_> {
+    /// Upsample chroma planes of the picture to the size of the luma plane.
+    ///
+    /// Samples keep their bit depth. Chroma planes of monochrome pictures
+    /// are filled with the neutral value (128 for 8-bit samples).
+    pub fn to_yuv444(&self, filter: ChromaFilter) -> Yuv444Buffer {
+        let planes =
+            CHANNELS.map(|channel| (self.plane_view(channel), self.bits_per_pixel(channel)));
+        let colour = (self.full_range(), self.matrix_coefficients());
+        upsample(planes, self.chroma_format(), filter, colour)
+    }
+}
+
+impl OwnedImage {
+    /// See [`Image::to_yuv444()`].
+    pub fn to_yuv444(&self, filter: ChromaFilter) -> Yuv444Buffer {
+        let planes =
+            CHANNELS.map(|channel| (self.plane_view(channel), self.bits_per_pixel(channel)));
+        let colour = (self.full_range(), self.matrix_coefficients());
+        upsample(planes, self.chroma_format(), filter, colour)
+    }
+}
+
+fn upsample(
+    planes: [(PlaneView<'_>, u32); 3],
+    chroma_format: ChromaFormat,
+    filter: ChromaFilter,
+    (full_range, matrix_coefficients): (bool, u8),
+) -> Yuv444Buffer {
+    let [(luma, luma_bits), cb, cr] = planes;
+    let width = luma.width() as usize;
+    let height = luma.height() as usize;
+    let luma_plane: Vec<u8> = luma.as_u8_rows().flatten().copied().collect();
+    let (cb_bits, cr_bits) = if !chroma_format.has_chroma() {
+        (luma_bits, luma_bits)
+    } else {
+        (cb.1, cr.1)
+    };
+    let upsample_plane = |(view, bits): (PlaneView<'_>, u32)| {
+        if !chroma_format.has_chroma() {
+            let neutral = 1 << luma_bits.saturating_sub(1);
+            let mut data = Vec::with_capacity(width * height * bytes_per_sample(luma_bits));
+            for _ in 0..width * height {
+                push_sample(&mut data, neutral, luma_bits);
+            }
+            return data;
+        }
+        let (sub_x, sub_y) = chroma_format.subsampling();
+        upsample_plane(
+            Samples::new(view, bits),
+            (sub_x as usize, sub_y as usize),
+            (width, height),
+            filter,
+        )
+    };
+    Yuv444Buffer {
+        planes: [luma_plane, upsample_plane(cb), upsample_plane(cr)],
+        bits_per_pixel: [luma_bits, cb_bits, cr_bits],
+        width: width as u32,
+        height: height as u32,
+        full_range,
+        matrix_coefficients,
+    }
+}
+
+fn upsample_plane(
+    samples: Samples<'_>,
+    (sub_x, sub_y): (usize, usize),
+    (width, height): (usize, usize),
+    filter: ChromaFilter,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(width * height * bytes_per_sample(samples.bits));
+    let last_column = samples.width().saturating_sub(1);
+    let last_row = samples.height().saturating_sub(1);
+    for y in 0..height {
+        match filter {
+            ChromaFilter::Nearest => {
+                let row = samples.row((y / sub_y).min(last_row));
+                for x in 0..width {
+                    let value = samples.get(row, (x / sub_x).min(last_column));
+                    push_sample(&mut data, value as u16, samples.bits);
+                }
+            }
+            ChromaFilter::Bilinear => {
+                let (y0, y1, fy) = taps(chroma_y(y, sub_y), samples.height());
+                let rows = (samples.row(y0), samples.row(y1));
+                for x in 0..width {
+                    let x_taps = taps(chroma_x(x, sub_x), samples.width());
+                    let value = samples.bilinear(rows, x_taps, fy).round();
+                    push_sample(&mut data, value as u16, samples.bits);
+                }
+            }
+        }
+    }
+    data
+}
+
+fn bytes_per_sample(bits: u32) -> usize {
+    bits.div_ceil(8) as usize
+}
+
+fn push_sample(data: &mut Vec<u8>, value: u16, bits: u32) {
+    if bits <= 8 {
+        data.push(value as u8);
+    } else {
+        data.extend_from_slice(&value.to_ne_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upsample_8bit(
+        chroma: &[u8],
+        (width, height): (u32, u32),
+        (chroma_width, chroma_height): (u32, u32),
+        chroma_format: ChromaFormat,
+        filter: ChromaFilter,
+    ) -> Yuv444Buffer {
+        let luma = vec![16; (width * height) as usize];
+        let plane =
+            |data, width: u32, height| (PlaneView::new(data, width as usize, width, height, 1), 8);
+        let planes = [
+            plane(&luma, width, height),
+            plane(chroma, chroma_width, chroma_height),
+            plane(chroma, chroma_width, chroma_height),
+        ];
+        upsample(planes, chroma_format, filter, (false, 2))
+    }
+
+    #[test]
+    fn constant_chroma() {
+        for filter in [ChromaFilter::Nearest, ChromaFilter::Bilinear] {
+            for (chroma_format, size, chroma_size) in [
+                (ChromaFormat::C420, (5, 3), (3, 2)),
+                (ChromaFormat::C420, (6, 4), (3, 2)),
+                (ChromaFormat::C422, (5, 3), (3, 3)),
+                (ChromaFormat::C444, (5, 3), (5, 3)),
+            ] {
+                let chroma = vec![77; (chroma_size.0 * chroma_size.1) as usize];
+                let buffer = upsample_8bit(&chroma, size, chroma_size, chroma_format, filter);
+                assert_eq!((buffer.width(), buffer.height()), size);
+                for channel in CHANNELS {
+                    assert_eq!(buffer.plane(channel).len(), (size.0 * size.1) as usize);
+                }
+                assert!(buffer.plane(Channel::Cb).iter().all(|&v| v == 77));
+                assert!(buffer.plane(Channel::Cr).iter().all(|&v| v == 77));
+            }
+        }
+    }
+
+    #[test]
+    fn constant_chroma_with_high_bit_depth() {
+        let luma = vec![0; 5 * 3 * 2];
+        let chroma: Vec<u8> = [700u16; 3 * 2]
+            .iter()
+            .flat_map(|v| v.to_ne_bytes())
+            .collect();
+        let planes = [
+            (PlaneView::new(&luma, 10, 5, 3, 2), 10),
+            (PlaneView::new(&chroma, 6, 3, 2, 2), 10),
+            (PlaneView::new(&chroma, 6, 3, 2, 2), 10),
+        ];
+        let buffer = upsample(
+            planes,
+            ChromaFormat::C420,
+            ChromaFilter::Bilinear,
+            (false, 2),
+        );
+        assert_eq!(buffer.bits_per_pixel(Channel::Cb), 10);
+        let view = buffer.plane_view(Channel::Cb);
+        assert_eq!(
+            (view.width(), view.height(), view.bytes_per_sample()),
+            (5, 3, 2)
+        );
+        for y in 0..3 {
+            let row = view.row(y).unwrap();
+            assert!(
+                row.chunks(2)
+                    .all(|v| u16::from_ne_bytes([v[0], v[1]]) == 700)
+            );
+        }
+    }
+
+    #[test]
+    fn odd_size_with_420_chroma() {
+        // 3x3 picture with 2x2 chroma samples.
+        let chroma = [10, 20, 50, 60];
+        let nearest = upsample_8bit(
+            &chroma,
+            (3, 3),
+            (2, 2),
+            ChromaFormat::C420,
+            ChromaFilter::Nearest,
+        );
+        assert_eq!(
+            nearest.plane(Channel::Cb),
+            [10, 10, 20, 10, 10, 20, 50, 50, 60]
+        );
+
+        // Chroma rows are located between luma rows 0-1 and 2-3,
+        // columns are co-sited with even luma columns.
+        let bilinear = upsample_8bit(
+            &chroma,
+            (3, 3),
+            (2, 2),
+            ChromaFormat::C420,
+            ChromaFilter::Bilinear,
+        );
+        assert_eq!(
+            bilinear.plane(Channel::Cb),
+            [10, 15, 20, 20, 25, 30, 40, 45, 50]
+        );
+    }
+}
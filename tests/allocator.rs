@@ -0,0 +1,246 @@
+use std::alloc::{self, Layout};
+use std::collections::HashSet;
+use std::ops::ControlFlow;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use libde265_rs::*;
+
+mod common;
+use common::for_first_image;
+
+/// Allocator that tracks allocated and released planes.
+struct TrackingAllocator {
+    allocated: Arc<AtomicUsize>,
+    released: Arc<AtomicUsize>,
+}
+
+impl TrackingAllocator {
+    fn layout(spec: &ImageSpec, channel: Channel) -> Layout {
+        Layout::from_size_align(spec.min_plane_size(channel), spec.alignment as usize).unwrap()
+    }
+}
+
+// SAFETY: planes are allocated with the size required by the spec
+// and are deallocated only on release.
+unsafe impl ImageAllocator for TrackingAllocator {
+    fn allocate(&mut self, spec: &ImageSpec) -> Option<PlaneBuffers> {
+        let mut buffers = PlaneBuffers::new();
+        for channel in [Channel::Y, Channel::Cb, Channel::Cr] {
+            if !spec.has_plane(channel) {
+                continue;
+            }
+            let layout = Self::layout(spec, channel);
+            let data = NonNull::new(unsafe { alloc::alloc(layout) })?;
+            // Keep the layout to deallocate the plane.
+            let user_data = Box::into_raw(Box::new(layout)) as _;
+            buffers.set(
+                channel,
+                PlaneBuffer {
+                    data,
+                    stride: spec.min_stride(channel),
+                    user_data,
+                },
+            );
+            self.allocated.fetch_add(1, Ordering::Relaxed);
+        }
+        Some(buffers)
+    }
+
+    fn release(&mut self, mut buffers: PlaneBuffers) {
+        for channel in [Channel::Y, Channel::Cb, Channel::Cr] {
+            if let Some(buffer) = buffers.take(channel) {
+                let layout = unsafe { Box::from_raw(buffer.user_data as *mut Layout) };
+                unsafe { alloc::dealloc(buffer.data.as_ptr(), *layout) };
+                self.released.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+fn luma_checksums(decoder: &mut Decoder, user_data_is_set: bool) -> Vec<u64> {
+    let file = std::fs::File::open("./data/girlshy.h265").unwrap();
+    let mut checksums = Vec::new();
+    decoder
+        .decode_from_reader(file, |image| {
+            assert_eq!(
+                image.plane_user_data(Channel::Y).is_null(),
+                !user_data_is_set
+            );
+            assert_eq!(
+                image.plane_user_data(Channel::Cb).is_null(),
+                !user_data_is_set
+            );
+            let (plane_buf, stride) = image.plane(Channel::Y);
+            let width = image.width(Channel::Y) as usize;
+            let checksum = plane_buf
+                .chunks(stride)
+                .flat_map(|row| &row[..width])
+                .map(|&v| v as u64)
+                .sum();
+            checksums.push(checksum);
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    checksums
+}
+
+#[test]
+fn custom_allocator_balances_allocations() {
+    let expected_checksums = luma_checksums(&mut Decoder::new().unwrap(), false);
+    assert_eq!(expected_checksums.len(), 75);
+
+    let allocated = Arc::new(AtomicUsize::new(0));
+    let released = Arc::new(AtomicUsize::new(0));
+    let mut decoder = Decoder::new().unwrap();
+    decoder.set_image_allocator(Box::new(TrackingAllocator {
+        allocated: allocated.clone(),
+        released: released.clone(),
+    }));
+    // The SAO filter of the stream is applied into images
+    // allocated by the custom allocator only in multithreaded mode.
+    decoder.start_worker_threads(4).unwrap();
+    let checksums = luma_checksums(&mut decoder, true);
+    assert_eq!(checksums, expected_checksums);
+    assert!(allocated.load(Ordering::Relaxed) > 0);
+
+    drop(decoder);
+    assert_eq!(
+        allocated.load(Ordering::Relaxed),
+        released.load(Ordering::Relaxed)
+    );
+}
+
+#[test]
+fn memory_usage_with_custom_allocator() {
+    let mut decoder = Decoder::new().unwrap();
+    decoder.set_image_allocator(Box::new(TrackingAllocator {
+        allocated: Arc::new(AtomicUsize::new(0)),
+        released: Arc::new(AtomicUsize::new(0)),
+    }));
+    decoder.start_worker_threads(4).unwrap();
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    decoder.push_data(&data, 0, 0).unwrap();
+    decoder.flush_data().unwrap();
+    assert!(
+        decoder
+            .decode_next_picture(|_| Ok(false))
+            .unwrap()
+            .is_some()
+    );
+    let usage = decoder.memory_usage();
+    // Sizes of buffers provided by the allocator are exact.
+    assert_eq!(usage.estimated_picture_bytes, 320 * 240 * 3 / 2);
+    assert!(usage.output_pictures > 0);
+}
+
+/// Allocator that assigns a unique token to every plane
+/// and records tokens of released planes.
+struct TokenAllocator {
+    inner: TrackingAllocator,
+    next_token: u64,
+    allocated: Arc<Mutex<HashSet<PlaneToken>>>,
+    retired: Arc<Mutex<Vec<PlaneToken>>>,
+}
+
+// SAFETY: buffers are allocated by the inner allocator.
+unsafe impl ImageAllocator for TokenAllocator {
+    fn allocate(&mut self, spec: &ImageSpec) -> Option<PlaneBuffers> {
+        let mut buffers = self.inner.allocate(spec)?;
+        for channel in [Channel::Y, Channel::Cb, Channel::Cr] {
+            if let Some(buffer) = buffers.take(channel) {
+                self.next_token += 1;
+                let token = PlaneToken(self.next_token);
+                self.allocated.lock().unwrap().insert(token);
+                buffers.set_with_token(channel, buffer, token);
+            }
+        }
+        Some(buffers)
+    }
+
+    fn release(&mut self, buffers: PlaneBuffers) {
+        for channel in [Channel::Y, Channel::Cb, Channel::Cr] {
+            if let Some(token) = buffers.token(channel) {
+                self.retired.lock().unwrap().push(token);
+            }
+        }
+        self.inner.release(buffers);
+    }
+}
+
+#[test]
+fn plane_tokens_of_custom_allocator() {
+    for_first_image("./data/girlshy.h265", |image| {
+        assert_eq!(image.plane_token(Channel::Y), None);
+    });
+
+    let allocated = Arc::new(Mutex::new(HashSet::new()));
+    let retired = Arc::new(Mutex::new(Vec::new()));
+    let mut decoder = Decoder::new().unwrap();
+    decoder.set_image_allocator(Box::new(TokenAllocator {
+        inner: TrackingAllocator {
+            allocated: Arc::new(AtomicUsize::new(0)),
+            released: Arc::new(AtomicUsize::new(0)),
+        },
+        next_token: 0,
+        allocated: allocated.clone(),
+        retired: retired.clone(),
+    }));
+    decoder.start_worker_threads(4).unwrap();
+    let mut observed = HashSet::new();
+    let file = std::fs::File::open("./data/girlshy.h265").unwrap();
+    decoder
+        .decode_from_reader(file, |image| {
+            let tokens = [Channel::Y, Channel::Cb, Channel::Cr]
+                .map(|channel| image.plane_token(channel).unwrap());
+            assert_ne!(tokens[0], tokens[1]);
+            assert_ne!(tokens[1], tokens[2]);
+            // Tokens of planes of an output picture are not retired yet.
+            let retired = retired.lock().unwrap();
+            assert!(tokens.iter().all(|token| !retired.contains(token)));
+            observed.extend(tokens);
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert_eq!(observed.len(), 75 * 3);
+    assert!(observed.is_subset(&allocated.lock().unwrap()));
+
+    // All tokens are retired once when buffers are released.
+    drop(decoder);
+    let retired = retired.lock().unwrap();
+    let retired_set: HashSet<_> = retired.iter().copied().collect();
+    assert_eq!(retired_set.len(), retired.len());
+    assert_eq!(retired_set, *allocated.lock().unwrap());
+}
+
+/// Allocator that panics on every allocation.
+struct PanickingAllocator {
+    calls: Arc<AtomicUsize>,
+}
+
+// SAFETY: it never returns buffers.
+unsafe impl ImageAllocator for PanickingAllocator {
+    fn allocate(&mut self, _spec: &ImageSpec) -> Option<PlaneBuffers> {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        panic!("allocation failed");
+    }
+
+    fn release(&mut self, _buffers: PlaneBuffers) {
+        unreachable!("nothing was allocated");
+    }
+}
+
+#[test]
+fn panic_in_allocator_does_not_unwind_into_decoder() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut decoder = Decoder::new().unwrap();
+    decoder.start_worker_threads(4).unwrap();
+    decoder.set_image_allocator(Box::new(PanickingAllocator {
+        calls: calls.clone(),
+    }));
+    let file = std::fs::File::open("./data/girlshy.h265").unwrap();
+    // Allocation failures are reported by the decoder as errors or warnings.
+    let _ = decoder.decode_from_reader(file, |_| ControlFlow::Continue(()));
+    assert!(calls.load(Ordering::Relaxed) > 0);
+}
@@ -0,0 +1,80 @@
+#![cfg(feature = "ndarray")]
+
+use libde265_rs::*;
+
+mod common;
+use common::for_first_image;
+
+const POINTS: [(usize, usize); 5] = [(0, 0), (1, 0), (0, 1), (57, 101), (119, 157)];
+
+#[test]
+fn plane_array_over_8bit_planes() {
+    for_first_image("./data/girlshy.h265", |image| {
+        for channel in [Channel::Y, Channel::Cb, Channel::Cr] {
+            let array = image.plane_array(channel).unwrap();
+            let (data, stride) = image.plane(channel);
+            assert_eq!(
+                array.dim(),
+                (
+                    image.height(channel) as usize,
+                    image.width(channel) as usize
+                )
+            );
+            assert_eq!(array.as_ptr(), data.as_ptr());
+            for (y, x) in POINTS {
+                assert_eq!(array[[y, x]], data[y * stride + x], "{channel:?} {y}x{x}");
+            }
+            let (last_y, last_x) = (array.nrows() - 1, array.ncols() - 1);
+            assert_eq!(array[[last_y, last_x]], data[last_y * stride + last_x]);
+        }
+        assert!(matches!(
+            image.plane_array_u16(Channel::Y),
+            Err(PlaneViewError::SampleSizeMismatch { .. })
+        ));
+
+        let owned = image.to_owned_trimmed();
+        assert_eq!(
+            owned.plane_array(Channel::Cb).unwrap(),
+            image.plane_array(Channel::Cb).unwrap()
+        );
+    });
+}
+
+#[test]
+fn plane_array_over_16bit_planes() {
+    for_first_image("./data/gradient_10bit.h265", |image| {
+        for channel in [Channel::Y, Channel::Cb] {
+            let array = image.plane_array_u16(channel).unwrap();
+            let (data, stride) = image.plane(channel);
+            assert_eq!(
+                array.dim(),
+                (
+                    image.height(channel) as usize,
+                    image.width(channel) as usize
+                )
+            );
+            for (y, x) in [(0, 0), (1, 0), (0, 1), (17, 23), (31, 31)] {
+                let offset = y * stride + x * 2;
+                let expected = u16::from_ne_bytes([data[offset], data[offset + 1]]);
+                assert_eq!(array[[y, x]], expected, "{channel:?} {y}x{x}");
+            }
+        }
+        assert!(image.plane_array(Channel::Y).is_err());
+    });
+}
+
+#[test]
+fn to_array3_matches_rgb8() {
+    for_first_image("./data/girlshy.h265", |image| {
+        let rgb = image.to_rgb8();
+        let array = image.to_array3();
+        assert_eq!(array.dim(), (240, 316, 3));
+        for (y, x) in POINTS {
+            let pixel = rgb.pixel(x as u32, y as u32).unwrap();
+            for c in 0..3 {
+                assert_eq!(array[[y, x, c]], pixel[c]);
+            }
+        }
+        assert_eq!(array.as_slice().unwrap(), rgb.data.as_slice());
+    });
+}
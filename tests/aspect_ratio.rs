@@ -0,0 +1,46 @@
+use libde265_rs::*;
+
+mod common;
+use common::for_first_image;
+
+#[test]
+fn predefined_sample_aspect_ratio() {
+    // The stream has aspect_ratio_idc equal to 1.
+    for_first_image("./data/gradient_sar.h265", |image| {
+        assert_eq!(image.sample_aspect_ratio(), Some((1, 1)));
+        assert_eq!(image.display_aspect_ratio(), Some((1, 1)));
+    });
+    let file = std::fs::File::open("./data/gradient_sar.h265").unwrap();
+    let info = probe_stream(file, 4096).unwrap();
+    assert_eq!(info.sample_aspect_ratio, Some((1, 1)));
+}
+
+#[test]
+fn extended_sample_aspect_ratio() {
+    // The stream has aspect_ratio_idc equal to EXTENDED_SAR
+    // with sar_width = 5790 and sar_height = 5767.
+    for_first_image("./data/girlshy.h265", |image| {
+        assert_eq!(image.sample_aspect_ratio(), Some((5790, 5767)));
+        // (316 * 5790) : (240 * 5767) reduced to lowest terms
+        assert_eq!(image.display_aspect_ratio(), Some((193, 146)));
+        let owned = image.to_owned();
+        assert_eq!(owned.sample_aspect_ratio(), Some((5790, 5767)));
+        assert_eq!(owned.display_aspect_ratio(), image.display_aspect_ratio());
+    });
+    let file = std::fs::File::open("./data/girlshy.h265").unwrap();
+    let info = probe_stream(file, 4096).unwrap();
+    assert_eq!(info.sample_aspect_ratio, Some((5790, 5767)));
+}
+
+#[test]
+fn unspecified_sample_aspect_ratio() {
+    // VUI without aspect ratio info
+    for_first_image("./data/gradient_bt709.h265", |image| {
+        assert_eq!(image.sample_aspect_ratio(), None);
+        assert_eq!(image.display_aspect_ratio(), None);
+    });
+    // No VUI
+    for_first_image("./data/gradient_444.h265", |image| {
+        assert_eq!(image.sample_aspect_ratio(), None);
+    });
+}
@@ -0,0 +1,138 @@
+use libde265_rs::*;
+
+mod common;
+use common::all_frames;
+
+/// MD5 of all planes of every frame of `girlshy.h265`.
+const GIRLSHY_FRAME_MD5: [&str; 75] = [
+    "eec6a75f002102912aeaedcd3876ddb3",
+    "24258f7c3d083a96288c2bef997cd7ca",
+    "eb9b3295fcf5a54316a4d50c5c0ffb7f",
+    "764e287fc2fe4b22ff27054378f11ba1",
+    "388e71860f63ffb6e17fa7e4b13cfdb2",
+    "3add94324c799f7b8aa9e90d01e0e64b",
+    "0bc895739b84a1a362f06942ef7cd928",
+    "e5d11d64c74e91d7b19a70454919234e",
+    "8e1ced3bd8684d53860687f8158a3bbb",
+    "dd77210dfabba9f6f3f28bc52dc0f41b",
+    "692af112c4ccb2b227233304d5c0fa1f",
+    "be733755fe3902149d419644813084e0",
+    "93905492fefc36a2dd1dd228e24ee58e",
+    "f2cacd58b107a36850334997340e3c9b",
+    "b94208c6eba982ff56d74963ac543a63",
+    "ee6fa52e7c40904cb47120e453d1c6bc",
+    "c07d701060fae3d6862b3605e7b59990",
+    "7dd9812041b20b02acc35cd9f1a97afb",
+    "a3bfd11ab6aa6b2898e5d4d2cacdcbc7",
+    "0bd88d18aff200ac9d3edc474efdafa4",
+    "6848c5a68314e4596aba7e16abe4565f",
+    "5e0708abecfcd55f78a9d6934a6a2847",
+    "d3b7abf952848b27ee1c91d4e545b2a3",
+    "9f40b4b772e70c36a56752bbbe9a5918",
+    "205707fe4710bfe0f18225aaafd576ce",
+    "bf50b90a37b57a25a3dad698ac31f18e",
+    "b5b09a9b078065509ed8f8f911203fa6",
+    "c0b08674033d8713b30aaec51de085c8",
+    "da399f2cccd6921884373cc0c2c0c5a3",
+    "c75790064714362f96f391e5579c2abe",
+    "34ff413a353cb6c44a7786a55b0535e5",
+    "4e1531dbb286e5317ecd768fd4c35733",
+    "372063b9f28e3ba5271cca511df13697",
+    "ab82a8b0e9bd29dfde494e93659d065a",
+    "3bec1e65c51d429d4d2e12faec8fc62c",
+    "8c4ca046b867dc7794a7ca5208c16a88",
+    "38b26808780a9f3eecdf3a9f3b39111b",
+    "2a1aeb7ea78bbaf4a3e2ac4351929a13",
+    "5839e105fab5ddb19ad68eaeba6b9b8a",
+    "fb4633df32e574ff4e9bce6b5c33b003",
+    "6992a523909345f55c9716971a906a1e",
+    "2c6225a3e078807112cea185c48f134b",
+    "562c436449a489dbcbce81439281e674",
+    "5624f6fe64d4fda093da9b9c0d09b21e",
+    "9ecfb7cf96f4782c7c6928ae26b47751",
+    "0116f0cb441c3aafc2cc1cd964942d3e",
+    "d5aeffce945e66f4d15963f408138d14",
+    "dcc9b7630988c50a5aab21ac41c615df",
+    "1fd47d11b423d70c662b6debdb7ac225",
+    "8f45a94e4c19b55eb88bd876e3387077",
+    "0ccb986a13b21790c7735ca2a059cdd7",
+    "8c749833cdc8137c4c7defca1bd2348c",
+    "fc404b27fd20c816c825eeafde68b410",
+    "f738c914476ba4f25b65e8c5fc722903",
+    "3924a10e1d3176b133590b03acd79c63",
+    "df373f3defcc978ee164db577b987289",
+    "9a75885acc79b12a6c2958a4a8a9cecd",
+    "813adbd48d751eee322ecc38aec4d7ea",
+    "c81fee1c0d401039a29fbbb578c692be",
+    "76674f3a81e3a2aa83f929265a755758",
+    "86275aa69ce9ce837dcdf48013d12585",
+    "2b788b7a59016a1a1bc6db15cacc2b05",
+    "7804d76c64dc8c740b9ff8c408527055",
+    "737158476b7fad461a21b3d5a464ed98",
+    "cc113001d2006f9fa43bc27033e49da5",
+    "2f5568cc7980dd005161332ba8bd1024",
+    "ced14bae78295fe4830850e4eb6e30de",
+    "6dcc893e9bbc2803d8733c2d3d8d1506",
+    "c4d6b0c167f32a41220aa509a031b4e3",
+    "6c61fc49e8897c43a1eb81abf93bd949",
+    "8f9c15fd41141734e70bdca3ea75d130",
+    "e5f4ccb02a5e661fc0f3b89b31d31635",
+    "98b0fd3d49695f9c158a5eb4ea09abcc",
+    "f7d7269b067349067af1b2fb9d94371b",
+    "b89bb9096e9f681a5ed37e3a8f5de0f6",
+];
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[test]
+fn frame_md5_of_bundled_clip() {
+    let mut decoder = Decoder::new().unwrap();
+    let file = std::fs::File::open("./data/girlshy.h265").unwrap();
+    let mut frame_md5s = Vec::new();
+    decoder
+        .decode_from_reader(file, |image| {
+            let md5 = image.frame_md5();
+            assert_eq!(image.to_owned().frame_md5(), md5);
+            assert_eq!(image.to_owned_trimmed().frame_md5(), md5);
+            frame_md5s.push(to_hex(&md5));
+            std::ops::ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert_eq!(frame_md5s, GIRLSHY_FRAME_MD5);
+}
+
+#[test]
+fn plane_checksums() {
+    let images = all_frames("./data/girlshy.h265");
+    let image = &images[0];
+    assert_eq!(image.plane_crc32(Channel::Y), Some(0x4f8578f4));
+    for channel in [Channel::Y, Channel::Cb, Channel::Cr] {
+        let md5 = image.plane_md5(channel).unwrap();
+        let crc = image.plane_crc32(channel).unwrap();
+        assert_ne!(images[1].plane_md5(channel), Some(md5));
+        assert_ne!(images[1].plane_crc32(channel), Some(crc));
+    }
+}
+
+#[test]
+fn checksums_of_10bit_picture() {
+    let images = all_frames("./data/gradient_10bit.h265");
+    let image = &images[0];
+    assert_eq!(image.bits_per_pixel(Channel::Y), 10);
+    assert_eq!(
+        to_hex(&image.frame_md5()),
+        "63f7b41843d062f9a8793dfc3fbdde93"
+    );
+    assert_eq!(image.plane_crc32(Channel::Y), Some(0x4181d796));
+}
+
+#[test]
+fn checksums_of_monochrome_picture() {
+    let images = all_frames("./data/gradient_mono.h265");
+    let image = &images[0];
+    assert_eq!(image.plane_md5(Channel::Cb), None);
+    assert_eq!(image.plane_crc32(Channel::Cr), None);
+    assert_eq!(Some(image.frame_md5()), image.plane_md5(Channel::Y));
+}
@@ -0,0 +1,63 @@
+use libde265_rs::*;
+
+mod common;
+use common::for_first_image;
+
+#[test]
+fn subsampling_of_formats() {
+    let formats = [
+        (ChromaFormat::Mono, (0, 0), false, 1),
+        (ChromaFormat::C420, (2, 2), true, 3),
+        (ChromaFormat::C422, (2, 1), true, 3),
+        (ChromaFormat::C444, (1, 1), true, 3),
+    ];
+    for (format, subsampling, has_chroma, plane_count) in formats {
+        assert_eq!(format.subsampling(), subsampling, "{format:?}");
+        assert_eq!(format.has_chroma(), has_chroma, "{format:?}");
+        assert_eq!(format.plane_count(), plane_count, "{format:?}");
+    }
+}
+
+#[test]
+fn chroma_dimensions_of_formats() {
+    let formats = [
+        (ChromaFormat::Mono, None, None),
+        (ChromaFormat::C420, Some((158, 120)), Some((3, 2))),
+        (ChromaFormat::C422, Some((158, 240)), Some((3, 3))),
+        (ChromaFormat::C444, Some((316, 240)), Some((5, 3))),
+    ];
+    for (format, even, odd) in formats {
+        assert_eq!(format.chroma_dimensions(316, 240), even, "{format:?}");
+        // Odd sizes are rounded up.
+        assert_eq!(format.chroma_dimensions(5, 3), odd, "{format:?}");
+    }
+    assert_eq!(ChromaFormat::C420.chroma_dimensions(1, 1), Some((1, 1)));
+    assert_eq!(ChromaFormat::C420.chroma_dimensions(0, 0), Some((0, 0)));
+    assert_eq!(
+        ChromaFormat::C420.chroma_dimensions(u32::MAX, u32::MAX),
+        Some((u32::MAX / 2 + 1, u32::MAX / 2 + 1))
+    );
+}
+
+#[test]
+fn chroma_dimensions_of_decoded_pictures() {
+    let files = [
+        "./data/girlshy.h265",
+        "./data/gradient_422.h265",
+        "./data/gradient_444.h265",
+        "./data/gradient_mono.h265",
+    ];
+    for path in files {
+        for_first_image(path, |image| {
+            let format = image.chroma_format();
+            let dimensions =
+                format.chroma_dimensions(image.width(Channel::Y), image.height(Channel::Y));
+            if format.has_chroma() {
+                let expected = (image.width(Channel::Cb), image.height(Channel::Cb));
+                assert_eq!(dimensions, Some(expected), "{path}");
+            } else {
+                assert_eq!(dimensions, None, "{path}");
+            }
+        });
+    }
+}
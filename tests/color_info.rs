@@ -0,0 +1,51 @@
+use libde265_rs::*;
+
+mod common;
+use common::for_first_image;
+
+fn color_infos(path: &str) -> Vec<ColorInfo> {
+    decode_file(path)
+        .unwrap()
+        .map(|image| image.unwrap().color_info())
+        .collect()
+}
+
+#[test]
+fn color_info_of_bundled_clip() {
+    let infos = color_infos("./data/girlshy.h265");
+    assert_eq!(infos.len(), 75);
+    let first = infos[0];
+    assert!(!first.full_range);
+    assert_eq!(first.primaries, ColorPrimaries::Unspecified);
+    assert_eq!(first.transfer, TransferCharacteristics::Unspecified);
+    assert_eq!(first.matrix, MatrixCoefficients::Unspecified);
+    assert_eq!(first.chroma_format, ChromaFormat::C420);
+    assert_eq!(first.bit_depth_luma, 8);
+    assert_eq!(first.bit_depth_chroma, 8);
+    assert!(!first.is_hdr());
+    assert!(infos.iter().all(|info| *info == first));
+}
+
+#[test]
+fn color_info_with_vui() {
+    let infos = color_infos("./data/gradient_bt709.h265");
+    let first = infos[0];
+    assert_eq!(first.primaries, ColorPrimaries::Bt709);
+    assert_eq!(first.transfer, TransferCharacteristics::Bt709);
+    assert_eq!(first.matrix, MatrixCoefficients::Bt709);
+    assert!(infos.iter().all(|info| *info == first));
+
+    let info = color_infos("./data/gradient_10bit.h265")[0];
+    assert_eq!(info.bit_depth_luma, 10);
+    assert_eq!(info.bit_depth_chroma, 10);
+    assert_ne!(info, first);
+}
+
+#[test]
+fn color_info_of_image_and_owned_image() {
+    for_first_image("./data/gradient_444.h265", |image| {
+        let info = image.color_info();
+        assert_eq!(image.to_owned().color_info(), info);
+        assert_eq!(info.chroma_format, ChromaFormat::C444);
+    });
+}
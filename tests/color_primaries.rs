@@ -0,0 +1,60 @@
+use libde265_rs::*;
+
+mod common;
+use common::for_first_image;
+
+/// Codes from table 2 of ITU-T H.273.
+const H273_CODES: [(u8, ColorPrimaries, &str); 12] = [
+    (1, ColorPrimaries::Bt709, "BT.709"),
+    (2, ColorPrimaries::Unspecified, "Unspecified"),
+    (4, ColorPrimaries::Bt470M, "BT.470 M"),
+    (5, ColorPrimaries::Bt470Bg, "BT.470 BG"),
+    (6, ColorPrimaries::Smpte170M, "SMPTE 170M"),
+    (7, ColorPrimaries::Smpte240M, "SMPTE 240M"),
+    (8, ColorPrimaries::Film, "Film"),
+    (9, ColorPrimaries::Bt2020, "BT.2020"),
+    (10, ColorPrimaries::SmpteSt428, "SMPTE ST 428-1"),
+    (11, ColorPrimaries::SmpteRp431, "SMPTE RP 431-2"),
+    (12, ColorPrimaries::SmpteEg432, "SMPTE EG 432-1"),
+    (22, ColorPrimaries::Ebu3213, "EBU Tech. 3213-E"),
+];
+
+#[test]
+fn color_primaries_codes() {
+    for (code, primaries, name) in H273_CODES {
+        assert_eq!(ColorPrimaries::from_code(code), primaries);
+        assert_eq!(primaries.code(), code);
+        assert_eq!(primaries.to_string(), name);
+    }
+    for code in 0..=u8::MAX {
+        let primaries = ColorPrimaries::from_code(code);
+        assert_eq!(primaries.code(), code);
+        assert_eq!(u8::from(primaries), code);
+        assert_eq!(ColorPrimaries::from(code), primaries);
+        let is_known = H273_CODES.iter().any(|&(c, _, _)| c == code);
+        assert_eq!(primaries == ColorPrimaries::Reserved(code), !is_known);
+    }
+    assert_eq!(ColorPrimaries::Reserved(3).to_string(), "Reserved (3)");
+}
+
+fn first_frame_primaries(path: &str) -> (u8, ColorPrimaries) {
+    let mut result = None;
+    for_first_image(path, |image| {
+        let owned = image.to_owned();
+        assert_eq!(owned.color_primaries_enum(), image.color_primaries_enum());
+        result = Some((image.colour_primaries(), image.color_primaries_enum()));
+    });
+    result.unwrap()
+}
+
+#[test]
+fn color_primaries_of_images() {
+    assert_eq!(
+        first_frame_primaries("./data/gradient_bt709.h265"),
+        (1, ColorPrimaries::Bt709)
+    );
+    assert_eq!(
+        first_frame_primaries("./data/girlshy.h265"),
+        (2, ColorPrimaries::Unspecified)
+    );
+}
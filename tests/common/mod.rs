@@ -0,0 +1,28 @@
+//! Helpers shared by integration tests.
+#![allow(dead_code)]
+
+use std::ops::ControlFlow;
+
+use libde265_rs::{Decoder, Image, OwnedImage, decode_file};
+
+/// Decode the first picture of the file and pass it into the callback.
+pub fn for_first_image(path: &str, mut check: impl FnMut(&Image<'_>)) {
+    let mut decoder = Decoder::new().unwrap();
+    let file = std::fs::File::open(path).unwrap();
+    decoder
+        .decode_from_reader(file, |image| {
+            check(&image);
+            ControlFlow::Break(())
+        })
+        .unwrap();
+}
+
+/// Decode the first picture of the file into an owned image.
+pub fn first_frame(path: &str) -> OwnedImage {
+    decode_file(path).unwrap().next().unwrap().unwrap()
+}
+
+/// Decode all pictures of the file into owned images.
+pub fn all_frames(path: &str) -> Vec<OwnedImage> {
+    decode_file(path).unwrap().map(Result::unwrap).collect()
+}
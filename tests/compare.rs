@@ -0,0 +1,99 @@
+use libde265_rs::compare::{max_abs_diff, psnr};
+use libde265_rs::*;
+
+mod common;
+use common::{all_frames, for_first_image};
+
+#[test]
+fn decodes_of_same_file_are_identical() {
+    let first = all_frames("./data/girlshy.h265");
+    let second = all_frames("./data/girlshy.h265");
+    assert_eq!(first.len(), 75);
+    assert_eq!(first.len(), second.len());
+    for (a, b) in first.iter().zip(&second) {
+        assert_eq!(max_abs_diff(a, b), Ok(0));
+        let psnr = psnr(a, b).unwrap();
+        assert_eq!(psnr.y, f64::INFINITY);
+        assert_eq!(psnr.cb, f64::INFINITY);
+        assert_eq!(psnr.cr, f64::INFINITY);
+    }
+}
+
+#[test]
+fn compare_different_pictures() {
+    let images = all_frames("./data/girlshy.h265");
+    let (a, b) = (&images[0], &images[10]);
+    let diff = max_abs_diff(a, b).unwrap();
+    assert!(diff > 0);
+    assert_eq!(max_abs_diff(b, a), Ok(diff));
+    let psnr = psnr(a, b).unwrap();
+    for value in [psnr.y, psnr.cb, psnr.cr] {
+        assert!(value.is_finite() && value > 0., "{psnr:?}");
+    }
+
+    // Padding at the end of rows is ignored.
+    let mut trimmed = None;
+    for_first_image("./data/girlshy.h265", |image| {
+        assert_eq!(max_abs_diff(image, a), Ok(0));
+        trimmed = Some(image.to_owned_trimmed());
+    });
+    let trimmed = trimmed.unwrap();
+    assert_ne!(trimmed.plane(Channel::Y).1, a.plane(Channel::Y).1);
+    assert_eq!(max_abs_diff(&trimmed, a), Ok(0));
+}
+
+#[test]
+fn compare_10bit_pictures() {
+    let images = all_frames("./data/gradient_10bit.h265");
+    let a = &images[0];
+    assert_eq!(max_abs_diff(a, a), Ok(0));
+    assert_eq!(psnr(a, a).unwrap().y, f64::INFINITY);
+}
+
+#[test]
+fn compare_monochrome_pictures() {
+    let images = all_frames("./data/gradient_mono.h265");
+    let psnr = psnr(&images[0], &images[0]).unwrap();
+    assert_eq!(psnr.cb, f64::INFINITY);
+    assert_eq!(psnr.cr, f64::INFINITY);
+}
+
+#[test]
+fn mismatched_pictures() {
+    let girlshy = &all_frames("./data/girlshy.h265")[0];
+    let gradient = &all_frames("./data/gradient_bt709.h265")[0];
+    let error = max_abs_diff(girlshy, gradient).unwrap_err();
+    assert!(matches!(
+        error,
+        CompareError::SizeMismatch {
+            channel: Channel::Y,
+            first: (316, 240),
+            ..
+        }
+    ));
+    assert_eq!(psnr(girlshy, gradient), Err(error));
+
+    let mono = &all_frames("./data/gradient_mono.h265")[0];
+    assert_eq!(
+        max_abs_diff(girlshy, mono),
+        Err(CompareError::ChromaFormatMismatch(
+            ChromaFormat::C420,
+            ChromaFormat::Mono
+        ))
+    );
+
+    let deep = &all_frames("./data/gradient_10bit.h265")[0];
+    let shallow = &all_frames("./data/gradient_bt709.h265")[0];
+    if deep.chroma_format() == shallow.chroma_format()
+        && deep.width(Channel::Y) == shallow.width(Channel::Y)
+    {
+        assert_eq!(
+            max_abs_diff(deep, shallow),
+            Err(CompareError::BitDepthMismatch {
+                channel: Channel::Y,
+                first: 10,
+                second: 8
+            })
+        );
+    }
+}
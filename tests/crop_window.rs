@@ -0,0 +1,94 @@
+use libde265_rs::*;
+
+mod common;
+use common::for_first_image;
+
+#[test]
+fn crop_window_of_bundled_clip() {
+    // 316x240 picture coded as 320x240.
+    for_first_image("./data/girlshy.h265", |image| {
+        let window = image.crop_window().unwrap();
+        assert_eq!(
+            window,
+            CropWindow {
+                left: 0,
+                right: 4,
+                top: 0,
+                bottom: 0
+            }
+        );
+        assert_eq!(image.display_size(), (316, 240));
+        assert_eq!(
+            window.for_plane(image.chroma_format(), Channel::Cb),
+            CropWindow {
+                left: 0,
+                right: 2,
+                top: 0,
+                bottom: 0
+            }
+        );
+        let owned = image.to_owned();
+        assert_eq!(owned.crop_window(), Some(window));
+        assert_eq!(owned.display_size(), (316, 240));
+    });
+}
+
+#[test]
+fn crop_window_of_cropped_clip() {
+    // 256x200 picture coded as 320x240.
+    for_first_image("./data/girlshy_cropped.h265", |image| {
+        let window = image.crop_window().unwrap();
+        assert_eq!(
+            window,
+            CropWindow {
+                left: 0,
+                right: 64,
+                top: 0,
+                bottom: 40
+            }
+        );
+        assert_eq!(image.display_size(), (256, 200));
+        assert_eq!(
+            window.for_plane(image.chroma_format(), Channel::Cr),
+            CropWindow {
+                left: 0,
+                right: 32,
+                top: 0,
+                bottom: 20
+            }
+        );
+    });
+}
+
+#[test]
+fn cropped_planes_have_display_size() {
+    for (path, luma_size, chroma_size) in [
+        ("./data/girlshy.h265", (316, 240), (158, 120)),
+        ("./data/girlshy_cropped.h265", (256, 200), (128, 100)),
+        ("./data/gradient_422.h265", (64, 64), (32, 64)),
+    ] {
+        for_first_image(path, |image| {
+            let (width, height) = image.display_size();
+            let view = image.cropped_plane(Channel::Y);
+            assert_eq!((width, height), luma_size, "{path}");
+            assert_eq!((view.width(), view.height()), luma_size, "{path}");
+            let window = image.crop_window().unwrap();
+            for channel in [Channel::Cb, Channel::Cr] {
+                let view = image.cropped_plane(channel);
+                assert_eq!((view.width(), view.height()), chroma_size, "{path}");
+                // The coded plane consists of the window and offsets around it.
+                let offsets = window.for_plane(image.chroma_format(), channel);
+                let (sub_width, sub_height) =
+                    (luma_size.0 / chroma_size.0, luma_size.1 / chroma_size.1);
+                assert_eq!(
+                    view.width() + offsets.left + offsets.right,
+                    (width + window.left + window.right) / sub_width
+                );
+                assert_eq!(
+                    view.height() + offsets.top + offsets.bottom,
+                    (height + window.top + window.bottom) / sub_height
+                );
+            }
+        });
+    }
+}
@@ -0,0 +1,60 @@
+use libde265_rs::*;
+
+#[test]
+fn decode_file_to_owned_frames() {
+    let frames = decode_file("./data/girlshy.h265").unwrap();
+    let info = frames.stream_info().unwrap();
+    assert_eq!(info.width, 316);
+    assert_eq!(info.height, 240);
+    assert_eq!(info.chroma_format, ChromaFormat::C420);
+    assert_eq!(info.bits_per_pixel, 8);
+
+    let mut images_count = 0;
+    for frame in frames {
+        let frame = frame.unwrap();
+        images_count += 1;
+        assert_eq!(frame.width(Channel::Y), 316);
+        assert_eq!(frame.height(Channel::Y), 240);
+        let (plane_buf, stride) = frame.plane(Channel::Y);
+        assert_eq!(stride, 320);
+        assert_eq!(plane_buf.len(), 320 * 240);
+    }
+    assert_eq!(images_count, 75);
+}
+
+#[test]
+fn decode_empty_file() {
+    let path = std::env::temp_dir().join("libde265_rs_empty.h265");
+    std::fs::write(&path, b"").unwrap();
+    let mut frames = decode_file(&path).unwrap();
+    assert!(frames.stream_info().is_none());
+    assert!(frames.next().is_none());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn decode_missing_file() {
+    let result = decode_file("./data/missing.h265");
+    assert!(matches!(result, Err(StreamError::Io(_))));
+}
+
+#[test]
+fn decode_truncated_stream() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let mut decoded_frames = DecodedFrames::new(&data[..data.len() / 2]).unwrap();
+    let results: Vec<_> = decoded_frames.by_ref().collect();
+    let (last, frames) = results.split_last().unwrap();
+    assert!(!frames.is_empty());
+    assert!(frames.len() < 75);
+    assert!(frames.iter().all(|frame| frame.is_ok()));
+    assert!(matches!(last, Err(StreamError::Decoder(_))));
+    assert!(!decoded_frames.warnings().is_empty());
+    assert!(decoded_frames.next().is_none());
+}
+
+#[test]
+fn decode_file_without_warnings() {
+    let mut frames = decode_file("./data/girlshy.h265").unwrap();
+    assert_eq!(frames.by_ref().filter(|frame| frame.is_ok()).count(), 75);
+    assert!(frames.warnings().is_empty());
+}
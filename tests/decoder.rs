@@ -1,11 +1,16 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs::File;
 use std::io::Read;
+use std::ops::ControlFlow;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
 use libde265_rs::*;
 
 #[test]
 fn decode_h265() {
-    let (mut input, mut output) = new_decoder().unwrap();
+    let (mut input, output) = new_decoder().unwrap();
 
     let mut images_count = 0;
     let mut file = File::open("./data/girlshy.h265").unwrap();
@@ -13,7 +18,7 @@ fn decode_h265() {
     loop {
         match input.decode() {
             Ok(DecodeResult::Done) => break,
-            Ok(DecodeResult::CallAgain) | Err(DeError::ErrorImageBufferFull) => {
+            Ok(DecodeResult::CallAgain | DecodeResult::OutputQueueFull) => {
                 while let Some(image) = output.next_picture() {
                     images_count += 1;
                     assert_eq!(image.width(Channel::Y), 316);
@@ -27,7 +32,7 @@ fn decode_h265() {
                     assert_eq!(plane_buf.len(), 320 * 240);
                 }
             }
-            Err(DeError::ErrorWaitingForInputData) => {
+            Ok(DecodeResult::NeedsMoreInput) => {
                 match file.read(&mut buf).unwrap() {
                     0 => input.flush_data().unwrap(), // EOF
                     size => input.push_data(&buf[0..size], 0, 0).unwrap(),
@@ -39,3 +44,2252 @@ fn decode_h265() {
 
     assert_eq!(images_count, 75);
 }
+
+#[test]
+fn decode_h265_with_frames_iterator() {
+    let (mut input, output) = new_decoder().unwrap();
+
+    let mut images_count = 0;
+    let mut file = File::open("./data/girlshy.h265").unwrap();
+    let mut buf = vec![0; 1024];
+    loop {
+        match input.decode() {
+            Ok(DecodeResult::Done) => break,
+            Ok(DecodeResult::CallAgain | DecodeResult::OutputQueueFull) => {
+                for image in output.frames() {
+                    images_count += 1;
+                    assert_eq!(image.width(Channel::Y), 316);
+                    assert_eq!(image.height(Channel::Y), 240);
+                }
+            }
+            Ok(DecodeResult::NeedsMoreInput) => {
+                match file.read(&mut buf).unwrap() {
+                    0 => input.flush_data().unwrap(), // EOF
+                    size => input.push_data(&buf[0..size], 0, 0).unwrap(),
+                }
+            }
+            Err(err) => panic!("{:?}", err),
+        }
+    }
+
+    assert_eq!(images_count, 75);
+}
+
+fn plane_checksum(image: &Image<'_>) -> u64 {
+    let (plane, _) = image.plane(Channel::Y);
+    plane.iter().map(|&v| v as u64).sum()
+}
+
+#[test]
+fn frames_iterator_yields_pictures_alive_together() {
+    let (mut input, output) = new_decoder().unwrap();
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    input.push_data(&data, 0, 0).unwrap();
+    input.flush_data().unwrap();
+    let mut images_count = 0;
+    loop {
+        let result = input.decode().unwrap();
+        let images: Vec<Image> = output.frames().collect();
+        let checksums: Vec<u64> = images.iter().map(plane_checksum).collect();
+        if !images.is_empty() {
+            // Pictures released from the output queue stay untouched
+            // while their images are alive.
+            assert_eq!(input.decode().unwrap(), DecodeResult::OutputQueueFull);
+        }
+        assert_eq!(
+            images.iter().map(plane_checksum).collect::<Vec<_>>(),
+            checksums
+        );
+        images_count += images.len();
+        if result == DecodeResult::Done {
+            break;
+        }
+    }
+    assert_eq!(images_count, 75);
+}
+
+#[test]
+fn pairs_of_consecutive_pictures() {
+    let (mut input, output) = new_decoder().unwrap();
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    input.push_data(&data, 0, 0).unwrap();
+    input.flush_data().unwrap();
+    // The last picture of a batch is kept as an owned image
+    // to pair it with the first picture of the next batch.
+    let mut last: Option<OwnedImage> = None;
+    let mut pairs_count = 0;
+    loop {
+        let result = input.decode().unwrap();
+        let mut previous: Option<(Image, u64)> = None;
+        while let Some(current) = output.next_picture() {
+            if let Some((previous, checksum)) = previous.take() {
+                // The previous picture is released from the output queue,
+                // but its image is still valid.
+                assert_eq!(plane_checksum(&previous), checksum);
+                pairs_count += 1;
+            } else if last.take().is_some() {
+                pairs_count += 1;
+            }
+            let checksum = plane_checksum(&current);
+            previous = Some((current, checksum));
+        }
+        if let Some((previous, _)) = previous {
+            last = Some(previous.to_owned());
+        }
+        if result == DecodeResult::Done {
+            break;
+        }
+    }
+    assert_eq!(pairs_count, 74);
+}
+
+#[test]
+fn holding_picture_fills_output_queue() {
+    let (mut input, output) = new_decoder().unwrap();
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    input.push_data(&data, 0, 0).unwrap();
+    input.flush_data().unwrap();
+    while !output.has_pending_pictures() {
+        input.decode().unwrap();
+    }
+    let image = output.next_picture().unwrap();
+    let checksum = plane_checksum(&image);
+    let mut decode_calls = 0;
+    while input.decode().unwrap() != DecodeResult::OutputQueueFull {
+        decode_calls += 1;
+        assert!(decode_calls < 10_000, "the output queue is never full");
+    }
+    assert_eq!(plane_checksum(&image), checksum);
+    assert!(matches!(
+        input.reset(),
+        Err(DeError::ErrorPictureIsBorrowed)
+    ));
+    drop(image);
+
+    let mut images_count = 1;
+    loop {
+        let result = input.decode().unwrap();
+        images_count += output.frames().count();
+        if result == DecodeResult::Done {
+            break;
+        }
+    }
+    assert_eq!(images_count, 75);
+    input.reset().unwrap();
+}
+
+#[test]
+fn decode_h265_with_combined_decoder() {
+    let mut decoder = Decoder::new().unwrap();
+
+    let mut images_count = 0;
+    let mut file = File::open("./data/girlshy.h265").unwrap();
+    let mut buf = vec![0; 1024];
+    loop {
+        match decoder.decode() {
+            Ok(DecodeResult::Done) => break,
+            Ok(DecodeResult::CallAgain | DecodeResult::OutputQueueFull) => {
+                while let Some(image) = decoder.next_picture() {
+                    images_count += 1;
+                    assert_eq!(image.width(Channel::Y), 316);
+                    assert_eq!(image.height(Channel::Y), 240);
+                }
+            }
+            Ok(DecodeResult::NeedsMoreInput) => {
+                match file.read(&mut buf).unwrap() {
+                    0 => decoder.flush_data().unwrap(), // EOF
+                    size => decoder.push_data(&buf[0..size], 0, 0).unwrap(),
+                }
+            }
+            Err(err) => panic!("{:?}", err),
+        }
+    }
+
+    assert_eq!(images_count, 75);
+}
+
+#[test]
+fn split_combined_decoder() {
+    let decoder = Decoder::new().unwrap();
+    let (mut input, output) = decoder.split();
+
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    input.push_data(&data, 0, 0).unwrap();
+    input.flush_data().unwrap();
+
+    let mut images_count = 0;
+    loop {
+        match input.decode() {
+            Ok(DecodeResult::Done) => break,
+            Ok(DecodeResult::CallAgain | DecodeResult::OutputQueueFull) => {
+                images_count += output.frames().count();
+            }
+            Ok(DecodeResult::NeedsMoreInput) => unreachable!("all data has been pushed"),
+            Err(err) => panic!("{:?}", err),
+        }
+    }
+
+    assert_eq!(images_count, 75);
+}
+
+#[test]
+fn decode_h265_with_configured_decoder() {
+    let mut decoder = DecoderBuilder::new()
+        .worker_threads(2)
+        .acceleration(Acceleration::Auto)
+        .sei_check_hash(true)
+        .suppress_faulty_pictures(true)
+        .build_decoder()
+        .unwrap();
+    assert!(decoder.input().get_parameter_bool(ParamBool::SeiCheckHash));
+    assert!(
+        decoder
+            .input()
+            .get_parameter_bool(ParamBool::SuppressFaultyPictures)
+    );
+    assert!(!decoder.input().get_parameter_bool(ParamBool::DisableSAO));
+
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    decoder.push_data(&data, 0, 0).unwrap();
+    decoder.flush_data().unwrap();
+
+    let mut images_count = 0;
+    loop {
+        match decoder.decode() {
+            Ok(DecodeResult::Done) => break,
+            Ok(DecodeResult::CallAgain | DecodeResult::OutputQueueFull) => {
+                images_count += decoder.frames().count();
+            }
+            Ok(DecodeResult::NeedsMoreInput) => unreachable!("all data has been pushed"),
+            Err(err) => panic!("{:?}", err),
+        }
+    }
+
+    assert_eq!(images_count, 75);
+}
+
+#[test]
+fn builder_rejects_zero_worker_threads() {
+    let result = DecoderBuilder::new().worker_threads(0).build();
+    assert!(matches!(
+        result,
+        Err(DeError::ErrorInvalidNumberOfWorkerThreads)
+    ));
+}
+
+/// Reader that returns at most `max_size` bytes per a call.
+struct ShortReader<R> {
+    inner: R,
+    max_size: usize,
+}
+
+impl<R: Read> Read for ShortReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let size = buf.len().min(self.max_size);
+        self.inner.read(&mut buf[..size])
+    }
+}
+
+#[test]
+fn decode_from_reader() {
+    let mut decoder = Decoder::new().unwrap();
+    let file = File::open("./data/girlshy.h265").unwrap();
+
+    let mut images_count = 0;
+    decoder
+        .decode_from_reader(file, |image| {
+            images_count += 1;
+            assert_eq!(image.width(Channel::Y), 316);
+            assert_eq!(image.height(Channel::Y), 240);
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+
+    assert_eq!(images_count, 75);
+}
+
+#[test]
+fn decode_from_reader_with_short_reads() {
+    let mut decoder = Decoder::new().unwrap();
+    let reader = ShortReader {
+        inner: File::open("./data/girlshy.h265").unwrap(),
+        max_size: 7,
+    };
+
+    let mut images_count = 0;
+    decoder
+        .decode_from_reader(reader, |_| {
+            images_count += 1;
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+
+    assert_eq!(images_count, 75);
+}
+
+#[test]
+fn decode_from_reader_early_termination() {
+    let mut decoder = Decoder::new().unwrap();
+    let file = File::open("./data/girlshy.h265").unwrap();
+
+    let mut images_count = 0;
+    decoder
+        .decode_from_reader(file, |_| {
+            images_count += 1;
+            if images_count == 10 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .unwrap();
+
+    assert_eq!(images_count, 10);
+    assert_eq!(decoder.number_of_input_bytes_pending(), 0);
+    assert!(decoder.next_picture().is_none());
+}
+
+#[test]
+fn drain_warnings_of_corrupted_stream() {
+    let mut data = std::fs::read("./data/girlshy.h265").unwrap();
+    // Corrupt the middle part of the stream
+    let middle = data.len() / 2;
+    data[middle..middle + 256].fill(0xaa);
+
+    let (mut input, output) = new_decoder().unwrap();
+    assert_eq!(input.warnings().count(), 0);
+
+    input.push_data(&data, 0, 0).unwrap();
+    input.flush_data().unwrap();
+    let mut warnings = Vec::new();
+    loop {
+        match input.decode() {
+            Ok(DecodeResult::Done) => break,
+            Ok(DecodeResult::CallAgain | DecodeResult::OutputQueueFull) => {
+                output.frames().for_each(drop);
+            }
+            Ok(DecodeResult::NeedsMoreInput) => unreachable!("all data has been pushed"),
+            Err(_) => {}
+        }
+        warnings.extend(input.warnings());
+    }
+
+    assert!(!warnings.is_empty());
+    assert_eq!(input.warnings().count(), 0);
+}
+
+/// Decode the corrupted stream and return drained warnings.
+fn decode_corrupted_stream(decoder: &mut Decoder) -> Vec<DeError> {
+    let mut data = std::fs::read("./data/girlshy.h265").unwrap();
+    let middle = data.len() / 2;
+    data[middle..middle + 256].fill(0xaa);
+
+    decoder.push_data(&data, 0, 0).unwrap();
+    decoder.flush_data().unwrap();
+    let mut warnings = Vec::new();
+    loop {
+        let result = decoder.decode();
+        decoder.frames().for_each(drop);
+        warnings.extend(decoder.input_mut().warnings());
+        if let Ok(DecodeResult::Done) = result {
+            break;
+        }
+    }
+    warnings
+}
+
+#[test]
+fn suppress_warnings() {
+    let mut decoder = Decoder::new().unwrap();
+    let warnings = decode_corrupted_stream(&mut decoder);
+    let suppressed = warnings[0];
+    let suppressed_count = warnings.iter().filter(|&&w| w == suppressed).count() as u64;
+    assert!(decoder.stats().suppressed_warnings.is_empty());
+
+    let mut decoder = Decoder::new().unwrap();
+    decoder.input_mut().suppress_warnings(&[suppressed]);
+    assert_eq!(decoder.input().suppressed_warnings(), [suppressed]);
+    let filtered_warnings = decode_corrupted_stream(&mut decoder);
+    assert!(!filtered_warnings.contains(&suppressed));
+    assert_eq!(
+        filtered_warnings.len() as u64,
+        warnings.len() as u64 - suppressed_count
+    );
+    let stats = decoder.stats();
+    assert_eq!(stats.suppressed_warnings[&suppressed], suppressed_count);
+    assert!(!stats.warnings.contains_key(&suppressed));
+}
+
+#[test]
+fn parameters_round_trip() {
+    let (mut input, _output) = new_decoder().unwrap();
+
+    let params_i32 = [
+        ParamI32::DumpSpsHeaders,
+        ParamI32::DumpVpsHeaders,
+        ParamI32::DumpPpsHeaders,
+        ParamI32::DumpSliceHeaders,
+    ];
+    for (i, &param) in params_i32.iter().enumerate() {
+        assert_eq!(input.get_parameter_i32(param), -1);
+        input.set_parameter_i32(param, 100 + i as i32);
+    }
+    for (i, &param) in params_i32.iter().enumerate() {
+        assert_eq!(input.get_parameter_i32(param), 100 + i as i32);
+        input.set_parameter_i32(param, -1);
+        assert_eq!(input.get_parameter_i32(param), -1);
+    }
+
+    let params_bool = [
+        ParamBool::SeiCheckHash,
+        ParamBool::SuppressFaultyPictures,
+        ParamBool::DisableDeblocking,
+        ParamBool::DisableSAO,
+    ];
+    for &param in &params_bool {
+        assert!(!input.get_parameter_bool(param));
+    }
+    // Enable parameters one by one to check that
+    // they don't affect each other.
+    for (i, &param) in params_bool.iter().enumerate() {
+        input.set_parameter_bool(param, true);
+        for (j, &other_param) in params_bool.iter().enumerate() {
+            assert_eq!(input.get_parameter_bool(other_param), j <= i);
+        }
+    }
+    for &param in &params_bool {
+        input.set_parameter_bool(param, false);
+        assert!(!input.get_parameter_bool(param));
+    }
+}
+
+fn assert_preset_parameters(input: &DecoderInput, sei_check_hash: bool, disable_filters: bool) {
+    assert_eq!(
+        input.get_parameter_bool(ParamBool::SeiCheckHash),
+        sei_check_hash
+    );
+    assert_eq!(
+        input.get_parameter_bool(ParamBool::DisableDeblocking),
+        disable_filters
+    );
+    assert_eq!(
+        input.get_parameter_bool(ParamBool::DisableSAO),
+        disable_filters
+    );
+    assert_eq!(input.get_acceleration(), Acceleration::Auto);
+}
+
+#[test]
+fn decode_presets() {
+    let (mut input, _output) = new_decoder().unwrap();
+    input.set_acceleration(Acceleration::Scalar);
+    input.apply_preset(DecodePreset::Quality);
+    assert_preset_parameters(&input, true, false);
+    input.apply_preset(DecodePreset::FastPreview);
+    assert_preset_parameters(&input, false, true);
+    input.apply_preset(DecodePreset::Balanced);
+    assert_preset_parameters(&input, false, false);
+
+    let mut decoder = DecoderBuilder::new()
+        .preset(DecodePreset::FastPreview)
+        .build_decoder()
+        .unwrap();
+    assert_preset_parameters(decoder.input(), false, true);
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let mut images_count = 0;
+    decoder
+        .decode_from_reader(data.as_slice(), |_| {
+            images_count += 1;
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert_eq!(images_count, 75);
+}
+
+#[test]
+fn worker_threads_can_be_started_only_once() {
+    let mut decoder = Decoder::new().unwrap();
+    assert_eq!(decoder.num_worker_threads(), 0);
+    decoder.start_worker_threads(0).unwrap();
+    assert_eq!(decoder.num_worker_threads(), 0);
+    decoder.start_worker_threads(100).unwrap();
+    assert_eq!(decoder.num_worker_threads(), MAX_WORKER_THREADS);
+    assert_eq!(
+        decoder.start_worker_threads(1),
+        Err(DeError::ErrorWorkerThreadsAlreadyStarted)
+    );
+    assert_eq!(decoder.num_worker_threads(), MAX_WORKER_THREADS);
+
+    // Thread pool is restarted after reset with the same number of threads.
+    decoder.reset().unwrap();
+    let file = File::open("./data/girlshy.h265").unwrap();
+    let mut images_count = 0;
+    decoder
+        .decode_from_reader(file, |_| {
+            images_count += 1;
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert_eq!(images_count, 75);
+}
+
+#[test]
+fn pending_pictures_are_reported() {
+    let mut decoder = Decoder::new().unwrap();
+    assert!(!decoder.has_pending_pictures());
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    decoder.push_data(&data, 0, 0).unwrap();
+    decoder.flush_data().unwrap();
+
+    let mut images_count = 0;
+    loop {
+        let result = decoder.decode().unwrap();
+        if decoder.has_pending_pictures() {
+            for _ in decoder.frames() {
+                images_count += 1;
+            }
+            assert!(!decoder.has_pending_pictures());
+        }
+        if result == DecodeResult::Done {
+            break;
+        }
+    }
+    assert_eq!(images_count, 75);
+    assert!(!decoder.has_pending_pictures());
+}
+
+#[test]
+fn decode_next_picture() {
+    let mut decoder = Decoder::new().unwrap();
+    let mut file = File::open("./data/girlshy.h265").unwrap();
+    let mut buf = vec![0; 1024];
+    let mut feed = |input: &mut DecoderInput| match file.read(&mut buf).unwrap() {
+        0 => Ok(false), // EOF
+        size => input.push_data(&buf[0..size], 0, 0).map(|_| true),
+    };
+
+    let mut images_count = 0;
+    while let Some(image) = decoder.decode_next_picture(&mut feed).unwrap() {
+        images_count += 1;
+        assert_eq!(image.width(Channel::Y), 316);
+        assert_eq!(image.height(Channel::Y), 240);
+    }
+    assert_eq!(images_count, 75);
+    assert!(decoder.decode_next_picture(&mut feed).unwrap().is_none());
+}
+
+#[test]
+fn decode_next_picture_by_access_units() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    // Split NAL units into access units: a new one starts with
+    // parameter sets, prefix SEI or the first slice segment of a picture
+    // following slices of the previous picture.
+    let mut access_units: Vec<Vec<&[u8]>> = Vec::new();
+    let mut has_slices = false;
+    for nal in split_annex_b(&data) {
+        let nal_type = nal.data[0] >> 1;
+        let is_vcl = nal_type < 32;
+        let starts_unit = if is_vcl {
+            nal.data[2] & 0x80 != 0
+        } else {
+            matches!(nal_type, 32..=35 | 39)
+        };
+        if starts_unit && has_slices || access_units.is_empty() {
+            access_units.push(Vec::new());
+            has_slices = false;
+        }
+        has_slices |= is_vcl;
+        access_units.last_mut().unwrap().push(nal.data);
+    }
+    assert_eq!(access_units.len(), 75);
+
+    let mut decoder = Decoder::new().unwrap();
+    let mut units = access_units.iter();
+    let mut feed = |input: &mut DecoderInput| match units.next() {
+        Some(nals) => input.push_access_unit(nals, 0, 0).map(|_| true),
+        None => Ok(false),
+    };
+    let mut images_count = 0;
+    while let Some(image) = decoder.decode_next_picture(&mut feed).unwrap() {
+        images_count += 1;
+        assert_eq!(image.width(Channel::Y), 316);
+    }
+    assert_eq!(images_count, 75);
+    assert!(decoder.is_flushed());
+}
+
+#[test]
+fn image_debug() {
+    let mut decoder = Decoder::new().unwrap();
+    let file = File::open("./data/girlshy.h265").unwrap();
+    decoder
+        .decode_from_reader(file, |image| {
+            let debug = format!("{image:?}");
+            assert!(debug.contains("C420"), "{debug}");
+            assert!(debug.contains("316x240"), "{debug}");
+            assert_eq!(
+                debug,
+                "Image { chroma_format: C420, y: 316x240 8-bit, cb: 158x120 8-bit, \
+                 cr: 158x120 8-bit, pts: 0, nal_unit_type: IDR_N_LP, full_range: false, \
+                 colour_primaries: 2, transfer_characteristics: 2, matrix_coefficients: 2, .. }"
+            );
+            ControlFlow::Break(())
+        })
+        .unwrap();
+}
+
+#[test]
+fn skip_until_keyframe_after_seeking() {
+    // Stream of two copies of the file, the second copy starts with a keyframe.
+    let mut data = std::fs::read("./data/girlshy.h265").unwrap();
+    data.extend_from_within(..);
+    let nal_units: Vec<_> = split_annex_b(&data).collect();
+
+    let mut decoder = Decoder::new().unwrap();
+    // Decode the beginning of the stream.
+    for nal in &nal_units[..10] {
+        decoder.push_nal(nal.data, 0, 0).unwrap();
+    }
+    decoder.decode().unwrap();
+    decoder.frames().for_each(drop);
+
+    // Seek into the middle of the first copy.
+    decoder.reset().unwrap();
+    decoder.set_skip_until_keyframe(true);
+    for nal in &nal_units[40..] {
+        decoder.push_nal(nal.data, 0, 0).unwrap();
+    }
+    assert!(!decoder.is_skipping_until_keyframe());
+    decoder.flush_data().unwrap();
+
+    let mut unit_types = Vec::new();
+    loop {
+        let result = decoder.decode().unwrap();
+        for image in decoder.frames() {
+            unit_types.push(image.nal_header().unit_type);
+        }
+        if result == DecodeResult::Done {
+            break;
+        }
+    }
+    assert_eq!(unit_types.len(), 75);
+    assert!(unit_types[0].is_irap());
+    assert_eq!(decoder.input_mut().warnings().count(), 0);
+}
+
+#[test]
+fn dump_headers_sets_dump_parameters() {
+    let (mut input, _) = new_decoder().unwrap();
+    let kinds = [
+        (HeaderKind::Vps, ParamI32::DumpVpsHeaders),
+        (HeaderKind::Sps, ParamI32::DumpSpsHeaders),
+        (HeaderKind::Pps, ParamI32::DumpPpsHeaders),
+        (HeaderKind::Slice, ParamI32::DumpSliceHeaders),
+    ];
+    for (kind, param) in kinds {
+        input.dump_headers(kind, Some(DumpTarget::Stdout));
+        assert_eq!(input.get_parameter_i32(param), 1);
+        input.dump_headers(kind, Some(DumpTarget::Stderr));
+        assert_eq!(input.get_parameter_i32(param), 2);
+        input.dump_headers(kind, None);
+        assert_eq!(input.get_parameter_i32(param), -1);
+    }
+}
+
+#[test]
+fn detect_acceleration() {
+    let acceleration = Acceleration::detect();
+    assert_ne!(acceleration, Acceleration::Auto);
+    #[cfg(target_arch = "x86_64")]
+    assert!(matches!(
+        acceleration,
+        Acceleration::SSE2 | Acceleration::SSE4
+    ));
+    let (mut input, _) = new_decoder().unwrap();
+    input.set_acceleration(acceleration);
+    assert_eq!(input.get_acceleration(), acceleration);
+}
+
+#[test]
+fn acceleration_round_trip() {
+    let (mut input, _) = new_decoder().unwrap();
+    assert_eq!(input.get_acceleration(), Acceleration::Auto);
+    let variants = [
+        Acceleration::Scalar,
+        Acceleration::MMS,
+        Acceleration::SSE,
+        Acceleration::SSE2,
+        Acceleration::SSE4,
+        Acceleration::AVX,
+        Acceleration::AVX2,
+        Acceleration::ARM,
+        Acceleration::NEON,
+        Acceleration::Auto,
+    ];
+    for acceleration in variants {
+        input.set_acceleration(acceleration);
+        assert_eq!(input.get_acceleration(), acceleration);
+    }
+}
+
+#[test]
+fn acceleration_round_trip_through_string() {
+    let variants = [
+        Acceleration::Scalar,
+        Acceleration::MMS,
+        Acceleration::SSE,
+        Acceleration::SSE2,
+        Acceleration::SSE4,
+        Acceleration::AVX,
+        Acceleration::AVX2,
+        Acceleration::ARM,
+        Acceleration::NEON,
+        Acceleration::Auto,
+    ];
+    for acceleration in variants {
+        let name = acceleration.to_string();
+        assert_eq!(name.parse(), Ok(acceleration));
+        assert_eq!(name.to_uppercase().parse(), Ok(acceleration));
+    }
+    assert_eq!(
+        "sse5".parse::<Acceleration>(),
+        Err(ParseAccelerationError("sse5".to_string()))
+    );
+}
+
+#[test]
+fn decode_in_separate_threads() {
+    let (mut input, output) = new_decoder().unwrap();
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let done = &AtomicBool::new(false);
+
+    let images_count = std::thread::scope(|scope| {
+        scope.spawn(move || {
+            let mut chunks = data.chunks(1024);
+            loop {
+                match input.decode() {
+                    Ok(DecodeResult::Done) => break,
+                    Ok(DecodeResult::CallAgain) => {}
+                    Ok(DecodeResult::OutputQueueFull) => std::thread::yield_now(),
+                    Ok(DecodeResult::NeedsMoreInput) => match chunks.next() {
+                        Some(chunk) => input.push_data(chunk, 0, 0).unwrap(),
+                        None => input.flush_data().unwrap(),
+                    },
+                    Err(err) => panic!("{:?}", err),
+                }
+            }
+            done.store(true, Ordering::Release);
+        });
+
+        let consumer = scope.spawn(move || {
+            let mut images_count = 0;
+            loop {
+                if let Some(image) = output.next_picture() {
+                    assert_eq!(image.width(Channel::Y), 316);
+                    images_count += 1;
+                } else if done.load(Ordering::Acquire) && !output.has_pending_pictures() {
+                    break;
+                } else {
+                    std::thread::yield_now();
+                }
+            }
+            images_count
+        });
+        consumer.join().unwrap()
+    });
+
+    assert_eq!(images_count, 75);
+}
+
+#[test]
+fn reset_is_rejected_while_image_is_alive() {
+    let (mut input, output) = new_decoder().unwrap();
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    input.push_data(&data, 0, 0).unwrap();
+    input.flush_data().unwrap();
+    while !output.has_pending_pictures() {
+        input.decode().unwrap();
+    }
+
+    let image = output.next_picture().unwrap();
+    let (plane_buf, _) = image.plane(Channel::Y);
+    let checksum: u64 = plane_buf.iter().map(|&v| v as u64).sum();
+    assert!(matches!(
+        input.reset(),
+        Err(DeError::ErrorPictureIsBorrowed)
+    ));
+    // Decoding doesn't touch the borrowed picture.
+    input.decode().unwrap();
+    let (plane_buf, _) = image.plane(Channel::Y);
+    assert_eq!(plane_buf.iter().map(|&v| v as u64).sum::<u64>(), checksum);
+
+    drop(image);
+    input.reset().unwrap();
+    assert!(output.next_picture().is_none());
+}
+
+#[test]
+fn reset_is_rejected_after_leaking_image() {
+    let mut decoder = Decoder::new().unwrap();
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    decoder.push_data(&data, 0, 0).unwrap();
+    decoder.flush_data().unwrap();
+    while !decoder.has_pending_pictures() {
+        decoder.decode().unwrap();
+    }
+
+    std::mem::forget(decoder.next_picture().unwrap());
+    assert!(matches!(
+        decoder.reset(),
+        Err(DeError::ErrorPictureIsBorrowed)
+    ));
+    assert!(matches!(
+        decoder.cancel(),
+        Err(DeError::ErrorPictureIsBorrowed)
+    ));
+    assert!(!decoder.is_cancelled());
+}
+
+#[test]
+fn peek_and_pop_pictures() {
+    let mut decoder = Decoder::new().unwrap();
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    for (i, nal) in split_annex_b(&data).enumerate() {
+        decoder.push_nal(nal.data, i as i64, 0).unwrap();
+    }
+    decoder.flush_data().unwrap();
+    while !decoder.has_pending_pictures() {
+        decoder.decode().unwrap();
+    }
+
+    let image = decoder.peek_picture().unwrap();
+    let pts = image.pts();
+    assert_eq!(image.width(Channel::Y), 316);
+    assert_eq!(image.nal_header().unit_type, NalUnitType::IdrNLp);
+    drop(image);
+    let image = decoder.peek_picture().unwrap();
+    assert_eq!(image.pts(), pts);
+    drop(image);
+
+    assert!(decoder.pop_picture());
+    while !decoder.has_pending_pictures() {
+        decoder.decode().unwrap();
+    }
+    let image = decoder.peek_picture().unwrap();
+    assert_ne!(image.pts(), pts);
+    let next_pts = image.pts();
+    drop(image);
+    // The peeked picture is returned by `next_picture()` too.
+    assert_eq!(decoder.next_picture().unwrap().pts(), next_pts);
+}
+
+#[test]
+fn try_push_data_respects_pending_bytes_limit() {
+    let mut decoder = Decoder::new().unwrap();
+    assert_eq!(decoder.max_pending_bytes(), usize::MAX);
+    assert_eq!(decoder.remaining_input_capacity(), usize::MAX);
+
+    decoder.set_max_pending_bytes(4096);
+    assert_eq!(decoder.max_pending_bytes(), 4096);
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let (first, rest) = data.split_at(4096);
+    assert_eq!(
+        decoder.try_push_data(first, 0, 0).unwrap(),
+        PushResult::Pushed
+    );
+    let capacity = decoder.remaining_input_capacity();
+    assert!(capacity < 4096);
+
+    let chunk = &rest[..capacity + 1];
+    assert_eq!(
+        decoder.try_push_data(chunk, 0, 0).unwrap(),
+        PushResult::WouldBlock
+    );
+    assert_eq!(decoder.remaining_input_capacity(), capacity);
+
+    while decoder.decode().unwrap() != DecodeResult::NeedsMoreInput {}
+    assert!(decoder.remaining_input_capacity() > capacity);
+    assert_eq!(
+        decoder.try_push_data(chunk, 0, 0).unwrap(),
+        PushResult::Pushed
+    );
+}
+
+#[test]
+fn flushed_and_finished_states() {
+    let mut decoder = Decoder::new().unwrap();
+    assert!(!decoder.is_flushed());
+    assert!(!decoder.is_finished());
+
+    let mut images_count = 0;
+    let mut file = File::open("./data/girlshy.h265").unwrap();
+    let mut buf = vec![0; 1024];
+    loop {
+        match decoder.decode().unwrap() {
+            DecodeResult::Done => break,
+            DecodeResult::CallAgain | DecodeResult::OutputQueueFull => {
+                assert!(!decoder.is_finished());
+                images_count += decoder.frames().count();
+            }
+            DecodeResult::NeedsMoreInput => {
+                assert!(!decoder.is_flushed());
+                match file.read(&mut buf).unwrap() {
+                    0 => {
+                        decoder.flush_data().unwrap();
+                        assert!(decoder.is_flushed());
+                        assert!(!decoder.is_finished());
+                    }
+                    size => decoder.push_data(&buf[0..size], 0, 0).unwrap(),
+                }
+            }
+        }
+    }
+    assert_eq!(images_count, 75);
+    assert!(decoder.is_flushed());
+    assert!(decoder.is_finished());
+
+    decoder.reset().unwrap();
+    assert!(!decoder.is_flushed());
+    assert!(!decoder.is_finished());
+}
+
+/// Decode the bundled file with help of `decode_some()` and return
+/// the number of calls and decoded images.
+fn decode_by_steps(max_steps: u32) -> (usize, usize) {
+    let mut decoder = Decoder::new().unwrap();
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    decoder.push_data(&data, 0, 0).unwrap();
+    decoder.flush_data().unwrap();
+    let mut calls = 0;
+    let mut images_count = 0;
+    loop {
+        let result = decoder.decode_some(max_steps).unwrap();
+        calls += 1;
+        assert!(result.steps >= 1 && result.steps <= max_steps);
+        assert_eq!(result.has_pending_pictures, decoder.has_pending_pictures());
+        images_count += decoder.frames().count();
+        if result.result == DecodeResult::Done {
+            break;
+        }
+    }
+    (calls, images_count)
+}
+
+#[test]
+fn decode_some_steps() {
+    // One step is the same as `decode()`.
+    let mut decoder = Decoder::new().unwrap();
+    let mut reference = Decoder::new().unwrap();
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    for chunk in data.chunks(4096) {
+        decoder.push_data(chunk, 0, 0).unwrap();
+        reference.push_data(chunk, 0, 0).unwrap();
+        loop {
+            let result = decoder.decode_some(1).unwrap();
+            assert_eq!(result.steps, 1);
+            assert_eq!(result.result, reference.decode().unwrap());
+            assert_eq!(decoder.frames().count(), reference.frames().count());
+            if result.result == DecodeResult::NeedsMoreInput {
+                break;
+            }
+        }
+    }
+
+    let (calls_by_one, images_count) = decode_by_steps(1);
+    assert_eq!(images_count, 75);
+    let (calls_by_many, images_count) = decode_by_steps(16);
+    assert_eq!(images_count, 75);
+    assert!(calls_by_many < calls_by_one);
+}
+
+/// Decode the bundled file with the given layer filter, attaching
+/// a shared payload to every picture. Returns the number of decoded images.
+fn decode_layer(decoder: &mut Decoder, layer_id: Option<u8>, payload: &Arc<()>) -> usize {
+    decoder.set_layer_filter(layer_id);
+    assert_eq!(decoder.layer_filter(), layer_id);
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let mut nals = split_annex_b(&data);
+    let mut images_count = 0;
+    loop {
+        match decoder.decode().unwrap() {
+            DecodeResult::Done => break,
+            DecodeResult::CallAgain | DecodeResult::OutputQueueFull => {
+                images_count += decoder.frames().count();
+            }
+            DecodeResult::NeedsMoreInput => match nals.next() {
+                Some(nal) => decoder.push_nal_with(nal.data, 0, payload.clone()).unwrap(),
+                None => decoder.flush_data().unwrap(),
+            },
+        }
+    }
+    images_count
+}
+
+#[test]
+fn layer_filter() {
+    let payload = Arc::new(());
+    let mut decoder = Decoder::new().unwrap();
+    assert!(decoder.layers_seen().is_empty());
+    assert_eq!(decode_layer(&mut decoder, Some(0), &payload), 75);
+    assert_eq!(decoder.layers_seen(), BTreeSet::from([0]));
+
+    // All pictures of the single layer stream are dropped
+    // without leaking of pictures and their payloads.
+    let mut decoder = Decoder::new().unwrap();
+    assert_eq!(decode_layer(&mut decoder, Some(1), &payload), 0);
+    assert!(decoder.is_finished());
+    assert_eq!(Arc::strong_count(&payload), 1);
+    assert_eq!(decoder.layers_seen(), BTreeSet::from([0]));
+
+    let mut decoder = Decoder::new().unwrap();
+    assert_eq!(decode_layer(&mut decoder, None, &payload), 75);
+}
+
+#[test]
+fn temporal_layers_snapshot() {
+    let (mut input, output) = new_decoder().unwrap();
+    let info = input.temporal_layers();
+    assert_eq!(info.limit_tid, 6);
+    assert_eq!(info.framerate_ratio_percent, 100);
+
+    input.set_framerate_ratio(50);
+    assert_eq!(input.temporal_layers().framerate_ratio_percent, 50);
+    input.set_limit_tid(0);
+    assert_eq!(input.temporal_layers().limit_tid, 0);
+
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    input.push_data(&data, 0, 0).unwrap();
+    input.flush_data().unwrap();
+    while input.decode().unwrap() != DecodeResult::Done {
+        output.frames().for_each(drop);
+    }
+
+    let ratio = input.change_framerate2(FramerateChange::Less);
+    let info = input.temporal_layers();
+    assert_eq!(info.framerate_ratio_percent, ratio.as_percent() as u32);
+    assert_eq!(info.highest_tid, input.highest_tid());
+    assert_eq!(info.current_tid, input.current_tid());
+    assert!(info.current_tid <= info.highest_tid);
+}
+
+#[test]
+fn pts_with_90khz_timebase() {
+    let timebase = TimeBase::new(1, 90_000).unwrap();
+    let mut decoder = Decoder::new().unwrap();
+    assert_eq!(decoder.timebase(), TimeBase::MICROSECONDS);
+    decoder.set_timebase(timebase);
+    assert_eq!(decoder.timebase(), timebase);
+
+    // 30 fps: 3000 ticks of 90 kHz clock per NAL
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    for (i, nal) in split_annex_b(&data).enumerate() {
+        decoder.push_nal(nal.data, i as i64 * 3000, 0).unwrap();
+    }
+    decoder.flush_data().unwrap();
+
+    let mut frames = 0;
+    loop {
+        let result = decoder.decode().unwrap();
+        while let Some(image) = decoder.next_picture() {
+            let pts = image.pts();
+            assert_eq!(pts % 3000, 0);
+            let n = (pts / 3000) as u64;
+            assert_eq!(
+                image.pts_duration(),
+                Some(Duration::from_nanos((n * 1_000_000_000 + 15) / 30))
+            );
+            assert_eq!(
+                image.pts_in(TimeBase::MICROSECONDS),
+                ((n * 1_000_000 + 15) / 30) as i64
+            );
+            assert_eq!(image.pts_in(timebase), pts);
+            frames += 1;
+        }
+        if result == DecodeResult::Done {
+            break;
+        }
+    }
+    assert!(frames > 3);
+}
+
+#[test]
+fn timebase_rescale() {
+    assert_eq!(TimeBase::new(0, 1), None);
+    assert_eq!(TimeBase::new(1, 0), None);
+    let khz90 = TimeBase::new(1, 90_000).unwrap();
+    assert_eq!(khz90.rescale(90_000, TimeBase::MICROSECONDS), 1_000_000);
+    assert_eq!(TimeBase::MICROSECONDS.rescale(1_000_000, khz90), 90_000);
+    // Rounding to the nearest tick
+    assert_eq!(khz90.rescale(1, TimeBase::MICROSECONDS), 11);
+    assert_eq!(khz90.rescale(-1, TimeBase::MICROSECONDS), -11);
+    // No overflow of intermediate values
+    let ns = TimeBase::new(1, 1_000_000_000).unwrap();
+    assert_eq!(
+        khz90.rescale(100_000_000_000_000, ns),
+        1_111_111_111_111_111_111
+    );
+    assert_eq!(khz90.to_duration(-1), None);
+    assert_eq!(khz90.to_duration(45), Some(Duration::from_micros(500)));
+}
+
+#[test]
+fn resolution_change_event() {
+    // The second stream differs only by the conformance window in SPS.
+    let mut data = std::fs::read("./data/girlshy.h265").unwrap();
+    data.extend(std::fs::read("./data/girlshy_cropped.h265").unwrap());
+
+    let mut decoder = Decoder::new().unwrap();
+    let mut events = Vec::new();
+    let mut sizes = Vec::new();
+    for chunk in data.chunks(4096) {
+        decoder.push_data(chunk, 0, 0).unwrap();
+        decode_available(&mut decoder, &mut sizes, &mut events);
+    }
+    decoder.flush_data().unwrap();
+    decode_available(&mut decoder, &mut sizes, &mut events);
+
+    assert_eq!(
+        events,
+        [StreamEvent::ResolutionChanged {
+            from: (316, 240),
+            to: (256, 200)
+        }]
+    );
+    assert_eq!(sizes.first(), Some(&(316, 240)));
+    assert_eq!(sizes.last(), Some(&(256, 200)));
+    assert!(decoder.take_events().is_empty());
+}
+
+fn decode_available(
+    decoder: &mut Decoder,
+    sizes: &mut Vec<(u32, u32)>,
+    events: &mut Vec<StreamEvent>,
+) {
+    loop {
+        let result = decoder.decode().unwrap();
+        // Peeking the picture doesn't duplicate events.
+        drop(decoder.peek_picture());
+        while let Some(image) = decoder.next_picture() {
+            sizes.push((image.width(Channel::Y), image.height(Channel::Y)));
+        }
+        events.extend(decoder.take_events());
+        if result != DecodeResult::CallAgain {
+            break;
+        }
+    }
+}
+
+#[test]
+fn stream_info_before_first_picture() {
+    let expected = |info: StreamInfo| {
+        assert_eq!((info.width, info.height), (316, 240));
+        assert_eq!(info.chroma_format, ChromaFormat::C420);
+        assert_eq!((info.bits_per_pixel, info.chroma_bits_per_pixel), (8, 8));
+        assert_eq!(info.profile_idc, 1);
+        assert_eq!(info.tier, Tier::Main);
+        assert_eq!(info.frame_rate, Some((25, 1)));
+    };
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+
+    // Complete NAL units
+    let mut decoder = Decoder::new().unwrap();
+    assert_eq!(decoder.stream_info(), None);
+    for nal in split_annex_b(&data).take(3) {
+        decoder.push_nal(nal.data, 0, 0).unwrap();
+    }
+    expected(decoder.stream_info().unwrap());
+    assert!(decoder.next_picture().is_none());
+
+    // Bytestream split into small chunks
+    let mut decoder = Decoder::new().unwrap();
+    for chunk in data.chunks(7) {
+        decoder.push_data(chunk, 0, 0).unwrap();
+        if decoder.stream_info().is_some() {
+            break;
+        }
+    }
+    expected(decoder.stream_info().unwrap());
+    assert!(decoder.next_picture().is_none());
+
+    // The info of the cropped stream
+    let data = std::fs::read("./data/girlshy_cropped.h265").unwrap();
+    decoder.push_data(&data, 0, 0).unwrap();
+    let info = decoder.stream_info().unwrap();
+    assert_eq!((info.width, info.height), (256, 200));
+}
+
+#[test]
+fn framerate_ratio_round_trip() {
+    let (mut input, _) = new_decoder().unwrap();
+    assert_eq!(input.framerate_ratio(), 100);
+    for percent in [0, 25, 50, 100] {
+        input.set_framerate_ratio(percent);
+        assert_eq!(input.framerate_ratio(), percent);
+    }
+    let ratio = input.change_framerate2(FramerateChange::Query);
+    assert_eq!(input.framerate_ratio(), ratio.as_percent());
+}
+
+#[test]
+#[cfg_attr(debug_assertions, should_panic(expected = "framerate ratio"))]
+fn framerate_ratio_is_clamped() {
+    let (mut input, _) = new_decoder().unwrap();
+    input.set_framerate_ratio(200);
+    assert_eq!(input.framerate_ratio(), 100);
+}
+
+/// Split the stream into access units.
+fn access_units(data: &[u8]) -> Vec<Vec<&[u8]>> {
+    // Every picture of the stream has a single slice, so an access unit
+    // ends with the first VCL NAL unit after non-VCL ones.
+    let mut access_units: Vec<Vec<&[u8]>> = vec![vec![]];
+    for nal in split_annex_b(data) {
+        access_units.last_mut().unwrap().push(nal.data);
+        if (nal.data[0] >> 1) & 0x3f < 32 {
+            access_units.push(vec![]);
+        }
+    }
+    access_units.retain(|nals| !nals.is_empty());
+    access_units
+}
+
+#[test]
+fn push_nals_by_access_units() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let access_units = access_units(&data);
+
+    let mut decoder = Decoder::new().unwrap();
+    let mut images_count = 0;
+    let mut nals_count = 0;
+    for (i, nals) in access_units.iter().enumerate() {
+        nals_count += decoder
+            .push_nals(nals.iter().copied(), i as i64, 0)
+            .unwrap();
+        while let DecodeResult::CallAgain | DecodeResult::OutputQueueFull =
+            decoder.decode().unwrap()
+        {
+            images_count += decoder.frames().count();
+        }
+    }
+    assert_eq!(nals_count, split_annex_b(&data).count());
+    decoder.flush_data().unwrap();
+    while decoder.decode().unwrap() != DecodeResult::Done {
+        images_count += decoder.frames().count();
+    }
+    assert_eq!(images_count, 75);
+}
+
+#[test]
+fn picture_is_available_after_one_access_unit() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let access_units = access_units(&data);
+
+    // Without the end of frame the decoder waits for the next access unit.
+    let mut decoder = Decoder::new().unwrap();
+    for nal in &access_units[0] {
+        decoder.push_nal(nal, 0, 0).unwrap();
+    }
+    assert!(!decode_until_picture(&mut decoder));
+
+    let mut decoder = Decoder::new().unwrap();
+    decoder.push_access_unit(&access_units[0], 0, 0).unwrap();
+    assert!(decode_until_picture(&mut decoder));
+    assert_eq!(decoder.next_picture().unwrap().pts(), 0);
+
+    let mut decoder = Decoder::new().unwrap();
+    let mut annexb = Vec::new();
+    for nal in &access_units[0] {
+        annexb.extend([0, 0, 1]);
+        annexb.extend_from_slice(nal);
+    }
+    decoder.push_access_unit_annexb(&annexb, 0, 0).unwrap();
+    assert!(decode_until_picture(&mut decoder));
+    assert_eq!(decoder.next_picture().unwrap().pts(), 0);
+}
+
+#[test]
+fn bytes_and_bitrate_per_temporal_layer() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let access_units = access_units(&data);
+    let mut decoder = Decoder::new().unwrap();
+    assert!(decoder.bytes_per_tid().is_empty());
+    assert_eq!(decoder.bitrate(), None);
+    assert_eq!(decoder.bitrate_window(), Duration::from_secs(1));
+    decoder.set_bitrate_window(Duration::from_secs(100));
+
+    // 25 fps
+    let mut payload_size = 0;
+    for (i, nals) in access_units.iter().enumerate() {
+        for nal in nals {
+            decoder.push_nal(nal, i as i64 * 40_000, 0).unwrap();
+            payload_size += nal.len() as u64;
+        }
+        while decoder.decode().unwrap() == DecodeResult::CallAgain {
+            decoder.frames().for_each(drop);
+        }
+    }
+
+    let bytes_per_tid = decoder.bytes_per_tid();
+    assert_eq!(
+        bytes_per_tid.iter().map(|&(_, b)| b).sum::<u64>(),
+        payload_size
+    );
+    assert!(bytes_per_tid.iter().is_sorted_by_key(|&(tid, _)| tid));
+    // All pictures are in the window.
+    let duration_ns = access_units.len() as u64 * 40_000_000;
+    assert_eq!(
+        decoder.bitrate(),
+        Some(payload_size * 8 * 1_000_000_000 / duration_ns)
+    );
+
+    // The window of 1 second contains 26 pictures.
+    decoder.set_bitrate_window(Duration::from_secs(1));
+    let last_bytes: u64 = access_units[access_units.len() - 26..]
+        .iter()
+        .flatten()
+        .map(|nal| nal.len() as u64)
+        .sum();
+    assert_eq!(
+        decoder.bitrate(),
+        Some(last_bytes * 8 * 1_000_000_000 / 1_040_000_000)
+    );
+}
+
+/// Decode without pushing more data until a picture is available.
+fn decode_until_picture(decoder: &mut Decoder) -> bool {
+    loop {
+        let result = decoder.decode().unwrap();
+        if decoder.has_pending_pictures() {
+            return true;
+        }
+        if result != DecodeResult::CallAgain {
+            return false;
+        }
+    }
+}
+
+#[test]
+fn decoder_stats() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let mut decoder = Decoder::new().unwrap();
+    assert_eq!(decoder.stats(), DecoderStats::default());
+
+    let mut images_count = 0;
+    decoder
+        .decode_from_reader(data.as_slice(), |_| {
+            images_count += 1;
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert_eq!(images_count, 75);
+    let stats = decoder.stats();
+    assert_eq!(stats.pictures_output, 75);
+    assert_eq!(stats.bytes_pushed, data.len() as u64);
+    assert_eq!(stats.nals_pushed, 0);
+    assert!(stats.decode_calls > 75);
+    assert_eq!(stats.errors, 0);
+    assert_eq!(stats.pictures_dropped, 0);
+    assert!(stats.warnings.is_empty());
+    assert_eq!(stats.resets, 0);
+
+    decoder.reset().unwrap();
+    assert_eq!(decoder.stats().resets, 1);
+    decoder.reset_stats();
+    assert_eq!(decoder.stats(), DecoderStats::default());
+
+    let mut nals_count = 0;
+    for nal in split_annex_b(&data) {
+        decoder.push_nal(nal.data, 0, 0).unwrap();
+        nals_count += 1;
+    }
+    assert_eq!(decoder.stats().nals_pushed, nals_count);
+}
+
+#[test]
+fn cancel_decoding() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let mut decoder = Decoder::new().unwrap();
+    decoder.start_worker_threads(4).unwrap();
+    for _ in 0..10 {
+        decoder.push_data(&data, 0, 0).unwrap();
+    }
+    decoder.flush_data().unwrap();
+    // Start decoding to fill the queue of worker threads.
+    for _ in 0..20 {
+        decoder.decode().unwrap();
+    }
+    assert!(!decoder.is_cancelled());
+
+    let start = std::time::Instant::now();
+    decoder.cancel().unwrap();
+    assert!(start.elapsed() < std::time::Duration::from_secs(1));
+    assert!(decoder.is_cancelled());
+    assert_eq!(decoder.number_of_input_bytes_pending(), 0);
+    // Pending work is abandoned, no more pictures are output.
+    assert!(decoder.next_picture().is_none());
+    for _ in 0..10 {
+        if let Err(err) = decoder.decode() {
+            assert_eq!(err, DeError::ErrorDecoderCancelled);
+        }
+        assert!(decoder.next_picture().is_none());
+    }
+    assert!(matches!(
+        decoder.push_data(&data, 0, 0),
+        Err(DeError::ErrorDecoderCancelled)
+    ));
+    assert!(matches!(
+        decoder.push_nal(&data[4..], 0, 0),
+        Err(DeError::ErrorDecoderCancelled)
+    ));
+    decoder.close().unwrap();
+}
+
+#[test]
+fn drop_decoder_with_pending_work() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let mut decoder = Decoder::new().unwrap();
+    decoder.start_worker_threads(4).unwrap();
+    for _ in 0..50 {
+        decoder.push_data(&data, 0, 0).unwrap();
+    }
+    // Start decoding to fill the queue of worker threads.
+    for _ in 0..20 {
+        decoder.decode().unwrap();
+    }
+
+    let start = std::time::Instant::now();
+    drop(decoder);
+    assert!(start.elapsed() < std::time::Duration::from_secs(1));
+}
+
+/// Decode the bundled file fed by chunks of the given size
+/// with help of `Decoder::run_until_eof()`.
+fn run_until_eof_by_chunks(chunk_size: usize) -> (usize, usize) {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let mut chunks = data.chunks(chunk_size);
+    let mut eof_count = 0;
+    let mut images_count = 0;
+    let mut decoder = Decoder::new().unwrap();
+    decoder
+        .run_until_eof(
+            |buf| match chunks.next() {
+                Some(chunk) => {
+                    buf.extend_from_slice(chunk);
+                    Ok(chunk.len())
+                }
+                None => {
+                    eof_count += 1;
+                    Ok(0)
+                }
+            },
+            |image| {
+                assert_eq!(image.width(Channel::Y), 316);
+                images_count += 1;
+            },
+        )
+        .unwrap();
+    assert_eq!(decoder.stats().bytes_pushed, data.len() as u64);
+    (images_count, eof_count)
+}
+
+#[test]
+fn run_until_eof() {
+    assert_eq!(run_until_eof_by_chunks(4096), (75, 1));
+    // Every NAL unit is reassembled from single bytes.
+    assert_eq!(run_until_eof_by_chunks(1), (75, 1));
+}
+
+#[test]
+fn run_until_eof_returns_input_errors() {
+    let mut decoder = Decoder::new().unwrap();
+    let mut calls = 0;
+    let result = decoder.run_until_eof(
+        |_| {
+            calls += 1;
+            match calls {
+                1 => Err(std::io::ErrorKind::Interrupted.into()),
+                _ => Err(std::io::Error::other("connection lost")),
+            }
+        },
+        |_| {},
+    );
+    assert!(matches!(result, Err(StreamError::Io(_))));
+    assert_eq!(calls, 2);
+}
+
+#[test]
+fn recv_picture_timeout() {
+    let (input, output) = new_decoder().unwrap();
+    let start = std::time::Instant::now();
+    let timeout = std::time::Duration::from_millis(50);
+    assert!(matches!(
+        output.recv_picture(Some(timeout)),
+        Err(DeError::ErrorTimedOut)
+    ));
+    assert!(start.elapsed() >= timeout);
+
+    // The end of the stream is reached when the input half is dropped.
+    drop(input);
+    assert!(output.recv_picture(None).unwrap().is_none());
+}
+
+#[test]
+fn recv_pictures_fed_from_another_thread() {
+    let (mut input, output) = new_decoder().unwrap();
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let feeder = std::thread::spawn(move || {
+        let mut chunks = data.chunks(1024);
+        loop {
+            match input.decode().unwrap() {
+                DecodeResult::Done => break,
+                DecodeResult::CallAgain => {}
+                DecodeResult::OutputQueueFull => std::thread::yield_now(),
+                DecodeResult::NeedsMoreInput => match chunks.next() {
+                    Some(chunk) => input.push_data(chunk, 0, 0).unwrap(),
+                    None => input.flush_data().unwrap(),
+                },
+            }
+        }
+        // Keep the input half alive to check the end of stream after flushing.
+        input
+    });
+
+    let mut images_count = 0;
+    while let Some(image) = output.recv_picture(None).unwrap() {
+        assert_eq!(image.width(Channel::Y), 316);
+        images_count += 1;
+    }
+    assert_eq!(images_count, 75);
+    let input = feeder.join().unwrap();
+    assert!(input.is_flushed());
+}
+
+const HASH_TYPES: [PictureHashType; 3] = [
+    PictureHashType::Md5,
+    PictureHashType::Crc,
+    PictureHashType::Checksum,
+];
+
+/// Build suffix SEI NAL unit with decoded picture hash message.
+fn picture_hash_sei(hashes: &[PlaneHash]) -> Vec<u8> {
+    let mut payload = vec![match hashes[0].hash_type() {
+        PictureHashType::Md5 => 0,
+        PictureHashType::Crc => 1,
+        PictureHashType::Checksum => 2,
+    }];
+    for hash in hashes {
+        match hash {
+            PlaneHash::Md5(digest) => payload.extend_from_slice(digest),
+            PlaneHash::Crc(crc) => payload.extend_from_slice(&crc.to_be_bytes()),
+            PlaneHash::Checksum(sum) => payload.extend_from_slice(&sum.to_be_bytes()),
+        }
+    }
+    let mut rbsp = vec![132, payload.len() as u8];
+    rbsp.extend(payload);
+    rbsp.push(0x80);
+    // Suffix SEI NAL header with temporal ID 0.
+    let mut nal = vec![40 << 1, 1];
+    let mut zeros = 0;
+    for byte in rbsp {
+        if zeros >= 2 && byte <= 3 {
+            nal.push(3);
+            zeros = 0;
+        }
+        zeros = if byte == 0 { zeros + 1 } else { 0 };
+        nal.push(byte);
+    }
+    nal
+}
+
+/// Decode access units with PTS equal to their indexes
+/// and call the callback for every picture.
+fn decode_access_units<F>(decoder: &mut Decoder, access_units: &[Vec<Vec<u8>>], mut on_image: F)
+where
+    F: FnMut(&Image),
+{
+    let mut decode = |decoder: &mut Decoder| loop {
+        // Mismatched hashes are reported as errors.
+        let result = decoder.decode().unwrap_or(DecodeResult::CallAgain);
+        while let Some(image) = decoder.next_picture() {
+            on_image(&image);
+        }
+        if result != DecodeResult::CallAgain && !decoder.has_pending_pictures() {
+            break result;
+        }
+    };
+    for (i, nals) in access_units.iter().enumerate() {
+        let nals: Vec<&[u8]> = nals.iter().map(|nal| nal.as_slice()).collect();
+        decoder.push_access_unit(&nals, i as i64, 0).unwrap();
+        decode(decoder);
+    }
+    decoder.flush_data().unwrap();
+    while decode(decoder) != DecodeResult::Done {}
+}
+
+#[test]
+fn picture_hash_check() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let mut access_units: Vec<Vec<Vec<u8>>> = access_units(&data)
+        .into_iter()
+        .map(|nals| nals.into_iter().map(|nal| nal.to_vec()).collect())
+        .collect();
+
+    // The stream has no hash SEI, compute hashes of decoded pictures.
+    let mut decoder = Decoder::new().unwrap();
+    let mut hashes = BTreeMap::new();
+    decode_access_units(&mut decoder, &access_units, |image| {
+        assert!(image.hash_check().is_none());
+        let hash_type = HASH_TYPES[image.pts() as usize % HASH_TYPES.len()];
+        let planes: Vec<PlaneHash> = [Channel::Y, Channel::Cb, Channel::Cr]
+            .into_iter()
+            .map(|channel| image.plane_hash(channel, hash_type).unwrap())
+            .collect();
+        hashes.insert(image.pts(), planes);
+    });
+    assert_eq!(hashes.len(), access_units.len());
+
+    let decode_with_sei = |access_units: &[Vec<Vec<u8>>]| {
+        let mut decoder = Decoder::new().unwrap();
+        decoder
+            .input_mut()
+            .set_parameter_bool(ParamBool::SeiCheckHash, true);
+        let mut checks = BTreeMap::new();
+        decode_access_units(&mut decoder, access_units, |image| {
+            checks.insert(image.pts(), image.hash_check().unwrap());
+        });
+        (checks, decoder.stats().errors)
+    };
+
+    // Hashes computed by the wrapper match hashes checked by the decoder.
+    for (i, nals) in access_units.iter_mut().enumerate() {
+        nals.push(picture_hash_sei(&hashes[&(i as i64)]));
+    }
+    let (checks, errors) = decode_with_sei(&access_units);
+    assert_eq!(errors, 0);
+    assert_eq!(checks.len(), access_units.len());
+    for check in checks.values() {
+        assert!(check.is_ok());
+        assert_eq!(check.planes.len(), 3);
+    }
+
+    // Corrupt the expected hash of the Cb plane of one picture.
+    let mut corrupted = hashes[&3].clone();
+    corrupted[1] = PlaneHash::Md5([0; 16]);
+    *access_units[3].last_mut().unwrap() = picture_hash_sei(&corrupted);
+    let (checks, errors) = decode_with_sei(&access_units);
+    assert!(errors > 0);
+    for (&pts, check) in &checks {
+        if pts == 3 {
+            assert_eq!(check.failed_planes(), [Channel::Cb]);
+            assert_eq!(check.planes[1].expected, PlaneHash::Md5([0; 16]));
+            assert_eq!(check.planes[1].computed, hashes[&3][1]);
+        } else {
+            assert!(check.is_ok());
+        }
+    }
+}
+
+#[test]
+fn picture_hash_sei_contents() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let mut access_units: Vec<Vec<Vec<u8>>> = access_units(&data)
+        .into_iter()
+        .map(|nals| nals.into_iter().map(|nal| nal.to_vec()).collect())
+        .collect();
+
+    let mut decoder = Decoder::new().unwrap();
+    let mut hashes = BTreeMap::new();
+    decode_access_units(&mut decoder, &access_units, |image| {
+        assert_eq!(image.picture_hash_sei(), None);
+        let planes: Vec<PlaneHash> = [Channel::Y, Channel::Cb, Channel::Cr]
+            .into_iter()
+            .map(|channel| PlaneHash::Md5(image.compute_plane_md5(channel).unwrap()))
+            .collect();
+        hashes.insert(image.pts(), planes);
+    });
+    for (i, nals) in access_units.iter_mut().enumerate() {
+        nals.push(picture_hash_sei(&hashes[&(i as i64)]));
+    }
+
+    // The decoder verifies pictures by the SEI messages too.
+    let mut decoder = Decoder::new().unwrap();
+    decoder
+        .input_mut()
+        .set_parameter_bool(ParamBool::SeiCheckHash, true);
+    let mut pictures = 0;
+    decode_access_units(&mut decoder, &access_units, |image| {
+        let sei = image.picture_hash_sei().unwrap();
+        assert_eq!(sei.hash_type, PictureHashType::Md5);
+        assert_eq!(sei.planes.len(), 3);
+        for (channel, expected) in [Channel::Y, Channel::Cb, Channel::Cr]
+            .into_iter()
+            .zip(&sei.planes)
+        {
+            let computed = PlaneHash::Md5(image.compute_plane_md5(channel).unwrap());
+            assert_eq!(&computed, expected);
+        }
+        pictures += 1;
+    });
+    assert_eq!(pictures, access_units.len());
+    assert_eq!(decoder.stats().errors, 0);
+}
+
+/// Read unsigned Exp-Golomb code.
+fn read_ue(bits: &[bool], pos: &mut usize) -> u32 {
+    let leading_zeros = bits[*pos..].iter().take_while(|&&bit| !bit).count();
+    let code = &bits[*pos + leading_zeros..=*pos + 2 * leading_zeros];
+    *pos += 2 * leading_zeros + 1;
+    code.iter().fold(0, |value, &bit| value << 1 | bit as u32) - 1
+}
+
+/// Write unsigned Exp-Golomb code.
+fn write_ue(bits: &mut Vec<bool>, value: u32) {
+    let value = value + 1;
+    let len = 32 - value.leading_zeros();
+    bits.extend(std::iter::repeat_n(false, len as usize - 1));
+    bits.extend((0..len).rev().map(|i| value >> i & 1 == 1));
+}
+
+/// Rewrite the picture size and the conformance window of SPS
+/// with `sps_max_sub_layers_minus1 == 0` and 4:2:0 chroma format.
+/// Offsets of the window are in chroma samples.
+fn rewrite_sps_size(sps: &[u8], width: u32, height: u32, window: [u32; 4]) -> Vec<u8> {
+    let mut rbsp = Vec::new();
+    let mut zeros = 0;
+    for &byte in &sps[2..] {
+        if zeros >= 2 && byte == 3 {
+            zeros = 0;
+            continue;
+        }
+        zeros = if byte == 0 { zeros + 1 } else { 0 };
+        rbsp.push(byte);
+    }
+    let bits: Vec<bool> = rbsp
+        .iter()
+        .flat_map(|byte| (0..8).rev().map(move |i| byte >> i & 1 == 1))
+        .collect();
+
+    // VPS ID and sub layers take 8 bits, profile, tier and level - 96 bits.
+    let mut out = bits[..104].to_vec();
+    let mut pos = 104;
+    let sps_id = read_ue(&bits, &mut pos);
+    assert_eq!(read_ue(&bits, &mut pos), 1);
+    read_ue(&bits, &mut pos);
+    read_ue(&bits, &mut pos);
+    pos += 1;
+    if bits[pos - 1] {
+        for _ in 0..4 {
+            read_ue(&bits, &mut pos);
+        }
+    }
+    write_ue(&mut out, sps_id);
+    write_ue(&mut out, 1);
+    write_ue(&mut out, width);
+    write_ue(&mut out, height);
+    out.push(true);
+    for offset in window {
+        write_ue(&mut out, offset);
+    }
+    // The rest of SPS with new trailing bits.
+    let end = bits.iter().rposition(|&bit| bit).unwrap();
+    out.extend(&bits[pos..end]);
+    out.push(true);
+    out.resize(out.len().next_multiple_of(8), false);
+
+    let mut nal = sps[..2].to_vec();
+    let mut zeros = 0;
+    for byte in out.chunks(8) {
+        let byte = byte.iter().fold(0u8, |byte, &bit| byte << 1 | bit as u8);
+        if zeros >= 2 && byte <= 3 {
+            nal.push(3);
+            zeros = 0;
+        }
+        zeros = if byte == 0 { zeros + 1 } else { 0 };
+        nal.push(byte);
+    }
+    nal
+}
+
+#[test]
+fn plane_hash_doesnt_read_outside_of_plane() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let nals: Vec<&[u8]> = split_annex_b(&data).map(|nal| nal.data).collect();
+    let sps = nals
+        .iter()
+        .find(|nal| nal[0] >> 1 == NalUnitType::SpsNut.to_u8())
+        .unwrap();
+    // 316x240 pictures coded as 320x256 with 16 rows above the window.
+    let other_sps = rewrite_sps_size(sps, 320, 256, [0, 2, 8, 0]);
+
+    let (mut input, output) = new_decoder().unwrap();
+    for nal in &nals {
+        input.push_nal(nal, 0, 0).unwrap();
+    }
+    input.flush_data().unwrap();
+    while !output.has_pending_pictures() {
+        input.decode().unwrap();
+    }
+    let image = output.next_picture().unwrap();
+    let hashes = |image: &Image<'_>| -> Vec<Option<PlaneHash>> {
+        [Channel::Y, Channel::Cb, Channel::Cr]
+            .into_iter()
+            .map(|channel| image.plane_hash(channel, PictureHashType::Md5))
+            .collect()
+    };
+    let expected = hashes(&image);
+    assert!(expected.iter().all(Option::is_some));
+
+    // The decoded plane of pictures with SPS of the same display size
+    // starts above the plane of the image and can't be accessed.
+    input.push_nal(&other_sps, 0, 0).unwrap();
+    let info = input.stream_info().unwrap();
+    assert_eq!((info.width, info.height), (316, 240));
+    assert_eq!(hashes(&image), [None; 3]);
+}
+
+/// Decode the stream and return decoding issues of all pictures.
+fn decode_issues(data: &[u8]) -> (Vec<Vec<DeError>>, Vec<DeError>) {
+    let mut decoder = Decoder::new().unwrap();
+    decoder.push_data(data, 0, 0).unwrap();
+    decoder.flush_data().unwrap();
+    let mut issues = Vec::new();
+    loop {
+        let result = decoder.decode();
+        for image in decoder.frames() {
+            assert_eq!(image.decode_was_clean(), image.decode_issues().is_empty());
+            issues.push(image.decode_issues().to_vec());
+        }
+        if let Ok(DecodeResult::Done) = result {
+            break;
+        }
+    }
+    // Warnings attributed to pictures are still returned by the decoder.
+    let warnings = decoder.input_mut().warnings().collect();
+    (issues, warnings)
+}
+
+#[test]
+fn decode_issues_of_truncated_stream() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let (issues, warnings) = decode_issues(&data);
+    assert_eq!(issues.len(), 75);
+    assert!(issues.iter().all(|issues| issues.is_empty()));
+    assert!(warnings.is_empty());
+
+    // Cut the slice data of the last picture in half.
+    let last_nal = split_annex_b(&data).last().unwrap();
+    let end = last_nal.offset as usize + last_nal.data.len() / 2;
+    let (issues, warnings) = decode_issues(&data[..end]);
+    assert_eq!(issues.len(), 75);
+    assert!(!warnings.is_empty());
+    // Because of reordering, issues are attributed to one
+    // of the last pictures that were not output yet.
+    let (earlier, last) = issues.split_at(issues.len() - 3);
+    assert!(earlier.iter().all(|issues| issues.is_empty()));
+    let flagged: Vec<&Vec<DeError>> = last.iter().filter(|issues| !issues.is_empty()).collect();
+    assert_eq!(flagged, [&warnings]);
+}
+
+#[test]
+fn strict_input_rejects_wrong_formats() {
+    let mp4 = b"\x00\x00\x00\x18ftypisom\x00\x00\x02\x00isomiso2\x00\x00\x00\x08free";
+    let h264 = [
+        0, 0, 0, 1, 0x09, 0xf0, // AUD
+        0, 0, 0, 1, 0x67, 0x42, 0xc0, 0x1e, 0xd9, 0x00, 0xa0, 0x47, 0xfe, 0xc8, // SPS
+        0, 0, 0, 1, 0x68, 0xce, 0x3c, 0x80, // PPS
+        0, 0, 1, 0x65, 0x88, 0x84, 0x00, 0x33, 0xff, // IDR slice
+    ];
+    let mut decoder = Decoder::new().unwrap();
+    assert!(!decoder.is_strict_input());
+    decoder.push_data(mp4, 0, 0).unwrap();
+    decoder.push_data(&h264, 0, 0).unwrap();
+
+    for (data, kind) in [(&mp4[..], InputKind::Mp4), (&h264[..], InputKind::H264)] {
+        let mut decoder = Decoder::new().unwrap();
+        decoder.set_strict_input(true);
+        assert!(decoder.is_strict_input());
+        // Data is inspected across chunks.
+        let (first, second) = data.split_at(7);
+        decoder.push_data(first, 0, 0).unwrap();
+        assert_eq!(
+            decoder.push_data(second, 0, 0),
+            Err(DeError::ErrorInvalidInputFormat(kind))
+        );
+        // The error is returned until resetting.
+        assert_eq!(
+            decoder.push_data(&[0, 0, 1], 0, 0),
+            Err(DeError::ErrorInvalidInputFormat(kind))
+        );
+        decoder.reset().unwrap();
+        decoder.push_data(&[0, 0, 1], 0, 0).unwrap();
+    }
+}
+
+#[test]
+fn strict_input_accepts_h265_stream() {
+    for path in ["./data/girlshy.h265", "./data/girlshy_cropped.h265"] {
+        let data = std::fs::read(path).unwrap();
+        let mut decoder = Decoder::new().unwrap();
+        decoder.set_strict_input(true);
+        let mut images_count = 0;
+        for chunk in data.chunks(5) {
+            decoder.push_data(chunk, 0, 0).unwrap();
+        }
+        decoder.flush_data().unwrap();
+        loop {
+            let result = decoder.decode().unwrap();
+            images_count += decoder.frames().count();
+            if result == DecodeResult::Done {
+                break;
+            }
+        }
+        assert_eq!(images_count, 75);
+    }
+}
+
+#[test]
+fn change_framerate_by_temporal_layers() {
+    // Non-reference pictures of the stream are in the temporal layer 1.
+    let data = std::fs::read("./data/girlshy_temporal.h265").unwrap();
+    let access_units = access_units(&data);
+    let mut decoder = Decoder::new().unwrap();
+    assert_eq!(
+        decoder
+            .input_mut()
+            .change_framerate2(FramerateChange::Query),
+        decoder.input_mut().change_framerate2(FramerateChange::Less),
+    );
+
+    // Activate SPS by decoding the first picture.
+    decoder.push_access_unit(&access_units[0], 0, 0).unwrap();
+    assert!(decode_until_picture(&mut decoder));
+    assert_eq!(decoder.input().highest_tid(), 1);
+    let mut images_count = decoder.frames().count();
+
+    let input = decoder.input_mut();
+    let full = input.change_framerate2(FramerateChange::Query);
+    assert!(full.is_full());
+    let mut ratios = vec![full];
+    for _ in 0..2 {
+        ratios.push(input.change_framerate2(FramerateChange::Less));
+    }
+    assert!(ratios.is_sorted_by(|a, b| a >= b));
+    assert!(ratios[1] < full);
+    assert_eq!(ratios[1], ratios[2]);
+    assert_eq!(input.framerate_ratio(), ratios[2].as_percent());
+    let reduced = ratios[2];
+
+    let mut ratios = vec![reduced];
+    for _ in 0..2 {
+        ratios.push(input.change_framerate2(FramerateChange::More));
+    }
+    assert!(ratios.is_sorted());
+    assert!(ratios[2].is_full());
+
+    // Decode the rest of the stream without the temporal layer 1.
+    assert_eq!(
+        decoder.input_mut().change_framerate2(FramerateChange::Less),
+        reduced
+    );
+    for (i, nals) in access_units.iter().enumerate().skip(1) {
+        decoder.push_access_unit(nals, i as i64, 0).unwrap();
+        while decode_until_picture(&mut decoder) {
+            images_count += decoder.frames().count();
+        }
+    }
+    decoder.flush_data().unwrap();
+    loop {
+        let result = decoder.decode().unwrap();
+        images_count += decoder.frames().count();
+        if result == DecodeResult::Done {
+            break;
+        }
+    }
+    assert!(images_count > 1);
+    assert!(images_count < access_units.len());
+}
+
+#[test]
+fn stream_with_temporal_layers_is_decoded_at_full_framerate() {
+    let checksums = |path: &str| {
+        let data = std::fs::read(path).unwrap();
+        let mut decoder = Decoder::new().unwrap();
+        let mut checksums = Vec::new();
+        decoder
+            .decode_from_reader(data.as_slice(), |image| {
+                let (plane, _) = image.plane(Channel::Y);
+                checksums.push(plane.iter().map(|&v| v as u64).sum::<u64>());
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+        checksums
+    };
+    let expected = checksums("./data/girlshy.h265");
+    assert_eq!(expected.len(), 75);
+    assert_eq!(checksums("./data/girlshy_temporal.h265"), expected);
+}
+
+#[test]
+fn realtime_target_adjusts_framerate_ratio() {
+    let mut decoder = Decoder::new().unwrap();
+    assert_eq!(decoder.stats().realtime_framerate_ratio, None);
+    decoder.set_realtime_target(Some(Duration::from_millis(10)));
+    decoder.set_realtime_floor(70);
+    assert_eq!(decoder.realtime_target(), Some(Duration::from_millis(10)));
+    assert_eq!(decoder.stats().realtime_framerate_ratio, Some(100));
+
+    let mut file = File::open("./data/girlshy.h265").unwrap();
+    let mut buf = vec![0; 1024];
+    let mut feed = |input: &mut DecoderInput| match file.read(&mut buf).unwrap() {
+        0 => Ok(false), // EOF
+        size => input.push_data(&buf[0..size], 0, 0).map(|_| true),
+    };
+
+    let mut images_count = 0;
+    let mut min_ratio = 100;
+    loop {
+        let Some(image) = decoder.decode_next_picture(&mut feed).unwrap() else {
+            break;
+        };
+        drop(image);
+        images_count += 1;
+        // Simulate a slow consumer of the first pictures.
+        if images_count <= 25 {
+            std::thread::sleep(Duration::from_millis(30));
+        }
+        let ratio = decoder.stats().realtime_framerate_ratio.unwrap();
+        assert_eq!(decoder.input().framerate_ratio(), ratio);
+        min_ratio = min_ratio.min(ratio);
+    }
+    assert_eq!(images_count, 75);
+    // The ratio is lowered down to the floor...
+    assert_eq!(min_ratio, 70);
+    // ... and recovers when the consumer catches up.
+    let stats = decoder.stats();
+    assert_eq!(stats.realtime_framerate_ratio, Some(100));
+    assert!(stats.realtime_adjustments >= 6);
+
+    decoder.set_realtime_target(None);
+    assert_eq!(decoder.stats().realtime_framerate_ratio, None);
+    assert_eq!(decoder.input().framerate_ratio(), 100);
+}
+
+#[test]
+fn decode_latency_of_pictures() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let mut decoder = Decoder::new().unwrap();
+    assert_eq!(decoder.stats().decode_latency, None);
+    let mut frame_index = 0;
+    for nal in split_annex_b(&data) {
+        // All pictures have the same PTS.
+        decoder.push_nal(nal.data, 0, frame_index).unwrap();
+        // first_slice_segment_in_pic_flag of VCL NAL unit
+        if (nal.data[0] >> 1) < 32 && nal.data[2] & 0x80 != 0 {
+            frame_index += 1;
+        }
+    }
+    decoder.flush_data().unwrap();
+    std::thread::sleep(Duration::from_millis(10));
+
+    let mut frame_indexes = BTreeSet::new();
+    let mut max_latency = Duration::ZERO;
+    loop {
+        let result = decoder.decode().unwrap();
+        for image in decoder.frames() {
+            // Each picture is identified by its user data.
+            assert!(frame_indexes.insert(image.user_data()));
+            let latency = image.decode_latency().unwrap();
+            assert!(latency >= Duration::from_millis(10));
+            max_latency = max_latency.max(latency);
+        }
+        if result == DecodeResult::Done {
+            break;
+        }
+    }
+    assert_eq!(frame_indexes, (0..75).collect());
+
+    let latency = decoder.stats().decode_latency.unwrap();
+    assert_eq!(latency.samples, 75);
+    assert!(latency.p50 >= Duration::from_millis(10));
+    assert!(latency.p50 <= latency.p90);
+    assert!(latency.p90 <= latency.p99);
+    assert_eq!(latency.max, max_latency);
+
+    decoder.reset_stats();
+    assert_eq!(decoder.stats().decode_latency, None);
+}
+
+#[test]
+fn decode_another_stream_after_reset() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let mut decoder = Decoder::new().unwrap();
+    for _ in 0..2 {
+        let mut images_count = 0;
+        decoder
+            .decode_from_reader(data.as_slice(), |_| {
+                images_count += 1;
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+        assert_eq!(images_count, 75);
+        assert!(decoder.is_finished());
+        decoder.reset().unwrap();
+    }
+}
+
+/// Push access units NAL by NAL and decode them one by one.
+/// Returns numbers of pictures output after each access unit
+/// and user data of output pictures.
+fn decode_access_units_separately(
+    decoder: &mut Decoder,
+    units: &[Vec<&[u8]>],
+    pts: impl Fn(usize) -> i64,
+) -> (Vec<usize>, Vec<usize>) {
+    let mut output = Vec::new();
+    let mut output_counts = Vec::new();
+    for (index, unit) in units.iter().enumerate() {
+        for nal in unit {
+            decoder.push_nal(nal, pts(index), index).unwrap();
+        }
+        loop {
+            let result = decoder.decode().unwrap();
+            output.extend(decoder.frames().map(|image| image.user_data()));
+            match result {
+                DecodeResult::CallAgain | DecodeResult::OutputQueueFull => {}
+                DecodeResult::NeedsMoreInput => break,
+                DecodeResult::Done => panic!("the stream isn't flushed"),
+            }
+        }
+        output_counts.push(output.len());
+    }
+    decoder.flush_data().unwrap();
+    while decoder.decode().unwrap() != DecodeResult::Done {
+        output.extend(decoder.frames().map(|image| image.user_data()));
+    }
+    output.extend(decoder.frames().map(|image| image.user_data()));
+    (output_counts, output)
+}
+
+#[test]
+fn low_delay_outputs_picture_after_each_access_unit() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let units = access_units(&data);
+    assert_eq!(units.len(), 75);
+
+    // Pictures are output in presentation order by default.
+    let mut decoder = Decoder::new().unwrap();
+    assert!(!decoder.is_low_delay());
+    let (output_counts, output) = decode_access_units_separately(&mut decoder, &units, |_| 0);
+    assert_eq!(output.len(), 75);
+    assert!(output_counts.iter().zip(1..).any(|(&count, n)| count < n));
+    // PTS of access units in presentation order.
+    let mut presentation_pts = vec![0; units.len()];
+    for (pts, &index) in output.iter().enumerate() {
+        presentation_pts[index] = pts as i64;
+    }
+    let mut decoder = Decoder::new().unwrap();
+    let (_, output) = decode_access_units_separately(&mut decoder, &units, |i| presentation_pts[i]);
+    assert_eq!(output.len(), 75);
+    assert!(!decoder.appears_reordered());
+
+    // In the low-delay mode, a picture is output after each access unit,
+    // so pictures are output in decoding order.
+    let mut decoder = Decoder::new().unwrap();
+    decoder.set_low_delay(true);
+    let (output_counts, output) =
+        decode_access_units_separately(&mut decoder, &units, |i| presentation_pts[i]);
+    assert_eq!(output_counts, (1..=75).collect::<Vec<_>>());
+    assert_eq!(output, (0..75).collect::<Vec<_>>());
+    // The stream is reordered, so the low-delay mode is misused.
+    let reordered = presentation_pts.windows(2).any(|w| w[1] < w[0]);
+    assert!(reordered);
+    assert!(decoder.appears_reordered());
+
+    decoder.reset().unwrap();
+    assert!(!decoder.appears_reordered());
+}
+
+#[test]
+fn memory_usage_of_decoder() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let mut decoder = Decoder::new().unwrap();
+    assert_eq!(decoder.memory_usage(), MemoryUsage::default());
+
+    decoder.push_data(&data, 0, 0).unwrap();
+    decoder.flush_data().unwrap();
+    let usage = decoder.memory_usage();
+    // Start codes are not counted.
+    assert!(usage.input_bytes > 0 && usage.input_bytes <= data.len());
+    let info = decoder.stream_info().unwrap();
+    assert!(info.max_dec_pic_buffering > 0);
+    assert_eq!(usage.output_pictures, info.max_dec_pic_buffering as usize);
+    // Coded pictures are 320x240, 4:2:0 with 8 bits per pixel.
+    let coded_picture_bytes = 320 * 240 * 3 / 2;
+    assert!(usage.estimated_picture_bytes <= coded_picture_bytes);
+    assert!(usage.estimated_picture_bytes > coded_picture_bytes * 95 / 100);
+    assert_eq!(
+        usage.total_estimate,
+        usage.input_bytes + usage.output_pictures * usage.estimated_picture_bytes
+    );
+
+    while decoder
+        .decode_next_picture(|_| Ok(false))
+        .unwrap()
+        .is_some()
+    {}
+    let usage = decoder.memory_usage();
+    assert_eq!(usage.input_bytes, 0);
+    assert_eq!(
+        usage.total_estimate,
+        usage.output_pictures * usage.estimated_picture_bytes
+    );
+}
+
+#[test]
+fn planes_with_high_bit_depth() {
+    let data = std::fs::read("./data/gradient_10bit.h265").unwrap();
+    let mut decoder = Decoder::new().unwrap();
+    decoder.push_data(&data, 0, 0).unwrap();
+    decoder.flush_data().unwrap();
+    let mut count = 0;
+    while let Some(image) = decoder.decode_next_picture(|_| Ok(false)).unwrap() {
+        for channel in [Channel::Y, Channel::Cb, Channel::Cr] {
+            assert_eq!(image.bits_per_pixel(channel), 10);
+            let (samples, stride) = image.plane_u16(channel).unwrap();
+            let width = image.width(channel) as usize;
+            let height = image.height(channel) as usize;
+            assert!(stride >= width);
+            assert!(samples.len() >= stride * height);
+
+            // The u8 variant returns raw bytes of the same samples.
+            let (bytes, bytes_stride) = image.plane(channel);
+            assert_eq!(bytes_stride, stride * 2);
+            assert_eq!(bytes.len(), samples.len() * 2);
+            let first_sample = u16::from_ne_bytes([bytes[0], bytes[1]]);
+            assert_eq!(first_sample, samples[0]);
+
+            let max_sample = samples
+                .chunks(stride)
+                .flat_map(|row| &row[..width])
+                .copied()
+                .max()
+                .unwrap();
+            assert!(max_sample > 255);
+            assert!(max_sample < 1024);
+        }
+        count += 1;
+    }
+    assert_eq!(count, 3);
+
+    // Samples with 8 bits per pixel are not available as u16.
+    let mut decoder = Decoder::new().unwrap();
+    let file = File::open("./data/girlshy.h265").unwrap();
+    decoder
+        .decode_from_reader(file, |image| {
+            assert_eq!(image.bits_per_pixel(Channel::Y), 8);
+            assert!(image.plane_u16(Channel::Y).is_none());
+            assert!(image.plane_u16(Channel::Cb).is_none());
+            ControlFlow::Break(())
+        })
+        .unwrap();
+}
@@ -0,0 +1,46 @@
+#![cfg(feature = "image")]
+
+use image::{DynamicImage, GenericImageView};
+
+mod common;
+use common::for_first_image;
+
+#[test]
+fn save_first_frame_as_png() {
+    let path = std::env::temp_dir().join("libde265_rs_girlshy_frame.png");
+    for_first_image("./data/girlshy.h265", |image| {
+        let dynamic_image = image.to_dynamic_image();
+        assert!(matches!(dynamic_image, DynamicImage::ImageRgb8(_)));
+        dynamic_image.save(&path).unwrap();
+    });
+    let loaded = image::open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.dimensions(), (316, 240));
+    assert_eq!(loaded.color(), image::ColorType::Rgb8);
+}
+
+#[test]
+fn colors_match_rgb8() {
+    for_first_image("./data/girlshy.h265", |image| {
+        let rgb = image.to_rgb8();
+        let dynamic_image = DynamicImage::from(image);
+        assert_eq!(dynamic_image.as_bytes(), rgb.data.as_slice());
+
+        let owned = image.to_owned();
+        let dynamic_image = DynamicImage::from(&owned);
+        assert_eq!(dynamic_image.as_bytes(), rgb.data.as_slice());
+    });
+}
+
+#[test]
+fn high_bit_depth_into_rgb16() {
+    for_first_image("./data/gradient_10bit.h265", |image| {
+        let rgb = image.to_rgb16();
+        let dynamic_image = image.to_dynamic_image();
+        let DynamicImage::ImageRgb16(buffer) = dynamic_image else {
+            panic!("unexpected color type");
+        };
+        assert_eq!(buffer.dimensions(), (64, 64));
+        assert_eq!(buffer.into_raw(), rgb.data);
+    });
+}
@@ -0,0 +1,247 @@
+use libde265_rs::*;
+
+const NAL_VPS: u8 = 32;
+const NAL_SPS: u8 = 33;
+const NAL_PPS: u8 = 34;
+
+fn nal_unit_type(nal: &[u8]) -> u8 {
+    (nal[0] >> 1) & 0x3f
+}
+
+/// Build HEVC decoder configuration record with given NAL units.
+fn build_hvcc(nal_units: &[&[u8]]) -> Vec<u8> {
+    let mut data = Vec::new();
+    // configuration_version
+    data.push(1);
+    // profile_space = 0, tier_flag = 1, profile_idc = 1
+    data.push(0x21);
+    // profile_compatibility_flags
+    data.extend_from_slice(&0x6000_0000u32.to_be_bytes());
+    // constraint_indicator_flags
+    data.extend_from_slice(&[0x90, 0, 0, 0, 0, 0]);
+    // level_idc
+    data.push(93);
+    // min_spatial_segmentation_idc, parallelism_type, chroma_format_idc = 1,
+    // bit_depth_luma_minus8, bit_depth_chroma_minus8
+    data.extend_from_slice(&[0xf0, 0, 0xfc, 0xfd, 0xf8, 0xf8]);
+    // avg_frame_rate
+    data.extend_from_slice(&[0, 0]);
+    // num_temporal_layers = 1, temporal_id_nested = 1, length_size_minus_one = 3
+    data.push(0x0f);
+    // num_of_arrays
+    data.push(nal_units.len() as u8);
+    for nal in nal_units {
+        data.push(0x80 | nal_unit_type(nal));
+        data.extend_from_slice(&1u16.to_be_bytes());
+        data.extend_from_slice(&(nal.len() as u16).to_be_bytes());
+        data.extend_from_slice(nal);
+    }
+    data
+}
+
+fn parameter_sets(stream: &[u8]) -> Vec<&[u8]> {
+    [NAL_VPS, NAL_SPS, NAL_PPS]
+        .into_iter()
+        .map(|nal_type| {
+            split_annex_b(stream)
+                .find(|nal| nal_unit_type(nal.data) == nal_type)
+                .unwrap()
+                .data
+        })
+        .collect()
+}
+
+#[test]
+fn parse_hvcc_config() {
+    let stream = std::fs::read("./data/girlshy.h265").unwrap();
+    let nal_units = parameter_sets(&stream);
+    let config = HvccConfig::parse(&build_hvcc(&nal_units)).unwrap();
+
+    assert_eq!(config.configuration_version, 1);
+    assert_eq!(config.general_profile_space, 0);
+    assert!(config.general_tier_flag);
+    assert_eq!(config.general_profile_idc, 1);
+    assert_eq!(config.general_profile_compatibility_flags, 0x6000_0000);
+    assert_eq!(config.general_constraint_indicator_flags, 0x9000_0000_0000);
+    assert_eq!(config.general_level_idc, 93);
+    assert_eq!(config.chroma_format_idc, 1);
+    assert_eq!(config.bit_depth_luma, 8);
+    assert_eq!(config.bit_depth_chroma, 8);
+    assert_eq!(config.num_temporal_layers, 1);
+    assert!(config.temporal_id_nested);
+    assert_eq!(config.nal_length_size, 4);
+    assert_eq!(config.arrays.len(), 3);
+    assert_eq!(config.arrays[1].nal_unit_type, NAL_SPS);
+    assert!(config.arrays[1].array_completeness);
+    assert_eq!(config.nal_units().collect::<Vec<_>>(), nal_units);
+}
+
+#[test]
+fn decode_with_hvcc_config() {
+    let stream = std::fs::read("./data/girlshy.h265").unwrap();
+    let config = HvccConfig::parse(&build_hvcc(&parameter_sets(&stream))).unwrap();
+
+    let mut decoder = Decoder::new().unwrap();
+    decoder.input_mut().push_hvcc_config(&config).unwrap();
+    for nal in split_annex_b(&stream) {
+        if ![NAL_VPS, NAL_SPS, NAL_PPS].contains(&nal_unit_type(nal.data)) {
+            decoder.push_nal(nal.data, 0, 0).unwrap();
+        }
+    }
+    decoder.flush_data().unwrap();
+
+    let mut images_count = 0;
+    loop {
+        match decoder.decode() {
+            Ok(DecodeResult::Done) => break,
+            Ok(DecodeResult::CallAgain | DecodeResult::OutputQueueFull) => {
+                images_count += decoder.frames().count();
+            }
+            Ok(DecodeResult::NeedsMoreInput) => unreachable!("all data has been pushed"),
+            Err(err) => panic!("{:?}", err),
+        }
+    }
+    assert_eq!(images_count, 75);
+}
+
+#[test]
+fn parse_malformed_hvcc_config() {
+    let stream = std::fs::read("./data/girlshy.h265").unwrap();
+    let data = build_hvcc(&parameter_sets(&stream));
+
+    // Truncated header and arrays
+    for size in [0, 10, 22, 23, 25, 27, data.len() - 1] {
+        assert_eq!(
+            HvccConfig::parse(&data[..size]),
+            Err(HvccError::UnexpectedEnd)
+        );
+    }
+
+    let mut invalid_data = data.clone();
+    invalid_data[0] = 0;
+    assert_eq!(
+        HvccConfig::parse(&invalid_data),
+        Err(HvccError::UnsupportedVersion(0))
+    );
+
+    let mut invalid_data = data.clone();
+    invalid_data[21] = 0x0e;
+    assert_eq!(
+        HvccConfig::parse(&invalid_data),
+        Err(HvccError::InvalidNalLengthSize(3))
+    );
+
+    let zero_length_nal = [
+        &data[..22],
+        &[1, 0x80 | NAL_VPS, 0, 1, 0, 0], // one array with one empty NAL unit
+    ]
+    .concat();
+    assert_eq!(
+        HvccConfig::parse(&zero_length_nal),
+        Err(HvccError::ZeroLengthNalUnit)
+    );
+}
+
+fn is_vcl(nal: &[u8]) -> bool {
+    nal_unit_type(nal) < 32
+}
+
+/// Group NAL units of the stream into access units
+/// and convert them into length-prefixed samples.
+fn build_samples(stream: &[u8], nal_length_size: usize) -> Vec<Vec<u8>> {
+    let mut samples: Vec<Vec<u8>> = Vec::new();
+    let mut prev_is_vcl = false;
+    for nal in split_annex_b(stream) {
+        let nal = nal.data;
+        let starts_access_unit = if is_vcl(nal) {
+            // first_slice_segment_in_pic_flag
+            nal[2] & 0x80 != 0 && prev_is_vcl
+        } else {
+            prev_is_vcl && matches!(nal_unit_type(nal), 32..=39)
+        };
+        if starts_access_unit || samples.is_empty() {
+            samples.push(Vec::new());
+        }
+        let sample = samples.last_mut().unwrap();
+        let length = (nal.len() as u32).to_be_bytes();
+        sample.extend_from_slice(&length[4 - nal_length_size..]);
+        sample.extend_from_slice(nal);
+        prev_is_vcl = is_vcl(nal);
+    }
+    samples
+}
+
+#[test]
+fn decode_length_prefixed_samples() {
+    let stream = std::fs::read("./data/girlshy.h265").unwrap();
+    for nal_length_size in [2, 4] {
+        let samples = build_samples(&stream, nal_length_size);
+        assert_eq!(samples.len(), 75);
+
+        let mut decoder = Decoder::new().unwrap();
+        let mut images_count = 0;
+        for (i, sample) in samples.iter().enumerate() {
+            decoder
+                .input_mut()
+                .push_sample(sample, nal_length_size as u8, i as i64, 0)
+                .unwrap();
+            loop {
+                match decoder.decode() {
+                    Ok(DecodeResult::CallAgain | DecodeResult::OutputQueueFull) => {
+                        images_count += decoder.frames().count();
+                    }
+                    Ok(DecodeResult::Done | DecodeResult::NeedsMoreInput) => break,
+                    Err(err) => panic!("{:?}", err),
+                }
+            }
+        }
+        decoder.flush_data().unwrap();
+        loop {
+            match decoder.decode() {
+                Ok(DecodeResult::Done) => break,
+                Ok(_) => images_count += decoder.frames().count(),
+                Err(err) => panic!("{:?}", err),
+            }
+        }
+        assert_eq!(images_count, 75);
+    }
+}
+
+#[test]
+fn push_corrupted_sample() {
+    let stream = std::fs::read("./data/girlshy.h265").unwrap();
+    let sample = build_samples(&stream, 4).swap_remove(0);
+    let (mut input, _output) = new_decoder().unwrap();
+
+    assert_eq!(
+        input.push_sample(&sample, 3, 0, 0),
+        Err(SampleError::InvalidNalLengthSize(3))
+    );
+
+    let mut corrupted = sample.clone();
+    corrupted[0] = 0x7f;
+    assert!(matches!(
+        input.push_sample(&corrupted, 4, 0, 0),
+        Err(SampleError::NalLengthOverrun { offset: 0, .. })
+    ));
+
+    // The largest length doesn't overflow offsets on 32-bit targets.
+    let overrun = [&sample[..], &[0xff; 5]].concat();
+    assert_eq!(
+        input.push_sample(&overrun, 4, 0, 0),
+        Err(SampleError::NalLengthOverrun {
+            offset: sample.len(),
+            length: u32::MAX as usize
+        })
+    );
+
+    let truncated = [&sample[..], &[0, 0]].concat();
+    assert_eq!(
+        input.push_sample(&truncated, 4, 0, 0),
+        Err(SampleError::TruncatedNalLength {
+            offset: sample.len()
+        })
+    );
+    // Nothing was pushed into the decoder.
+    assert_eq!(input.number_of_nal_units_pending(), 0);
+}
@@ -0,0 +1,78 @@
+use std::ops::ControlFlow;
+
+use libde265_rs::*;
+
+mod common;
+use common::first_frame;
+
+#[test]
+fn i420_contains_packed_planes() {
+    let mut decoder = Decoder::new().unwrap();
+    let file = std::fs::File::open("./data/girlshy.h265").unwrap();
+    let mut frames = 0;
+    decoder
+        .decode_from_reader(file, |image| {
+            let size = image.i420_size();
+            assert_eq!(size, 316 * 240 * 3 / 2);
+            let i420 = image.to_i420();
+            assert_eq!(i420.len(), size);
+
+            let mut expected = vec![0; size];
+            let mut offset = 0;
+            for channel in [Channel::Y, Channel::Cb, Channel::Cr] {
+                offset += image
+                    .copy_plane_into(channel, &mut expected[offset..])
+                    .unwrap();
+            }
+            assert_eq!(offset, size);
+            assert!(i420 == expected);
+
+            frames += 1;
+            if frames == 5 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .unwrap();
+    assert_eq!(frames, 5);
+}
+
+#[test]
+fn i420_into_buffer() {
+    let image = first_frame("./data/girlshy.h265");
+    let size = image.i420_size();
+    let mut dst = vec![0xAA; size + 10];
+    assert_eq!(image.write_i420_into(&mut dst).unwrap(), size);
+    assert!(dst[..size] == image.to_i420());
+    // Bytes after the picture are not changed.
+    assert!(dst[size..].iter().all(|&v| v == 0xAA));
+
+    assert!(matches!(
+        image.write_i420_into(&mut dst[..size - 1]),
+        Err(DeError::ErrorBufferTooSmall(s)) if s == size
+    ));
+}
+
+#[test]
+fn i420_matches_nv12() {
+    for path in [
+        "./data/gradient_10bit.h265",
+        "./data/gradient_mono.h265",
+        "./data/gradient_422.h265",
+        "./data/gradient_444.h265",
+    ] {
+        let image = first_frame(path);
+        let i420 = image.to_i420();
+        assert_eq!(i420.len(), 64 * 64 * 3 / 2, "{path}");
+        let nv12 = image.to_nv12();
+        let (y, chroma) = i420.split_at(64 * 64);
+        let (u, v) = chroma.split_at(32 * 32);
+        assert_eq!(y, nv12.y, "{path}");
+        let uv: Vec<u8> = u.iter().zip(v).flat_map(|(&u, &v)| [u, v]).collect();
+        assert_eq!(uv, nv12.uv, "{path}");
+    }
+
+    let image = first_frame("./data/gradient_mono.h265");
+    assert!(image.to_i420()[64 * 64..].iter().all(|&v| v == 128));
+}
@@ -0,0 +1,25 @@
+use libde265_rs::*;
+
+#[test]
+fn verbosity_level() {
+    assert_eq!(libde265_rs::verbosity_level(), VerbosityLevel::Errors);
+
+    set_verbosity_level(VerbosityLevel::Info);
+    assert_eq!(libde265_rs::verbosity_level(), VerbosityLevel::Info);
+    // Old function is still working.
+    set_verbosity(2);
+    assert_eq!(libde265_rs::verbosity_level(), VerbosityLevel::Debug);
+    set_verbosity(0);
+    assert_eq!(libde265_rs::verbosity_level(), VerbosityLevel::Errors);
+
+    {
+        let _guard = LoggingGuard::new(VerbosityLevel::Info);
+        assert_eq!(libde265_rs::verbosity_level(), VerbosityLevel::Info);
+        {
+            let _guard = LoggingGuard::new(VerbosityLevel::Trace);
+            assert_eq!(libde265_rs::verbosity_level(), VerbosityLevel::Trace);
+        }
+        assert_eq!(libde265_rs::verbosity_level(), VerbosityLevel::Info);
+    }
+    assert_eq!(libde265_rs::verbosity_level(), VerbosityLevel::Errors);
+}
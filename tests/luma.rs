@@ -0,0 +1,58 @@
+use libde265_rs::*;
+
+mod common;
+use common::for_first_image;
+
+/// Returns samples of the luma plane without padding at the end of rows.
+fn luma_rows(image: &Image) -> Vec<u8> {
+    let (data, stride) = image.plane(Channel::Y);
+    let width = image.width(Channel::Y) as usize;
+    let row_size = width * image.bits_per_pixel(Channel::Y).div_ceil(8) as usize;
+    data.chunks(stride)
+        .take(image.height(Channel::Y) as usize)
+        .flat_map(|row| &row[..row_size])
+        .copied()
+        .collect()
+}
+
+#[test]
+fn luma8_without_padding() {
+    for_first_image("./data/girlshy.h265", |image| {
+        assert!(!image.full_range());
+        let luma = image.to_luma8(false);
+        assert_eq!((luma.width, luma.height), (316, 240));
+        assert_eq!(luma.data, luma_rows(image));
+        assert_eq!(luma.pixel(315, 239), luma.data.last().copied());
+        assert_eq!(luma.pixel(316, 0), None);
+
+        let expanded = image.to_luma8(true);
+        for (&value, &source) in expanded.data.iter().zip(&luma.data) {
+            let expected = ((source.max(16) - 16) as f32 * 255. / 219.)
+                .round()
+                .min(255.);
+            assert_eq!(value, expected as u8, "{source}");
+        }
+    });
+}
+
+#[test]
+fn luma8_of_full_range_picture_is_not_expanded() {
+    for_first_image("./data/gradient_bt709.h265", |image| {
+        assert!(image.full_range());
+        assert_eq!(image.to_luma8(true).data, luma_rows(image));
+    });
+}
+
+#[test]
+fn luma8_from_high_bit_depth() {
+    for_first_image("./data/gradient_10bit.h265", |image| {
+        let luma = image.to_luma8(false);
+        assert_eq!((luma.width, luma.height), (64, 64));
+        let samples = luma_rows(image);
+        let expected: Vec<u8> = samples
+            .chunks(2)
+            .map(|v| ((u16::from_ne_bytes([v[0], v[1]]) + 2) >> 2).min(255) as u8)
+            .collect();
+        assert_eq!(luma.data, expected);
+    });
+}
@@ -0,0 +1,94 @@
+use libde265_rs::*;
+
+mod common;
+use common::for_first_image;
+
+/// Codes from table 4 of ITU-T H.273.
+const H273_CODES: [(u8, MatrixCoefficients); 14] = [
+    (0, MatrixCoefficients::Identity),
+    (1, MatrixCoefficients::Bt709),
+    (2, MatrixCoefficients::Unspecified),
+    (4, MatrixCoefficients::Fcc),
+    (5, MatrixCoefficients::Bt470Bg),
+    (6, MatrixCoefficients::Smpte170M),
+    (7, MatrixCoefficients::Smpte240M),
+    (8, MatrixCoefficients::YCgCo),
+    (9, MatrixCoefficients::Bt2020Ncl),
+    (10, MatrixCoefficients::Bt2020Cl),
+    (11, MatrixCoefficients::SmpteSt2085),
+    (12, MatrixCoefficients::ChromaDerivedNcl),
+    (13, MatrixCoefficients::ChromaDerivedCl),
+    (14, MatrixCoefficients::ICtCp),
+];
+
+#[test]
+fn matrix_coefficients_codes() {
+    for (code, matrix) in H273_CODES {
+        assert_eq!(MatrixCoefficients::from_code(code), matrix);
+        assert_eq!(matrix.code(), code);
+    }
+    for code in 0..=u8::MAX {
+        let matrix = MatrixCoefficients::from_code(code);
+        assert_eq!(matrix.code(), code);
+        assert_eq!(u8::from(matrix), code);
+        assert_eq!(MatrixCoefficients::from(code), matrix);
+        let is_reserved = matches!(code, 3 | 15..);
+        assert_eq!(matrix == MatrixCoefficients::Reserved(code), is_reserved);
+    }
+    assert_eq!(MatrixCoefficients::Bt2020Ncl.to_string(), "BT.2020 NCL");
+    assert_eq!(MatrixCoefficients::Reserved(3).to_string(), "Reserved (3)");
+}
+
+fn assert_coefficients(matrix: MatrixCoefficients, expected: [f32; 4]) {
+    let coefficients = matrix.yuv_to_rgb_coefficients().unwrap();
+    for (actual, expected) in coefficients.into_iter().zip(expected) {
+        assert!(
+            (actual - expected).abs() < 1e-5,
+            "{matrix}: {coefficients:?} != {expected:?}"
+        );
+    }
+}
+
+#[test]
+fn yuv_to_rgb_coefficients() {
+    let bt601 = [1.402, -0.344136, -0.714136, 1.772];
+    assert_coefficients(MatrixCoefficients::Bt470Bg, bt601);
+    assert_coefficients(MatrixCoefficients::Smpte170M, bt601);
+    assert_coefficients(
+        MatrixCoefficients::Bt709,
+        [1.5748, -0.187324, -0.468124, 1.8556],
+    );
+    assert_coefficients(
+        MatrixCoefficients::Bt2020Ncl,
+        [1.4746, -0.164553, -0.571353, 1.8814],
+    );
+    for matrix in [
+        MatrixCoefficients::Identity,
+        MatrixCoefficients::Unspecified,
+        MatrixCoefficients::YCgCo,
+        MatrixCoefficients::Bt2020Cl,
+        MatrixCoefficients::ICtCp,
+        MatrixCoefficients::Reserved(3),
+    ] {
+        assert_eq!(matrix.yuv_to_rgb_coefficients(), None, "{matrix}");
+    }
+}
+
+#[test]
+fn matrix_coefficients_of_images() {
+    for (path, expected) in [
+        ("./data/gradient_bt709.h265", MatrixCoefficients::Bt709),
+        ("./data/girlshy.h265", MatrixCoefficients::Unspecified),
+    ] {
+        let mut result = None;
+        for_first_image(path, |image| {
+            let owned = image.to_owned();
+            assert_eq!(
+                owned.matrix_coefficients_enum(),
+                image.matrix_coefficients_enum()
+            );
+            result = Some(image.matrix_coefficients_enum());
+        });
+        assert_eq!(result, Some(expected), "{path}");
+    }
+}
@@ -0,0 +1,183 @@
+use libde265_rs::*;
+
+fn split_by_chunks(data: &[u8], chunk_size: usize) -> Vec<(u64, Vec<u8>)> {
+    let mut splitter = AnnexBSplitter::new();
+    let mut nal_units = Vec::new();
+    for chunk in data.chunks(chunk_size) {
+        splitter.push(chunk);
+        while let Some(nal) = splitter.next_nal() {
+            nal_units.push((nal.offset, nal.data.to_vec()));
+        }
+    }
+    if let Some(nal) = splitter.finish() {
+        nal_units.push((nal.offset, nal.data.to_vec()));
+    }
+    nal_units
+}
+
+#[test]
+fn split_annex_b_stream() {
+    let data = [
+        0, 0, 0, 1, 0x40, 0x01, // 4-byte start code
+        0, 0, 1, 0x42, 0x01, 0x01, // 3-byte start code
+        0, 0, 0, 1, 0x44, 0x01, 0, 0, // trailing zero bytes
+    ];
+    let nal_units: Vec<_> = split_annex_b(&data).collect();
+    assert_eq!(
+        nal_units,
+        [
+            NalUnit {
+                offset: 4,
+                data: &[0x40, 0x01]
+            },
+            NalUnit {
+                offset: 9,
+                data: &[0x42, 0x01, 0x01]
+            },
+            NalUnit {
+                offset: 16,
+                data: &[0x44, 0x01]
+            },
+        ]
+    );
+}
+
+#[test]
+fn split_stream_without_start_codes() {
+    let data = [0x40, 0x01, 0, 0, 0x42, 0x01];
+    assert_eq!(split_annex_b(&data).count(), 0);
+    assert!(split_by_chunks(&data, 1).is_empty());
+    assert_eq!(split_annex_b(&[]).count(), 0);
+}
+
+#[test]
+fn split_start_codes_at_chunk_boundaries() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let expected: Vec<_> = split_annex_b(&data)
+        .map(|nal| (nal.offset, nal.data.to_vec()))
+        .collect();
+    assert!(!expected.is_empty());
+    assert!(expected.iter().all(|(_, nal)| !nal.ends_with(&[0])));
+
+    for chunk_size in [1, 2, 3, 4, 5, 7, 1024, data.len()] {
+        assert_eq!(split_by_chunks(&data, chunk_size), expected);
+    }
+}
+
+#[test]
+fn decode_split_nal_units() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let mut decoder = Decoder::new().unwrap();
+    for nal in split_annex_b(&data) {
+        decoder.push_nal(nal.data, 0, 0).unwrap();
+    }
+    decoder.flush_data().unwrap();
+
+    let mut images_count = 0;
+    loop {
+        match decoder.decode() {
+            Ok(DecodeResult::Done) => break,
+            Ok(DecodeResult::CallAgain | DecodeResult::OutputQueueFull) => {
+                images_count += decoder.frames().count();
+            }
+            Ok(DecodeResult::NeedsMoreInput) => unreachable!("all data has been pushed"),
+            Err(err) => panic!("{:?}", err),
+        }
+    }
+    assert_eq!(images_count, 75);
+}
+
+#[test]
+fn nal_unit_type_values() {
+    for value in 0..64u8 {
+        let unit_type = NalUnitType::from_u8(value);
+        assert_eq!(unit_type.to_u8(), value);
+        assert_eq!(NalUnitType::from(value), unit_type);
+        assert_eq!(u8::from(unit_type), value);
+        assert_eq!(NalUnitType::from_u8(unit_type.to_u8()), unit_type);
+        assert_eq!(unit_type.to_string(), unit_type.name());
+        let is_known = matches!(value, 0..=9 | 16..=21 | 32..=40);
+        assert_eq!(!matches!(unit_type, NalUnitType::Unknown(_)), is_known);
+    }
+    assert_eq!(NalUnitType::from_u8(1), NalUnitType::TrailR);
+    assert_eq!(NalUnitType::from_u8(19), NalUnitType::IdrWRadl);
+    assert_eq!(NalUnitType::from_u8(21), NalUnitType::CraNut);
+    assert_eq!(NalUnitType::from_u8(33), NalUnitType::SpsNut);
+    assert_eq!(NalUnitType::from_u8(40), NalUnitType::SuffixSeiNut);
+    assert_eq!(NalUnitType::from_u8(23), NalUnitType::Unknown(23));
+    assert_eq!(NalUnitType::from_u8(48), NalUnitType::Unknown(48));
+    assert_eq!(NalUnitType::from_u8(200), NalUnitType::Unknown(200));
+
+    assert_eq!(NalUnitType::IdrWRadl.to_string(), "IDR_W_RADL");
+    assert_eq!(NalUnitType::CraNut.name(), "CRA_NUT");
+    assert_eq!(NalUnitType::PrefixSeiNut.name(), "PREFIX_SEI_NUT");
+    assert_eq!(NalUnitType::Unknown(10).name(), "RSV_VCL_N10");
+    assert_eq!(NalUnitType::Unknown(22).name(), "RSV_IRAP_VCL22");
+    assert_eq!(NalUnitType::Unknown(47).name(), "RSV_NVCL47");
+    assert_eq!(NalUnitType::Unknown(63).name(), "UNSPEC63");
+    assert_eq!(NalUnitType::Unknown(200).name(), "INVALID");
+
+    let types_where = |f: fn(NalUnitType) -> bool| -> Vec<u8> {
+        (0..64u8).filter(|&v| f(NalUnitType::from_u8(v))).collect()
+    };
+    assert_eq!(
+        types_where(NalUnitType::is_vcl),
+        (0..32).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        types_where(NalUnitType::is_irap),
+        (16..24).collect::<Vec<_>>()
+    );
+    assert_eq!(types_where(NalUnitType::is_idr), [19, 20]);
+    assert_eq!(types_where(NalUnitType::is_bla), [16, 17, 18]);
+    assert_eq!(types_where(NalUnitType::is_leading), [6, 7, 8, 9]);
+    assert_eq!(types_where(NalUnitType::is_parameter_set), [32, 33, 34]);
+    assert_eq!(types_where(NalUnitType::is_sei), [39, 40]);
+    let mut reference = vec![1, 3, 5, 7, 9, 11, 13];
+    reference.extend(15..32);
+    assert_eq!(types_where(NalUnitType::is_reference), reference);
+}
+
+#[test]
+fn irap_pictures() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let mut decoder = Decoder::new().unwrap();
+    for nal in split_annex_b(&data) {
+        decoder.push_nal(nal.data, 0, 0).unwrap();
+    }
+    decoder.flush_data().unwrap();
+
+    let mut pictures = Vec::new();
+    loop {
+        let result = decoder.decode().unwrap();
+        for image in decoder.frames() {
+            let owned = image.to_owned();
+            assert_eq!(owned.is_irap(), image.is_irap());
+            assert_eq!(owned.is_idr(), image.is_idr());
+            assert_eq!(owned.is_reference(), image.is_reference());
+            let header = image.nal_header();
+            assert_eq!(header.unit_name(), header.unit_type.name());
+            pictures.push((image.is_irap(), image.is_idr(), image.is_reference()));
+        }
+        if result == DecodeResult::Done {
+            break;
+        }
+    }
+    assert_eq!(pictures.len(), 75);
+    assert_eq!(pictures[0], (true, true, true));
+    assert!(pictures[1..].iter().all(|&(irap, idr, _)| !irap && !idr));
+    assert!(pictures.iter().any(|&(_, _, reference)| !reference));
+}
+
+#[test]
+fn nal_header_debug() {
+    let header = NalHeader {
+        unit_type: NalUnitType::IdrWRadl,
+        layer_id: 0,
+        temporal_id: 1,
+    };
+    assert_eq!(
+        format!("{header:?}"),
+        "NalHeader { unit_type: IDR_W_RADL (19), layer_id: 0, temporal_id: 1 }"
+    );
+}
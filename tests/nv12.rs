@@ -0,0 +1,142 @@
+use std::ops::ControlFlow;
+
+use libde265_rs::*;
+
+mod common;
+use common::first_frame;
+
+/// Returns samples of the plane without padding.
+fn packed(view: PlaneView) -> Vec<u8> {
+    view.as_u8_rows().flatten().copied().collect()
+}
+
+/// Returns rows of the NV12 plane without padding.
+fn rows(data: &[u8], stride: usize, row_size: usize) -> Vec<&[u8]> {
+    data.chunks(stride).map(|row| &row[..row_size]).collect()
+}
+
+#[test]
+fn nv12_interleaves_chroma_planes() {
+    let mut decoder = Decoder::new().unwrap();
+    let file = std::fs::File::open("./data/girlshy.h265").unwrap();
+    let mut frames = 0;
+    decoder
+        .decode_from_reader(file, |image| {
+            let nv12 = image.to_nv12();
+            assert_eq!((nv12.width, nv12.height), (316, 240));
+            assert_eq!((nv12.y_stride, nv12.uv_stride), (316, 316));
+            assert_eq!(nv12.y.len(), 316 * 240);
+            assert_eq!(nv12.uv.len(), 316 * 120);
+            assert!(nv12.y == image.plane_to_vec(Channel::Y));
+
+            let cb = image.plane_to_vec(Channel::Cb);
+            let cr = image.plane_to_vec(Channel::Cr);
+            let expected_uv: Vec<u8> = cb.iter().zip(&cr).flat_map(|(&u, &v)| [u, v]).collect();
+            assert!(nv12.uv == expected_uv);
+
+            frames += 1;
+            if frames == 5 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .unwrap();
+    assert_eq!(frames, 5);
+}
+
+#[test]
+fn nv12_into_buffers_with_strides() {
+    let image = first_frame("./data/girlshy.h265");
+    let nv12 = image.to_nv12();
+
+    let strides = (384, 352);
+    let mut y = vec![0xAA; 384 * 240];
+    let mut uv = vec![0xAA; 352 * 120];
+    image.write_nv12_into(&mut y, &mut uv, strides).unwrap();
+    assert_eq!(rows(&y, 384, 316), rows(&nv12.y, 316, 316));
+    assert_eq!(rows(&uv, 352, 316), rows(&nv12.uv, 316, 316));
+    // Padding is not changed.
+    assert!(
+        y.chunks(384)
+            .all(|row| row[316..].iter().all(|&v| v == 0xAA))
+    );
+    assert!(
+        uv.chunks(352)
+            .all(|row| row[316..].iter().all(|&v| v == 0xAA))
+    );
+
+    assert!(matches!(
+        image.write_nv12_into(&mut y, &mut uv, (315, 352)),
+        Err(DeError::ErrorStrideTooSmall(316))
+    ));
+    assert!(matches!(
+        image.write_nv12_into(&mut y, &mut uv, (384, 314)),
+        Err(DeError::ErrorStrideTooSmall(316))
+    ));
+    assert!(matches!(
+        image.write_nv12_into(&mut y[..1000], &mut uv, strides),
+        Err(DeError::ErrorBufferTooSmall(92160))
+    ));
+    assert!(matches!(
+        image.write_nv12_into(&mut y, &mut uv[..1000], strides),
+        Err(DeError::ErrorBufferTooSmall(42240))
+    ));
+}
+
+#[test]
+fn nv12_from_high_bit_depth() {
+    let image = first_frame("./data/gradient_10bit.h265");
+    let nv12 = image.to_nv12();
+    assert_eq!((nv12.width, nv12.height), (64, 64));
+    let (luma, _) = image.plane_u16(Channel::Y).unwrap();
+    let expected: Vec<u8> = luma.iter().map(|&v| (v >> 2) as u8).collect();
+    assert_eq!(nv12.y, expected);
+
+    let (cb, _) = image.plane_u16(Channel::Cb).unwrap();
+    let (cr, _) = image.plane_u16(Channel::Cr).unwrap();
+    let expected: Vec<u8> = cb
+        .iter()
+        .zip(cr.iter())
+        .flat_map(|(&u, &v)| [(u >> 2) as u8, (v >> 2) as u8])
+        .collect();
+    assert_eq!(nv12.uv, expected);
+}
+
+#[test]
+fn nv12_from_monochrome() {
+    let image = first_frame("./data/gradient_mono.h265");
+    let nv12 = image.to_nv12();
+    assert_eq!(nv12.y, packed(image.plane_view(Channel::Y)));
+    assert_eq!(nv12.uv.len(), 64 * 32);
+    assert!(nv12.uv.iter().all(|&v| v == 128));
+}
+
+#[test]
+fn nv12_from_422_and_444() {
+    // Chroma samples of test streams: Cb = 64 + x * 128 / chroma_width,
+    // Cr = 192 - y * 128 / chroma_height.
+    let image = first_frame("./data/gradient_422.h265");
+    assert_eq!(image.chroma_format(), ChromaFormat::C422);
+    let nv12 = image.to_nv12();
+    assert_eq!(nv12.y, packed(image.plane_view(Channel::Y)));
+    assert_eq!(nv12.uv.len(), 64 * 32);
+    for (y, row) in nv12.uv.chunks(64).enumerate() {
+        for (x, uv) in row.chunks(2).enumerate() {
+            // Cb is equal in averaged rows, Cr is the rounded average
+            // of 192 - 4y and 190 - 4y.
+            assert_eq!(uv, [64 + 4 * x as u8, 191 - 4 * y as u8], "({x}, {y})");
+        }
+    }
+
+    let image = first_frame("./data/gradient_444.h265");
+    assert_eq!(image.chroma_format(), ChromaFormat::C444);
+    let nv12 = image.to_nv12();
+    assert_eq!(nv12.uv.len(), 64 * 32);
+    for (y, row) in nv12.uv.chunks(64).enumerate() {
+        for (x, uv) in row.chunks(2).enumerate() {
+            // The rounded average of 64 + 4x and 66 + 4x is 65 + 4x.
+            assert_eq!(uv, [65 + 4 * x as u8, 191 - 4 * y as u8], "({x}, {y})");
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use std::ops::ControlFlow;
+
+use libde265_rs::*;
+
+mod common;
+use common::for_first_image;
+
+const CHANNELS: [Channel; 3] = [Channel::Y, Channel::Cb, Channel::Cr];
+
+fn decode_to_owned(path: &str, trim_padding: bool) -> Vec<OwnedImage> {
+    let mut decoder = Decoder::new().unwrap();
+    let file = std::fs::File::open(path).unwrap();
+    let mut images = Vec::new();
+    decoder
+        .decode_from_reader(file, |image| {
+            let owned = if trim_padding {
+                image.to_owned_trimmed()
+            } else {
+                image.to_owned()
+            };
+            assert_eq!(owned.pts(), image.pts());
+            assert_eq!(owned.user_data(), image.user_data());
+            assert_eq!(owned.chroma_format(), image.chroma_format());
+            assert_eq!(owned.nal_header().unit_type, image.nal_header().unit_type);
+            assert_eq!(
+                owned.nal_header().temporal_id,
+                image.nal_header().temporal_id
+            );
+            assert_eq!(owned.full_range(), image.full_range());
+            assert_eq!(owned.colour_primaries(), image.colour_primaries());
+            assert_eq!(
+                owned.transfer_characteristics(),
+                image.transfer_characteristics()
+            );
+            assert_eq!(owned.matrix_coefficients(), image.matrix_coefficients());
+            assert_eq!(owned.decode_issues(), image.decode_issues());
+            for channel in CHANNELS {
+                assert_eq!(owned.bits_per_pixel(channel), image.bits_per_pixel(channel));
+                if !trim_padding {
+                    assert!(owned.plane(channel) == image.plane(channel));
+                }
+            }
+            images.push(owned);
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    // The decoder is dropped here.
+    images
+}
+
+/// Returns rows of the plane without padding.
+fn rows(plane: (&[u8], usize), row_size: usize) -> Vec<&[u8]> {
+    let (data, stride) = plane;
+    data.chunks(stride).map(|row| &row[..row_size]).collect()
+}
+
+#[test]
+fn owned_images_outlive_decoder() {
+    fn assert_send_sync_static<T: Send + Sync + 'static>() {}
+    assert_send_sync_static::<OwnedImage>();
+
+    let images = decode_to_owned("./data/girlshy.h265", false);
+    assert_eq!(images.len(), 75);
+    // Frames may be handled in another thread.
+    let images = std::thread::spawn(move || images).join().unwrap();
+
+    let frames: Vec<OwnedImage> = decode_file("./data/girlshy.h265")
+        .unwrap()
+        .map(|frame| frame.unwrap())
+        .collect();
+    assert_eq!(frames.len(), 75);
+    for (image, frame) in images.iter().zip(&frames) {
+        assert_eq!(image.pts(), frame.pts());
+        for channel in CHANNELS {
+            assert_eq!(image.width(channel), frame.width(channel));
+            assert_eq!(image.height(channel), frame.height(channel));
+            assert!(image.plane(channel) == frame.plane(channel));
+        }
+    }
+}
+
+#[test]
+fn owned_images_without_padding() {
+    let images = decode_to_owned("./data/girlshy.h265", false);
+    let trimmed_images = decode_to_owned("./data/girlshy.h265", true);
+    assert_eq!(trimmed_images.len(), 75);
+    for (image, trimmed) in images.iter().zip(&trimmed_images) {
+        for channel in CHANNELS {
+            let width = trimmed.width(channel) as usize;
+            let height = trimmed.height(channel) as usize;
+            let (data, stride) = trimmed.plane(channel);
+            assert_eq!(stride, width);
+            assert_eq!(data.len(), width * height);
+            assert!(image.plane(channel).1 > width);
+            assert!(rows(trimmed.plane(channel), width) == rows(image.plane(channel), width));
+        }
+    }
+}
+
+#[test]
+fn owned_images_with_high_bit_depth() {
+    let images = decode_to_owned("./data/gradient_10bit.h265", false);
+    let trimmed_images = decode_to_owned("./data/gradient_10bit.h265", true);
+    assert_eq!(images.len(), 3);
+    for (image, trimmed) in images.iter().zip(&trimmed_images) {
+        for channel in CHANNELS {
+            let width = image.width(channel) as usize;
+            let (samples, stride) = trimmed.plane_u16(channel).unwrap();
+            assert_eq!(stride, width);
+            let trimmed_rows: Vec<&[u16]> = samples.chunks(stride).collect();
+            let image_rows: Vec<&[u16]> =
+                image.plane_view(channel).as_u16_rows().unwrap().collect();
+            assert!(trimmed_rows == image_rows);
+            assert!(
+                trimmed_rows
+                    .iter()
+                    .flat_map(|row| row.iter())
+                    .any(|&v| v > 255)
+            );
+        }
+    }
+}
+
+#[test]
+fn copy_plane_without_padding() {
+    for path in ["./data/girlshy.h265", "./data/gradient_10bit.h265"] {
+        for_first_image(path, |image| {
+            for channel in CHANNELS {
+                let bytes_per_sample = image.bits_per_pixel(channel).div_ceil(8) as usize;
+                let row_size = image.width(channel) as usize * bytes_per_sample;
+                let size = row_size * image.height(channel) as usize;
+
+                let mut dst = vec![0xaa; size + 10];
+                assert_eq!(image.copy_plane_into(channel, &mut dst), Ok(size));
+                // Bytes after the plane are not touched.
+                assert!(dst[size..].iter().all(|&b| b == 0xaa));
+                assert_eq!(
+                    image.copy_plane_into(channel, &mut dst[..size - 1]),
+                    Err(DeError::ErrorBufferTooSmall(size))
+                );
+
+                let packed = image.plane_to_vec(channel);
+                assert!(packed[..] == dst[..size]);
+                let (data, stride) = image.plane(channel);
+                assert!(stride >= row_size);
+                assert!(
+                    packed
+                        .chunks(row_size)
+                        .eq(data.chunks(stride).map(|row| &row[..row_size]))
+                );
+            }
+        });
+    }
+}
@@ -0,0 +1,59 @@
+use std::ops::ControlFlow;
+
+use libde265_rs::*;
+
+fn decode_nal_units(data: &[u8]) -> Vec<PictureType> {
+    let mut decoder = Decoder::new().unwrap();
+    for nal in split_annex_b(data) {
+        decoder.push_nal(nal.data, 0, 0).unwrap();
+    }
+    decoder.flush_data().unwrap();
+    let mut types = Vec::new();
+    loop {
+        let result = decoder.decode().unwrap();
+        for image in decoder.frames() {
+            let owned = image.to_owned();
+            assert_eq!(owned.picture_type(), image.picture_type());
+            types.push(image.picture_type());
+        }
+        if result == DecodeResult::Done {
+            break;
+        }
+    }
+    types
+}
+
+#[test]
+fn picture_types_of_nal_units() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let types = decode_nal_units(&data);
+    assert_eq!(types.len(), 75);
+    assert_eq!(types[0], PictureType::I);
+    assert!(types[1..].iter().any(|&t| t != PictureType::I));
+    assert!(types.contains(&PictureType::P));
+    assert!(types.contains(&PictureType::B));
+    assert!(!types.contains(&PictureType::Unknown));
+}
+
+#[test]
+fn picture_types_of_idr_pictures() {
+    let data = std::fs::read("./data/gradient_10bit.h265").unwrap();
+    assert_eq!(decode_nal_units(&data), [PictureType::I; 3]);
+}
+
+#[test]
+fn picture_types_of_bytestream() {
+    let mut decoder = Decoder::new().unwrap();
+    let file = std::fs::File::open("./data/girlshy.h265").unwrap();
+    let mut types = Vec::new();
+    decoder
+        .decode_from_reader(file, |image| {
+            types.push(image.picture_type());
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert_eq!(types.len(), 75);
+    // Only IRAP pictures are recognized without parsing of slice headers.
+    assert_eq!(types[0], PictureType::I);
+    assert!(types[1..].iter().all(|&t| t == PictureType::Unknown));
+}
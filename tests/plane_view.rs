@@ -0,0 +1,259 @@
+use libde265_rs::*;
+
+mod common;
+use common::for_first_image;
+
+const CHANNELS: [Channel; 3] = [Channel::Y, Channel::Cb, Channel::Cr];
+
+/// Returns rows of the plane without padding taken from [`Image::plane()`].
+fn raw_rows(image: &Image<'_>, channel: Channel) -> Vec<Vec<u8>> {
+    let (data, stride) = image.plane(channel);
+    let bytes_per_sample = image.bits_per_pixel(channel).div_ceil(8) as usize;
+    let row_size = image.width(channel) as usize * bytes_per_sample;
+    data.chunks(stride)
+        .take(image.height(channel) as usize)
+        .map(|row| row[..row_size].to_vec())
+        .collect()
+}
+
+#[test]
+fn plane_view_of_8bit_picture() {
+    for_first_image("./data/girlshy.h265", |image| {
+        for channel in CHANNELS {
+            let view = image.plane_view(channel);
+            let (data, stride) = image.plane(channel);
+            assert_eq!(view.as_ptr(), data.as_ptr());
+            assert_eq!(view.stride(), stride);
+            assert_eq!(view.width(), image.width(channel));
+            assert_eq!(view.height(), image.height(channel));
+            assert_eq!(view.bytes_per_sample(), 1);
+
+            let rows: Vec<Vec<u8>> = view.as_u8_rows().map(|row| row.to_vec()).collect();
+            assert_eq!(rows, raw_rows(image, channel));
+            assert_eq!(view.row(0).unwrap(), &rows[0][..]);
+
+            assert_eq!(
+                view.row_u16(0),
+                Err(PlaneViewError::SampleSizeMismatch {
+                    bytes_per_sample: 1,
+                    element_size: 2
+                })
+            );
+            assert!(view.as_u16_rows().is_err());
+        }
+    });
+}
+
+#[test]
+fn plane_view_of_10bit_picture() {
+    for_first_image("./data/gradient_10bit.h265", |image| {
+        for channel in CHANNELS {
+            let view = image.plane_view(channel);
+            assert_eq!(view.bytes_per_sample(), 2);
+
+            let raw_rows = raw_rows(image, channel);
+            let rows: Vec<Vec<u8>> = view.as_u8_rows().map(|row| row.to_vec()).collect();
+            assert_eq!(rows, raw_rows);
+
+            let rows: Vec<Vec<u16>> = view
+                .as_u16_rows()
+                .unwrap()
+                .map(|row| row.to_vec())
+                .collect();
+            let expected_rows: Vec<Vec<u16>> = raw_rows
+                .iter()
+                .map(|row| {
+                    row.chunks(2)
+                        .map(|bytes| u16::from_ne_bytes([bytes[0], bytes[1]]))
+                        .collect()
+                })
+                .collect();
+            assert_eq!(rows, expected_rows);
+            assert!(rows.iter().flatten().any(|&sample| sample > 255));
+            assert_eq!(view.row_u16(1).unwrap(), &rows[1][..]);
+        }
+    });
+}
+
+#[test]
+fn plane_view_out_of_bounds() {
+    for_first_image("./data/gradient_10bit.h265", |image| {
+        let view = image.plane_view(Channel::Y);
+        let height = view.height();
+        assert!(view.row(height - 1).is_ok());
+        let error = PlaneViewError::RowOutOfBounds {
+            row: height,
+            height,
+        };
+        assert_eq!(view.row(height), Err(error));
+        assert_eq!(view.row_u16(height), Err(error));
+        assert_eq!(view.as_u8_rows().count(), height as usize);
+    });
+}
+
+/// Returns samples of the plane copied by [`Image::copy_plane_into()`].
+fn copied_plane(image: &Image<'_>, channel: Channel) -> Vec<u8> {
+    let mut data = vec![0; image.plane(channel).0.len()];
+    let size = image.copy_plane_into(channel, &mut data).unwrap();
+    data.truncate(size);
+    data
+}
+
+#[test]
+fn rows_of_planes() {
+    for path in ["./data/girlshy.h265", "./data/gradient_10bit.h265"] {
+        for_first_image(path, |image| {
+            let owned = image.to_owned();
+            for channel in CHANNELS {
+                let height = image.height(channel) as usize;
+                let bytes_per_sample = image.bits_per_pixel(channel).div_ceil(8) as usize;
+                let row_size = image.width(channel) as usize * bytes_per_sample;
+                assert_eq!(image.rows(channel).len(), height);
+                let rows: Vec<&[u8]> = image.rows(channel).collect();
+                assert!(rows.iter().all(|row| row.len() == row_size));
+                assert_eq!(rows.concat(), copied_plane(image, channel));
+                assert!(owned.rows(channel).eq(image.rows(channel)));
+
+                // Bottom-up iteration.
+                let mut reversed: Vec<&[u8]> = image.rows(channel).rev().collect();
+                reversed.reverse();
+                assert_eq!(reversed.concat(), copied_plane(image, channel));
+                let mut rows = image.rows(channel);
+                assert_eq!(
+                    rows.next_back(),
+                    image.plane_view(channel).row(height as u32 - 1).ok()
+                );
+                assert_eq!(rows.len(), height - 1);
+            }
+        });
+    }
+}
+
+#[test]
+fn rows_u16_of_planes() {
+    for_first_image("./data/gradient_10bit.h265", |image| {
+        for channel in CHANNELS {
+            let rows = image.rows_u16(channel).unwrap();
+            assert_eq!(rows.len(), image.height(channel) as usize);
+            let samples: Vec<u8> = rows
+                .flatten()
+                .flat_map(|sample| sample.to_ne_bytes())
+                .collect();
+            assert_eq!(samples, copied_plane(image, channel));
+
+            let last = image.rows_u16(channel).unwrap().next_back().unwrap();
+            assert_eq!(last.len(), image.width(channel) as usize);
+            assert!(
+                image
+                    .to_owned()
+                    .rows_u16(channel)
+                    .unwrap()
+                    .rev()
+                    .eq(image.rows_u16(channel).unwrap().rev())
+            );
+        }
+    });
+
+    for_first_image("./data/girlshy.h265", |image| {
+        assert!(matches!(
+            image.rows_u16(Channel::Y),
+            Err(PlaneViewError::SampleSizeMismatch { .. })
+        ));
+    });
+}
+
+#[test]
+fn samples_of_420_picture() {
+    for_first_image("./data/girlshy.h265", |image| {
+        assert_eq!(image.chroma_format(), ChromaFormat::C420);
+        let owned = image.to_owned();
+        for channel in CHANNELS {
+            let (width, height) = (image.width(channel), image.height(channel));
+            let rows = raw_rows(image, channel);
+            let last_row = &rows[height as usize - 1];
+            assert_eq!(image.sample(channel, 0, 0), Some(rows[0][0] as u16));
+            assert_eq!(
+                image.sample(channel, width - 1, height - 1),
+                Some(last_row[width as usize - 1] as u16)
+            );
+            assert_eq!(image.sample(channel, width, 0), None);
+            assert_eq!(image.sample(channel, 0, height), None);
+            assert_eq!(image.sample(channel, width, height), None);
+            assert_eq!(image.sample(channel, u32::MAX, u32::MAX), None);
+            assert_eq!(
+                owned.sample(channel, width - 1, height - 1),
+                image.sample(channel, width - 1, height - 1)
+            );
+        }
+        // Chroma planes are subsampled by 2 in both directions.
+        let width = image.width(Channel::Cb);
+        assert_eq!(image.width(Channel::Y), width * 2);
+        assert!(image.sample(Channel::Y, width, 0).is_some());
+        assert_eq!(image.sample(Channel::Cb, width, 0), None);
+    });
+}
+
+#[test]
+fn samples_of_10bit_picture() {
+    for_first_image("./data/gradient_10bit.h265", |image| {
+        for channel in CHANNELS {
+            let (width, height) = (image.width(channel), image.height(channel));
+            let rows: Vec<&[u16]> = image.rows_u16(channel).unwrap().collect();
+            assert_eq!(image.sample(channel, 0, 0), Some(rows[0][0]));
+            assert_eq!(
+                image.sample(channel, width - 1, height - 1),
+                Some(rows[height as usize - 1][width as usize - 1])
+            );
+            assert_eq!(image.sample(channel, width, height - 1), None);
+            assert_eq!(image.sample(channel, width - 1, height), None);
+        }
+        let max = (0..image.width(Channel::Y))
+            .filter_map(|x| image.sample(Channel::Y, x, 0))
+            .max();
+        assert!(max.unwrap() > 255);
+    });
+}
+
+#[test]
+fn samples_of_monochrome_picture() {
+    for_first_image("./data/gradient_mono.h265", |image| {
+        assert_eq!(image.chroma_format(), ChromaFormat::Mono);
+        assert!(image.sample(Channel::Y, 0, 0).is_some());
+        assert_eq!(image.sample(Channel::Cb, 0, 0), None);
+        assert_eq!(image.sample(Channel::Cr, 0, 0), None);
+    });
+}
+
+#[cfg(feature = "bytemuck")]
+#[test]
+fn plane_view_with_bytemuck() {
+    for_first_image("./data/gradient_10bit.h265", |image| {
+        let view = image.plane_view(Channel::Y);
+        let rows: Vec<&[u16]> = view.rows_as::<u16>().unwrap().collect();
+        let expected_rows: Vec<&[u16]> = view.as_u16_rows().unwrap().collect();
+        assert_eq!(rows, expected_rows);
+        assert_eq!(view.row_as::<u16>(2).unwrap(), expected_rows[2]);
+        // Pairs of bytes are 2-byte samples without alignment requirements.
+        let row: &[[u8; 2]] = view.row_as(0).unwrap();
+        assert_eq!(row.len(), view.width() as usize);
+        assert_eq!(
+            view.row_as::<u8>(0),
+            Err(PlaneViewError::SampleSizeMismatch {
+                bytes_per_sample: 2,
+                element_size: 1
+            })
+        );
+        assert!(matches!(
+            view.row_as::<u16>(view.height()),
+            Err(PlaneViewError::RowOutOfBounds { .. })
+        ));
+    });
+
+    for_first_image("./data/girlshy.h265", |image| {
+        let view = image.plane_view(Channel::Cb);
+        let rows: Vec<&[u8]> = view.rows_as::<u8>().unwrap().collect();
+        let expected_rows: Vec<&[u8]> = view.as_u8_rows().collect();
+        assert_eq!(rows, expected_rows);
+        assert!(view.rows_as::<u16>().is_err());
+    });
+}
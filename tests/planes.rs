@@ -0,0 +1,67 @@
+use libde265_rs::*;
+
+mod common;
+use common::for_first_image;
+
+#[test]
+fn planes_of_420_picture() {
+    for_first_image("./data/girlshy.h265", |image| {
+        let planes = image.planes();
+        let (cb, cr) = (planes.cb.unwrap(), planes.cr.unwrap());
+        assert_eq!((planes.y.width(), planes.y.height()), (316, 240));
+        for view in [cb, cr] {
+            assert_eq!(view.width(), planes.y.width() / 2);
+            assert_eq!(view.height(), planes.y.height() / 2);
+        }
+
+        for channel in [Channel::Y, Channel::Cb, Channel::Cr] {
+            let (data, stride) = image.plane(channel);
+            let view = planes.get(channel).unwrap();
+            assert_eq!(view.as_ptr(), data.as_ptr());
+            assert_eq!(view.stride(), stride);
+            assert_eq!(planes.data(channel), Some(data));
+        }
+
+        let raw_parts = planes.as_raw_parts();
+        for (channel, (ptr, stride)) in [Channel::Y, Channel::Cb, Channel::Cr]
+            .into_iter()
+            .zip(raw_parts)
+        {
+            let (data, expected_stride) = image.plane(channel);
+            assert_eq!(ptr, Some(data.as_ptr()));
+            assert_eq!(stride, expected_stride);
+        }
+
+        let owned = image.to_owned();
+        let owned_planes = owned.planes();
+        assert_eq!(owned_planes.cb.unwrap().width(), cb.width());
+        assert_eq!(
+            owned_planes.data(Channel::Cr),
+            Some(owned.plane(Channel::Cr).0)
+        );
+    });
+}
+
+#[test]
+fn planes_of_monochrome_picture() {
+    for_first_image("./data/gradient_mono.h265", |image| {
+        let planes = image.planes();
+        assert!(planes.cb.is_none());
+        assert!(planes.cr.is_none());
+        assert!(planes.get(Channel::Cb).is_none());
+        assert_eq!(planes.data(Channel::Cr), None);
+        assert_eq!(planes.data(Channel::Y), Some(image.plane(Channel::Y).0));
+
+        let [y, cb, cr] = planes.as_raw_parts();
+        assert_eq!(
+            y,
+            (Some(image.plane(Channel::Y).0.as_ptr()), planes.y.stride())
+        );
+        assert_eq!(cb, (None, 0));
+        assert_eq!(cr, (None, 0));
+
+        let owned = image.to_owned();
+        assert!(owned.planes().cb.is_none());
+        assert_eq!(owned.planes().as_raw_parts()[2], (None, 0));
+    });
+}
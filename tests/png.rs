@@ -0,0 +1,98 @@
+#![cfg(feature = "png")]
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use libde265_rs::*;
+
+mod common;
+use common::first_frame;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("libde265-rs-{}-{name}", std::process::id()))
+}
+
+/// Read a PNG file and returns its info and pixel data.
+fn read_png(path: &Path) -> (png::OutputInfo, Vec<u8>) {
+    let decoder = png::Decoder::new(BufReader::new(File::open(path).unwrap()));
+    let mut reader = decoder.read_info().unwrap();
+    let mut data = vec![0; reader.output_buffer_size().unwrap()];
+    let info = reader.next_frame(&mut data).unwrap();
+    data.truncate(info.buffer_size());
+    std::fs::remove_file(path).unwrap();
+    (info, data)
+}
+
+#[test]
+fn save_rgb8_png() {
+    let image = first_frame("./data/girlshy.h265");
+    let path = temp_path("rgb8.png");
+    image.save_png(&path).unwrap();
+    let (info, data) = read_png(&path);
+    assert_eq!((info.width, info.height), (316, 240));
+    assert_eq!(info.color_type, png::ColorType::Rgb);
+    assert_eq!(info.bit_depth, png::BitDepth::Eight);
+    let rgb = image.to_rgb8();
+    assert_eq!(data, rgb.data);
+    let offset = (100 * 316 + 50) * 3;
+    assert_eq!(data[offset..offset + 3], rgb.pixel(50, 100).unwrap());
+}
+
+#[test]
+fn save_rgb16_png() {
+    let image = first_frame("./data/gradient_10bit.h265");
+    let path = temp_path("rgb16.png");
+    image.save_png(&path).unwrap();
+    let (info, data) = read_png(&path);
+    let rgb = image.to_rgb16();
+    assert_eq!((info.width, info.height), (rgb.width, rgb.height));
+    assert_eq!(info.color_type, png::ColorType::Rgb);
+    assert_eq!(info.bit_depth, png::BitDepth::Sixteen);
+    let (x, y) = (rgb.width / 2, rgb.height / 3);
+    let offset = (y * rgb.width + x) as usize * 6;
+    let pixel: Vec<u16> = data[offset..offset + 6]
+        .chunks_exact(2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        .collect();
+    assert_eq!(pixel, rgb.pixel(x, y).unwrap());
+}
+
+#[test]
+fn save_plane_png() {
+    let image = first_frame("./data/girlshy.h265");
+    let path = temp_path("cb.png");
+    image.save_plane_png(Channel::Cb, &path).unwrap();
+    let (info, data) = read_png(&path);
+    assert_eq!((info.width, info.height), (158, 120));
+    assert_eq!(info.color_type, png::ColorType::Grayscale);
+    assert_eq!(info.bit_depth, png::BitDepth::Eight);
+    assert_eq!(
+        data[10 * 158 + 20] as u16,
+        image.sample(Channel::Cb, 20, 10).unwrap()
+    );
+
+    let image = first_frame("./data/gradient_10bit.h265");
+    let path = temp_path("y16.png");
+    image.save_plane_png(Channel::Y, &path).unwrap();
+    let (info, data) = read_png(&path);
+    assert_eq!(info.bit_depth, png::BitDepth::Sixteen);
+    let width = image.width(Channel::Y) as usize;
+    let offset = (3 * width + 5) * 2;
+    let value = image.sample(Channel::Y, 5, 3).unwrap();
+    assert_eq!(
+        u16::from_be_bytes([data[offset], data[offset + 1]]),
+        BitExpansion::Replicate.widen(value, 10, 16)
+    );
+}
+
+#[test]
+fn save_missing_plane() {
+    let image = first_frame("./data/gradient_mono.h265");
+    let path = temp_path("mono-cb.png");
+    let result = image.save_plane_png(Channel::Cb, &path);
+    assert!(matches!(result, Err(PngError::NoPlane(Channel::Cb))));
+    assert!(!path.exists());
+
+    let result = image.save_png(Path::new("./no/such/dir/picture.png"));
+    assert!(matches!(result, Err(PngError::Io(_))));
+}
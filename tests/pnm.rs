@@ -0,0 +1,102 @@
+use libde265_rs::*;
+
+mod common;
+use common::{first_frame, for_first_image};
+
+/// Parse the header of a binary PNM image and returns
+/// its magic number, width, height, maxval and data.
+fn parse_pnm(data: &[u8]) -> (&str, u32, u32, u32, &[u8]) {
+    let mut parts = data.splitn(5, |&b| b == b'\n' || b == b' ');
+    let mut next = || std::str::from_utf8(parts.next().unwrap()).unwrap();
+    let magic = next();
+    let width = next().parse().unwrap();
+    let height = next().parse().unwrap();
+    let maxval = next().parse().unwrap();
+    (magic, width, height, maxval, parts.next().unwrap())
+}
+
+#[test]
+fn write_pgm_of_luma_plane() {
+    for_first_image("./data/girlshy.h265", |image| {
+        let mut pgm = Vec::new();
+        image.write_pgm(Channel::Y, &mut pgm).unwrap();
+        let (magic, width, height, maxval, samples) = parse_pnm(&pgm);
+        assert_eq!(magic, "P5");
+        assert_eq!((width, height), (316, 240));
+        assert_eq!(maxval, 255);
+        assert_eq!(samples, image.plane_to_vec(Channel::Y));
+        assert_eq!(
+            samples[100 * 316 + 50] as u16,
+            image.sample(Channel::Y, 50, 100).unwrap()
+        );
+
+        let mut owned_pgm = Vec::new();
+        image
+            .to_owned()
+            .write_pgm(Channel::Y, &mut owned_pgm)
+            .unwrap();
+        assert_eq!(owned_pgm, pgm);
+    });
+}
+
+#[test]
+fn write_pgm_of_chroma_plane() {
+    let image = first_frame("./data/girlshy.h265");
+    let mut pgm = Vec::new();
+    image.write_pgm(Channel::Cr, &mut pgm).unwrap();
+    let (magic, width, height, maxval, samples) = parse_pnm(&pgm);
+    assert_eq!(magic, "P5");
+    assert_eq!((width, height, maxval), (158, 120, 255));
+    assert_eq!(samples.len(), 158 * 120);
+}
+
+#[test]
+fn write_pgm_of_10bit_plane() {
+    let image = first_frame("./data/gradient_10bit.h265");
+    let mut pgm = Vec::new();
+    image.write_pgm(Channel::Y, &mut pgm).unwrap();
+    let (magic, width, height, maxval, samples) = parse_pnm(&pgm);
+    assert_eq!(magic, "P5");
+    assert_eq!(width, image.width(Channel::Y));
+    assert_eq!(height, image.height(Channel::Y));
+    assert_eq!(maxval, 1023);
+    assert_eq!(samples.len(), width as usize * height as usize * 2);
+    let offset = (2 * width as usize + 9) * 2;
+    assert_eq!(
+        u16::from_be_bytes([samples[offset], samples[offset + 1]]),
+        image.sample(Channel::Y, 9, 2).unwrap()
+    );
+}
+
+#[test]
+fn write_pgm_of_missing_plane() {
+    let image = first_frame("./data/gradient_mono.h265");
+    let mut pgm = Vec::new();
+    let error = image.write_pgm(Channel::Cb, &mut pgm).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(pgm.is_empty());
+}
+
+#[test]
+fn write_ppm() {
+    let image = first_frame("./data/girlshy.h265");
+    let mut ppm = Vec::new();
+    image.write_ppm(&mut ppm).unwrap();
+    let (magic, width, height, maxval, pixels) = parse_pnm(&ppm);
+    assert_eq!(magic, "P6");
+    assert_eq!((width, height, maxval), (316, 240, 255));
+    assert_eq!(pixels, image.to_rgb8().data);
+
+    let image = first_frame("./data/gradient_10bit.h265");
+    let mut ppm = Vec::new();
+    image.write_ppm(&mut ppm).unwrap();
+    let (magic, width, height, maxval, pixels) = parse_pnm(&ppm);
+    let rgb = image.to_rgb16();
+    assert_eq!(magic, "P6");
+    assert_eq!((width, height, maxval), (rgb.width, rgb.height, 65535));
+    let components: Vec<u16> = pixels
+        .chunks_exact(2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        .collect();
+    assert_eq!(components, rgb.data);
+}
@@ -0,0 +1,84 @@
+use libde265_rs::*;
+
+mod common;
+use common::for_first_image;
+
+/// Returns POC and NAL unit type of pictures in output order.
+fn decode_nal_units(data: &[u8]) -> Vec<(Option<i32>, NalUnitType)> {
+    let mut decoder = Decoder::new().unwrap();
+    for nal in split_annex_b(data) {
+        decoder.push_nal(nal.data, 0, 0).unwrap();
+    }
+    decoder.flush_data().unwrap();
+    let mut pictures = Vec::new();
+    loop {
+        let result = decoder.decode().unwrap();
+        for image in decoder.frames() {
+            pictures.push((image.picture_order_count(), image.nal_header().unit_type));
+        }
+        if result == DecodeResult::Done {
+            break;
+        }
+    }
+    pictures
+}
+
+#[test]
+fn poc_increases_within_gop() {
+    // Two copies of the clip, every copy starts with IDR picture.
+    let mut data = std::fs::read("./data/girlshy.h265").unwrap();
+    data.extend_from_within(..);
+    let pictures = decode_nal_units(&data);
+    assert_eq!(pictures.len(), 150);
+
+    let mut gops: Vec<Vec<i32>> = Vec::new();
+    for (poc, unit_type) in pictures {
+        if unit_type.is_irap() {
+            gops.push(Vec::new());
+        }
+        gops.last_mut().unwrap().push(poc.unwrap());
+    }
+    assert_eq!(gops.len(), 2);
+    for gop in gops {
+        assert_eq!(gop[0], 0);
+        assert!(gop.windows(2).all(|w| w[0] < w[1]), "{gop:?}");
+    }
+}
+
+#[test]
+fn poc_of_idr_pictures() {
+    let data = std::fs::read("./data/gradient_10bit.h265").unwrap();
+    let pictures = decode_nal_units(&data);
+    assert_eq!(pictures, [(Some(0), NalUnitType::IdrWRadl); 3]);
+}
+
+#[test]
+fn poc_is_unknown_for_bytestream() {
+    for_first_image("./data/girlshy.h265", |image| {
+        assert_eq!(image.picture_order_count(), None);
+    });
+}
+
+#[test]
+fn owned_image_keeps_poc() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let mut decoder = Decoder::new().unwrap();
+    for nal in split_annex_b(&data).take(20) {
+        decoder.push_nal(nal.data, 0, 0).unwrap();
+    }
+    decoder.flush_data().unwrap();
+    let mut pocs = Vec::new();
+    loop {
+        let result = decoder.decode().unwrap();
+        for image in decoder.frames() {
+            let owned = image.to_owned();
+            assert_eq!(owned.picture_order_count(), image.picture_order_count());
+            pocs.push(owned.picture_order_count().unwrap());
+        }
+        if result == DecodeResult::Done {
+            break;
+        }
+    }
+    assert!(!pocs.is_empty());
+    assert!(pocs.windows(2).all(|w| w[0] < w[1]), "{pocs:?}");
+}
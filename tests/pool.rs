@@ -0,0 +1,85 @@
+use std::ops::ControlFlow;
+use std::time::Instant;
+
+use libde265_rs::*;
+
+/// Decode the stream and return checksums of luma planes of all pictures.
+fn luma_checksums(decoder: &mut Decoder, data: &[u8]) -> Vec<u64> {
+    let mut checksums = Vec::new();
+    decoder
+        .decode_from_reader(data, |image| {
+            let (plane, _) = image.plane(Channel::Y);
+            checksums.push(plane.iter().map(|&v| v as u64).sum());
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    checksums
+}
+
+#[test]
+fn pool_is_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<DecoderPool>();
+    fn assert_send<T: Send>() {}
+    assert_send::<PooledDecoder>();
+}
+
+#[test]
+fn decode_clips_by_pooled_decoders() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let builder = DecoderBuilder::new().worker_threads(2);
+
+    let start = Instant::now();
+    let mut expected = Vec::new();
+    for _ in 0..20 {
+        let mut decoder = builder.build_decoder().unwrap();
+        expected.push(luma_checksums(&mut decoder, &data));
+    }
+    let fresh_elapsed = start.elapsed();
+    assert_eq!(expected[0].len(), 75);
+
+    let pool = DecoderPool::with_builder(1, builder);
+    let start = Instant::now();
+    for expected in &expected {
+        let mut decoder = pool.get().unwrap();
+        assert_eq!(&luma_checksums(&mut decoder, &data), expected);
+        assert_eq!(decoder.stats().pictures_output, 75);
+    }
+    let pooled_elapsed = start.elapsed();
+    assert_eq!(pool.idle_count(), 1);
+    println!("fresh decoders: {fresh_elapsed:?}, pooled decoders: {pooled_elapsed:?}");
+}
+
+#[test]
+fn pool_caps_idle_decoders() {
+    let pool = DecoderPool::with_capacity(2);
+    assert_eq!(pool.capacity(), 2);
+    let decoders: Vec<_> = (0..3).map(|_| pool.get().unwrap()).collect();
+    assert_eq!(pool.idle_count(), 0);
+    drop(decoders);
+    assert_eq!(pool.idle_count(), 2);
+
+    // Detached and cancelled decoders aren't returned into the pool.
+    let decoder = pool.get().unwrap().detach();
+    drop(decoder);
+    let mut decoder = pool.get().unwrap();
+    decoder.cancel().unwrap();
+    drop(decoder);
+    assert_eq!(pool.idle_count(), 0);
+}
+
+#[test]
+fn pooled_decoder_is_reset() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let pool = DecoderPool::with_capacity(1);
+    {
+        let mut decoder = pool.get().unwrap();
+        // Leave a part of the stream undecoded.
+        decoder.push_data(&data[..data.len() / 2], 0, 0).unwrap();
+        decoder.decode().unwrap();
+    }
+    let mut decoder = pool.get().unwrap();
+    assert_eq!(decoder.stats(), DecoderStats::default());
+    assert!(!decoder.is_flushed());
+    assert_eq!(luma_checksums(&mut decoder, &data).len(), 75);
+}
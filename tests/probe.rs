@@ -0,0 +1,29 @@
+use std::fs::File;
+
+use libde265_rs::*;
+
+#[test]
+fn probe_h265_file() {
+    let file = File::open("./data/girlshy.h265").unwrap();
+    let info = probe_stream(file, 4096).unwrap();
+    assert_eq!((info.width, info.height), (316, 240));
+    assert_eq!(info.chroma_format, ChromaFormat::C420);
+    assert_eq!((info.bits_per_pixel, info.chroma_bits_per_pixel), (8, 8));
+
+    let file = File::open("./data/girlshy_cropped.h265").unwrap();
+    let info = probe_stream(file, 4096).unwrap();
+    assert_eq!((info.width, info.height), (256, 200));
+}
+
+#[test]
+fn probe_limited_part_of_stream() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    // The SPS is the second NAL unit, it ends at the offset 80.
+    let info = probe_stream(data.as_slice(), 80).unwrap();
+    assert_eq!((info.width, info.height), (316, 240));
+
+    let result = probe_stream(data.as_slice(), 32);
+    assert!(matches!(result, Err(ProbeError::NotFound)));
+    let result = probe_stream([0u8; 0].as_slice(), 4096);
+    assert!(matches!(result, Err(ProbeError::NotFound)));
+}
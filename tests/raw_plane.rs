@@ -0,0 +1,80 @@
+use libde265_rs::*;
+
+mod common;
+use common::for_first_image;
+
+const CHANNELS: [Channel; 3] = [Channel::Y, Channel::Cb, Channel::Cr];
+
+/// Copies samples of the plane into a packed buffer like
+/// uploading into a staging buffer of GPU does.
+fn upload(raw: RawPlane) -> Vec<u8> {
+    let row_size = raw.width as usize * raw.bytes_per_sample as usize;
+    let mut staging = vec![0u8; row_size * raw.height as usize];
+    for (y, dst) in staging.chunks_exact_mut(row_size).enumerate() {
+        unsafe {
+            let src = raw.ptr.as_ptr().add(y * raw.pitch);
+            std::ptr::copy_nonoverlapping(src, dst.as_mut_ptr(), row_size);
+        }
+    }
+    staging
+}
+
+/// Returns samples of the plane without padding taken from [`Image::plane()`].
+fn packed_plane(image: &Image<'_>, channel: Channel) -> Vec<u8> {
+    let (data, stride) = image.plane(channel);
+    let bytes_per_sample = image.bits_per_pixel(channel).div_ceil(8) as usize;
+    let row_size = image.width(channel) as usize * bytes_per_sample;
+    data.chunks(stride)
+        .take(image.height(channel) as usize)
+        .flat_map(|row| &row[..row_size])
+        .copied()
+        .collect()
+}
+
+#[test]
+fn upload_planes_by_raw_pointers() {
+    for path in ["./data/girlshy.h265", "./data/gradient_10bit.h265"] {
+        for_first_image(path, |image| {
+            for channel in CHANNELS {
+                let raw = image.plane_raw(channel).unwrap();
+                let (data, stride) = image.plane(channel);
+                assert_eq!(raw.ptr.as_ptr().cast_const(), data.as_ptr());
+                assert_eq!(raw.pitch, stride);
+                assert_eq!(raw.width, image.width(channel));
+                assert_eq!(raw.height, image.height(channel));
+                assert_eq!(
+                    raw.bytes_per_sample as u32,
+                    image.bits_per_pixel(channel).div_ceil(8)
+                );
+                assert_eq!(upload(raw), packed_plane(image, channel));
+
+                let mut row_ptrs = Vec::new();
+                image.for_each_row_ptr(channel, |ptr| row_ptrs.push(ptr));
+                assert_eq!(row_ptrs.len(), raw.height as usize);
+                for (y, ptr) in row_ptrs.into_iter().enumerate() {
+                    assert_eq!(ptr, data[y * stride..].as_ptr());
+                }
+            }
+
+            let owned = image.to_owned_trimmed();
+            let raw = owned.plane_raw(Channel::Cb).unwrap();
+            assert_eq!(
+                raw.pitch,
+                raw.width as usize * raw.bytes_per_sample as usize
+            );
+            assert_eq!(upload(raw), packed_plane(image, Channel::Cb));
+        });
+    }
+}
+
+#[test]
+fn raw_planes_of_monochrome_picture() {
+    for_first_image("./data/gradient_mono.h265", |image| {
+        assert!(image.plane_raw(Channel::Y).is_some());
+        assert_eq!(image.plane_raw(Channel::Cb), None);
+        assert_eq!(image.to_owned().plane_raw(Channel::Cr), None);
+        let mut rows = 0;
+        image.for_each_row_ptr(Channel::Cr, |_| rows += 1);
+        assert_eq!(rows, 0);
+    });
+}
@@ -0,0 +1,326 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::ops::ControlFlow;
+
+use libde265_rs::*;
+
+mod common;
+use common::first_frame;
+
+/// Allocator that counts allocations made by the current thread.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(|count| count.get())
+}
+
+/// Maximal allowed difference of a component from the reference value.
+const TOLERANCE: u8 = 2;
+
+/// Reference values were computed offline from the decoded planes
+/// with double precision arithmetic.
+fn check_pixels(rgb: &RgbImageBuffer, expected: &[((u32, u32), [u8; 3])]) {
+    for &((x, y), expected_rgb) in expected {
+        let rgb = rgb.pixel(x, y).unwrap();
+        for (actual, expected) in rgb.into_iter().zip(expected_rgb) {
+            assert!(
+                actual.abs_diff(expected) <= TOLERANCE,
+                "pixel ({x}, {y}): {rgb:?} != {expected_rgb:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn rgb_from_bt601_limited_range() {
+    let image = first_frame("./data/girlshy.h265");
+    assert_eq!(image.matrix_coefficients(), 2);
+    assert!(!image.full_range());
+
+    let rgb = image.to_rgb8();
+    assert_eq!(rgb.width, 316);
+    assert_eq!(rgb.height, 240);
+    assert_eq!(rgb.data.len(), 316 * 240 * 3);
+    assert_eq!(rgb.pixel(316, 0), None);
+    assert_eq!(rgb.pixel(0, 240), None);
+    check_pixels(
+        &rgb,
+        &[
+            ((0, 0), [11, 9, 5]),
+            ((1, 1), [16, 14, 10]),
+            ((100, 50), [77, 73, 69]),
+            ((157, 120), [91, 90, 86]),
+            ((315, 239), [12, 12, 8]),
+            ((200, 181), [45, 43, 37]),
+            ((63, 10), [90, 83, 83]),
+            ((31, 47), [108, 107, 98]),
+        ],
+    );
+}
+
+#[test]
+fn rgb_from_high_bit_depth() {
+    let image = first_frame("./data/gradient_10bit.h265");
+    assert_eq!(image.bits_per_pixel(Channel::Y), 10);
+
+    let rgb = image.to_rgb8();
+    assert_eq!((rgb.width, rgb.height), (64, 64));
+    check_pixels(
+        &rgb,
+        &[
+            ((0, 0), [84, 0, 0]),
+            ((1, 1), [89, 0, 0]),
+            ((10, 5), [127, 0, 0]),
+            ((31, 32), [255, 84, 32]),
+            ((40, 60), [255, 166, 129]),
+            ((63, 0), [255, 66, 64]),
+            ((20, 20), [194, 37, 0]),
+            ((55, 7), [255, 68, 54]),
+        ],
+    );
+}
+
+#[test]
+fn rgb_from_bt709_full_range() {
+    let image = first_frame("./data/gradient_bt709.h265");
+    assert_eq!(image.matrix_coefficients(), 1);
+    assert!(image.full_range());
+
+    let rgb = image.to_rgb8();
+    assert_eq!((rgb.width, rgb.height), (64, 64));
+    check_pixels(
+        &rgb,
+        &[
+            ((0, 0), [101, 0, 0]),
+            ((1, 1), [103, 0, 0]),
+            ((10, 5), [117, 12, 0]),
+            ((31, 32), [129, 127, 123]),
+            ((40, 60), [115, 225, 232]),
+            ((63, 0), [228, 86, 238]),
+            ((20, 20), [119, 73, 35]),
+            ((55, 7), [205, 93, 210]),
+            ((63, 63), [161, 255, 255]),
+        ],
+    );
+}
+
+#[test]
+fn gray_rgb_from_monochrome() {
+    let image = first_frame("./data/gradient_mono.h265");
+    assert_eq!(image.chroma_format(), ChromaFormat::Mono);
+
+    let rgb = image.to_rgb8();
+    assert_eq!((rgb.width, rgb.height), (64, 64));
+    assert!(rgb.data.chunks(3).all(|p| p[0] == p[1] && p[1] == p[2]));
+    check_pixels(
+        &rgb,
+        &[
+            ((0, 0), [0, 0, 0]),
+            ((5, 2), [0, 0, 0]),
+            ((10, 20), [51, 51, 51]),
+            ((63, 63), [255, 255, 255]),
+        ],
+    );
+}
+
+#[test]
+fn rgb_from_image_and_owned_image_are_equal() {
+    let mut decoder = Decoder::new().unwrap();
+    let file = std::fs::File::open("./data/girlshy.h265").unwrap();
+    let mut count = 0;
+    decoder
+        .decode_from_reader(file, |image| {
+            assert!(image.to_rgb8() == image.to_owned_trimmed().to_rgb8());
+            count += 1;
+            if count == 3 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .unwrap();
+    assert_eq!(count, 3);
+}
+
+#[test]
+fn rgba_with_alpha() {
+    let image = first_frame("./data/girlshy.h265");
+    let rgb = image.to_rgb8();
+    let rgba = image.to_rgba8(200);
+    assert_eq!((rgba.width, rgba.height), (rgb.width, rgb.height));
+    assert_eq!(rgba.data.len(), 316 * 240 * 4);
+    assert_eq!(rgba.pixel(100, 50), Some([77, 73, 69, 200]));
+    assert_eq!(rgba.pixel(316, 0), None);
+    for (rgba, rgb) in rgba.data.chunks(4).zip(rgb.data.chunks(3)) {
+        assert_eq!(&rgba[..3], rgb);
+        assert_eq!(rgba[3], 200);
+    }
+
+    let image = first_frame("./data/gradient_mono.h265");
+    let rgba = image.to_rgba8(u8::MAX);
+    assert_eq!(rgba.pixel(10, 20), Some([51, 51, 51, 255]));
+}
+
+#[test]
+fn rgba_into_reused_buffer_does_not_allocate() {
+    let mut decoder = Decoder::new().unwrap();
+    let file = std::fs::File::open("./data/girlshy.h265").unwrap();
+    let mut buffer = Vec::new();
+    let mut frames = 0;
+    decoder
+        .decode_from_reader(file, |image| {
+            let before = allocations();
+            let size = image.into_rgba8(255, &mut buffer);
+            let allocated = allocations() - before;
+            assert_eq!(size, (316, 240));
+            assert_eq!(buffer.len(), 316 * 240 * 4);
+            if frames == 0 {
+                assert_eq!(allocated, 1);
+            } else {
+                assert_eq!(allocated, 0, "frame {frames}");
+            }
+            if frames % 10 == 0 {
+                assert!(buffer == image.to_rgba8(255).data);
+            }
+            frames += 1;
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert_eq!(frames, 75);
+
+    // The owned image converts into the same buffer too.
+    let image = first_frame("./data/girlshy.h265");
+    let capacity = buffer.capacity();
+    let before = allocations();
+    image.into_rgba8(255, &mut buffer);
+    assert_eq!(allocations(), before);
+    assert_eq!(buffer.capacity(), capacity);
+}
+
+#[test]
+fn rgb16_from_high_bit_depth() {
+    let image = first_frame("./data/gradient_10bit.h265");
+    let rgb = image.to_rgb16();
+    assert_eq!((rgb.width, rgb.height), (64, 64));
+    assert_eq!(rgb.data.len(), 64 * 64 * 3);
+
+    let mut levels = rgb.data.clone();
+    levels.sort_unstable();
+    levels.dedup();
+    assert!(levels.len() > 256, "{} levels", levels.len());
+
+    // Reference values were computed offline from the known samples
+    // of the stream with double precision arithmetic.
+    let expected: [((u32, u32), [u16; 3]); 8] = [
+        ((7, 55), [60529, 24831, 1477]),
+        ((12, 49), [60956, 23458, 2175]),
+        ((29, 26), [60777, 17161, 2916]),
+        ((39, 13), [61033, 13817, 3712]),
+        ((42, 6), [59179, 10884, 2021]),
+        ((44, 11), [63853, 14839, 6804]),
+        ((0, 0), [21464, 0, 0]),
+        ((63, 63), [65535, 55218, 54636]),
+    ];
+    for ((x, y), expected_rgb) in expected {
+        let rgb = rgb.pixel(x, y).unwrap();
+        for (actual, expected) in rgb.into_iter().zip(expected_rgb) {
+            assert!(
+                actual.abs_diff(expected) <= 16,
+                "pixel ({x}, {y}): {rgb:?} != {expected_rgb:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn rgb16_from_8bit_source() {
+    let image = first_frame("./data/girlshy.h265");
+    let rgb8 = image.to_rgb8();
+    let rgb16 = image.to_rgb16();
+    assert_eq!((rgb16.width, rgb16.height), (rgb8.width, rgb8.height));
+    assert_eq!(rgb16.pixel(316, 0), None);
+    // Both buffers are rounded from the same values.
+    for (&c16, &c8) in rgb16.data.iter().zip(&rgb8.data) {
+        assert!(c16.abs_diff(c8 as u16 * 257) <= 129, "{c16} != {c8}");
+    }
+}
+
+#[test]
+fn bgra_channel_order() {
+    let image = first_frame("./data/gradient_10bit.h265");
+    // The pixel is strongly red.
+    let rgba = image.to_rgba8(255);
+    assert_eq!(rgba.pixel(31, 32), Some([255, 84, 32, 255]));
+    let bgra = image.to_bgra8(255, 1);
+    assert_eq!((bgra.width, bgra.height, bgra.stride), (64, 64, 64 * 4));
+    assert_eq!(bgra.pixel(31, 32), Some([32, 84, 255, 255]));
+    assert_eq!(bgra.pixel(64, 0), None);
+    for (bgra, rgba) in bgra.data.chunks(4).zip(rgba.data.chunks(4)) {
+        assert_eq!(bgra, [rgba[2], rgba[1], rgba[0], rgba[3]]);
+    }
+}
+
+#[test]
+fn bgra_with_aligned_rows() {
+    let image = first_frame("./data/girlshy.h265");
+    let rgba = image.to_rgba8(128);
+    for row_alignment in [0, 1, 4] {
+        let bgra = image.to_bgra8(128, row_alignment);
+        assert_eq!(bgra.stride, 316 * 4);
+        assert_eq!(bgra.data.len(), 316 * 4 * 240);
+    }
+
+    for row_alignment in [64, 256] {
+        let bgra = image.to_bgra8(128, row_alignment);
+        assert_eq!((bgra.width, bgra.height), (316, 240));
+        assert_eq!(bgra.stride, 1280);
+        assert_eq!(bgra.data.len(), 1280 * 240);
+        for (bgra_row, rgba_row) in bgra.data.chunks(bgra.stride).zip(rgba.data.chunks(316 * 4)) {
+            let (pixels, padding) = bgra_row.split_at(316 * 4);
+            assert!(padding.iter().all(|&v| v == 0));
+            for (bgra, rgba) in pixels.chunks(4).zip(rgba_row.chunks(4)) {
+                assert_eq!(bgra, [rgba[2], rgba[1], rgba[0], rgba[3]]);
+            }
+        }
+        let [b, g, r, a] = bgra.pixel(100, 50).unwrap();
+        assert_eq!([r, g, b, a], [77, 73, 69, 128]);
+    }
+}
+
+#[test]
+fn bgra_into_reused_buffer() {
+    let image = first_frame("./data/girlshy.h265");
+    let mut buffer = Vec::new();
+    let layout = image.into_bgra8(255, 256, &mut buffer);
+    assert_eq!(layout, (316, 240, 1280));
+    assert!(buffer == image.to_bgra8(255, 256).data);
+
+    let capacity = buffer.capacity();
+    let before = allocations();
+    assert_eq!(image.into_bgra8(255, 256, &mut buffer), layout);
+    assert_eq!(allocations(), before);
+    assert_eq!(buffer.capacity(), capacity);
+}
@@ -0,0 +1,164 @@
+use libde265_rs::*;
+
+mod common;
+use common::for_first_image;
+
+const DISPLAY: MasteringDisplay = MasteringDisplay {
+    primaries: [(13250, 34500), (7500, 3000), (34000, 16000)],
+    white_point: (15635, 16450),
+    max_luminance: 10_000_000,
+    // It is coded as 00 00 00 32 that requires an emulation prevention byte.
+    min_luminance: 50,
+};
+
+fn mastering_display_payload(display: &MasteringDisplay) -> Vec<u8> {
+    let mut payload = Vec::new();
+    for (x, y) in display.primaries {
+        payload.extend_from_slice(&x.to_be_bytes());
+        payload.extend_from_slice(&y.to_be_bytes());
+    }
+    payload.extend_from_slice(&display.white_point.0.to_be_bytes());
+    payload.extend_from_slice(&display.white_point.1.to_be_bytes());
+    payload.extend_from_slice(&display.max_luminance.to_be_bytes());
+    payload.extend_from_slice(&display.min_luminance.to_be_bytes());
+    payload
+}
+
+/// Returns prefix SEI NAL unit with the given messages
+/// preceded by a user data unregistered message.
+fn sei_nal(messages: &[(u8, Vec<u8>)]) -> Vec<u8> {
+    // User data unregistered message with 16 bytes of UUID.
+    let mut rbsp = vec![5, 16];
+    rbsp.extend_from_slice(&[0xaa; 16]);
+    for (payload_type, payload) in messages {
+        rbsp.extend_from_slice(&[*payload_type, payload.len() as u8]);
+        rbsp.extend_from_slice(payload);
+    }
+    rbsp.push(0x80);
+
+    let mut nal = vec![39 << 1, 1];
+    let mut zeros = 0;
+    for byte in rbsp {
+        if zeros >= 2 && byte <= 3 {
+            nal.push(3);
+            zeros = 0;
+        }
+        zeros = if byte == 0 { zeros + 1 } else { 0 };
+        nal.push(byte);
+    }
+    nal
+}
+
+/// Decode NAL units of the clip with the SEI NAL unit inserted
+/// before the first VCL NAL unit.
+fn decode_with_sei(sei: Option<&[u8]>) -> Vec<OwnedImage> {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let mut decoder = Decoder::new().unwrap();
+    let mut sei = sei;
+    for nal in split_annex_b(&data) {
+        let unit_type = NalUnitType::from_u8((nal.data[0] >> 1) & 0x3f);
+        if unit_type.is_vcl() {
+            if let Some(sei) = sei.take() {
+                decoder.push_nal(sei, 0, 0).unwrap();
+            }
+        }
+        decoder.push_nal(nal.data, 0, 0).unwrap();
+    }
+    decoder.flush_data().unwrap();
+    let mut images = Vec::new();
+    loop {
+        let result = decoder.decode().unwrap();
+        for image in decoder.frames() {
+            let owned = image.to_owned();
+            assert_eq!(owned.mastering_display(), image.mastering_display());
+            assert_eq!(owned.content_light_level(), image.content_light_level());
+            assert_eq!(
+                owned.alternative_transfer_characteristics(),
+                image.alternative_transfer_characteristics()
+            );
+            images.push(owned);
+        }
+        if result == DecodeResult::Done {
+            break;
+        }
+    }
+    images
+}
+
+#[test]
+fn mastering_display_from_sei() {
+    let sei = sei_nal(&[(137, mastering_display_payload(&DISPLAY))]);
+    assert!(sei.windows(3).any(|w| w == [0, 0, 3]));
+    let images = decode_with_sei(Some(&sei));
+    assert_eq!(images.len(), 75);
+    // The message applies to all pictures of the coded video sequence.
+    for image in images {
+        assert_eq!(image.mastering_display(), Some(&DISPLAY));
+        assert_eq!(image.content_light_level(), None);
+    }
+}
+
+#[test]
+fn content_light_level_from_sei() {
+    // MaxCLL is 1000, MaxFALL is unknown.
+    let sei = sei_nal(&[
+        (137, mastering_display_payload(&DISPLAY)),
+        (144, vec![0x03, 0xe8, 0, 0]),
+    ]);
+    let images = decode_with_sei(Some(&sei));
+    assert_eq!(images.len(), 75);
+    let expected = ContentLightLevel {
+        max_cll: Some(1000),
+        max_fall: None,
+    };
+    for image in images {
+        assert_eq!(image.content_light_level(), Some(expected));
+        assert_eq!(image.mastering_display(), Some(&DISPLAY));
+    }
+}
+
+#[test]
+fn alternative_transfer_characteristics_from_sei() {
+    // ARIB STD-B67 (HLG) preceded by a message of unknown type.
+    let sei = sei_nal(&[(200, vec![1, 2, 3]), (147, vec![18])]);
+    let images = decode_with_sei(Some(&sei));
+    assert_eq!(images.len(), 75);
+    for image in images {
+        assert_eq!(
+            image.alternative_transfer_characteristics(),
+            Some(TransferCharacteristics::AribStdB67)
+        );
+        let color_info = image.color_info();
+        assert_eq!(color_info.transfer, TransferCharacteristics::Unspecified);
+        assert_eq!(
+            color_info.preferred_transfer(),
+            TransferCharacteristics::AribStdB67
+        );
+        assert!(color_info.is_hdr());
+    }
+
+    let images = decode_with_sei(None);
+    let color_info = images[0].color_info();
+    assert_eq!(images[0].alternative_transfer_characteristics(), None);
+    assert_eq!(color_info.preferred_transfer(), color_info.transfer);
+    assert!(!color_info.is_hdr());
+}
+
+#[test]
+fn stream_without_mastering_display() {
+    let images = decode_with_sei(None);
+    assert_eq!(images.len(), 75);
+    assert!(
+        images
+            .iter()
+            .all(|image| image.mastering_display().is_none())
+    );
+}
+
+#[test]
+fn mastering_display_of_bytestream() {
+    for_first_image("./data/girlshy.h265", |image| {
+        assert_eq!(image.mastering_display(), None);
+        assert_eq!(image.content_light_level(), None);
+    });
+}
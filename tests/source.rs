@@ -0,0 +1,79 @@
+use libde265_rs::*;
+
+#[test]
+fn decode_from_annex_b_file_source() {
+    let mut source = AnnexBFileSource::open("./data/girlshy.h265").unwrap();
+    let mut decoder = Decoder::new().unwrap();
+    let mut images_count = 0;
+    decoder
+        .run_from_source(&mut source, |image| {
+            assert_eq!(image.width(Channel::Y), 316);
+            images_count += 1;
+        })
+        .unwrap();
+    assert_eq!(images_count, 75);
+    assert!(source.next_nal().unwrap().is_none());
+}
+
+/// Source of access units with timestamps, like a demuxer of a container.
+struct AccessUnitSource<'a> {
+    access_units: Vec<Vec<&'a [u8]>>,
+    /// Indexes of the next access unit and NAL unit inside it.
+    au_index: usize,
+    nal_index: usize,
+}
+
+impl<'a> AccessUnitSource<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        // Every picture of the stream has a single slice, so an access unit
+        // ends with the first VCL NAL unit after non-VCL ones.
+        let mut access_units: Vec<Vec<&[u8]>> = vec![vec![]];
+        for nal in split_annex_b(data) {
+            access_units.last_mut().unwrap().push(nal.data);
+            if (nal.data[0] >> 1) & 0x3f < 32 {
+                access_units.push(vec![]);
+            }
+        }
+        access_units.retain(|nals| !nals.is_empty());
+        Self {
+            access_units,
+            au_index: 0,
+            nal_index: 0,
+        }
+    }
+}
+
+impl NalSource for AccessUnitSource<'_> {
+    fn next_nal(&mut self) -> std::result::Result<Option<SourceNal<'_>>, StreamError> {
+        let Some(nals) = self.access_units.get(self.au_index) else {
+            return Ok(None);
+        };
+        let nal = SourceNal {
+            data: nals[self.nal_index],
+            pts: self.au_index as i64,
+            is_access_unit_end: self.nal_index + 1 == nals.len(),
+        };
+        self.nal_index += 1;
+        if nal.is_access_unit_end {
+            self.au_index += 1;
+            self.nal_index = 0;
+        }
+        Ok(Some(nal))
+    }
+}
+
+#[test]
+fn decode_from_custom_source() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let mut source = AccessUnitSource::new(&data);
+    let mut decoder = Decoder::new().unwrap();
+    let mut pts = Vec::new();
+    decoder
+        .run_from_source(&mut source, |image| pts.push(image.pts()))
+        .unwrap();
+    assert_eq!(pts.len(), 75);
+    // All pictures have different timestamps of their access units.
+    pts.sort_unstable();
+    pts.dedup();
+    assert_eq!(pts.len(), 75);
+}
@@ -0,0 +1,45 @@
+use std::ops::ControlFlow;
+
+use libde265_rs::*;
+
+#[test]
+fn worker_threads_are_limited_by_global_budget() {
+    assert_eq!(global_thread_budget(), None);
+    set_global_thread_budget(Some(4));
+    assert_eq!(global_thread_budget(), Some(4));
+
+    let mut decoders: Vec<Decoder> = (0..6).map(|_| Decoder::new().unwrap()).collect();
+    for decoder in decoders.iter_mut() {
+        decoder.start_worker_threads(2).unwrap();
+    }
+    let started: Vec<u32> = decoders.iter().map(|d| d.num_worker_threads()).collect();
+    assert_eq!(started, [2, 2, 0, 0, 0, 0]);
+    assert_eq!(global_worker_threads(), 4);
+
+    for decoder in decoders.iter_mut() {
+        let file = std::fs::File::open("./data/girlshy.h265").unwrap();
+        let mut images_count = 0;
+        decoder
+            .decode_from_reader(file, |_| {
+                images_count += 1;
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+        assert_eq!(images_count, 75);
+    }
+
+    // Threads return into the budget when decoders are dropped.
+    decoders.truncate(1);
+    assert_eq!(global_worker_threads(), 2);
+    let mut decoder = Decoder::new().unwrap();
+    decoder.start_worker_threads(8).unwrap();
+    assert_eq!(decoder.num_worker_threads(), 2);
+    drop(decoders);
+    drop(decoder);
+    assert_eq!(global_worker_threads(), 0);
+
+    set_global_thread_budget(None);
+    let mut decoder = Decoder::new().unwrap();
+    decoder.start_worker_threads(8).unwrap();
+    assert_eq!(decoder.num_worker_threads(), 8);
+}
@@ -0,0 +1,53 @@
+use libde265_rs::*;
+
+mod common;
+use common::for_first_image;
+
+#[test]
+fn timing_of_25_fps_stream() {
+    for_first_image("./data/girlshy.h265", |image| {
+        let timing = image.timing().unwrap();
+        assert_eq!(
+            timing.time_scale as f64 / timing.num_units_in_tick as f64,
+            25.0
+        );
+        let fps = image.frames_per_second().unwrap();
+        assert!((fps - 25.0).abs() < 1e-9, "{fps}");
+
+        let owned = image.to_owned();
+        assert_eq!(owned.timing(), Some(timing));
+        assert_eq!(owned.frames_per_second(), Some(fps));
+    });
+}
+
+#[test]
+fn timing_of_ntsc_stream() {
+    for_first_image("./data/gradient_timing.h265", |image| {
+        assert_eq!(
+            image.timing(),
+            Some(FrameTiming {
+                num_units_in_tick: 1001,
+                time_scale: 30000
+            })
+        );
+        let fps = image.frames_per_second().unwrap();
+        assert!((fps - 29.97).abs() < 0.001, "{fps}");
+    });
+    let file = std::fs::File::open("./data/gradient_timing.h265").unwrap();
+    let info = probe_stream(file, 4096).unwrap();
+    assert_eq!(info.frame_rate, Some((30000, 1001)));
+}
+
+#[test]
+fn stream_without_timing() {
+    // VUI without timing info
+    for_first_image("./data/gradient_bt709.h265", |image| {
+        assert_eq!(image.timing(), None);
+        assert_eq!(image.frames_per_second(), None);
+    });
+    // No VUI
+    for_first_image("./data/gradient_10bit.h265", |image| {
+        assert_eq!(image.timing(), None);
+        assert_eq!(image.frames_per_second(), None);
+    });
+}
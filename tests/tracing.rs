@@ -0,0 +1,96 @@
+#![cfg(feature = "tracing")]
+use std::io;
+use std::ops::ControlFlow;
+use std::sync::{Arc, Mutex};
+
+use libde265_rs::*;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// Writer that collects formatted events in memory.
+#[derive(Clone, Default)]
+struct CapturedLog(Arc<Mutex<Vec<u8>>>);
+
+impl CapturedLog {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl io::Write for CapturedLog {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for CapturedLog {
+    type Writer = Self;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+#[test]
+fn decoding_emits_events() {
+    let log = CapturedLog::default();
+    let _guard = tracing_subscriber::fmt()
+        .with_max_level(LevelFilter::TRACE)
+        .with_writer(log.clone())
+        .with_ansi(false)
+        .with_span_events(FmtSpan::CLOSE)
+        .finish()
+        .set_default();
+
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let mut decoder = Decoder::new().unwrap();
+    // Cut the slice data of the last picture in half to get warnings
+    // (they aren't reported by worker threads).
+    let last_nal = split_annex_b(&data).last().unwrap();
+    let end = last_nal.offset as usize + last_nal.data.len() / 2;
+    decoder
+        .decode_from_reader(&data[..end], |_| ControlFlow::Continue(()))
+        .unwrap();
+    assert!(decoder.input_mut().warnings().count() > 0);
+    decoder.reset().unwrap();
+    decoder.close().unwrap();
+
+    let mut decoder = Decoder::new().unwrap();
+    decoder.start_worker_threads(1).unwrap();
+    let mut images_count = 0;
+    decoder
+        .decode_from_reader(data.as_slice(), |image| {
+            // Access to planes doesn't emit anything.
+            let (plane, _) = image.plane(Channel::Y);
+            assert!(!plane.is_empty());
+            images_count += 1;
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert_eq!(images_count, 75);
+    decoder.close().unwrap();
+
+    let text = log.text();
+    let count = |message: &str| text.lines().filter(|l| l.contains(message)).count();
+    let spans = |name: &str| {
+        let prefix = format!("{name}: ");
+        let lines = text.lines();
+        lines
+            .filter(|l| l.contains(&prefix) && l.contains("close"))
+            .count()
+    };
+    assert_eq!(count("worker threads started"), 1);
+    assert_eq!(count("picture output pts=0 width=316 height=240"), 2 * 75);
+    // A span per call of `decode()`.
+    assert!(spans("decode") > 2 * 75);
+    assert!(count("decoder warning") > 0);
+    assert_eq!(count("decoder reset"), 1);
+    assert_eq!(count("worker threads stopped"), 1);
+}
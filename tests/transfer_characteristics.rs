@@ -0,0 +1,85 @@
+use libde265_rs::*;
+
+mod common;
+use common::for_first_image;
+
+/// Codes from table 3 of ITU-T H.273.
+const H273_CODES: [(u8, TransferCharacteristics); 18] = [
+    (1, TransferCharacteristics::Bt709),
+    (2, TransferCharacteristics::Unspecified),
+    (4, TransferCharacteristics::Gamma22),
+    (5, TransferCharacteristics::Gamma28),
+    (6, TransferCharacteristics::Smpte170M),
+    (7, TransferCharacteristics::Smpte240M),
+    (8, TransferCharacteristics::Linear),
+    (9, TransferCharacteristics::Log100),
+    (10, TransferCharacteristics::Log100Sqrt10),
+    (11, TransferCharacteristics::Iec61966),
+    (12, TransferCharacteristics::Bt1361),
+    (13, TransferCharacteristics::Srgb),
+    (14, TransferCharacteristics::Bt2020_10),
+    (15, TransferCharacteristics::Bt2020_12),
+    (16, TransferCharacteristics::SmpteSt2084),
+    (17, TransferCharacteristics::SmpteSt428),
+    (18, TransferCharacteristics::AribStdB67),
+    (0, TransferCharacteristics::Reserved(0)),
+];
+
+#[test]
+fn transfer_characteristics_codes() {
+    for (code, characteristics) in H273_CODES {
+        assert_eq!(TransferCharacteristics::from_code(code), characteristics);
+        assert_eq!(characteristics.code(), code);
+    }
+    for code in 0..=u8::MAX {
+        let characteristics = TransferCharacteristics::from_code(code);
+        assert_eq!(characteristics.code(), code);
+        assert_eq!(u8::from(characteristics), code);
+        assert_eq!(TransferCharacteristics::from(code), characteristics);
+        let is_reserved = matches!(code, 0 | 3 | 19..);
+        assert_eq!(
+            characteristics == TransferCharacteristics::Reserved(code),
+            is_reserved
+        );
+    }
+    assert_eq!(
+        TransferCharacteristics::SmpteSt2084.to_string(),
+        "SMPTE ST 2084 (PQ)"
+    );
+    assert_eq!(
+        TransferCharacteristics::Reserved(19).to_string(),
+        "Reserved (19)"
+    );
+}
+
+#[test]
+fn hdr_transfer_characteristics() {
+    let hdr_codes: Vec<u8> = (0..=u8::MAX)
+        .filter(|&code| TransferCharacteristics::from_code(code).is_hdr())
+        .collect();
+    assert_eq!(hdr_codes, [16, 18]);
+    assert!(TransferCharacteristics::SmpteSt2084.is_hdr());
+    assert!(TransferCharacteristics::AribStdB67.is_hdr());
+    assert!(!TransferCharacteristics::Bt709.is_hdr());
+    assert!(!TransferCharacteristics::Bt2020_10.is_hdr());
+    assert!(!TransferCharacteristics::Unspecified.is_hdr());
+}
+
+#[test]
+fn transfer_characteristics_of_images() {
+    for (path, expected) in [
+        ("./data/gradient_bt709.h265", TransferCharacteristics::Bt709),
+        ("./data/girlshy.h265", TransferCharacteristics::Unspecified),
+    ] {
+        let mut result = None;
+        for_first_image(path, |image| {
+            let owned = image.to_owned();
+            assert_eq!(
+                owned.transfer_characteristics_enum(),
+                image.transfer_characteristics_enum()
+            );
+            result = Some(image.transfer_characteristics_enum());
+        });
+        assert_eq!(result, Some(expected), "{path}");
+    }
+}
@@ -0,0 +1,287 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use libde265_rs::*;
+
+fn decode_all(decoder: &mut Decoder, mut on_image: impl FnMut(Image<'_>)) {
+    decoder.flush_data().unwrap();
+    loop {
+        match decoder.decode() {
+            Ok(DecodeResult::Done) => break,
+            Ok(DecodeResult::CallAgain | DecodeResult::OutputQueueFull) => {
+                decoder.frames().for_each(&mut on_image);
+            }
+            Ok(DecodeResult::NeedsMoreInput) => unreachable!("all data has been pushed"),
+            Err(err) => panic!("{:?}", err),
+        }
+    }
+}
+
+#[test]
+fn attach_string_to_each_frame() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let mut decoder = Decoder::new().unwrap();
+    let mut frame_index = 0;
+    for nal in split_annex_b(&data) {
+        let payload = format!("frame {frame_index}");
+        decoder
+            .push_nal_with(nal.data, frame_index, payload)
+            .unwrap();
+        // first_slice_segment_in_pic_flag of VCL NAL unit
+        if (nal.data[0] >> 1) < 32 && nal.data[2] & 0x80 != 0 {
+            frame_index += 1;
+        }
+    }
+    assert_eq!(frame_index, 75);
+
+    let mut images_count = 0;
+    decode_all(&mut decoder, |image| {
+        images_count += 1;
+        // Wrong type
+        assert_eq!(image.take_user_data::<u32>(), None);
+        let payload = image.take_user_data::<String>().unwrap();
+        assert_eq!(payload, format!("frame {}", image.pts()));
+        // Payload can be taken only once
+        assert_eq!(image.take_user_data::<String>(), None);
+    });
+    assert_eq!(images_count, 75);
+}
+
+/// Payload that counts its live instances.
+struct CountedPayload {
+    counter: Arc<AtomicUsize>,
+}
+
+impl CountedPayload {
+    fn new(counter: &Arc<AtomicUsize>) -> Self {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Self {
+            counter: counter.clone(),
+        }
+    }
+}
+
+impl Drop for CountedPayload {
+    fn drop(&mut self) {
+        self.counter.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+fn push_counted_payloads(decoder: &mut Decoder, counter: &Arc<AtomicUsize>) {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    for nal in split_annex_b(&data) {
+        decoder
+            .push_nal_with(nal.data, 0, CountedPayload::new(counter))
+            .unwrap();
+    }
+}
+
+#[test]
+fn payloads_are_not_leaked() {
+    let counter = Arc::new(AtomicUsize::new(0));
+
+    // Payloads of images that were output and not taken are dropped.
+    let mut decoder = Decoder::new().unwrap();
+    push_counted_payloads(&mut decoder, &counter);
+    // Payloads of NAL units that don't start a picture are dropped immediately.
+    assert_eq!(counter.load(Ordering::Relaxed), 75);
+    decode_all(&mut decoder, |_| {});
+    assert_eq!(counter.load(Ordering::Relaxed), 0);
+
+    // Payloads are dropped on reset.
+    push_counted_payloads(&mut decoder, &counter);
+    assert_eq!(counter.load(Ordering::Relaxed), 75);
+    decoder.reset().unwrap();
+    assert_eq!(counter.load(Ordering::Relaxed), 0);
+
+    // Payloads are dropped with the decoder.
+    push_counted_payloads(&mut decoder, &counter);
+    decoder.decode().unwrap();
+    assert!(counter.load(Ordering::Relaxed) > 0);
+    drop(decoder);
+    assert_eq!(counter.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn payloads_of_dropped_pictures_are_not_accumulated() {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let mut data = std::fs::read("./data/girlshy_temporal.h265").unwrap();
+    for _ in 0..2 {
+        data.extend_from_within(..);
+    }
+
+    let mut decoder = Decoder::new().unwrap();
+    // Pictures of the temporal layer 1 are never output.
+    decoder.input_mut().set_limit_tid(0);
+    let mut images_count = 0;
+    for nal in split_annex_b(&data) {
+        decoder
+            .push_nal_with(nal.data, 0, CountedPayload::new(&counter))
+            .unwrap();
+        while let DecodeResult::CallAgain | DecodeResult::OutputQueueFull =
+            decoder.decode().unwrap()
+        {
+            decoder.frames().for_each(|_| images_count += 1);
+        }
+        // Payloads are kept only for pictures that may be still output.
+        assert!(counter.load(Ordering::Relaxed) <= 48);
+    }
+    decode_all(&mut decoder, |_| images_count += 1);
+    assert_eq!(images_count, 4 * 35);
+    assert!(counter.load(Ordering::Relaxed) <= 32);
+    drop(decoder);
+    assert_eq!(counter.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn user_data_ptr() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let mut decoder = Decoder::new().unwrap();
+    decoder.push_data(&data, 0, 0x1234).unwrap();
+    let mut images_count = 0;
+    decode_all(&mut decoder, |image| {
+        images_count += 1;
+        let ptr = image.user_data_ptr().unwrap();
+        assert_eq!(ptr.as_ptr() as usize, 0x1234);
+        assert_eq!(image.to_owned().user_data_ptr(), Some(ptr));
+    });
+    assert_eq!(images_count, 75);
+
+    let mut decoder = Decoder::new().unwrap();
+    for nal in split_annex_b(&data) {
+        decoder.push_nal(nal.data, 0, 0).unwrap();
+    }
+    decode_all(&mut decoder, |image| {
+        assert_eq!(image.user_data_ptr(), None);
+    });
+}
+
+#[test]
+fn mix_push_data_and_push_nal() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let mut decoder = Decoder::new().unwrap();
+    let mut frame_index = 0;
+    for nal in split_annex_b(&data) {
+        // Small user data values are the same as internal tokens
+        // of pictures pushed by push_nal() could be.
+        let user_data = frame_index as usize + 1;
+        if frame_index % 2 == 0 {
+            decoder.push_nal(nal.data, frame_index, user_data).unwrap();
+        } else {
+            let bytestream = [&[0, 0, 1], nal.data].concat();
+            decoder
+                .push_data(&bytestream, frame_index, user_data)
+                .unwrap();
+            decoder.push_end_of_nal();
+        }
+        // first_slice_segment_in_pic_flag of VCL NAL unit
+        if (nal.data[0] >> 1) < 32 && nal.data[2] & 0x80 != 0 {
+            frame_index += 1;
+        }
+    }
+
+    let mut images_count = 0;
+    decode_all(&mut decoder, |image| {
+        images_count += 1;
+        assert_eq!(image.user_data(), image.pts() as usize + 1);
+        assert_eq!(image.decode_latency().is_some(), image.pts() % 2 == 0);
+    });
+    assert_eq!(images_count, 75);
+}
+
+#[test]
+fn borrow_boxed_payloads() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let mut decoder = Decoder::new().unwrap();
+    let mut frame_index = 0;
+    for nal in split_annex_b(&data) {
+        let payload = Box::new(format!("frame {frame_index}"));
+        decoder
+            .push_nal_boxed(nal.data, frame_index, payload)
+            .unwrap();
+        if (nal.data[0] >> 1) < 32 && nal.data[2] & 0x80 != 0 {
+            frame_index += 1;
+        }
+    }
+
+    let mut images_count = 0;
+    decode_all(&mut decoder, |image| {
+        images_count += 1;
+        // Wrong type
+        assert_eq!(image.user_data_ref::<u32>(), None);
+        let payload = image.user_data_ref::<String>().unwrap();
+        assert_eq!(payload, &format!("frame {}", image.pts()));
+        assert_eq!(image.user_data_ref::<String>(), Some(payload));
+        // Borrowed payload is owned by the image
+        assert_eq!(image.take_user_data::<String>(), None);
+    });
+    assert_eq!(images_count, 75);
+}
+
+#[test]
+fn payload_of_peeked_picture() {
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let mut decoder = Decoder::new().unwrap();
+    for nal in split_annex_b(&data) {
+        decoder.push_nal_with(nal.data, 0, 42u32).unwrap();
+    }
+    decoder.flush_data().unwrap();
+    while decoder.peek_picture().is_none() {
+        decoder.decode().unwrap();
+    }
+
+    let image = decoder.peek_picture().unwrap();
+    assert_eq!(image.user_data_ref::<u32>(), Some(&42));
+    drop(image);
+    // The payload is given back to the decoder.
+    let image = decoder.next_picture().unwrap();
+    assert_eq!(image.take_user_data::<u32>(), Some(42));
+    assert_eq!(image.user_data_ref::<u32>(), None);
+}
+
+#[test]
+fn borrowed_payloads_are_not_leaked() {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let data = std::fs::read("./data/girlshy.h265").unwrap();
+    let push_boxed = |decoder: &mut Decoder| {
+        for nal in split_annex_b(&data) {
+            let payload = Box::new(CountedPayload::new(&counter));
+            decoder.push_nal_boxed(nal.data, 0, payload).unwrap();
+        }
+    };
+
+    // Borrowed payloads are dropped with their images.
+    let mut decoder = Decoder::new().unwrap();
+    push_boxed(&mut decoder);
+    assert_eq!(counter.load(Ordering::Relaxed), 75);
+    let mut images_count = 0;
+    decode_all(&mut decoder, |image| {
+        let live = counter.load(Ordering::Relaxed);
+        assert!(image.user_data_ref::<CountedPayload>().is_some());
+        assert_eq!(counter.load(Ordering::Relaxed), live);
+        drop(image);
+        assert_eq!(counter.load(Ordering::Relaxed), live - 1);
+        images_count += 1;
+    });
+    assert_eq!(images_count, 75);
+    assert_eq!(counter.load(Ordering::Relaxed), 0);
+
+    // Payloads of pending pictures are dropped on reset.
+    push_boxed(&mut decoder);
+    decoder.decode().unwrap();
+    decoder.reset().unwrap();
+    assert_eq!(counter.load(Ordering::Relaxed), 0);
+
+    // Payloads of pending pictures are dropped with the decoder.
+    push_boxed(&mut decoder);
+    decoder.flush_data().unwrap();
+    while decoder.peek_picture().is_none() {
+        decoder.decode().unwrap();
+    }
+    let image = decoder.peek_picture().unwrap();
+    assert!(image.user_data_ref::<CountedPayload>().is_some());
+    drop(image);
+    assert!(counter.load(Ordering::Relaxed) > 0);
+    drop(decoder);
+    assert_eq!(counter.load(Ordering::Relaxed), 0);
+}
@@ -0,0 +1,96 @@
+use libde265_rs::*;
+
+mod common;
+use common::first_frame;
+
+#[test]
+fn widen_sample() {
+    let replicate = BitExpansion::Replicate;
+    assert_eq!(replicate.widen(0, 8, 16), 0);
+    assert_eq!(replicate.widen(255, 8, 16), 65535);
+    assert_eq!(replicate.widen(128, 8, 16), 0x8080);
+    assert_eq!(replicate.widen(0x12, 8, 16), 0x1212);
+    assert_eq!(replicate.widen(1023, 10, 16), 65535);
+    assert_eq!(replicate.widen(512, 10, 16), 0x8020);
+    assert_eq!(replicate.widen(255, 8, 10), 1023);
+    assert_eq!(replicate.widen(128, 8, 10), 514);
+    assert_eq!(replicate.widen(4095, 12, 16), 65535);
+    assert_eq!(replicate.widen(1000, 10, 10), 1000);
+
+    let shift = BitExpansion::Shift;
+    assert_eq!(shift.widen(255, 8, 16), 65280);
+    assert_eq!(shift.widen(1023, 10, 16), 65472);
+    assert_eq!(shift.widen(255, 8, 10), 1020);
+}
+
+#[test]
+fn widen_8bit_picture() {
+    let image = first_frame("./data/girlshy.h265");
+    let wide = image.to_u16_planes(16, BitExpansion::Replicate);
+    assert_eq!(wide.chroma_format(), image.chroma_format());
+    assert_eq!(wide.pts(), image.pts());
+    assert_eq!(wide.nal_header().unit_type, image.nal_header().unit_type);
+    assert_eq!(wide.full_range(), image.full_range());
+    assert_eq!(wide.colour_primaries(), image.colour_primaries());
+    assert_eq!(wide.matrix_coefficients(), image.matrix_coefficients());
+    for channel in [Channel::Y, Channel::Cb, Channel::Cr] {
+        assert_eq!(wide.bits_per_pixel(channel), 16);
+        assert_eq!(wide.width(channel), image.width(channel));
+        assert_eq!(wide.height(channel), image.height(channel));
+        let (samples, stride) = wide.plane_u16(channel).unwrap();
+        assert_eq!(stride, image.width(channel) as usize);
+        for (y, row) in image.rows(channel).enumerate() {
+            let wide_row = &samples[y * stride..(y + 1) * stride];
+            for (&value, &wide_value) in row.iter().zip(wide_row) {
+                assert_eq!(wide_value, value as u16 * 0x101);
+            }
+        }
+    }
+
+    let shifted = image.to_u16_planes(10, BitExpansion::Shift);
+    assert_eq!(shifted.bits_per_pixel(Channel::Y), 10);
+    assert_eq!(
+        shifted.sample(Channel::Cb, 3, 5),
+        image.sample(Channel::Cb, 3, 5).map(|value| value << 2)
+    );
+}
+
+#[test]
+fn widen_high_bit_depth_picture() {
+    let image = first_frame("./data/gradient_10bit.h265");
+    assert_eq!(image.bits_per_pixel(Channel::Y), 10);
+
+    // Bit depth can't be reduced.
+    let same = image.to_u16_planes(8, BitExpansion::Replicate);
+    assert_eq!(same.bits_per_pixel(Channel::Y), 10);
+    assert!(same.rows(Channel::Y).eq(image.rows(Channel::Y)));
+
+    let wide = image.to_u16_planes(16, BitExpansion::Replicate);
+    let again = wide.to_u16_planes(16, BitExpansion::Shift);
+    for channel in [Channel::Y, Channel::Cb, Channel::Cr] {
+        assert_eq!(wide.bits_per_pixel(channel), 16);
+        assert_eq!(again.bits_per_pixel(channel), 16);
+        assert_eq!(again.plane_u16(channel), wide.plane_u16(channel));
+        assert_eq!(
+            wide.sample(channel, 7, 2),
+            image
+                .sample(channel, 7, 2)
+                .map(|value| BitExpansion::Replicate.widen(value, 10, 16))
+        );
+    }
+}
+
+#[test]
+fn widen_monochrome_picture() {
+    let image = first_frame("./data/gradient_mono.h265");
+    let wide = image.to_u16_planes(12, BitExpansion::Replicate);
+    assert_eq!(wide.chroma_format(), ChromaFormat::Mono);
+    assert_eq!(wide.bits_per_pixel(Channel::Y), 12);
+    assert!(wide.plane(Channel::Cb).0.is_empty());
+    assert_eq!(
+        wide.sample(Channel::Y, 0, 0),
+        image
+            .sample(Channel::Y, 0, 0)
+            .map(|value| value << 4 | value >> 4)
+    );
+}
@@ -0,0 +1,113 @@
+use std::ops::ControlFlow;
+
+use libde265_rs::y4m::{Writer, Y4mParams};
+use libde265_rs::*;
+
+mod common;
+use common::for_first_image;
+
+const GIRLSHY_HEADER: &[u8] = b"YUV4MPEG2 W316 H240 F25:1 Ip A0:0 C420jpeg\n";
+
+#[test]
+fn write_girlshy_into_file() {
+    let info = probe_stream(std::fs::File::open("./data/girlshy.h265").unwrap(), 4096).unwrap();
+    assert_eq!(info.frame_rate, Some((25, 1)));
+
+    let path = std::env::temp_dir().join("libde265_rs_girlshy.y4m");
+    let file = std::fs::File::create(&path).unwrap();
+    let mut writer = Writer::new(
+        std::io::BufWriter::new(file),
+        Y4mParams::from_stream_info(&info),
+    );
+    let mut decoder = Decoder::new().unwrap();
+    let mut frames = 0;
+    decoder
+        .decode_from_reader(
+            std::fs::File::open("./data/girlshy.h265").unwrap(),
+            |image| {
+                writer.write_image(&image).unwrap();
+                frames += 1;
+                ControlFlow::Continue(())
+            },
+        )
+        .unwrap();
+    writer.flush().unwrap();
+    drop(writer);
+    assert_eq!(frames, 75);
+
+    let data = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(data.starts_with(GIRLSHY_HEADER));
+    let frame_size = b"FRAME\n".len() + 316 * 240 + 2 * 158 * 120;
+    assert_eq!(data.len(), GIRLSHY_HEADER.len() + 75 * frame_size);
+    let first_frame = &data[GIRLSHY_HEADER.len()..];
+    assert!(first_frame.starts_with(b"FRAME\n"));
+    assert!(first_frame[frame_size..].starts_with(b"FRAME\n"));
+}
+
+fn write_first_image(
+    path: &str,
+    params: Y4mParams,
+) -> (Vec<u8>, std::result::Result<(), Y4mError>) {
+    let mut writer = Writer::new(Vec::new(), params);
+    let mut result = Ok(());
+    for_first_image(path, |image| {
+        result = writer.write_image(image);
+    });
+    (writer.into_inner(), result)
+}
+
+#[test]
+fn frame_rate_supplied_by_caller() {
+    let params = Y4mParams::default().with_frame_rate(30000, 1001);
+    let (data, result) = write_first_image("./data/girlshy.h265", params);
+    result.unwrap();
+    assert!(data.starts_with(b"YUV4MPEG2 W316 H240 F30000:1001 Ip A0:0 C420jpeg\n"));
+
+    // Default frame rate
+    let (data, result) = write_first_image("./data/gradient_444.h265", Y4mParams::default());
+    result.unwrap();
+    let header = b"YUV4MPEG2 W64 H64 F25:1 Ip A0:0 C444\n";
+    assert!(data.starts_with(header));
+    assert_eq!(data.len(), header.len() + 6 + 3 * 64 * 64);
+}
+
+#[test]
+fn frame_rate_from_timing_info() {
+    // 30000 / 1001 fps in VUI timing info
+    let (data, result) = write_first_image("./data/gradient_timing.h265", Y4mParams::default());
+    result.unwrap();
+    assert!(data.starts_with(b"YUV4MPEG2 W64 H64 F30000:1001 Ip A0:0 C420jpeg\n"));
+
+    // The frame rate supplied by the caller has priority.
+    let params = Y4mParams::default().with_frame_rate(50, 1);
+    let (data, result) = write_first_image("./data/gradient_timing.h265", params);
+    result.unwrap();
+    assert!(data.starts_with(b"YUV4MPEG2 W64 H64 F50:1 Ip A0:0 C420jpeg\n"));
+}
+
+#[test]
+fn chroma_tags() {
+    for (path, tag, chroma_size) in [
+        ("./data/gradient_mono.h265", "Cmono", 0),
+        ("./data/gradient_422.h265", "C422", 2 * 32 * 64),
+        ("./data/gradient_444.h265", "C444", 2 * 64 * 64),
+    ] {
+        let (data, result) = write_first_image(path, Y4mParams::default());
+        result.unwrap();
+        let header = format!("YUV4MPEG2 W64 H64 F25:1 Ip A0:0 {tag}\n");
+        assert!(data.starts_with(header.as_bytes()), "{path}");
+        assert_eq!(
+            data.len(),
+            header.len() + 6 + 64 * 64 + chroma_size,
+            "{path}"
+        );
+    }
+}
+
+#[test]
+fn high_bit_depth_is_not_supported() {
+    let (data, result) = write_first_image("./data/gradient_10bit.h265", Y4mParams::default());
+    assert!(matches!(result, Err(Y4mError::UnsupportedBitDepth(10))));
+    assert!(data.is_empty());
+}
@@ -0,0 +1,115 @@
+use libde265_rs::*;
+
+mod common;
+use common::first_frame;
+
+fn packed(view: PlaneView) -> Vec<u8> {
+    view.as_u8_rows().flatten().copied().collect()
+}
+
+#[test]
+fn yuv444_has_size_of_luma_plane() {
+    let image = first_frame("./data/girlshy.h265");
+    for filter in [ChromaFilter::Nearest, ChromaFilter::Bilinear] {
+        let yuv = image.to_yuv444(filter);
+        assert_eq!((yuv.width(), yuv.height()), (316, 240));
+        for channel in [Channel::Y, Channel::Cb, Channel::Cr] {
+            assert_eq!(yuv.bits_per_pixel(channel), 8);
+            assert_eq!(yuv.plane(channel).len(), 316 * 240);
+        }
+        assert_eq!(yuv.plane(Channel::Y), packed(image.plane_view(Channel::Y)));
+    }
+}
+
+#[test]
+fn yuv444_from_420_chroma() {
+    // Chroma samples of the stream: Cb = 64 + 4 * x, Cr = 192 - 4 * y.
+    let image = first_frame("./data/gradient_bt709.h265");
+    assert_eq!(ChromaFilter::default(), ChromaFilter::Bilinear);
+    let yuv = image.to_yuv444(ChromaFilter::default());
+    assert_eq!((yuv.width(), yuv.height()), (64, 64));
+    assert!(yuv.full_range());
+    assert_eq!(yuv.matrix_coefficients(), 1);
+    let cb = yuv.plane_view(Channel::Cb);
+    let cr = yuv.plane_view(Channel::Cr);
+    for y in 0..64 {
+        // Chroma rows are located between luma rows, the first
+        // and the last rows of luma are beyond them.
+        let expected_cr = match y {
+            0 => 192,
+            63 => 68,
+            _ => 193 - 2 * y as u8,
+        };
+        assert!(cr.row(y).unwrap().iter().all(|&v| v == expected_cr), "{y}");
+        // Chroma columns are co-sited with even luma columns.
+        let expected_cb: Vec<u8> = (0..64u8).map(|x| 64 + 2 * x.min(62)).collect();
+        assert_eq!(cb.row(y).unwrap(), expected_cb);
+    }
+
+    let yuv = image.to_yuv444(ChromaFilter::Nearest);
+    let cb = yuv.plane_view(Channel::Cb);
+    let cr = yuv.plane_view(Channel::Cr);
+    for y in 0..64u32 {
+        let expected_cr = 192 - 4 * (y / 2) as u8;
+        assert!(cr.row(y).unwrap().iter().all(|&v| v == expected_cr), "{y}");
+        let expected_cb: Vec<u8> = (0..64u8).map(|x| 64 + 4 * (x / 2)).collect();
+        assert_eq!(cb.row(y).unwrap(), expected_cb);
+    }
+}
+
+#[test]
+fn yuv444_keeps_444_and_high_bit_depth() {
+    let image = first_frame("./data/gradient_444.h265");
+    for filter in [ChromaFilter::Nearest, ChromaFilter::Bilinear] {
+        let yuv = image.to_yuv444(filter);
+        for channel in [Channel::Y, Channel::Cb, Channel::Cr] {
+            assert_eq!(yuv.plane(channel), packed(image.plane_view(channel)));
+        }
+    }
+
+    let image = first_frame("./data/gradient_10bit.h265");
+    let yuv = image.to_yuv444(ChromaFilter::Bilinear);
+    let luma = yuv.plane_view(Channel::Y);
+    assert_eq!(luma.bytes_per_sample(), 2);
+    assert_eq!(yuv.bits_per_pixel(Channel::Cb), 10);
+    assert_eq!(yuv.plane(Channel::Y), packed(image.plane_view(Channel::Y)));
+    assert_eq!(yuv.plane(Channel::Cb).len(), 64 * 64 * 2);
+}
+
+#[test]
+fn yuv444_from_monochrome() {
+    let image = first_frame("./data/gradient_mono.h265");
+    let yuv = image.to_yuv444(ChromaFilter::Bilinear);
+    assert!(yuv.plane(Channel::Cb).iter().all(|&v| v == 128));
+    assert!(yuv.plane(Channel::Cr).iter().all(|&v| v == 128));
+    assert!(yuv.to_rgb8() == image.to_rgb8());
+}
+
+#[test]
+fn rgb_through_yuv444() {
+    let image = first_frame("./data/girlshy.h265");
+    let direct = image.to_rgb8();
+    let yuv = image.to_yuv444(ChromaFilter::Bilinear);
+    let rgb = yuv.to_rgb8();
+    assert_eq!((rgb.width, rgb.height), (316, 240));
+    // Upsampled chroma is rounded to integers before the conversion.
+    for (&a, &b) in rgb.data.iter().zip(&direct.data) {
+        assert!(a.abs_diff(b) <= 2);
+    }
+
+    let rgba = yuv.to_rgba8(255);
+    let bgra = yuv.to_bgra8(255, 64);
+    let rgb16 = yuv.to_rgb16();
+    assert_eq!(
+        rgba.pixel(100, 50).unwrap()[..3],
+        rgb.pixel(100, 50).unwrap()
+    );
+    let [b, g, r, _] = bgra.pixel(100, 50).unwrap();
+    assert_eq!([r, g, b], rgb.pixel(100, 50).unwrap());
+    assert_eq!(rgb16.data.len(), rgb.data.len());
+
+    // The bt709 stream has linear chroma, so results are the same.
+    let image = first_frame("./data/gradient_bt709.h265");
+    let rgb = image.to_yuv444(ChromaFilter::Bilinear).to_rgb8();
+    assert!(rgb == image.to_rgb8());
+}